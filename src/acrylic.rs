@@ -0,0 +1,135 @@
+// Frosted glass behind the border using the (undocumented) SetWindowCompositionAttribute API, which
+// is what the taskbar and start menu use. DwmEnableBlurBehindWindow doesn't actually blur anything
+// on Windows 8 and up, so it's no help here.
+//
+// The accent covers the entire border window, so we also clip the window to the ring that the
+// border occupies. Otherwise, the tracking window's contents would get blurred too.
+use std::ffi::c_void;
+use std::mem;
+
+use anyhow::{Context, anyhow};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F;
+use windows::Win32::Graphics::Direct2D::D2D1_ROUNDED_RECT;
+use windows::Win32::Graphics::Gdi::{
+    CombineRgn, CreateRoundRectRgn, DeleteObject, HRGN, RGN_DIFF, RGN_ERROR, SetWindowRgn,
+};
+use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+use windows::core::{BOOL, s, w};
+
+const WCA_ACCENT_POLICY: u32 = 19;
+const ACCENT_ENABLE_ACRYLICBLURBEHIND: u32 = 4;
+
+#[repr(C)]
+struct AccentPolicy {
+    accent_state: u32,
+    accent_flags: u32,
+    gradient_color: u32,
+    animation_id: u32,
+}
+
+#[repr(C)]
+struct WindowCompositionAttribData {
+    attrib: u32,
+    pv_data: *mut c_void,
+    cb_data: usize,
+}
+
+type SetWindowCompositionAttributeFn =
+    unsafe extern "system" fn(HWND, *mut WindowCompositionAttribData) -> BOOL;
+
+pub fn enable_acrylic(border_window: HWND, tint: &D2D1_COLOR_F) -> anyhow::Result<()> {
+    // The gradient color is stored as 0xAABBGGRR
+    let to_u8 = |channel: f32| (channel.clamp(0.0, 1.0) * 255.0).round() as u32;
+    let gradient_color =
+        (to_u8(tint.a) << 24) | (to_u8(tint.b) << 16) | (to_u8(tint.g) << 8) | to_u8(tint.r);
+
+    set_accent_policy(
+        border_window,
+        ACCENT_ENABLE_ACRYLICBLURBEHIND,
+        gradient_color,
+    )
+}
+
+// Clips the border window to the area between the outer and inner edges of the border. Anything
+// drawn outside of it (e.g. shadows and glows) gets clipped as well.
+pub fn update_acrylic_region(
+    border_window: HWND,
+    render_rect: &D2D1_ROUNDED_RECT,
+    border_width: f32,
+    outer_padding: f32,
+) -> anyhow::Result<()> {
+    let outer_inflate = border_width / 2.0 + outer_padding;
+    let inner_inflate = -border_width / 2.0;
+
+    unsafe {
+        let outer_rgn = create_round_rect_rgn(render_rect, outer_inflate);
+        let inner_rgn = create_round_rect_rgn(render_rect, inner_inflate);
+
+        let combine_res = CombineRgn(Some(outer_rgn), Some(outer_rgn), Some(inner_rgn), RGN_DIFF);
+        let _ = DeleteObject(inner_rgn.into());
+
+        if combine_res == RGN_ERROR {
+            let _ = DeleteObject(outer_rgn.into());
+            return Err(anyhow!("could not combine acrylic regions"));
+        }
+
+        // The system owns the region after a successful call, so we only clean it up on failure
+        if SetWindowRgn(border_window, Some(outer_rgn), true) == 0 {
+            let _ = DeleteObject(outer_rgn.into());
+            return Err(anyhow!("could not set acrylic window region"));
+        }
+    }
+
+    Ok(())
+}
+
+unsafe fn create_round_rect_rgn(render_rect: &D2D1_ROUNDED_RECT, inflate: f32) -> HRGN {
+    let rect = &render_rect.rect;
+    let diameter = ((render_rect.radiusX + inflate).max(0.0) * 2.0).round() as i32;
+
+    // CreateRoundRectRgn excludes the bottom-right edge, hence the + 1
+    unsafe {
+        CreateRoundRectRgn(
+            (rect.left - inflate).round() as i32,
+            (rect.top - inflate).round() as i32,
+            (rect.right + inflate).round() as i32 + 1,
+            (rect.bottom + inflate).round() as i32 + 1,
+            diameter,
+            diameter,
+        )
+    }
+}
+
+fn set_accent_policy(
+    border_window: HWND,
+    accent_state: u32,
+    gradient_color: u32,
+) -> anyhow::Result<()> {
+    // SetWindowCompositionAttribute isn't in the Windows SDK, so we need to look it up at runtime
+    let set_window_composition_attribute = unsafe {
+        let user32 = GetModuleHandleW(w!("user32.dll")).context("could not get user32.dll")?;
+        let proc = GetProcAddress(user32, s!("SetWindowCompositionAttribute"))
+            .context("could not find SetWindowCompositionAttribute")?;
+
+        mem::transmute::<unsafe extern "system" fn() -> isize, SetWindowCompositionAttributeFn>(
+            proc,
+        )
+    };
+
+    let mut policy = AccentPolicy {
+        accent_state,
+        accent_flags: 0,
+        gradient_color,
+        animation_id: 0,
+    };
+    let mut data = WindowCompositionAttribData {
+        attrib: WCA_ACCENT_POLICY,
+        pv_data: &mut policy as *mut _ as _,
+        cb_data: mem::size_of::<AccentPolicy>(),
+    };
+
+    unsafe { set_window_composition_attribute(border_window, &mut data) }
+        .ok()
+        .context("could not set window accent policy")
+}
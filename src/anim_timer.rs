@@ -1,6 +1,6 @@
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
 
 use crate::post_message_w;
@@ -8,13 +8,26 @@ use crate::utils::WM_APP_ANIMATE;
 
 #[derive(Debug, Clone)]
 pub struct AnimationTimer {
-    stop_flag: Arc<Mutex<bool>>,
+    // The condvar wakes the timer thread up whenever the interval changes or the timer stops
+    state: Arc<(Mutex<TimerState>, Condvar)>,
+}
+
+#[derive(Debug)]
+struct TimerState {
+    is_stopped: bool,
+    interval_ms: u64,
 }
 
 impl AnimationTimer {
     pub fn start(hwnd: HWND, interval_ms: u64) -> Self {
-        let stop_flag = Arc::new(Mutex::new(false));
-        let stop_flag_clone = stop_flag.clone();
+        let state = Arc::new((
+            Mutex::new(TimerState {
+                is_stopped: false,
+                interval_ms,
+            }),
+            Condvar::new(),
+        ));
+        let state_clone = state.clone();
 
         // Convert hwnd to an isize so we can pass it into the thread
         let hwnd_isize = hwnd.0 as isize;
@@ -22,23 +35,47 @@ impl AnimationTimer {
         // Spawn a worker thread for the timer
         thread::spawn(move || {
             let hwnd = HWND(hwnd_isize as _);
-            let interval = Duration::from_millis(interval_ms);
+            let (state_lock, condvar) = &*state_clone;
 
-            while !*stop_flag_clone.lock().unwrap() {
+            loop {
                 if let Err(err) = post_message_w(Some(hwnd), WM_APP_ANIMATE, WPARAM(0), LPARAM(0)) {
                     error!("could not send animation timer message for {hwnd:?}: {err}");
                     break;
                 }
-                thread::sleep(interval);
+                let last_tick = Instant::now();
+
+                // If the interval changes while we're waiting, it counts from the last tick
+                let mut state = state_lock.lock().unwrap();
+                loop {
+                    if state.is_stopped {
+                        return;
+                    }
+
+                    let interval = Duration::from_millis(state.interval_ms);
+                    let elapsed = last_tick.elapsed();
+                    if elapsed >= interval {
+                        break;
+                    }
+
+                    state = condvar.wait_timeout(state, interval - elapsed).unwrap().0;
+                }
             }
         });
 
-        Self { stop_flag }
+        Self { state }
+    }
+
+    pub fn set_interval(&self, interval_ms: u64) {
+        let (state_lock, condvar) = &*self.state;
+        state_lock.lock().unwrap().interval_ms = interval_ms;
+        condvar.notify_one();
     }
 
     pub fn stop(&mut self) {
-        if let Ok(mut stop_flag) = self.stop_flag.lock() {
-            *stop_flag = true;
+        let (state_lock, condvar) = &*self.state;
+        if let Ok(mut state) = state_lock.lock() {
+            state.is_stopped = true;
         }
+        condvar.notify_one();
     }
 }
@@ -1,4 +1,5 @@
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time;
 use windows::Win32::Foundation::{HWND, RECT};
@@ -6,20 +7,31 @@ use windows::Win32::Foundation::{HWND, RECT};
 use windows_numerics::{Matrix3x2, Vector2};
 
 use crate::anim_timer::AnimationTimer;
-use crate::colors::ColorBrush;
+use crate::colors::{ColorBrush, ColorInterpolation};
 use crate::config::{serde_default_bool, serde_default_i32};
-use crate::utils::cubic_bezier;
+use crate::utils::{
+    BezierError, cubic_bezier, ease_in_bounce, ease_in_elastic, ease_in_out_bounce,
+    ease_in_out_elastic, ease_out_bounce, ease_out_elastic, parse_cubic_bezier,
+};
 use crate::window_border::WindowState;
 
-#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct AnimationsConfig {
     #[serde(default)]
     active: Vec<AnimParamsConfig>,
     #[serde(default)]
     inactive: Vec<AnimParamsConfig>,
+    // Scales and fades the whole border in when its window opens, and out when it closes
+    #[serde(default)]
+    open: Option<PresenceAnimConfig>,
+    #[serde(default)]
+    close: Option<PresenceAnimConfig>,
     #[serde(default = "serde_default_i32::<60>")]
     fps: i32,
+    // Tick rate for inactive borders once they've finished fading. 0 stops their animations.
+    #[serde(default)]
+    inactive_fps: Option<i32>,
     #[serde(default = "serde_default_bool::<true>")]
     enabled: bool,
 }
@@ -31,14 +43,20 @@ impl AnimationsConfig {
                 active: self
                     .active
                     .iter()
-                    .map(|params_config| params_config.to_anim_params())
+                    .map(|params_config| params_config.to_anim_params(self.fps))
                     .collect(),
                 inactive: self
                     .inactive
                     .iter()
-                    .map(|params_config| params_config.to_anim_params())
+                    .map(|params_config| params_config.to_anim_params(self.fps))
                     .collect(),
+                open: self.open.as_ref().map(PresenceAnimConfig::to_presence_anim),
+                close: self
+                    .close
+                    .as_ref()
+                    .map(PresenceAnimConfig::to_presence_anim),
                 fps: self.fps,
+                inactive_fps: self.inactive_fps.unwrap_or(self.fps),
                 ..Default::default()
             }
         } else {
@@ -47,17 +65,156 @@ impl AnimationsConfig {
     }
 }
 
+// Each border holds a set of animators that are all advanced by the same WM_APP_ANIMATE tick.
+// Every animator drives one channel, and only one animator can run on a channel at a time:
+//   - Starting an animator replaces the one already running on its channel, unless the running one
+//     has a higher priority. With equal priorities, the newest one wins.
+//   - The set is rebuilt from the active/inactive lists whenever the window state changes, which
+//     cancels animators that aren't in the new list. Progress is kept per channel rather than per
+//     animator, so e.g. a fade reverses smoothly instead of restarting.
 #[derive(Debug, Default, Clone)]
 pub struct Animations {
     pub active: Vec<AnimParams>,
     pub inactive: Vec<AnimParams>,
+    pub animators: Vec<AnimParams>,
+    // The window state that 'animators' was last built for
+    pub animators_state: Option<WindowState>,
+    pub open: Option<PresenceAnim>,
+    pub close: Option<PresenceAnim>,
+    // Set from the restore_animation option rather than the animations config
+    pub restore: Option<PresenceAnim>,
     pub timer: Option<AnimationTimer>,
     pub fps: i32,
+    pub inactive_fps: i32,
+    // Time that each channel has waited since it last advanced. Animators with a lower fps than
+    // the timer skip ticks until a whole frame's worth has built up.
+    pub pending_elapsed: HashMap<AnimChannel, time::Duration>,
     pub fade_progress: f32,
     pub spiral_progress: f32,
+    // How far the dashes have marched, as a fraction of the dash pattern's length
+    pub dash_progress: f32,
+    pub pulse_progress: f32,
 }
 
 impl Animations {
+    pub fn sync_animators(&mut self, window_state: WindowState) {
+        if self.animators_state == Some(window_state) {
+            return;
+        }
+
+        self.animators.clear();
+        self.pending_elapsed.clear();
+        self.pulse_progress = 0.0;
+        for anim_params in self.get_current(window_state).clone() {
+            self.start_animator(anim_params);
+        }
+        self.animators_state = Some(window_state);
+    }
+
+    // Returns false if an animator with a higher priority is already running on the same channel
+    pub fn start_animator(&mut self, anim_params: AnimParams) -> bool {
+        let channel = anim_params.anim_type.channel();
+
+        if let Some(index) = self
+            .animators
+            .iter()
+            .position(|running| running.anim_type.channel() == channel)
+        {
+            if self.animators[index].priority > anim_params.priority {
+                return false;
+            }
+            self.animators.remove(index);
+        }
+
+        self.animators.push(anim_params);
+        true
+    }
+
+    pub fn cancel_animator(&mut self, channel: AnimChannel) {
+        self.animators
+            .retain(|running| running.anim_type.channel() != channel);
+    }
+
+    pub fn is_running(&self, anim_type: AnimType) -> bool {
+        self.animators.contains_type(anim_type)
+    }
+
+    // Advances every running animator by 'anim_elapsed'. Returns true if anything changed, meaning
+    // the border should be re-rendered.
+    pub fn advance(
+        &mut self,
+        window_rect: &RECT,
+        window_state: WindowState,
+        active_color: &ColorBrush,
+        inactive_color: &ColorBrush,
+        anim_elapsed: &time::Duration,
+    ) -> anyhow::Result<bool> {
+        self.sync_animators(window_state);
+
+        let mut update = false;
+
+        // The pulse removes itself from 'animators' once it's done, so we go by index and only move
+        // on if the current animator is still there
+        let mut index = 0;
+        while index < self.animators.len() {
+            let animator_count = self.animators.len();
+            // This only bumps the easing function's refcount
+            let anim_params = &self.animators[index].clone();
+            let channel = anim_params.anim_type.channel();
+            let pending_elapsed = self.pending_elapsed.entry(channel).or_default();
+            *pending_elapsed += *anim_elapsed;
+
+            // Same 1ms of leeway as the render interval in BorderDrawer::animate()
+            let frame_interval = time::Duration::from_secs_f32(1.0 / anim_params.fps.max(1) as f32);
+            if *pending_elapsed + time::Duration::from_millis(1) < frame_interval {
+                index += 1;
+                continue;
+            }
+            let anim_elapsed = &std::mem::take(pending_elapsed);
+
+            match channel {
+                AnimChannel::Transform => {
+                    self.animate_spiral(
+                        window_rect,
+                        active_color,
+                        inactive_color,
+                        anim_elapsed,
+                        anim_params,
+                    );
+                    update = true;
+                }
+                AnimChannel::Dash => {
+                    self.animate_marching_ants(anim_elapsed, anim_params);
+                    update = true;
+                }
+                AnimChannel::Pulse => {
+                    self.animate_pulse(anim_elapsed, anim_params);
+                    update = true;
+                }
+                // The fade animator stays in the set once it's done so that it can pick up the
+                // next window state change, but there's nothing to do until then
+                AnimChannel::Opacity => {
+                    if is_fade_in_progress(window_state, active_color, inactive_color)? {
+                        self.animate_fade(
+                            window_state,
+                            active_color,
+                            inactive_color,
+                            anim_elapsed,
+                            anim_params,
+                        )?;
+                        update = true;
+                    }
+                }
+            }
+
+            if self.animators.len() == animator_count {
+                index += 1;
+            }
+        }
+
+        Ok(update)
+    }
+
     pub fn animate_spiral(
         &mut self,
         window_rect: &RECT,
@@ -97,6 +254,58 @@ impl Animations {
         inactive_color.set_transform(&transform);
     }
 
+    pub fn animate_marching_ants(
+        &mut self,
+        anim_elapsed: &time::Duration,
+        anim_params: &AnimParams,
+    ) {
+        let delta_x = anim_elapsed.as_secs_f32() * 1000.0 / anim_params.duration;
+        self.dash_progress = (self.dash_progress + delta_x).rem_euclid(1.0);
+    }
+
+    // The pulse only plays once per window state change, so it removes itself once it's done
+    pub fn animate_pulse(&mut self, anim_elapsed: &time::Duration, anim_params: &AnimParams) {
+        let delta_x = anim_elapsed.as_secs_f32() * 1000.0 / anim_params.duration;
+        self.pulse_progress += delta_x;
+
+        if self.pulse_progress >= 1.0 {
+            self.pulse_progress = 0.0;
+            self.cancel_animator(AnimChannel::Pulse);
+        }
+    }
+
+    // Returns how far the pulse currently reaches past the border (in pixels) and how strong it
+    // is, from 0.0 to 1.0. The pulse swells and then settles back down over its duration.
+    pub fn get_pulse(&self) -> Option<(f32, f32)> {
+        let anim_params = self
+            .animators
+            .iter()
+            .find(|anim_params| anim_params.anim_type == AnimType::Pulse)?;
+
+        let y_coord = anim_params.easing_fn.as_ref()(self.pulse_progress);
+        let intensity = (std::f32::consts::PI * y_coord).sin().max(0.0);
+
+        Some((anim_params.amplitude * intensity, intensity))
+    }
+
+    // How far the biggest configured pulse reaches past the border (in pixels), for the padding
+    pub fn get_max_pulse_amplitude(&self) -> f32 {
+        self.active
+            .iter()
+            .chain(self.inactive.iter())
+            .filter(|anim_params| anim_params.anim_type == AnimType::Pulse)
+            .map(|anim_params| anim_params.amplitude.max(0.0))
+            .fold(0.0, f32::max)
+    }
+
+    // Returns None if the dashes aren't marching
+    pub fn get_dash_progress(&self) -> Option<f32> {
+        self.animators
+            .iter()
+            .find(|anim_params| anim_params.anim_type == AnimType::MarchingAnts)
+            .map(|anim_params| anim_params.easing_fn.as_ref()(self.dash_progress))
+    }
+
     pub fn animate_fade(
         &mut self,
         window_state: WindowState,
@@ -158,6 +367,7 @@ impl Animations {
         Ok(())
     }
 
+    // NOTE: this returns the configured list. Use 'animators' for what's actually running.
     pub fn get_current(&self, window_state: WindowState) -> &Vec<AnimParams> {
         match window_state {
             WindowState::Active => &self.active,
@@ -165,19 +375,67 @@ impl Animations {
         }
     }
 
+    pub fn get_fade_interpolation(&self, window_state: WindowState) -> ColorInterpolation {
+        self.get_current(window_state)
+            .iter()
+            .find(|anim_params| anim_params.anim_type == AnimType::Fade)
+            .map(|anim_params| anim_params.interpolation)
+            .unwrap_or_default()
+    }
+
+    // The timer ticks as fast as the fastest running animator needs it to. A fade that's done
+    // doesn't need the timer at all until the window state changes again.
+    pub fn get_timer_fps(&self, is_fade_in_progress: bool) -> i32 {
+        if self.animators.is_empty() {
+            return self.fps;
+        }
+
+        self.animators
+            .iter()
+            .filter(|anim_params| is_fade_in_progress || anim_params.anim_type != AnimType::Fade)
+            .map(|anim_params| anim_params.fps)
+            .max()
+            .unwrap_or(0)
+    }
+
     pub fn set_timer_if_enabled(
         &mut self,
         border_window: HWND,
         last_anim_time: &mut Option<time::Instant>,
     ) {
         if (!self.active.is_empty() || !self.inactive.is_empty()) && self.timer.is_none() {
-            let timer_duration = (1000.0 / self.fps as f32) as u64;
+            let timer_duration = (1000.0 / self.get_timer_fps(true).max(1) as f32) as u64;
             self.timer = Some(AnimationTimer::start(border_window, timer_duration));
 
             *last_anim_time = Some(time::Instant::now());
         }
     }
 
+    // Inactive borders can tick at a lower rate (or not at all) once their fade is done. We switch
+    // back to the full rate as soon as the window is focused again.
+    pub fn update_timer_rate(
+        &mut self,
+        border_window: HWND,
+        window_state: WindowState,
+        is_fade_in_progress: bool,
+        last_anim_time: &mut Option<time::Instant>,
+    ) {
+        let fps = match window_state {
+            WindowState::Inactive if !is_fade_in_progress => {
+                self.inactive_fps.min(self.get_timer_fps(false))
+            }
+            _ => self.get_timer_fps(is_fade_in_progress),
+        };
+
+        match self.timer {
+            // The border just stays the way it was last rendered
+            Some(_) if fps <= 0 => self.destroy_timer(),
+            Some(ref timer) => timer.set_interval((1000.0 / fps as f32) as u64),
+            None if fps > 0 => self.set_timer_if_enabled(border_window, last_anim_time),
+            None => {}
+        }
+    }
+
     pub fn destroy_timer(&mut self) {
         if let Some(anim_timer) = self.timer.as_mut() {
             anim_timer.stop();
@@ -193,29 +451,60 @@ impl Animations {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+// Returns true while the colors haven't reached their final opacities for 'window_state'
+pub fn is_fade_in_progress(
+    window_state: WindowState,
+    active_color: &ColorBrush,
+    inactive_color: &ColorBrush,
+) -> anyhow::Result<bool> {
+    let correct_active_opacity = if window_state == WindowState::Active {
+        1.0
+    } else {
+        0.0
+    };
+
+    Ok(active_color.get_opacity()? != correct_active_opacity
+        || inactive_color.get_opacity()? != 1.0 - correct_active_opacity)
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct AnimParamsConfig {
     #[serde(rename = "type")]
     pub anim_type: AnimType,
     pub duration: Option<f32>,
     pub easing: Option<AnimEasing>,
+    // Only used by the fade animation
+    pub interpolation: Option<ColorInterpolation>,
+    // Decides which animation runs when several of them drive the same channel
+    pub priority: Option<i32>,
+    // Only used by the pulse animation. How far it swells past the border (in pixels).
+    pub amplitude: Option<f32>,
+    // Caps how often this animation updates. Defaults to the animations' fps.
+    pub fps: Option<i32>,
 }
 
 impl AnimParamsConfig {
-    fn to_anim_params(&self) -> AnimParams {
+    fn to_anim_params(&self, default_fps: i32) -> AnimParams {
         let duration = self.duration.unwrap_or(match self.anim_type {
             AnimType::Spiral | AnimType::ReverseSpiral => 1800.0,
             AnimType::Fade => 200.0,
+            AnimType::MarchingAnts => 1000.0,
+            AnimType::Pulse => 400.0,
         });
 
         let easing = self.easing.unwrap_or_default();
-        let easing_function = cubic_bezier(&easing.to_points()).unwrap();
+        let easing_fn = easing.to_easing_fn().unwrap();
 
         AnimParams {
             anim_type: self.anim_type,
             duration,
-            easing_fn: Arc::new(easing_function),
+            easing_fn,
+            interpolation: self.interpolation.unwrap_or_default(),
+            priority: self.priority.unwrap_or_default(),
+            amplitude: self.amplitude.unwrap_or(4.0),
+            // The global fps is a cap, so animations can only ask for fewer frames
+            fps: self.fps.unwrap_or(default_fps).min(default_fps),
         }
     }
 }
@@ -225,6 +514,10 @@ pub struct AnimParams {
     pub anim_type: AnimType,
     pub duration: f32,
     pub easing_fn: Arc<dyn Fn(f32) -> f32 + Send + Sync>,
+    pub interpolation: ColorInterpolation,
+    pub priority: i32,
+    pub amplitude: f32,
+    pub fps: i32,
 }
 
 // We must manually implement Debug for AnimParams because Fn(f32) -> f32 doesn't implement it
@@ -234,10 +527,120 @@ impl std::fmt::Debug for AnimParams {
             .field("type", &self.anim_type)
             .field("duration", &self.duration)
             .field("easing_fn", &Arc::as_ptr(&self.easing_fn))
+            .field("interpolation", &self.interpolation)
+            .field("priority", &self.priority)
+            .field("amplitude", &self.amplitude)
+            .field("fps", &self.fps)
+            .finish()
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PresenceAnimConfig {
+    pub duration: Option<f32>,
+    pub easing: Option<AnimEasing>,
+    // How big the border starts (or ends) compared to its full size, scaling from the window's center
+    pub scale: Option<f32>,
+}
+
+impl PresenceAnimConfig {
+    fn to_presence_anim(&self) -> PresenceAnim {
+        PresenceAnim {
+            duration: self.duration.unwrap_or(200.0),
+            easing_fn: self.easing.unwrap_or_default().to_easing_fn().unwrap(),
+            motion: PresenceMotion::Scale(self.scale.unwrap_or(0.9).max(0.0)),
+        }
+    }
+}
+
+// What the border does when its window is restored from being minimized
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum RestoreAnimStyle {
+    // Just waits for unminimize_delay
+    #[default]
+    None,
+    Fade,
+    // Grows out of the side of the window that faces the taskbar
+    Grow,
+    // Slides in from the taskbar's direction
+    Slide,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct RestoreAnimConfig {
+    #[serde(default)]
+    pub style: RestoreAnimStyle,
+    pub duration: Option<f32>,
+    pub easing: Option<AnimEasing>,
+}
+
+impl RestoreAnimConfig {
+    pub fn to_presence_anim(&self) -> Option<PresenceAnim> {
+        let motion = match self.style {
+            RestoreAnimStyle::None => return None,
+            RestoreAnimStyle::Fade => PresenceMotion::None,
+            RestoreAnimStyle::Grow => PresenceMotion::GrowFromTaskbar,
+            RestoreAnimStyle::Slide => PresenceMotion::SlideFromTaskbar,
+        };
+
+        Some(PresenceAnim {
+            duration: self.duration.unwrap_or(250.0),
+            easing_fn: self
+                .easing
+                .unwrap_or(AnimEasing::EaseOutCubic)
+                .to_easing_fn()
+                .unwrap(),
+            motion,
+        })
+    }
+}
+
+// How the border moves while it fades in or out
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PresenceMotion {
+    None,
+    // Scales from the window's center, starting (or ending) at this fraction of its full size
+    Scale(f32),
+    GrowFromTaskbar,
+    SlideFromTaskbar,
+}
+
+#[derive(Clone)]
+pub struct PresenceAnim {
+    pub duration: f32,
+    pub easing_fn: Arc<dyn Fn(f32) -> f32 + Send + Sync>,
+    pub motion: PresenceMotion,
+}
+
+impl std::fmt::Debug for PresenceAnim {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("PresenceAnim")
+            .field("duration", &self.duration)
+            .field("easing_fn", &Arc::as_ptr(&self.easing_fn))
+            .field("motion", &self.motion)
             .finish()
     }
 }
 
+impl PresenceAnim {
+    // Returns how much of the border is showing (0.0 is gone, 1.0 is fully in) and whether the
+    // animation is done
+    pub fn get_presence(&self, elapsed: time::Duration, is_closing: bool) -> (f32, bool) {
+        let progress = match self.duration > 0.0 {
+            true => (elapsed.as_secs_f32() * 1000.0 / self.duration).min(1.0),
+            false => 1.0,
+        };
+        let eased = self.easing_fn.as_ref()(progress);
+
+        match is_closing {
+            true => (1.0 - eased, progress >= 1.0),
+            false => (eased, progress >= 1.0),
+        }
+    }
+}
+
 pub trait AnimVec {
     fn contains_type(&self, anim_type: AnimType) -> bool;
 }
@@ -249,15 +652,42 @@ impl AnimVec for Vec<AnimParams> {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 pub enum AnimType {
     Spiral,
     ReverseSpiral,
     Fade,
+    MarchingAnts,
+    // A one-shot glow that swells out of the border and settles back down
+    Pulse,
+}
+
+impl AnimType {
+    pub fn channel(self) -> AnimChannel {
+        match self {
+            AnimType::Spiral | AnimType::ReverseSpiral => AnimChannel::Transform,
+            AnimType::Fade => AnimChannel::Opacity,
+            AnimType::MarchingAnts => AnimChannel::Dash,
+            AnimType::Pulse => AnimChannel::Pulse,
+        }
+    }
+}
+
+// The part of the border that an animator drives
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AnimChannel {
+    // The brushes' transforms
+    Transform,
+    // The active/inactive brushes' opacities
+    Opacity,
+    // The dash offset of the border's stroke
+    Dash,
+    // The glow that's drawn on top of the border
+    Pulse,
 }
 
 // Thanks to 0xJWLabs for the AnimEasing enum along with its methods
-#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum AnimEasing {
     // Linear
     #[default]
@@ -273,6 +703,8 @@ pub enum AnimEasing {
     EaseInExpo,
     EaseInCirc,
     EaseInBack,
+    EaseInBounce,
+    EaseInElastic,
 
     // EaseOut variants
     EaseOut,
@@ -284,6 +716,8 @@ pub enum AnimEasing {
     EaseOutExpo,
     EaseOutCirc,
     EaseOutBack,
+    EaseOutBounce,
+    EaseOutElastic,
 
     // EaseInOut variants
     EaseInOut,
@@ -295,17 +729,79 @@ pub enum AnimEasing {
     EaseInOutExpo,
     EaseInOutCirc,
     EaseInOutBack,
+    EaseInOutBounce,
+    EaseInOutElastic,
 
     #[serde(untagged)]
-    CubicBezier([f32; 4]),
+    CubicBezier(BezierPoints),
+}
+
+// Control points for AnimEasing::CubicBezier. These can be written as a list, e.g.
+// [0.25, 0.1, 0.25, 1.0], or like in CSS, e.g. "cubic-bezier(0.25, 0.1, 0.25, 1.0)".
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "BezierPointsRepr", into = "BezierPointsRepr")]
+pub struct BezierPoints(pub [f32; 4]);
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum BezierPointsRepr {
+    Points([f32; 4]),
+    Text(String),
+}
+
+impl TryFrom<BezierPointsRepr> for BezierPoints {
+    type Error = String;
+
+    fn try_from(repr: BezierPointsRepr) -> Result<Self, Self::Error> {
+        let points = match repr {
+            BezierPointsRepr::Points(points) => points,
+            BezierPointsRepr::Text(text) => parse_cubic_bezier(&text)?,
+        };
+
+        // Catch bad control points now rather than when the animation gets created
+        if let Err(err) = cubic_bezier(&points) {
+            return Err(err.to_string());
+        }
+
+        Ok(BezierPoints(points))
+    }
+}
+
+impl From<BezierPoints> for BezierPointsRepr {
+    fn from(points: BezierPoints) -> Self {
+        BezierPointsRepr::Points(points.0)
+    }
 }
 
 impl AnimEasing {
+    /// Converts the easing to the function that the animations use.
+    /// Bounce and elastic variants go back and forth more than a cubic bezier can,
+    /// so they get their own functions instead of control points.
+    pub fn to_easing_fn(self) -> Result<Arc<dyn Fn(f32) -> f32 + Send + Sync>, BezierError> {
+        let easing_fn: fn(f32) -> f32 = match self {
+            AnimEasing::EaseInBounce => ease_in_bounce,
+            AnimEasing::EaseOutBounce => ease_out_bounce,
+            AnimEasing::EaseInOutBounce => ease_in_out_bounce,
+            AnimEasing::EaseInElastic => ease_in_elastic,
+            AnimEasing::EaseOutElastic => ease_out_elastic,
+            AnimEasing::EaseInOutElastic => ease_in_out_elastic,
+            // Everything else is a cubic bezier, which to_points() has the control points for
+            bezier_easing => {
+                let points = bezier_easing
+                    .to_points()
+                    .ok_or(BezierError::InvalidControlPoint)?;
+                return Ok(Arc::new(cubic_bezier(&points)?));
+            }
+        };
+
+        Ok(Arc::new(easing_fn))
+    }
+
     /// Converts the easing to a corresponding array of points.
     /// Linear and named easing variants will return predefined control points,
-    /// while CubicBezier returns its own array.
-    pub fn to_points(self) -> [f32; 4] {
-        match self {
+    /// while CubicBezier returns its own array. Bounce and elastic variants return None.
+    pub fn to_points(self) -> Option<[f32; 4]> {
+        let points = match self {
             // Linear
             AnimEasing::Linear => [0.0, 0.0, 1.0, 1.0],
 
@@ -343,7 +839,103 @@ impl AnimEasing {
             AnimEasing::EaseInOutBack => [0.68, -0.6, 0.32, 1.6],
 
             // CubicBezier variant returns its own points.
-            AnimEasing::CubicBezier(bezier) => bezier,
+            AnimEasing::CubicBezier(bezier) => bezier.0,
+
+            AnimEasing::EaseInBounce
+            | AnimEasing::EaseOutBounce
+            | AnimEasing::EaseInOutBounce
+            | AnimEasing::EaseInElastic
+            | AnimEasing::EaseOutElastic
+            | AnimEasing::EaseInOutElastic => return None,
+        };
+
+        Some(points)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_anim_params_config(anim_type: AnimType) -> AnimParamsConfig {
+        AnimParamsConfig {
+            anim_type,
+            duration: None,
+            easing: None,
+            interpolation: None,
+            priority: None,
+            amplitude: None,
+            fps: None,
+        }
+    }
+
+    fn get_anim_params(anim_type: AnimType, priority: i32) -> AnimParams {
+        AnimParamsConfig {
+            priority: Some(priority),
+            ..get_anim_params_config(anim_type)
         }
+        .to_anim_params(60)
+    }
+
+    #[test]
+    fn test_start_animator_priority() {
+        let mut animations = Animations::default();
+
+        assert!(animations.start_animator(get_anim_params(AnimType::Spiral, 1)));
+        // Lower priorities can't replace a running animator on the same channel
+        assert!(!animations.start_animator(get_anim_params(AnimType::ReverseSpiral, 0)));
+        assert!(animations.is_running(AnimType::Spiral));
+        assert!(!animations.is_running(AnimType::ReverseSpiral));
+
+        // Equal priorities go to the newest one
+        assert!(animations.start_animator(get_anim_params(AnimType::ReverseSpiral, 1)));
+        assert!(!animations.is_running(AnimType::Spiral));
+        assert!(animations.is_running(AnimType::ReverseSpiral));
+
+        // Other channels aren't affected
+        assert!(animations.start_animator(get_anim_params(AnimType::Fade, 0)));
+        assert_eq!(animations.animators.len(), 2);
+    }
+
+    #[test]
+    fn test_cancel_animator() {
+        let mut animations = Animations::default();
+        animations.start_animator(get_anim_params(AnimType::Spiral, 0));
+        animations.start_animator(get_anim_params(AnimType::Pulse, 0));
+
+        animations.cancel_animator(AnimChannel::Transform);
+        assert!(!animations.is_running(AnimType::Spiral));
+        assert!(animations.is_running(AnimType::Pulse));
+
+        // Cancelling a channel that has nothing running is fine too
+        animations.cancel_animator(AnimChannel::Dash);
+        assert_eq!(animations.animators.len(), 1);
+    }
+
+    #[test]
+    fn test_get_timer_fps() {
+        let mut animations = Animations {
+            fps: 60,
+            ..Default::default()
+        };
+        assert_eq!(animations.get_timer_fps(false), 60);
+
+        let mut spiral = get_anim_params(AnimType::Spiral, 0);
+        spiral.fps = 30;
+        animations.start_animator(spiral);
+        animations.start_animator(get_anim_params(AnimType::Fade, 0));
+        assert_eq!(animations.get_timer_fps(true), 60);
+        // Finished fades don't count
+        assert_eq!(animations.get_timer_fps(false), 30);
+
+        animations.cancel_animator(AnimChannel::Transform);
+        assert_eq!(animations.get_timer_fps(false), 0);
+
+        // Animations can't go above the global fps
+        let config = AnimParamsConfig {
+            fps: Some(144),
+            ..get_anim_params_config(AnimType::Spiral)
+        };
+        assert_eq!(config.to_anim_params(60).fps, 60);
     }
 }
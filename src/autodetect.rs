@@ -0,0 +1,89 @@
+// Turns on the komorebi integration while komorebi is running, so that users don't have to enable
+// it in the config (and restart) themselves. Other window managers (e.g. GlazeWM) aren't detected
+// because we don't have an integration to turn on for them; they work without one.
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{thread, time};
+
+use crate::APP_STATE;
+use crate::config::Config;
+use crate::utils::{LogIfErr, is_process_running};
+
+const DETECTION_INTERVAL: time::Duration = time::Duration::from_secs(3);
+const KOMOREBI_PROCESS_NAME: &str = "komorebi";
+
+static IS_KOMOREBI_DETECTED: AtomicBool = AtomicBool::new(false);
+static IS_DETECTOR_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct IntegrationsConfig {
+    // While komorebi is running, act as if komorebi_colors.enabled were True
+    #[serde(default)]
+    pub detect_komorebi: bool,
+}
+
+pub fn is_komorebi_detected(config: &Config) -> bool {
+    config.integrations.detect_komorebi && IS_KOMOREBI_DETECTED.load(Ordering::SeqCst)
+}
+
+// The first check happens right away so that the integration can be started along with everything
+// else. After that, we poll from a background thread until detect_komorebi gets turned off.
+pub fn start_detector_if_enabled(config: &Config) {
+    if !config.integrations.detect_komorebi {
+        return;
+    }
+
+    IS_KOMOREBI_DETECTED.store(is_process_running(KOMOREBI_PROCESS_NAME), Ordering::SeqCst);
+
+    if IS_DETECTOR_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    debug!("starting integration detector");
+
+    let _ = thread::spawn(|| {
+        loop {
+            thread::sleep(DETECTION_INTERVAL);
+
+            if !APP_STATE
+                .config
+                .read()
+                .unwrap()
+                .integrations
+                .detect_komorebi
+            {
+                debug!("stopping integration detector");
+                IS_DETECTOR_RUNNING.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            let is_detected = is_process_running(KOMOREBI_PROCESS_NAME);
+            if IS_KOMOREBI_DETECTED.swap(is_detected, Ordering::SeqCst) == is_detected {
+                continue;
+            }
+
+            match is_detected {
+                true => info!("komorebi has started; enabling komorebi integration"),
+                false => info!("komorebi has exited; disabling komorebi integration"),
+            }
+
+            update_komorebi_integration();
+        }
+    });
+}
+
+fn update_komorebi_integration() {
+    // Elsewhere, the config gets locked before komorebi_integration, so we do the same here
+    let config = APP_STATE.config.read().unwrap();
+    let mut komorebi_integration = APP_STATE.komorebi_integration.lock().unwrap();
+    let is_enabled = komorebi_integration.is_enabled(&config);
+    drop(config);
+
+    if is_enabled && !komorebi_integration.is_running() {
+        komorebi_integration.start().log_if_err();
+    } else if !is_enabled && komorebi_integration.is_running() {
+        komorebi_integration.stop().log_if_err();
+        komorebi_integration.reset_borders();
+    }
+}
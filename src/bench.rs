@@ -0,0 +1,524 @@
+// Synthetic benchmark for --bench. This spawns a bunch of dummy windows, lets the engine attach
+// borders to them, then takes turns moving and focusing them for a while and reports how it went.
+//
+// The dummy windows live in a child process because the event hook skips events from our own
+// process (WINEVENT_SKIPOWNPROCESS).
+use anyhow::{Context, anyhow};
+use std::fmt::Write;
+use std::process::{Child, Command};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{env, fs, thread, time};
+use windows::Win32::Foundation::{FILETIME, HWND, LPARAM, LRESULT, RECT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::System::Threading::{
+    GetCurrentProcess, GetCurrentThread, GetCurrentThreadId, GetProcessTimes, GetThreadTimes,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, EnumWindows, GetMessageW, GetWindowRect,
+    GetWindowThreadProcessId, MB_ICONINFORMATION, MSG, PostThreadMessageW, RegisterClassExW,
+    SWP_NOACTIVATE, SWP_NOSIZE, SWP_NOZORDER, SetForegroundWindow, SetWindowPos, TranslateMessage,
+    WINDOW_EX_STYLE, WM_QUIT, WNDCLASSEXW, WS_OVERLAPPEDWINDOW, WS_VISIBLE,
+};
+use windows::core::{BOOL, PCWSTR, w};
+
+use crate::cli::BenchOptions;
+use crate::config::{Config, EnableMode, MatchKind, MatchStrategy, WindowRule};
+use crate::memory_trim::trim_memory;
+use crate::utils::{LogIfErr, get_border_for_window, get_window_class};
+use crate::{APP_STATE, run_message_loop, show_message_box, start_engine, stop_engine};
+
+const BENCH_CLASS_NAME: &str = "tacky-borders-bench";
+const BENCH_CLASS_NAME_W: PCWSTR = w!("tacky-borders-bench");
+const TICK_MS: u64 = 16;
+// Past this, we count an event as missed instead of waiting on it any longer
+const LATENCY_TIMEOUT_MS: u64 = 500;
+// How often we check whether the engine has caught up. Polling any faster would keep the churn
+// thread busy enough to compete with the engine for cpu time, so the latencies are only accurate
+// to within this interval (the report says as much).
+const POLL_INTERVAL_MS: u64 = 10;
+
+static IS_RECORDING: AtomicBool = AtomicBool::new(false);
+static FRAME_TIMES: Mutex<Vec<time::Duration>> = Mutex::new(Vec::new());
+
+// Called by every border after it renders. This is a no-op outside of --bench.
+pub fn record_frame_time(frame_time: time::Duration) {
+    if IS_RECORDING.load(Ordering::Relaxed) {
+        FRAME_TIMES.lock().unwrap().push(frame_time);
+    }
+}
+
+// Kills the dummy window process even if we bail out early
+struct BenchHost(Child);
+
+impl Drop for BenchHost {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+#[derive(Debug, Default)]
+struct ChurnResults {
+    move_latencies: Vec<time::Duration>,
+    missed_moves: u32,
+    focus_latencies: Vec<time::Duration>,
+    missed_focuses: u32,
+    // Spent by the churn thread itself, which shouldn't count towards the engine's cpu usage
+    cpu_time: time::Duration,
+}
+
+pub fn run_bench(options: &BenchOptions) -> anyhow::Result<()> {
+    info!(
+        "starting benchmark with {} windows for {} seconds",
+        options.windows, options.seconds
+    );
+
+    if options.windows == 0 {
+        return Err(anyhow!("--bench-windows must be at least 1"));
+    }
+
+    let config = get_bench_config(options)?;
+
+    let bench_host = BenchHost(
+        Command::new(env::current_exe().context("could not get current exe")?)
+            .args(["--bench-host", &options.windows.to_string()])
+            .spawn()
+            .context("could not spawn bench host")?,
+    );
+    let dummy_windows = wait_for_dummy_windows(bench_host.0.id(), options.windows)?;
+
+    let cpu_time_before = get_process_cpu_time();
+    let start_time = time::Instant::now();
+    IS_RECORDING.store(true, Ordering::Relaxed);
+
+    let hwineventhook = start_engine(Some(config))?;
+
+    let main_thread_id = unsafe { GetCurrentThreadId() };
+    let seconds = options.seconds;
+    let churn_thread = thread::spawn(move || {
+        let results = churn_windows(&dummy_windows, time::Duration::from_secs(seconds));
+
+        unsafe { PostThreadMessageW(main_thread_id, WM_QUIT, WPARAM(0), LPARAM(0)) }
+            .context("could not post WM_QUIT")
+            .log_if_err();
+
+        results
+    });
+
+    run_message_loop();
+
+    let churn_results = churn_thread
+        .join()
+        .map_err(|_| anyhow!("churn thread panicked"))?;
+
+    IS_RECORDING.store(false, Ordering::Relaxed);
+    let elapsed = start_time.elapsed();
+    let cpu_time = get_process_cpu_time()
+        .saturating_sub(cpu_time_before)
+        .saturating_sub(churn_results.cpu_time);
+
+    stop_engine(hwineventhook)?;
+    drop(bench_host);
+
+    // Closing every border at once is exactly the kind of burst that trimming is meant for
+    let (memory_before_trim, memory_after_trim) = trim_memory();
+
+    let frame_times = std::mem::take(&mut *FRAME_TIMES.lock().unwrap());
+    let results = report(
+        options,
+        elapsed,
+        cpu_time,
+        &frame_times,
+        &churn_results,
+        (memory_before_trim, memory_after_trim),
+    );
+    info!("benchmark results:\n{results}");
+
+    // The release build has no console, so the results also go to a file and a message box
+    let results_path = Config::get_dir()?.join("bench-results.txt");
+    fs::write(&results_path, &results).context("could not write benchmark results")?;
+    show_message_box(
+        &format!("{results}\nSaved to {}", results_path.display()),
+        w!("tacky-borders benchmark"),
+        MB_ICONINFORMATION,
+    );
+
+    Ok(())
+}
+
+fn get_bench_config(options: &BenchOptions) -> anyhow::Result<Config> {
+    let mut config = match options.theme {
+        Some(ref theme) => {
+            let contents = fs::read_to_string(theme)
+                .with_context(|| format!("could not read {}", theme.display()))?;
+            Config::from_yaml(&contents)?
+        }
+        None => Config::create()?,
+    };
+
+    // Only the dummy windows should get borders, and the theme shouldn't change under us
+    config.watch_config_changes = false;
+    config.window_rules.splice(
+        0..0,
+        [
+            WindowRule {
+                kind: Some(MatchKind::Class),
+                name: Some(BENCH_CLASS_NAME.to_string()),
+                enabled: Some(EnableMode::Bool(true)),
+                ..Default::default()
+            },
+            WindowRule {
+                kind: Some(MatchKind::Class),
+                name: Some(".*".to_string()),
+                strategy: Some(MatchStrategy::Regex),
+                enabled: Some(EnableMode::Bool(false)),
+                ..Default::default()
+            },
+        ],
+    );
+
+    Ok(config)
+}
+
+fn wait_for_dummy_windows(process_id: u32, count: u32) -> anyhow::Result<Vec<isize>> {
+    const TIMEOUT: time::Duration = time::Duration::from_secs(5);
+
+    let start_time = time::Instant::now();
+    loop {
+        let dummy_windows = find_dummy_windows(process_id);
+        if dummy_windows.len() >= count as usize {
+            return Ok(dummy_windows);
+        }
+
+        if start_time.elapsed() > TIMEOUT {
+            return Err(anyhow!(
+                "only found {} of {count} dummy windows",
+                dummy_windows.len()
+            ));
+        }
+
+        thread::sleep(time::Duration::from_millis(50));
+    }
+}
+
+fn find_dummy_windows(process_id: u32) -> Vec<isize> {
+    let mut lparam_data = (process_id, Vec::<isize>::new());
+
+    unsafe extern "system" fn enum_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+        let (process_id, dummy_windows) = unsafe { &mut *(lparam.0 as *mut (u32, Vec<isize>)) };
+
+        let mut window_process_id = 0;
+        unsafe { GetWindowThreadProcessId(hwnd, Some(&mut window_process_id)) };
+
+        if window_process_id == *process_id
+            && get_window_class(hwnd).is_ok_and(|class| class == BENCH_CLASS_NAME)
+        {
+            dummy_windows.push(hwnd.0 as isize);
+        }
+
+        true.into()
+    }
+
+    let _ = unsafe {
+        EnumWindows(
+            Some(enum_callback),
+            LPARAM(&mut lparam_data as *mut _ as isize),
+        )
+    };
+
+    lparam_data.1
+}
+
+fn churn_windows(dummy_windows: &[isize], duration: time::Duration) -> ChurnResults {
+    let mut results = ChurnResults::default();
+
+    // Give the engine a moment to attach borders to every window before we start measuring
+    thread::sleep(time::Duration::from_millis(500));
+
+    let start_time = time::Instant::now();
+    let mut tick: usize = 0;
+
+    while start_time.elapsed() < duration {
+        let hwnd = HWND(dummy_windows[tick % dummy_windows.len()] as _);
+
+        // Alternate between moving and focusing so both paths get exercised evenly
+        if tick.is_multiple_of(2) {
+            match measure_move_latency(hwnd, tick) {
+                Some(latency) => results.move_latencies.push(latency),
+                None => results.missed_moves += 1,
+            }
+        } else {
+            match measure_focus_latency(hwnd) {
+                Some(latency) => results.focus_latencies.push(latency),
+                None => results.missed_focuses += 1,
+            }
+        }
+
+        tick += 1;
+        thread::sleep(time::Duration::from_millis(TICK_MS));
+    }
+
+    results.cpu_time = get_thread_cpu_time();
+    results
+}
+
+// Returns how long it took for the window's border to follow it
+fn measure_move_latency(hwnd: HWND, tick: usize) -> Option<time::Duration> {
+    let border_window = get_border_for_window(hwnd)?;
+    let border_rect_before = get_window_rect(border_window)?;
+    let window_rect = get_window_rect(hwnd)?;
+
+    // Bounce back and forth so the windows don't wander off the screen
+    let offset = if (tick / 2).is_multiple_of(2) {
+        40
+    } else {
+        -40
+    };
+    unsafe {
+        SetWindowPos(
+            hwnd,
+            None,
+            window_rect.left + offset,
+            window_rect.top,
+            0,
+            0,
+            SWP_NOSIZE | SWP_NOZORDER | SWP_NOACTIVATE,
+        )
+    }
+    .ok()?;
+
+    let start_time = time::Instant::now();
+    wait_until(|| get_window_rect(border_window) != Some(border_rect_before))?;
+
+    Some(start_time.elapsed())
+}
+
+// Returns how long it took for the engine to notice that the window became active
+fn measure_focus_latency(hwnd: HWND) -> Option<time::Duration> {
+    // This can fail because of Windows' foreground lock, in which case we just count it as missed
+    if !unsafe { SetForegroundWindow(hwnd) }.as_bool() {
+        return None;
+    }
+
+    let start_time = time::Instant::now();
+    wait_until(|| *APP_STATE.active_window.lock().unwrap() == hwnd.0 as isize)?;
+
+    Some(start_time.elapsed())
+}
+
+fn wait_until(condition: impl Fn() -> bool) -> Option<()> {
+    let start_time = time::Instant::now();
+
+    while !condition() {
+        if start_time.elapsed() > time::Duration::from_millis(LATENCY_TIMEOUT_MS) {
+            return None;
+        }
+        thread::sleep(time::Duration::from_millis(POLL_INTERVAL_MS));
+    }
+
+    Some(())
+}
+
+fn get_window_rect(hwnd: HWND) -> Option<RECT> {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect) }.ok()?;
+
+    Some(rect)
+}
+
+fn get_process_cpu_time() -> time::Duration {
+    let mut creation_time = FILETIME::default();
+    let mut exit_time = FILETIME::default();
+    let mut kernel_time = FILETIME::default();
+    let mut user_time = FILETIME::default();
+
+    if let Err(err) = unsafe {
+        GetProcessTimes(
+            GetCurrentProcess(),
+            &mut creation_time,
+            &mut exit_time,
+            &mut kernel_time,
+            &mut user_time,
+        )
+    } {
+        error!("could not get process times: {err}");
+        return time::Duration::ZERO;
+    }
+
+    filetimes_to_duration(kernel_time, user_time)
+}
+
+fn get_thread_cpu_time() -> time::Duration {
+    let mut creation_time = FILETIME::default();
+    let mut exit_time = FILETIME::default();
+    let mut kernel_time = FILETIME::default();
+    let mut user_time = FILETIME::default();
+
+    if let Err(err) = unsafe {
+        GetThreadTimes(
+            GetCurrentThread(),
+            &mut creation_time,
+            &mut exit_time,
+            &mut kernel_time,
+            &mut user_time,
+        )
+    } {
+        error!("could not get thread times: {err}");
+        return time::Duration::ZERO;
+    }
+
+    filetimes_to_duration(kernel_time, user_time)
+}
+
+fn filetimes_to_duration(kernel_time: FILETIME, user_time: FILETIME) -> time::Duration {
+    // FILETIMEs are in 100-nanosecond intervals
+    let filetime_to_u64 =
+        |ft: FILETIME| ((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64;
+    time::Duration::from_nanos((filetime_to_u64(kernel_time) + filetime_to_u64(user_time)) * 100)
+}
+
+fn report(
+    options: &BenchOptions,
+    elapsed: time::Duration,
+    cpu_time: time::Duration,
+    frame_times: &[time::Duration],
+    churn_results: &ChurnResults,
+    private_mb: (Option<f64>, Option<f64>),
+) -> String {
+    let cpu_count = thread::available_parallelism().map_or(1, |count| count.get());
+    let cpu_percent = cpu_time.as_secs_f64() / elapsed.as_secs_f64() / cpu_count as f64 * 100.0;
+
+    let mut results = String::new();
+    let _ = writeln!(
+        results,
+        "{} windows, {:.1}s, theme: {}",
+        options.windows,
+        elapsed.as_secs_f64(),
+        options
+            .theme
+            .as_ref()
+            .map_or("config.yaml".to_string(), |theme| theme
+                .display()
+                .to_string())
+    );
+    let _ = writeln!(
+        results,
+        "cpu: {cpu_percent:.2}% of all cores ({:.2}s of cpu time)",
+        cpu_time.as_secs_f64()
+    );
+    let _ = writeln!(
+        results,
+        "frames: {} ({:.1} per second)",
+        frame_times.len(),
+        frame_times.len() as f64 / elapsed.as_secs_f64()
+    );
+    let _ = writeln!(results, "frame time: {}", format_stats(frame_times));
+    let _ = writeln!(
+        results,
+        "move latency: {} ({} missed, +/- {POLL_INTERVAL_MS}ms)",
+        format_stats(&churn_results.move_latencies),
+        churn_results.missed_moves
+    );
+    let _ = writeln!(
+        results,
+        "focus latency: {} ({} missed, +/- {POLL_INTERVAL_MS}ms)",
+        format_stats(&churn_results.focus_latencies),
+        churn_results.missed_focuses
+    );
+
+    match private_mb {
+        (Some(before), Some(after)) => {
+            let _ = writeln!(
+                results,
+                "private bytes: {before:.1}MB after closing borders, {after:.1}MB after trimming"
+            );
+        }
+        _ => {
+            let _ = writeln!(results, "private bytes: unavailable");
+        }
+    }
+
+    results
+}
+
+fn format_stats(durations: &[time::Duration]) -> String {
+    if durations.is_empty() {
+        return "no samples".to_string();
+    }
+
+    let mut durations = durations.to_vec();
+    durations.sort();
+
+    let percentile = |p: f64| {
+        let index = ((durations.len() - 1) as f64 * p).round() as usize;
+        durations[index].as_secs_f64() * 1000.0
+    };
+    let average =
+        durations.iter().sum::<time::Duration>().as_secs_f64() * 1000.0 / durations.len() as f64;
+
+    format!(
+        "avg {average:.2}ms, p50 {:.2}ms, p95 {:.2}ms, p99 {:.2}ms, max {:.2}ms",
+        percentile(0.5),
+        percentile(0.95),
+        percentile(0.99),
+        percentile(1.0)
+    )
+}
+
+unsafe extern "system" fn bench_wnd_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
+
+// Entry point for the child process spawned by run_bench()
+pub fn run_bench_host(count: u32) -> anyhow::Result<()> {
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+
+        let window_class = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(bench_wnd_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: BENCH_CLASS_NAME_W,
+            ..Default::default()
+        };
+        if RegisterClassExW(&window_class) == 0 {
+            return Err(anyhow!("could not register bench window class"));
+        }
+
+        // Lay the windows out in a loose cascade so they overlap like they would on a busy desktop
+        for i in 0..count {
+            let title: Vec<u16> = format!("tacky-borders bench {i}\0")
+                .encode_utf16()
+                .collect();
+
+            CreateWindowExW(
+                WINDOW_EX_STYLE::default(),
+                BENCH_CLASS_NAME_W,
+                PCWSTR(title.as_ptr()),
+                WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+                80 + (i as i32 % 10) * 60,
+                80 + (i as i32 / 10 % 10) * 60,
+                480,
+                320,
+                None,
+                None,
+                Some(hinstance.into()),
+                None,
+            )?;
+        }
+
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, None, 0, 0).into() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    Ok(())
+}
@@ -1,28 +1,63 @@
-use anyhow::{Context, anyhow};
+use anyhow::Context;
+use std::cell::RefCell;
+use std::f32::consts::PI;
+use std::mem::ManuallyDrop;
 use std::time;
 use windows::Win32::Foundation::{DXGI_STATUS_OCCLUDED, HWND, RECT, S_OK};
 use windows::Win32::Graphics::Direct2D::Common::{
-    D2D_RECT_F, D2D_SIZE_U, D2D1_COLOR_F, D2D1_COMPOSITE_MODE_SOURCE_OVER,
+    D2D_RECT_F, D2D_SIZE_F, D2D_SIZE_U, D2D1_COLOR_F, D2D1_COMPOSITE_MODE_SOURCE_OVER,
+    D2D1_FIGURE_BEGIN_FILLED, D2D1_FIGURE_BEGIN_HOLLOW, D2D1_FIGURE_END_CLOSED,
+    D2D1_FIGURE_END_OPEN, D2D1_FILL_MODE_ALTERNATE,
 };
 use windows::Win32::Graphics::Direct2D::{
-    D2D1_BRUSH_PROPERTIES, D2D1_INTERPOLATION_MODE_LINEAR, D2D1_ROUNDED_RECT, ID2D1Brush,
-    ID2D1RenderTarget,
+    D2D1_ANTIALIAS_MODE_ALIASED, D2D1_ANTIALIAS_MODE_PER_PRIMITIVE, D2D1_ARC_SEGMENT,
+    D2D1_ARC_SIZE_SMALL, D2D1_BRUSH_PROPERTIES, D2D1_CAP_STYLE_FLAT, D2D1_CAP_STYLE_ROUND,
+    D2D1_DASH_STYLE_CUSTOM, D2D1_DASH_STYLE_SOLID, D2D1_DEFAULT_FLATTENING_TOLERANCE,
+    D2D1_INTERPOLATION_MODE_LINEAR, D2D1_LAYER_OPTIONS_NONE, D2D1_LAYER_PARAMETERS,
+    D2D1_LINE_JOIN_MITER, D2D1_ROUNDED_RECT, D2D1_STROKE_STYLE_PROPERTIES,
+    D2D1_SWEEP_DIRECTION_CLOCKWISE, ID2D1Brush, ID2D1Geometry, ID2D1Layer, ID2D1RenderTarget,
+    ID2D1SolidColorBrush, ID2D1StrokeStyle,
 };
 use windows::Win32::Graphics::Dxgi::DXGI_PRESENT;
-use windows_numerics::Matrix3x2;
+use windows_numerics::{Matrix3x2, Vector2};
 
-use crate::animations::{AnimType, Animations};
-use crate::colors::ColorBrush;
+use crate::animations::{AnimType, Animations, is_fade_in_progress};
+use crate::colors::{ColorBrush, ColorInterpolation, GradientBrush};
+use crate::config::{BorderEdge, BorderStyle, NotchAlign};
 use crate::effects::Effects;
 use crate::render_backend::{RenderBackend, RenderBackendConfig};
-use crate::utils::{T_E_UNINIT, ToWindowsResult};
+use crate::utils::{LogIfErr, T_E_UNINIT, ToWindowsResult, snap_stroke_rect};
 use crate::window_border::WindowState;
 
 #[derive(Debug, Default, Clone)]
 pub struct BorderDrawer {
     pub border_width: i32,
-    pub border_offset: i32,
+    pub border_offset: BorderOffset,
+    // In physical pixels. Replaces border_offset for the sides in 'screen_edges'.
+    pub screen_edge_offset: Option<i32>,
+    // The sides of a snapped window that touch its monitor's work area (None if it isn't snapped)
+    pub screen_edges: Option<BorderEdges>,
     pub border_radius: f32,
+    pub border_style: BorderStyle,
+    // Replaces the border style's dashes. In multiples of the border width, like get_dashes().
+    pub dash_pattern: Option<Vec<f32>>,
+    pub edges: BorderEdges,
+    // In physical pixels. Only used by BorderStyle::Brackets.
+    pub bracket_length: f32,
+    // In physical pixels. How far past its outer edge the border fades out to transparent.
+    pub feather_width: f32,
+    // Where the border gets cut away, if anywhere
+    pub notch: Option<Notch>,
+    // In physical pixels. The strip is filled in below the top of the border.
+    pub titlebar_strip_height: Option<f32>,
+    // Whether to line the stroke up with the pixel grid, and whether to draw it without
+    // anti-aliasing when it's 1px wide or thinner
+    pub snap_to_pixels: bool,
+    pub alias_thin_borders: bool,
+    // Fills the window inside the border while it's inactive
+    pub dim_overlay_color: Option<D2D1_COLOR_F>,
+    // The part of the tracking window inside the border, which is what the dim overlay covers
+    pub dim_overlay_rect: D2D1_ROUNDED_RECT,
     // TODO: maybe get rid of render_rect; it would make sense to have the WindowBorder struct
     // calculate the coordinates for the border, and then delegate the rendering here
     pub render_rect: D2D1_ROUNDED_RECT,
@@ -33,6 +68,191 @@ pub struct BorderDrawer {
     pub effects: Effects,
     pub last_render_time: Option<time::Instant>,
     pub last_anim_time: Option<time::Instant>,
+    // Size of the last frame we cleared because there was nothing to draw
+    pub last_blank_size: Option<(i32, i32)>,
+    // The old color that's being faded out after a color swap
+    pub crossfade: Option<ColorCrossfade>,
+    pub outline: Option<Outline>,
+    // Moves and shrinks the border during the open, close, and restore animations
+    pub presence_transform: Option<PresenceTransform>,
+    // Only used for sizes that change while drawing, like the pulse's swell
+    pub dpi: u32,
+    // The render methods only borrow self, so this needs its own mutability
+    pub mask_cache: RefCell<MaskCache>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PresenceTransform {
+    pub scale: f32,
+    // The point the border shrinks towards, as a fraction of its size ((0.5, 0.5) is the center)
+    pub anchor: (f32, f32),
+}
+
+// In physical pixels. Positive values push that side of the border outwards.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct BorderOffset {
+    pub top: i32,
+    pub left: i32,
+    pub right: i32,
+    pub bottom: i32,
+}
+
+impl BorderOffset {
+    pub fn uniform(offset: i32) -> Self {
+        Self {
+            top: offset,
+            left: offset,
+            right: offset,
+            bottom: offset,
+        }
+    }
+
+    // The border window is padded uniformly, so it has to make room for the largest offset
+    pub fn max(&self) -> i32 {
+        self.top.max(self.left).max(self.right).max(self.bottom)
+    }
+
+    pub fn shifted(&self, delta: i32) -> Self {
+        Self {
+            top: self.top + delta,
+            left: self.left + delta,
+            right: self.right + delta,
+            bottom: self.bottom + delta,
+        }
+    }
+}
+
+// Which sides of the border get drawn. When some of them are left out, the border is drawn as
+// separate straight segments, so border_radius doesn't apply.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BorderEdges {
+    pub top: bool,
+    pub left: bool,
+    pub right: bool,
+    pub bottom: bool,
+}
+
+impl Default for BorderEdges {
+    fn default() -> Self {
+        Self {
+            top: true,
+            left: true,
+            right: true,
+            bottom: true,
+        }
+    }
+}
+
+impl BorderEdges {
+    pub fn from_config(edges: Option<&[BorderEdge]>) -> Self {
+        let Some(edges) = edges else {
+            return Self::default();
+        };
+
+        Self {
+            top: edges.contains(&BorderEdge::Top),
+            left: edges.contains(&BorderEdge::Left),
+            right: edges.contains(&BorderEdge::Right),
+            bottom: edges.contains(&BorderEdge::Bottom),
+        }
+    }
+
+    pub fn is_all(&self) -> bool {
+        self.top && self.left && self.right && self.bottom
+    }
+
+    // Picks the side closest to the given point, e.g. to figure out which edge a segment of a
+    // perimeter gradient belongs to
+    fn contains_point(&self, rect: &D2D_RECT_F, point: &Vector2) -> bool {
+        let distances = [
+            (self.top, (point.Y - rect.top).abs()),
+            (self.left, (point.X - rect.left).abs()),
+            (self.right, (rect.right - point.X).abs()),
+            (self.bottom, (rect.bottom - point.Y).abs()),
+        ];
+
+        distances
+            .iter()
+            .min_by(|(_, a), (_, b)| a.total_cmp(b))
+            .is_some_and(|(is_enabled, _)| *is_enabled)
+    }
+}
+
+// Widths are in physical pixels, like border_width
+#[derive(Debug, Clone)]
+pub struct Outline {
+    pub width: i32,
+    pub gap: i32,
+    // Whether to outline the border's inner edge too
+    pub is_inside_too: bool,
+    pub active_color: ColorBrush,
+    pub inactive_color: ColorBrush,
+}
+
+// Lengths are in physical pixels, like border_width
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Notch {
+    pub edge: BorderEdge,
+    pub align: NotchAlign,
+    pub width: f32,
+    pub offset: f32,
+}
+
+// Mixed into the border color for BorderStyle::Raised and Sunken
+const BEVEL_LIGHT: D2D1_COLOR_F = D2D1_COLOR_F {
+    r: 1.0,
+    g: 1.0,
+    b: 1.0,
+    a: 0.4,
+};
+const BEVEL_DARK: D2D1_COLOR_F = D2D1_COLOR_F {
+    r: 0.0,
+    g: 0.0,
+    b: 0.0,
+    a: 0.4,
+};
+
+// How far the notch reaches out past the border. Anything outside of the border is cut away, so
+// it just has to be further than anything we draw.
+const NOTCH_REACH: f32 = 100_000.0;
+
+// Masks get rebuilt whenever the rect they're cut out of or the DPI changes. Their layers and
+// brushes belong to the render backend, so release_renderer() and init_renderer() clear the whole
+// cache.
+#[derive(Debug, Default, Clone)]
+pub struct MaskCache {
+    notch: Option<Cached<D2D_RECT_F, MaskLayer>>,
+    // The top-left half first. The key also has the border width and style, since those decide
+    // how far the halves reach and which one gets which shade.
+    bevel: Option<Cached<BevelKey, [(MaskLayer, ID2D1SolidColorBrush); 2]>>,
+}
+
+type BevelKey = (D2D1_ROUNDED_RECT, i32, BorderStyle);
+
+#[derive(Debug, Clone)]
+struct Cached<K, V> {
+    key: K,
+    dpi: u32,
+    value: V,
+}
+
+impl<K: PartialEq, V> Cached<K, V> {
+    fn is_valid_for(&self, key: &K, dpi: u32) -> bool {
+        self.key == *key && self.dpi == dpi
+    }
+}
+
+#[derive(Debug, Clone)]
+struct MaskLayer {
+    mask: ID2D1Geometry,
+    layer: ID2D1Layer,
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorCrossfade {
+    pub color: ColorBrush,
+    pub start: time::Instant,
+    pub duration: time::Duration,
 }
 
 impl BorderDrawer {
@@ -40,12 +260,13 @@ impl BorderDrawer {
     pub fn configure_appearance(
         &mut self,
         border_width: i32,
-        border_offset: i32,
+        border_offset: BorderOffset,
         border_radius: f32,
         active_color: ColorBrush,
         inactive_color: ColorBrush,
         animations: Animations,
         effects: Effects,
+        outline: Option<Outline>,
     ) {
         self.border_width = border_width;
         self.border_offset = border_offset;
@@ -54,6 +275,7 @@ impl BorderDrawer {
         self.inactive_color = inactive_color;
         self.animations = animations;
         self.effects = effects;
+        self.outline = outline;
     }
 
     pub fn init_renderer(
@@ -61,12 +283,29 @@ impl BorderDrawer {
         width: u32,
         height: u32,
         border_window: HWND,
+        tracking_window: HWND,
         window_rect: &RECT,
         render_backend_config: RenderBackendConfig,
     ) -> anyhow::Result<()> {
+        self.mask_cache = RefCell::default();
         self.render_backend = render_backend_config
-            .to_render_backend(width, height, border_window, self.effects.is_enabled())
+            .to_render_backend(
+                width,
+                height,
+                border_window,
+                tracking_window,
+                self.effects.is_enabled(),
+            )
             .context("could not initialize render backend in init()")?;
+        // The new render target hasn't been cleared yet
+        self.last_blank_size = None;
+        // Any brush we were cross-fading from belongs to the old render target
+        self.crossfade = None;
+
+        // Windows draws native borders itself, so there are no brushes to create
+        if let RenderBackend::NativeDwm(_) = self.render_backend {
+            return Ok(());
+        }
 
         if self.render_backend.supports_effects() {
             self.effects
@@ -74,11 +313,10 @@ impl BorderDrawer {
                 .context("could not initialize command list")?;
         }
 
-        let renderer: &ID2D1RenderTarget = match self.render_backend {
-            RenderBackend::V2(ref backend) => &backend.d2d_context,
-            RenderBackend::Legacy(ref backend) => &backend.render_target,
-            RenderBackend::None => return Err(anyhow!("render backend is None")),
-        };
+        let renderer = self
+            .render_backend
+            .get_renderer()
+            .context("render backend is None")?;
 
         // We will adjust opacity later. For now, we set it to 0.
         let brush_properties = D2D1_BRUSH_PROPERTIES {
@@ -97,13 +335,141 @@ impl BorderDrawer {
         self.inactive_color
             .init_brush(renderer, window_rect, &brush_properties)?;
 
+        if let Some(ref mut outline) = self.outline {
+            outline
+                .active_color
+                .init_brush(renderer, window_rect, &brush_properties)?;
+            outline
+                .inactive_color
+                .init_brush(renderer, window_rect, &brush_properties)?;
+        }
+
         Ok(())
     }
 
+    pub fn set_active_color(
+        &mut self,
+        active_color: ColorBrush,
+        window_rect: &RECT,
+    ) -> anyhow::Result<()> {
+        self.active_color = Self::init_replacement_brush(
+            &self.render_backend,
+            &self.active_color,
+            active_color,
+            window_rect,
+        )?;
+
+        Ok(())
+    }
+
+    pub fn set_inactive_color(
+        &mut self,
+        inactive_color: ColorBrush,
+        window_rect: &RECT,
+    ) -> anyhow::Result<()> {
+        self.inactive_color = Self::init_replacement_brush(
+            &self.render_backend,
+            &self.inactive_color,
+            inactive_color,
+            window_rect,
+        )?;
+
+        Ok(())
+    }
+
+    // Initializes the new brush using the old brush's opacity and transform so that any
+    // in-progress animations carry on seamlessly
+    fn init_replacement_brush(
+        render_backend: &RenderBackend,
+        old_color: &ColorBrush,
+        mut new_color: ColorBrush,
+        window_rect: &RECT,
+    ) -> anyhow::Result<ColorBrush> {
+        // Without a renderer (e.g. while dormant), init_renderer() creates the brush later on
+        if let RenderBackend::NativeDwm(_) | RenderBackend::None = render_backend {
+            return Ok(new_color);
+        }
+
+        let renderer = render_backend
+            .get_renderer()
+            .context("render backend is None")?;
+
+        let brush_properties = D2D1_BRUSH_PROPERTIES {
+            opacity: old_color.get_opacity().unwrap_or_default(),
+            transform: old_color
+                .get_transform()
+                .unwrap_or_else(Matrix3x2::identity),
+        };
+
+        new_color.init_brush(renderer, window_rect, &brush_properties)?;
+
+        Ok(new_color)
+    }
+
+    // Sides that touch the monitor's work area get screen_edge_offset instead of border_offset
+    pub fn get_border_offset(&self) -> BorderOffset {
+        let (Some(offset), Some(edges)) = (self.screen_edge_offset, self.screen_edges) else {
+            return self.border_offset;
+        };
+        let pick = |is_screen_edge: bool, border_offset: i32| match is_screen_edge {
+            true => offset,
+            false => border_offset,
+        };
+
+        BorderOffset {
+            top: pick(edges.top, self.border_offset.top),
+            left: pick(edges.left, self.border_offset.left),
+            right: pick(edges.right, self.border_offset.right),
+            bottom: pick(edges.bottom, self.border_offset.bottom),
+        }
+    }
+
+    // Keeps drawing 'old_color' on top of the current colors while fading it out
+    pub fn start_crossfade(&mut self, old_color: ColorBrush, duration: time::Duration) {
+        self.crossfade = Some(ColorCrossfade {
+            color: old_color,
+            start: time::Instant::now(),
+            duration,
+        });
+    }
+
+    // Returns false once the crossfade is finished
+    pub fn update_crossfade(&mut self) -> bool {
+        let Some(ref crossfade) = self.crossfade else {
+            return false;
+        };
+
+        let progress = crossfade.start.elapsed().as_secs_f32() / crossfade.duration.as_secs_f32();
+        if progress >= 1.0 {
+            self.crossfade = None;
+            return false;
+        }
+
+        crossfade.color.set_opacity(1.0 - progress).log_if_err();
+        true
+    }
+
+    // Drops everything that belongs to the render backend, so that it can be released. Call
+    // init_renderer() to get it all back.
+    pub fn release_renderer(&mut self) {
+        self.active_color.release_brush();
+        self.inactive_color.release_brush();
+        if let Some(ref mut outline) = self.outline {
+            outline.active_color.release_brush();
+            outline.inactive_color.release_brush();
+        }
+        self.effects.release_command_lists();
+        self.crossfade = None;
+        self.last_blank_size = None;
+        self.mask_cache = RefCell::default();
+        self.render_backend = RenderBackend::None;
+    }
+
     pub fn update_renderer_size(&mut self, width: u32, height: u32) -> anyhow::Result<()> {
         self.render_backend
             .update(width, height, self.effects.is_enabled())
             .context("could not update render resources")?;
+        self.last_blank_size = None;
 
         if self.render_backend.supports_effects() {
             self.effects
@@ -122,26 +488,65 @@ impl BorderDrawer {
     ) -> windows::core::Result<()> {
         self.last_render_time = Some(time::Instant::now());
 
+        if let RenderBackend::NativeDwm(ref backend) = self.render_backend {
+            let color = match window_state {
+                WindowState::Active => &self.active_color,
+                WindowState::Inactive => &self.inactive_color,
+            };
+            return backend.set_border_color(color);
+        }
+
+        // If there's nothing to draw and we've already cleared the border at this size, we can
+        // skip the whole render pass
+        let window_size = (
+            window_rect.right - window_rect.left,
+            window_rect.bottom - window_rect.top,
+        );
+        let is_blank = self.is_blank(window_state)?;
+        if is_blank && self.last_blank_size == Some(window_size) {
+            return Ok(());
+        }
+        self.last_blank_size = None;
+
         let border_width = self.border_width as f32;
-        let border_offset = self.border_offset as f32;
+        let border_offset = self.get_border_offset();
         let window_padding = window_padding as f32;
 
         self.render_rect = D2D1_ROUNDED_RECT {
             rect: D2D_RECT_F {
-                left: border_width / 2.0 + window_padding - border_offset,
-                top: border_width / 2.0 + window_padding - border_offset,
+                left: border_width / 2.0 + window_padding - border_offset.left as f32,
+                top: border_width / 2.0 + window_padding - border_offset.top as f32,
                 right: (window_rect.right - window_rect.left) as f32
                     - border_width / 2.0
                     - window_padding
-                    + border_offset,
+                    + border_offset.right as f32,
                 bottom: (window_rect.bottom - window_rect.top) as f32
                     - border_width / 2.0
                     - window_padding
-                    + border_offset,
+                    + border_offset.bottom as f32,
             },
             radiusX: self.border_radius,
             radiusY: self.border_radius,
         };
+        if self.snap_to_pixels {
+            self.render_rect.rect = snap_stroke_rect(&self.render_rect.rect, border_width);
+        }
+        if let Some(transform) = self.presence_transform {
+            let rect = self.render_rect.rect;
+            let anchor_x = rect.left + (rect.right - rect.left) * transform.anchor.0;
+            let anchor_y = rect.top + (rect.bottom - rect.top) * transform.anchor.1;
+            let scale = |value: f32, anchor: f32| anchor + (value - anchor) * transform.scale;
+
+            self.render_rect.rect = D2D_RECT_F {
+                left: scale(rect.left, anchor_x),
+                top: scale(rect.top, anchor_y),
+                right: scale(rect.right, anchor_x),
+                bottom: scale(rect.bottom, anchor_y),
+            };
+        }
+        if self.dim_overlay_color.is_some() {
+            self.dim_overlay_rect = self.get_dim_overlay_rect(window_rect, window_padding);
+        }
 
         // Note that Rust's borrow checker prevents passing the render backend from the match arm,
         // so I'll need to grab it from within the respective functions instead
@@ -151,6 +556,7 @@ impl BorderDrawer {
             }
             RenderBackend::V2(_) => self.render_v2(window_rect, window_state)?,
             RenderBackend::Legacy(_) => self.render_legacy(window_rect, window_state)?,
+            RenderBackend::NativeDwm(_) => {} // Already handled above
             RenderBackend::None => {
                 return Err(windows::core::Error::new(
                     T_E_UNINIT,
@@ -159,9 +565,29 @@ impl BorderDrawer {
             }
         }
 
+        if is_blank {
+            self.last_blank_size = Some(window_size);
+        }
+
         Ok(())
     }
 
+    // Returns true if the color for the current state is "none" and no other color is still
+    // fading in or out
+    fn is_blank(&self, window_state: WindowState) -> windows::core::Result<bool> {
+        let (bottom_color, top_color) = match window_state {
+            WindowState::Active => (&self.inactive_color, &self.active_color),
+            WindowState::Inactive => (&self.active_color, &self.inactive_color),
+        };
+
+        if !top_color.is_none() || self.crossfade.is_some() || self.dim_overlay_color.is_some() {
+            return Ok(false);
+        }
+
+        Ok(bottom_color.is_none()
+            || bottom_color.get_opacity().to_windows_result(T_E_UNINIT)? <= 0.0)
+    }
+
     fn render_legacy(
         &mut self,
         window_rect: &RECT,
@@ -180,37 +606,44 @@ impl BorderDrawer {
             height: (window_rect.bottom - window_rect.top) as u32,
         };
 
+        let stroke_style = self.create_stroke_style(render_target)?;
+
         unsafe {
             render_target.Resize(&pixel_size)?;
 
-            // Determine which color should be drawn on top (for color fade animation)
-            let (bottom_color, top_color) = match window_state {
-                WindowState::Active => (&self.inactive_color, &self.active_color),
-                WindowState::Inactive => (&self.active_color, &self.inactive_color),
-            };
-
             render_target.BeginDraw();
             render_target.Clear(None);
 
-            if bottom_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = bottom_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match bottom_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
-                    None => debug!("ID2D1Brush for bottom_color has not been created yet"),
-                }
-            }
-            if top_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = top_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match top_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(render_target, id2d1_brush),
-                    None => debug!("ID2D1Brush for top_color has not been created yet"),
-                }
+            self.fill_dim_overlay(render_target, window_state)?;
+            let has_notch_layer = self.push_notch_layer(render_target)?;
+            self.draw_colors(
+                render_target,
+                window_rect,
+                window_state,
+                stroke_style.as_ref(),
+                |id2d1_brush, is_stroke_drawn| {
+                    if !is_stroke_drawn {
+                        self.draw_rectangle(
+                            render_target,
+                            &self.render_rect,
+                            self.border_width as f32,
+                            id2d1_brush,
+                            stroke_style.as_ref(),
+                        );
+                    }
+                    if let Err(err) =
+                        self.draw_bevel(render_target, id2d1_brush, stroke_style.as_ref())
+                    {
+                        error!("could not draw bevel: {err}");
+                    }
+                    self.draw_feather(render_target, id2d1_brush, stroke_style.as_ref());
+                    self.fill_titlebar_strip(render_target, id2d1_brush);
+                },
+            )?;
+            self.draw_pulse(render_target, window_rect)?;
+            self.draw_outline(render_target, window_rect, window_state)?;
+            if has_notch_layer {
+                render_target.PopLayer();
             }
 
             render_target.EndDraw(None, None)?;
@@ -231,36 +664,42 @@ impl BorderDrawer {
             ));
         };
         let d2d_context = &backend.d2d_context;
+        let stroke_style = self.create_stroke_style(d2d_context)?;
 
         unsafe {
-            // Determine which color should be drawn on top (for color fade animation)
-            let (bottom_color, top_color) = match window_state {
-                WindowState::Active => (&self.inactive_color, &self.active_color),
-                WindowState::Inactive => (&self.active_color, &self.inactive_color),
-            };
-
             d2d_context.BeginDraw();
             d2d_context.Clear(None);
 
-            if bottom_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = bottom_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match bottom_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(d2d_context, id2d1_brush),
-                    None => debug!("ID2D1Brush for bottom_color has not been created yet"),
-                }
-            }
-            if top_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = top_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match top_color.get_brush() {
-                    Some(id2d1_brush) => self.draw_rectangle(d2d_context, id2d1_brush),
-                    None => debug!("ID2D1Brush for top_color has not been created yet"),
-                }
+            self.fill_dim_overlay(d2d_context, window_state)?;
+            let has_notch_layer = self.push_notch_layer(d2d_context)?;
+            self.draw_colors(
+                d2d_context,
+                window_rect,
+                window_state,
+                stroke_style.as_ref(),
+                |id2d1_brush, is_stroke_drawn| {
+                    if !is_stroke_drawn {
+                        self.draw_rectangle(
+                            d2d_context,
+                            &self.render_rect,
+                            self.border_width as f32,
+                            id2d1_brush,
+                            stroke_style.as_ref(),
+                        );
+                    }
+                    if let Err(err) =
+                        self.draw_bevel(d2d_context, id2d1_brush, stroke_style.as_ref())
+                    {
+                        error!("could not draw bevel: {err}");
+                    }
+                    self.draw_feather(d2d_context, id2d1_brush, stroke_style.as_ref());
+                    self.fill_titlebar_strip(d2d_context, id2d1_brush);
+                },
+            )?;
+            self.draw_pulse(d2d_context, window_rect)?;
+            self.draw_outline(d2d_context, window_rect, window_state)?;
+            if has_notch_layer {
+                d2d_context.PopLayer();
             }
 
             d2d_context.EndDraw(None, None)?;
@@ -291,14 +730,9 @@ impl BorderDrawer {
             ));
         };
         let d2d_context = &backend.d2d_context;
+        let stroke_style = self.create_stroke_style(d2d_context)?;
 
         unsafe {
-            // Determine which color should be drawn on top (for color fade animation)
-            let (bottom_color, top_color) = match window_state {
-                WindowState::Active => (&self.inactive_color, &self.active_color),
-                WindowState::Inactive => (&self.active_color, &self.inactive_color),
-            };
-
             // Create a rect that covers up to the outer edge of the border
             let border_width = self.border_width as f32;
             let render_rect_adjusted = D2D1_ROUNDED_RECT {
@@ -326,30 +760,49 @@ impl BorderDrawer {
 
             // We use filled rectangles here because it helps make the effects more visible.
             // Additionally, if someone sets the border width to 0, the effects will still be
-            // visible (whereas they wouldn't be if we used a hollow rectangle).
-            if bottom_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = bottom_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match bottom_color.get_brush() {
-                    Some(id2d1_brush) => {
-                        self.fill_rectangle(&render_rect_adjusted, d2d_context, id2d1_brush)
+            // visible (whereas they wouldn't be if we used a hollow rectangle). Dashes and partial
+            // edges need the actual stroke though.
+            let has_notch_layer = self.push_notch_layer(d2d_context)?;
+            self.draw_colors(
+                d2d_context,
+                window_rect,
+                window_state,
+                stroke_style.as_ref(),
+                |id2d1_brush, is_stroke_drawn| {
+                    match stroke_style {
+                        _ if is_stroke_drawn => {}
+                        Some(ref stroke_style) => self.draw_rectangle(
+                            d2d_context,
+                            &self.render_rect,
+                            border_width,
+                            id2d1_brush,
+                            Some(stroke_style),
+                        ),
+                        None if !self.is_full_rectangle() => self.draw_rectangle(
+                            d2d_context,
+                            &self.render_rect,
+                            border_width,
+                            id2d1_brush,
+                            None,
+                        ),
+                        None => {
+                            self.fill_rectangle(&render_rect_adjusted, d2d_context, id2d1_brush)
+                        }
                     }
-                    None => debug!("ID2D1Brush for bottom_color has not been created yet"),
-                }
-            }
-            if top_color.get_opacity().to_windows_result(T_E_UNINIT)? > 0.0 {
-                if let ColorBrush::Gradient(gradient) = top_color {
-                    gradient.update_start_end_points(window_rect);
-                }
-
-                match top_color.get_brush() {
-                    Some(id2d1_brush) => {
-                        self.fill_rectangle(&render_rect_adjusted, d2d_context, id2d1_brush)
+                    if let Err(err) =
+                        self.draw_bevel(d2d_context, id2d1_brush, stroke_style.as_ref())
+                    {
+                        error!("could not draw bevel: {err}");
                     }
-                    None => debug!("ID2D1Brush for top_color has not been created yet"),
-                }
+                    self.draw_feather(d2d_context, id2d1_brush, stroke_style.as_ref());
+                    self.fill_titlebar_strip(d2d_context, id2d1_brush);
+                },
+            )?;
+            self.draw_pulse(d2d_context, window_rect)?;
+            // The outline is outside of the mask below, so it gets the effects too
+            self.draw_outline(d2d_context, window_rect, window_state)?;
+            if has_notch_layer {
+                d2d_context.PopLayer();
             }
 
             d2d_context.EndDraw(None, None)?;
@@ -392,6 +845,13 @@ impl BorderDrawer {
 
             self.fill_rectangle(&render_rect_adjusted, d2d_context, &opaque_brush);
 
+            // Punch the titlebar strip back out of the mask so the effects don't hide it
+            if let Some(strip_rect) = self.get_titlebar_strip_rect() {
+                d2d_context.PushAxisAlignedClip(&strip_rect, D2D1_ANTIALIAS_MODE_ALIASED);
+                d2d_context.Clear(None);
+                d2d_context.PopAxisAlignedClip();
+            }
+
             d2d_context.EndDraw(None, None)?;
 
             // Set d2d_context's target back to the target_bitmap so we can draw to the display
@@ -412,6 +872,8 @@ impl BorderDrawer {
             d2d_context.BeginDraw();
             d2d_context.Clear(None);
 
+            // The overlay goes on after the effects, since the mask would otherwise cut it out
+            self.fill_dim_overlay(d2d_context, window_state)?;
             d2d_context.DrawImage(
                 command_list,
                 None,
@@ -436,22 +898,934 @@ impl BorderDrawer {
         Ok(())
     }
 
+    // Draws the bottom color, then the top color (for the color fade animation). If the fade
+    // animation interpolates in a color space other than RGB, we instead draw a single blended
+    // color while the fade is in progress.
+    fn draw_colors(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        window_rect: &RECT,
+        window_state: WindowState,
+        stroke_style: Option<&ID2D1StrokeStyle>,
+        draw_fn: impl Fn(&ID2D1Brush, bool),
+    ) -> windows::core::Result<()> {
+        let stroke = (&self.render_rect, self.border_width as f32, stroke_style);
+
+        if let Some(blended_color) = self.get_blended_fade_color(window_state)? {
+            let id2d1_brush = unsafe { renderer.CreateSolidColorBrush(&blended_color, None)? };
+            draw_fn((&id2d1_brush).into(), false);
+        } else {
+            // Determine which color should be drawn on top (for color fade animation)
+            let (bottom_color, top_color) = match window_state {
+                WindowState::Active => (&self.inactive_color, &self.active_color),
+                WindowState::Inactive => (&self.active_color, &self.inactive_color),
+            };
+
+            for (color, color_name) in [(bottom_color, "bottom_color"), (top_color, "top_color")] {
+                self.draw_color(renderer, window_rect, color, color_name, stroke, &draw_fn)?;
+            }
+        }
+
+        // The old color goes on top of everything so that it fades out into the new one
+        if let Some(ref crossfade) = self.crossfade {
+            self.draw_color(
+                renderer,
+                window_rect,
+                &crossfade.color,
+                "crossfade_color",
+                stroke,
+                &draw_fn,
+            )?;
+        }
+
+        Ok(())
+    }
+
+    fn draw_color(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        window_rect: &RECT,
+        color: &ColorBrush,
+        color_name: &str,
+        // Where the stroke goes and how it's dashed, which perimeter gradients need to know to draw
+        // themselves
+        (render_rect, width, stroke_style): (&D2D1_ROUNDED_RECT, f32, Option<&ID2D1StrokeStyle>),
+        // Also gets whether the stroke was already drawn, in which case only the extras that go
+        // with it (like the bevel and feathering) are left
+        draw_fn: &impl Fn(&ID2D1Brush, bool),
+    ) -> windows::core::Result<()> {
+        let opacity = color.get_opacity().to_windows_result(T_E_UNINIT)?;
+        if opacity <= 0.0 {
+            return Ok(());
+        }
+
+        let mut is_stroke_drawn = false;
+        if let ColorBrush::Gradient(gradient) = color {
+            if gradient.is_perimeter() {
+                self.draw_perimeter_gradient(
+                    renderer,
+                    gradient,
+                    opacity,
+                    (render_rect, width, stroke_style),
+                )?;
+                is_stroke_drawn = true;
+            }
+
+            // The extras use the gradient's regular brush, since they aren't drawn along the
+            // perimeter
+            gradient.update_start_end_points(window_rect);
+        }
+
+        match color.get_brush() {
+            Some(id2d1_brush) => draw_fn(id2d1_brush, is_stroke_drawn),
+            None => debug!("ID2D1Brush for {color_name} has not been created yet"),
+        }
+
+        Ok(())
+    }
+
+    // Linear gradient brushes are axis-aligned, so to make the colors follow the perimeter, we split
+    // the border into small segments and draw each one with its own solid color. The segments get
+    // round caps so that neighbors overlap without seams, and they're clipped to the actual stroke
+    // (dashes included) with a layer.
+    fn draw_perimeter_gradient(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        gradient: &GradientBrush,
+        opacity: f32,
+        (render_rect, width, stroke_style): (&D2D1_ROUNDED_RECT, f32, Option<&ID2D1StrokeStyle>),
+    ) -> windows::core::Result<()> {
+        let points = get_perimeter_points(&render_rect.rect, render_rect.radiusX);
+        let Some(&(_, perimeter_len)) = points.last() else {
+            return Ok(());
+        };
+
+        let segment_style_properties = D2D1_STROKE_STYLE_PROPERTIES {
+            startCap: D2D1_CAP_STYLE_ROUND,
+            endCap: D2D1_CAP_STYLE_ROUND,
+            dashCap: D2D1_CAP_STYLE_ROUND,
+            lineJoin: D2D1_LINE_JOIN_MITER,
+            miterLimit: 10.0,
+            dashStyle: D2D1_DASH_STYLE_SOLID,
+            dashOffset: 0.0,
+        };
+
+        let (id2d1_brush, segment_style, stroke_mask) = unsafe {
+            let factory = renderer.GetFactory()?;
+
+            let stroke_mask = factory.CreatePathGeometry()?;
+            let sink = stroke_mask.Open()?;
+            factory.CreateRoundedRectangleGeometry(render_rect)?.Widen(
+                width,
+                stroke_style,
+                None,
+                D2D1_DEFAULT_FLATTENING_TOLERANCE,
+                &sink,
+            )?;
+            sink.Close()?;
+
+            (
+                renderer.CreateSolidColorBrush(&D2D1_COLOR_F::default(), None)?,
+                factory.CreateStrokeStyle(&segment_style_properties, None)?,
+                stroke_mask,
+            )
+        };
+
+        // The segments overlap, so the opacity goes on the layer rather than on the brush
+        unsafe { push_layer(renderer, &stroke_mask.into(), None, opacity) };
+
+        for window in points.windows(2) {
+            let [(start, start_len), (end, end_len)] = window else {
+                continue;
+            };
+
+            let midpoint = (*start + *end) / 2.0;
+            let is_visible = match self.border_style {
+                BorderStyle::Brackets => self.is_in_bracket(&render_rect.rect, &midpoint),
+                _ => self.edges.contains_point(&render_rect.rect, &midpoint),
+            };
+            if !is_visible {
+                continue;
+            }
+
+            // Use the color at the middle of the segment
+            let t = (start_len + end_len) / 2.0 / perimeter_len;
+            unsafe {
+                id2d1_brush.SetColor(&gradient.sample_perimeter_color(t));
+                renderer.DrawLine(*start, *end, &id2d1_brush, width, &segment_style);
+            }
+        }
+
+        unsafe { renderer.PopLayer() };
+
+        Ok(())
+    }
+
+    fn get_blended_fade_color(
+        &self,
+        window_state: WindowState,
+    ) -> windows::core::Result<Option<D2D1_COLOR_F>> {
+        let interpolation = self.animations.get_fade_interpolation(window_state);
+        if interpolation == ColorInterpolation::Rgb {
+            return Ok(None);
+        }
+
+        // TODO: gradients still cross-fade in RGB; blending them would require recreating the
+        // gradient stop collection on every frame
+        let (Some(active_color), Some(inactive_color)) = (
+            self.active_color.get_solid_color(),
+            self.inactive_color.get_solid_color(),
+        ) else {
+            return Ok(None);
+        };
+
+        let active_opacity = self
+            .active_color
+            .get_opacity()
+            .to_windows_result(T_E_UNINIT)?;
+        let inactive_opacity = self
+            .inactive_color
+            .get_opacity()
+            .to_windows_result(T_E_UNINIT)?;
+
+        // Only blend while the fade is actually in progress
+        if active_opacity <= 0.0 || inactive_opacity <= 0.0 {
+            return Ok(None);
+        }
+
+        Ok(Some(interpolation.interpolate(
+            &inactive_color,
+            &active_color,
+            active_opacity,
+        )))
+    }
+
+    // The focus pulse is a wider, translucent copy of the active color's stroke on top of the
+    // border, so it brightens the border while it swells out past it
+    fn draw_pulse(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        window_rect: &RECT,
+    ) -> windows::core::Result<()> {
+        let Some((expand, intensity)) = self.animations.get_pulse() else {
+            return Ok(());
+        };
+        if intensity <= 0.0 {
+            return Ok(());
+        }
+
+        // The pulse only swells outwards, so the inner edge of the stroke stays where it is
+        let expand = expand * self.dpi as f32 / 96.0;
+        let width = self.border_width as f32 + expand;
+        let radius = match self.border_radius {
+            0.0 => 0.0,
+            _ => self.border_radius + expand / 2.0,
+        };
+        let pulse_rect = D2D1_ROUNDED_RECT {
+            rect: D2D_RECT_F {
+                left: self.render_rect.rect.left - expand / 2.0,
+                top: self.render_rect.rect.top - expand / 2.0,
+                right: self.render_rect.rect.right + expand / 2.0,
+                bottom: self.render_rect.rect.bottom + expand / 2.0,
+            },
+            radiusX: radius,
+            radiusY: radius,
+        };
+
+        let opacity = self
+            .active_color
+            .get_opacity()
+            .to_windows_result(T_E_UNINIT)?;
+        self.active_color
+            .set_opacity(opacity * intensity * 0.5)
+            .to_windows_result(T_E_UNINIT)?;
+
+        let draw_res = self.draw_color(
+            renderer,
+            window_rect,
+            &self.active_color,
+            "pulse_color",
+            (&pulse_rect, width, None),
+            &|id2d1_brush, is_stroke_drawn| {
+                if !is_stroke_drawn {
+                    self.draw_rectangle(renderer, &pulse_rect, width, id2d1_brush, None)
+                }
+            },
+        );
+
+        // Put the fade's opacity back before anything else reads it
+        self.active_color
+            .set_opacity(opacity)
+            .to_windows_result(T_E_UNINIT)?;
+
+        draw_res
+    }
+
+    // Draws the outline's colors with the same opacities and transforms as the border's colors, so
+    // that it fades and animates right along with the border
+    fn draw_outline(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        window_rect: &RECT,
+        window_state: WindowState,
+    ) -> windows::core::Result<()> {
+        let Some(ref outline) = self.outline else {
+            return Ok(());
+        };
+
+        let outline_width = outline.width as f32;
+        let outline_rects: Vec<_> = [false, true]
+            .into_iter()
+            .filter(|is_inside| !is_inside || outline.is_inside_too)
+            .filter_map(|is_inside| self.get_outline_rect(outline, is_inside))
+            .collect();
+
+        let color_pairs = match window_state {
+            WindowState::Active => [
+                (&outline.inactive_color, &self.inactive_color),
+                (&outline.active_color, &self.active_color),
+            ],
+            WindowState::Inactive => [
+                (&outline.active_color, &self.active_color),
+                (&outline.inactive_color, &self.inactive_color),
+            ],
+        };
+
+        for (outline_color, border_color) in color_pairs {
+            let opacity = border_color.get_opacity().to_windows_result(T_E_UNINIT)?;
+            outline_color
+                .set_opacity(opacity)
+                .to_windows_result(T_E_UNINIT)?;
+            if let Some(transform) = border_color.get_transform() {
+                outline_color.set_transform(&transform);
+            }
+
+            for outline_rect in outline_rects.iter() {
+                self.draw_color(
+                    renderer,
+                    window_rect,
+                    outline_color,
+                    "outline_color",
+                    (outline_rect, outline_width, None),
+                    &|id2d1_brush, is_stroke_drawn| {
+                        if !is_stroke_drawn {
+                            self.draw_rectangle(
+                                renderer,
+                                outline_rect,
+                                outline_width,
+                                id2d1_brush,
+                                None,
+                            )
+                        }
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // The outline is centered on a rect that's pushed out past the border's outer edge and the gap
+    // (or pulled in past its inner edge). Returns None if the inner rect would have nothing left.
+    fn get_outline_rect(&self, outline: &Outline, is_inside: bool) -> Option<D2D1_ROUNDED_RECT> {
+        let distance =
+            self.border_width as f32 / 2.0 + outline.gap as f32 + outline.width as f32 / 2.0;
+        let expand = match is_inside {
+            true => -distance,
+            false => distance,
+        };
+        let radius = match self.border_radius {
+            0.0 => 0.0,
+            _ => (self.border_radius + expand).max(0.0),
+        };
+
+        let rect = &self.render_rect.rect;
+        if rect.right - rect.left + expand * 2.0 <= 0.0
+            || rect.bottom - rect.top + expand * 2.0 <= 0.0
+        {
+            return None;
+        }
+
+        Some(D2D1_ROUNDED_RECT {
+            rect: D2D_RECT_F {
+                left: self.render_rect.rect.left - expand,
+                top: self.render_rect.rect.top - expand,
+                right: self.render_rect.rect.right + expand,
+                bottom: self.render_rect.rect.bottom + expand,
+            },
+            radiusX: radius,
+            radiusY: radius,
+        })
+    }
+
+    // Returns None for solid borders. Marching ants on a solid border use the Dashed pattern, and a
+    // custom dash_pattern replaces the dashes of every style except Brackets.
+    // NOTE: perimeter gradients are drawn in small segments, so they're always solid.
+    fn create_stroke_style(
+        &self,
+        renderer: &ID2D1RenderTarget,
+    ) -> windows::core::Result<Option<ID2D1StrokeStyle>> {
+        let dash_progress = self.animations.get_dash_progress();
+        let border_style = match (self.border_style, dash_progress) {
+            (BorderStyle::Solid, Some(_)) => BorderStyle::Dashed,
+            (border_style, _) => border_style,
+        };
+        let custom_dashes = match border_style {
+            BorderStyle::Brackets => None,
+            _ => self.dash_pattern.as_deref(),
+        };
+        let Some(dashes) = custom_dashes.or(border_style.get_dashes()) else {
+            return Ok(None);
+        };
+
+        let pattern_len: f32 = dashes.iter().sum();
+        let stroke_style_properties = D2D1_STROKE_STYLE_PROPERTIES {
+            startCap: D2D1_CAP_STYLE_FLAT,
+            endCap: D2D1_CAP_STYLE_FLAT,
+            dashCap: match border_style {
+                BorderStyle::Dotted => D2D1_CAP_STYLE_ROUND,
+                _ => D2D1_CAP_STYLE_FLAT,
+            },
+            lineJoin: D2D1_LINE_JOIN_MITER,
+            miterLimit: 10.0,
+            dashStyle: D2D1_DASH_STYLE_CUSTOM,
+            // A negative offset moves the dashes clockwise
+            dashOffset: -dash_progress.unwrap_or(0.0) * pattern_len,
+        };
+
+        unsafe {
+            let factory = renderer.GetFactory()?;
+            factory
+                .CreateStrokeStyle(&stroke_style_properties, Some(dashes))
+                .map(Some)
+        }
+    }
+
     // NOTE: ID2D1DeviceContext implements From<&ID2D1DeviceContext> for &ID2D1RenderTarget
-    fn draw_rectangle(&self, renderer: &ID2D1RenderTarget, brush: &ID2D1Brush) {
+    fn draw_rectangle(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        render_rect: &D2D1_ROUNDED_RECT,
+        width: f32,
+        brush: &ID2D1Brush,
+        stroke_style: Option<&ID2D1StrokeStyle>,
+    ) {
+        // Anti-aliasing a 1px line that's already on the pixel grid only ever makes it softer
+        let is_aliased = self.alias_thin_borders && width <= 1.0;
+        if is_aliased {
+            unsafe { renderer.SetAntialiasMode(D2D1_ANTIALIAS_MODE_ALIASED) };
+        }
+
+        if self.border_style == BorderStyle::Brackets {
+            if let Err(err) = self.draw_brackets(renderer, render_rect, width, brush) {
+                error!("could not draw corner brackets: {err}");
+            }
+        } else if !self.edges.is_all() {
+            self.draw_edges(renderer, &render_rect.rect, width, brush, stroke_style);
+        } else {
+            unsafe {
+                match render_rect.radiusX {
+                    0.0 => renderer.DrawRectangle(&render_rect.rect, brush, width, stroke_style),
+                    _ => renderer.DrawRoundedRectangle(render_rect, brush, width, stroke_style),
+                }
+            }
+        }
+
+        if is_aliased {
+            unsafe { renderer.SetAntialiasMode(D2D1_ANTIALIAS_MODE_PER_PRIMITIVE) };
+        }
+    }
+
+    // Clips the notch out of everything drawn until the layer gets popped. Returns whether there
+    // was a notch to push a layer for.
+    fn push_notch_layer(&self, renderer: &ID2D1RenderTarget) -> windows::core::Result<bool> {
+        let Some(notch_rect) = self.get_notch_rect() else {
+            return Ok(false);
+        };
+
+        let mut mask_cache = self.mask_cache.borrow_mut();
+        if !mask_cache
+            .notch
+            .as_ref()
+            .is_some_and(|cached| cached.is_valid_for(&notch_rect, self.dpi))
+        {
+            unsafe {
+                // With the alternate fill mode, the notch punches a hole into the rect around it
+                let factory = renderer.GetFactory()?;
+                let everything_rect = D2D_RECT_F {
+                    left: -NOTCH_REACH * 2.0,
+                    top: -NOTCH_REACH * 2.0,
+                    right: NOTCH_REACH * 2.0,
+                    bottom: NOTCH_REACH * 2.0,
+                };
+                let geometries = [
+                    Some(factory.CreateRectangleGeometry(&everything_rect)?.into()),
+                    Some(factory.CreateRectangleGeometry(&notch_rect)?.into()),
+                ];
+                let mask = factory.CreateGeometryGroup(D2D1_FILL_MODE_ALTERNATE, &geometries)?;
+
+                mask_cache.notch = Some(Cached {
+                    key: notch_rect,
+                    dpi: self.dpi,
+                    value: MaskLayer {
+                        mask: mask.into(),
+                        layer: renderer.CreateLayer(None)?,
+                    },
+                });
+            }
+        }
+
+        if let Some(ref cached) = mask_cache.notch {
+            unsafe { push_mask_layer(renderer, &cached.value) };
+        }
+
+        Ok(true)
+    }
+
+    // Raised and Sunken borders get a lighter top-left half and a darker bottom-right half, split
+    // diagonally through the top-right and bottom-left corners like the miters of a picture frame.
+    // We shade the stroke that's already there with translucent white and black, so this works
+    // with any color (except perimeter gradients, which are drawn separately).
+    fn draw_bevel(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        brush: &ID2D1Brush,
+        stroke_style: Option<&ID2D1StrokeStyle>,
+    ) -> windows::core::Result<()> {
+        let (top_left_shade, bottom_right_shade) = match self.border_style {
+            BorderStyle::Raised => (BEVEL_LIGHT, BEVEL_DARK),
+            BorderStyle::Sunken => (BEVEL_DARK, BEVEL_LIGHT),
+            _ => return Ok(()),
+        };
+
+        let key = (self.render_rect, self.border_width, self.border_style);
+        let mut mask_cache = self.mask_cache.borrow_mut();
+        if !mask_cache
+            .bevel
+            .as_ref()
+            .is_some_and(|cached| cached.is_valid_for(&key, self.dpi))
+        {
+            mask_cache.bevel = Some(Cached {
+                key,
+                dpi: self.dpi,
+                value: [
+                    self.create_bevel_half(renderer, top_left_shade, true)?,
+                    self.create_bevel_half(renderer, bottom_right_shade, false)?,
+                ],
+            });
+        }
+
+        if let Some(ref cached) = mask_cache.bevel {
+            unsafe {
+                for (mask_layer, shade_brush) in &cached.value {
+                    shade_brush.SetOpacity(brush.GetOpacity());
+                    push_mask_layer(renderer, mask_layer);
+                    self.draw_rectangle(
+                        renderer,
+                        &self.render_rect,
+                        self.border_width as f32,
+                        shade_brush.into(),
+                        stroke_style,
+                    );
+                    renderer.PopLayer();
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn create_bevel_half(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        shade: D2D1_COLOR_F,
+        is_top_left: bool,
+    ) -> windows::core::Result<(MaskLayer, ID2D1SolidColorBrush)> {
+        let rect = &self.render_rect.rect;
+        // How far the halves reach past the stroke, and how far in the miters go before they'd
+        // cross each other
+        let outer = self.border_width as f32 + self.render_rect.radiusX + 1.0;
+        let inner = outer
+            .min((rect.right - rect.left) / 2.0)
+            .min((rect.bottom - rect.top) / 2.0);
+
+        let far_corner = match is_top_left {
+            true => Vector2::new(rect.left - outer, rect.top - outer),
+            false => Vector2::new(rect.right + outer, rect.bottom + outer),
+        };
+
         unsafe {
-            match self.border_radius {
-                0.0 => renderer.DrawRectangle(
-                    &self.render_rect.rect,
-                    brush,
-                    self.border_width as f32,
-                    None,
-                ),
-                _ => renderer.DrawRoundedRectangle(
-                    &self.render_rect,
-                    brush,
-                    self.border_width as f32,
-                    None,
-                ),
+            let geometry = renderer.GetFactory()?.CreatePathGeometry()?;
+            let sink = geometry.Open()?;
+            sink.BeginFigure(far_corner, D2D1_FIGURE_BEGIN_FILLED);
+            sink.AddLines(&[
+                Vector2::new(rect.right + outer, rect.top - outer),
+                Vector2::new(rect.right - inner, rect.top + inner),
+                Vector2::new(rect.left + inner, rect.bottom - inner),
+                Vector2::new(rect.left - outer, rect.bottom + outer),
+            ]);
+            sink.EndFigure(D2D1_FIGURE_END_CLOSED);
+            sink.Close()?;
+
+            let mask_layer = MaskLayer {
+                mask: geometry.into(),
+                layer: renderer.CreateLayer(None)?,
+            };
+            let shade_brush = renderer.CreateSolidColorBrush(&shade, None)?;
+
+            Ok((mask_layer, shade_brush))
+        }
+    }
+
+    // The notch goes from outside of the border to its inner edge (and through an inside outline)
+    fn get_notch_rect(&self) -> Option<D2D_RECT_F> {
+        let notch = self.notch.as_ref()?;
+        let rect = &self.render_rect.rect;
+
+        let inner_reach = self.border_width as f32 / 2.0
+            + self
+                .outline
+                .as_ref()
+                .filter(|outline| outline.is_inside_too)
+                .map_or(0.0, |outline| (outline.gap + outline.width) as f32);
+
+        let (edge_start, edge_end) = match notch.edge {
+            BorderEdge::Top | BorderEdge::Bottom => (rect.left, rect.right),
+            BorderEdge::Left | BorderEdge::Right => (rect.top, rect.bottom),
+        };
+        let start = match notch.align {
+            NotchAlign::Start => edge_start + notch.offset,
+            NotchAlign::Center => (edge_start + edge_end - notch.width) / 2.0 + notch.offset,
+            NotchAlign::End => edge_end - notch.offset - notch.width,
+        };
+        let end = start + notch.width;
+        if end <= start {
+            return None;
+        }
+
+        Some(match notch.edge {
+            BorderEdge::Top => D2D_RECT_F {
+                left: start,
+                top: -NOTCH_REACH,
+                right: end,
+                bottom: rect.top + inner_reach,
+            },
+            BorderEdge::Bottom => D2D_RECT_F {
+                left: start,
+                top: rect.bottom - inner_reach,
+                right: end,
+                bottom: NOTCH_REACH,
+            },
+            BorderEdge::Left => D2D_RECT_F {
+                left: -NOTCH_REACH,
+                top: start,
+                right: rect.left + inner_reach,
+                bottom: end,
+            },
+            BorderEdge::Right => D2D_RECT_F {
+                left: rect.right - inner_reach,
+                top: start,
+                right: NOTCH_REACH,
+                bottom: end,
+            },
+        })
+    }
+
+    // Brackets and partial edges only cover part of the perimeter
+    fn is_full_rectangle(&self) -> bool {
+        self.border_style != BorderStyle::Brackets && self.edges.is_all()
+    }
+
+    // Gets the length of each bracket arm, which can't be shorter than the corner's radius or
+    // longer than half of the side it's on
+    fn get_bracket_length(&self, rect: &D2D_RECT_F, radius: f32) -> f32 {
+        let max_length = f32::min(rect.right - rect.left, rect.bottom - rect.top) / 2.0;
+        self.bracket_length.max(radius).min(max_length)
+    }
+
+    fn is_in_bracket(&self, rect: &D2D_RECT_F, point: &Vector2) -> bool {
+        let length = self.get_bracket_length(rect, self.border_radius);
+        let x_distance = f32::min((point.X - rect.left).abs(), (rect.right - point.X).abs());
+        let y_distance = f32::min((point.Y - rect.top).abs(), (rect.bottom - point.Y).abs());
+
+        x_distance <= length && y_distance <= length
+    }
+
+    // Each bracket is an open figure that follows the corner's curve, so that the arms are joined
+    // properly (and don't overlap with translucent colors)
+    fn draw_brackets(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        render_rect: &D2D1_ROUNDED_RECT,
+        width: f32,
+        brush: &ID2D1Brush,
+    ) -> windows::core::Result<()> {
+        let rect = &render_rect.rect;
+        let radius = render_rect.radiusX;
+        let length = self.get_bracket_length(rect, radius);
+
+        // (corner, direction going into the corner, direction coming out of it), clockwise
+        let corners = [
+            (
+                Vector2::new(rect.left, rect.top),
+                Vector2::new(0.0, -1.0),
+                Vector2::new(1.0, 0.0),
+            ),
+            (
+                Vector2::new(rect.right, rect.top),
+                Vector2::new(1.0, 0.0),
+                Vector2::new(0.0, 1.0),
+            ),
+            (
+                Vector2::new(rect.right, rect.bottom),
+                Vector2::new(0.0, 1.0),
+                Vector2::new(-1.0, 0.0),
+            ),
+            (
+                Vector2::new(rect.left, rect.bottom),
+                Vector2::new(-1.0, 0.0),
+                Vector2::new(0.0, -1.0),
+            ),
+        ];
+
+        unsafe {
+            let geometry = renderer.GetFactory()?.CreatePathGeometry()?;
+            let sink = geometry.Open()?;
+
+            for (corner, in_dir, out_dir) in corners {
+                sink.BeginFigure(corner - in_dir * length, D2D1_FIGURE_BEGIN_HOLLOW);
+                if radius > 0.0 {
+                    sink.AddLine(corner - in_dir * radius);
+                    sink.AddArc(&D2D1_ARC_SEGMENT {
+                        point: corner + out_dir * radius,
+                        size: D2D_SIZE_F {
+                            width: radius,
+                            height: radius,
+                        },
+                        rotationAngle: 0.0,
+                        sweepDirection: D2D1_SWEEP_DIRECTION_CLOCKWISE,
+                        arcSize: D2D1_ARC_SIZE_SMALL,
+                    });
+                } else {
+                    sink.AddLine(corner);
+                }
+                sink.AddLine(corner + out_dir * length);
+                sink.EndFigure(D2D1_FIGURE_END_OPEN);
+            }
+
+            sink.Close()?;
+            renderer.DrawGeometry(&geometry, brush, width, None);
+        }
+
+        Ok(())
+    }
+
+    // Top and bottom span the corners, and left and right only extend into a corner if the edge next
+    // to them isn't drawn. That way, translucent colors don't overlap. The segments go clockwise so
+    // that dashes march the same way they do around the full rectangle.
+    fn draw_edges(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        rect: &D2D_RECT_F,
+        width: f32,
+        brush: &ID2D1Brush,
+        stroke_style: Option<&ID2D1StrokeStyle>,
+    ) {
+        let half_width = width / 2.0;
+        let vertical_top = match self.edges.top {
+            true => rect.top + half_width,
+            false => rect.top - half_width,
+        };
+        let vertical_bottom = match self.edges.bottom {
+            true => rect.bottom - half_width,
+            false => rect.bottom + half_width,
+        };
+
+        let segments = [
+            (
+                self.edges.top,
+                Vector2::new(rect.left - half_width, rect.top),
+                Vector2::new(rect.right + half_width, rect.top),
+            ),
+            (
+                self.edges.right,
+                Vector2::new(rect.right, vertical_top),
+                Vector2::new(rect.right, vertical_bottom),
+            ),
+            (
+                self.edges.bottom,
+                Vector2::new(rect.right + half_width, rect.bottom),
+                Vector2::new(rect.left - half_width, rect.bottom),
+            ),
+            (
+                self.edges.left,
+                Vector2::new(rect.left, vertical_bottom),
+                Vector2::new(rect.left, vertical_top),
+            ),
+        ];
+
+        for (is_enabled, start, end) in segments {
+            if is_enabled {
+                unsafe { renderer.DrawLine(start, end, brush, width, stroke_style) };
+            }
+        }
+    }
+
+    // Covers the tracking window up to the inner edge of the border. If the border is pushed out
+    // past the window, we stop at the window's edges instead, since that's what gets dimmed.
+    fn get_dim_overlay_rect(&self, window_rect: &RECT, window_padding: f32) -> D2D1_ROUNDED_RECT {
+        let half_width = self.border_width as f32 / 2.0;
+        let rect = &self.render_rect.rect;
+        let width = (window_rect.right - window_rect.left) as f32;
+        let height = (window_rect.bottom - window_rect.top) as f32;
+
+        let overlay_rect = D2D_RECT_F {
+            left: (rect.left + half_width).max(window_padding),
+            top: (rect.top + half_width).max(window_padding),
+            right: (rect.right - half_width).min(width - window_padding),
+            bottom: (rect.bottom - half_width).min(height - window_padding),
+        };
+        let radius =
+            (self.border_radius - half_width - self.border_offset.max().max(0) as f32).max(0.0);
+
+        D2D1_ROUNDED_RECT {
+            rect: overlay_rect,
+            radiusX: radius,
+            radiusY: radius,
+        }
+    }
+
+    // The overlay fades in and out along with the inactive color
+    fn fill_dim_overlay(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        window_state: WindowState,
+    ) -> windows::core::Result<()> {
+        let Some(mut color) = self.dim_overlay_color else {
+            return Ok(());
+        };
+
+        let strength = match self.inactive_color.is_none() {
+            true => match window_state {
+                WindowState::Active => 0.0,
+                WindowState::Inactive => 1.0,
+            },
+            false => self
+                .inactive_color
+                .get_opacity()
+                .to_windows_result(T_E_UNINIT)?,
+        };
+        color.a *= strength;
+        if color.a <= 0.0 {
+            return Ok(());
+        }
+
+        let overlay_rect = &self.dim_overlay_rect;
+        if overlay_rect.rect.right <= overlay_rect.rect.left
+            || overlay_rect.rect.bottom <= overlay_rect.rect.top
+        {
+            return Ok(());
+        }
+
+        unsafe {
+            let id2d1_brush = renderer.CreateSolidColorBrush(&color, None)?;
+            match overlay_rect.radiusX {
+                0.0 => renderer.FillRectangle(&overlay_rect.rect, &id2d1_brush),
+                _ => renderer.FillRoundedRectangle(overlay_rect, &id2d1_brush),
+            }
+        }
+
+        Ok(())
+    }
+
+    // The strip spans the inside of the border, right below its top edge
+    fn get_titlebar_strip_rect(&self) -> Option<D2D_RECT_F> {
+        let height = self.titlebar_strip_height?;
+        let half_width = self.border_width as f32 / 2.0;
+        let rect = &self.render_rect.rect;
+
+        let top = rect.top + half_width;
+        let bottom = (top + height).min(rect.bottom - half_width);
+        if height <= 0.0 || bottom <= top {
+            return None;
+        }
+
+        Some(D2D_RECT_F {
+            left: rect.left + half_width,
+            top,
+            right: rect.right - half_width,
+            bottom,
+        })
+    }
+
+    // Fades the border out past its outer edge by drawing 1px rings around it, each a bit more
+    // transparent than the last. Dashes would get squashed at 1px, so dashed borders aren't
+    // feathered.
+    // NOTE: perimeter gradients don't go through here either.
+    fn draw_feather(
+        &self,
+        renderer: &ID2D1RenderTarget,
+        brush: &ID2D1Brush,
+        stroke_style: Option<&ID2D1StrokeStyle>,
+    ) {
+        if self.feather_width <= 0.0 || stroke_style.is_some() {
+            return;
+        }
+
+        let base_opacity = unsafe { brush.GetOpacity() };
+        let half_width = self.border_width as f32 / 2.0;
+
+        for ring in 0..self.feather_width.ceil() as i32 {
+            // Measured from the middle of the stroke to the middle of the ring
+            let distance = half_width + ring as f32 + 0.5;
+            let falloff = 1.0 - (ring as f32 + 0.5) / self.feather_width;
+            let radius = match self.render_rect.radiusX {
+                0.0 => 0.0,
+                radius => radius + distance,
+            };
+            let ring_rect = D2D1_ROUNDED_RECT {
+                rect: D2D_RECT_F {
+                    left: self.render_rect.rect.left - distance,
+                    top: self.render_rect.rect.top - distance,
+                    right: self.render_rect.rect.right + distance,
+                    bottom: self.render_rect.rect.bottom + distance,
+                },
+                radiusX: radius,
+                radiusY: radius,
+            };
+
+            unsafe { brush.SetOpacity(base_opacity * falloff.max(0.0)) };
+            self.draw_rectangle(renderer, &ring_rect, 1.0, brush, None);
+        }
+
+        unsafe { brush.SetOpacity(base_opacity) };
+    }
+
+    // The strip's top corners follow the inside of the border's corners. We fill a rounded rect
+    // that's taller than the strip and clip it, so that the bottom corners stay square.
+    // NOTE: perimeter gradients don't go through here, so they don't fill the strip.
+    fn fill_titlebar_strip(&self, renderer: &ID2D1RenderTarget, brush: &ID2D1Brush) {
+        let Some(strip_rect) = self.get_titlebar_strip_rect() else {
+            return;
+        };
+        let inner_radius = (self.border_radius - self.border_width as f32 / 2.0).max(0.0);
+
+        unsafe {
+            match inner_radius {
+                0.0 => renderer.FillRectangle(&strip_rect, brush),
+                _ => {
+                    let rounded_rect = D2D1_ROUNDED_RECT {
+                        rect: D2D_RECT_F {
+                            bottom: strip_rect.bottom + inner_radius,
+                            ..strip_rect
+                        },
+                        radiusX: inner_radius,
+                        radiusY: inner_radius,
+                    };
+                    renderer.PushAxisAlignedClip(&strip_rect, D2D1_ANTIALIAS_MODE_ALIASED);
+                    renderer.FillRoundedRectangle(&rounded_rect, brush);
+                    renderer.PopAxisAlignedClip();
+                }
             }
         }
     }
@@ -471,6 +1845,20 @@ impl BorderDrawer {
         }
     }
 
+    pub fn update_anim_timer_rate(&mut self, border_window: HWND, window_state: WindowState) {
+        self.animations.sync_animators(window_state);
+        let is_fade_in_progress = self.animations.is_running(AnimType::Fade)
+            && is_fade_in_progress(window_state, &self.active_color, &self.inactive_color)
+                .unwrap_or(false);
+
+        self.animations.update_timer_rate(
+            border_window,
+            window_state,
+            is_fade_in_progress,
+            &mut self.last_anim_time,
+        );
+    }
+
     pub fn animate(
         &mut self,
         window_rect: &RECT,
@@ -486,46 +1874,18 @@ impl BorderDrawer {
             .get_or_insert_with(time::Instant::now)
             .elapsed();
 
-        let mut update = false;
-
-        for anim_params in self.animations.get_current(window_state).clone().iter() {
-            match anim_params.anim_type {
-                AnimType::Spiral | AnimType::ReverseSpiral => {
-                    self.animations.animate_spiral(
-                        window_rect,
-                        &self.active_color,
-                        &self.inactive_color,
-                        &anim_elapsed,
-                        anim_params,
-                    );
-                    update = true;
-                }
-                AnimType::Fade => {
-                    let correct_active_opacity = if window_state == WindowState::Active {
-                        1.0
-                    } else {
-                        0.0
-                    };
-
-                    if self.active_color.get_opacity()? != correct_active_opacity
-                        || self.inactive_color.get_opacity()? != 1.0 - correct_active_opacity
-                    {
-                        self.animations.animate_fade(
-                            window_state,
-                            &self.active_color,
-                            &self.inactive_color,
-                            &anim_elapsed,
-                            anim_params,
-                        )?;
-                        update = true;
-                    }
-                }
-            }
-        }
+        let update = self.animations.advance(
+            window_rect,
+            window_state,
+            &self.active_color,
+            &self.inactive_color,
+            &anim_elapsed,
+        )?;
 
         self.last_anim_time = Some(time::Instant::now());
 
-        let render_interval = 1.0 / self.animations.fps as f32;
+        // A fade may have only just finished, so its last frame still counts here
+        let render_interval = 1.0 / self.animations.get_timer_fps(true).max(1) as f32;
         let time_diff = render_elapsed.as_secs_f32() - render_interval;
         if update && (time_diff.abs() <= 0.001 || time_diff >= 0.0) {
             self.render(window_rect, window_padding, window_state)?;
@@ -534,3 +1894,255 @@ impl BorderDrawer {
         Ok(())
     }
 }
+
+// Returns points (along with the distance travelled so far) going clockwise around the rounded
+// rect, starting from the left end of the top edge. The last point is the same as the first.
+fn get_perimeter_points(rect: &D2D_RECT_F, radius: f32) -> Vec<(Vector2, f32)> {
+    // Max length of straight segments, and max angle of arc segments. The arc segments need to be
+    // fairly small, otherwise there will be visible gaps between them on the outer edge.
+    const MAX_SEGMENT_LEN: f32 = 4.0;
+    const MAX_SEGMENT_ANGLE: f32 = 5.0 * PI / 180.0;
+
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    let radius = radius.clamp(0.0, width.min(height) / 2.0);
+
+    let mut points: Vec<Vector2> = Vec::new();
+
+    let add_line = |points: &mut Vec<Vector2>, from: Vector2, to: Vector2| {
+        let len = ((to.X - from.X).powi(2) + (to.Y - from.Y).powi(2)).sqrt();
+        let steps = (len / MAX_SEGMENT_LEN).ceil().max(1.0) as usize;
+
+        // We don't push the end point because the next line or arc starts there
+        for i in 0..steps {
+            let t = i as f32 / steps as f32;
+            points.push(Vector2 {
+                X: from.X + (to.X - from.X) * t,
+                Y: from.Y + (to.Y - from.Y) * t,
+            });
+        }
+    };
+    let add_arc = |points: &mut Vec<Vector2>, center: Vector2, start_angle: f32| {
+        if radius <= 0.0 {
+            return;
+        }
+
+        let arc_len = radius * PI / 2.0;
+        let steps = (arc_len / MAX_SEGMENT_LEN)
+            .max(PI / 2.0 / MAX_SEGMENT_ANGLE)
+            .ceil() as usize;
+
+        for i in 0..steps {
+            let angle = start_angle + PI / 2.0 * (i as f32 / steps as f32);
+            points.push(Vector2 {
+                X: center.X + radius * angle.cos(),
+                Y: center.Y + radius * angle.sin(),
+            });
+        }
+    };
+
+    let (l, t, r, b) = (rect.left, rect.top, rect.right, rect.bottom);
+    let point = |x: f32, y: f32| Vector2 { X: x, Y: y };
+
+    // Top edge, top-right corner, right edge, bottom-right corner, and so on
+    add_line(&mut points, point(l + radius, t), point(r - radius, t));
+    add_arc(&mut points, point(r - radius, t + radius), -PI / 2.0);
+    add_line(&mut points, point(r, t + radius), point(r, b - radius));
+    add_arc(&mut points, point(r - radius, b - radius), 0.0);
+    add_line(&mut points, point(r - radius, b), point(l + radius, b));
+    add_arc(&mut points, point(l + radius, b - radius), PI / 2.0);
+    add_line(&mut points, point(l, b - radius), point(l, t + radius));
+    add_arc(&mut points, point(l + radius, t + radius), PI);
+
+    // Close the loop
+    if let Some(&first) = points.first() {
+        points.push(first);
+    }
+
+    let mut distance = 0.0;
+    let mut prev: Option<Vector2> = None;
+
+    points
+        .into_iter()
+        .map(|p| {
+            if let Some(prev) = prev {
+                distance += ((p.X - prev.X).powi(2) + (p.Y - prev.Y).powi(2)).sqrt();
+            }
+            prev = Some(p);
+
+            (p, distance)
+        })
+        .collect()
+}
+
+// Clips everything drawn until the next PopLayer() to the given mask
+unsafe fn push_mask_layer(renderer: &ID2D1RenderTarget, mask_layer: &MaskLayer) {
+    unsafe { push_layer(renderer, &mask_layer.mask, Some(&mask_layer.layer), 1.0) };
+}
+
+// Without a layer to reuse, Direct2D makes one from its own pool
+unsafe fn push_layer(
+    renderer: &ID2D1RenderTarget,
+    mask: &ID2D1Geometry,
+    layer: Option<&ID2D1Layer>,
+    opacity: f32,
+) {
+    let mut layer_parameters = D2D1_LAYER_PARAMETERS {
+        contentBounds: D2D_RECT_F {
+            left: -f32::MAX,
+            top: -f32::MAX,
+            right: f32::MAX,
+            bottom: f32::MAX,
+        },
+        geometricMask: ManuallyDrop::new(Some(mask.clone())),
+        maskAntialiasMode: D2D1_ANTIALIAS_MODE_PER_PRIMITIVE,
+        maskTransform: Matrix3x2::identity(),
+        opacity,
+        opacityBrush: ManuallyDrop::new(None),
+        layerOptions: D2D1_LAYER_OPTIONS_NONE,
+    };
+
+    unsafe {
+        renderer.PushLayer(&layer_parameters, layer);
+        // PushLayer holds its own reference to the mask
+        ManuallyDrop::drop(&mut layer_parameters.geometricMask);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get_test_drawer(border_radius: f32) -> BorderDrawer {
+        BorderDrawer {
+            border_width: 4,
+            border_radius,
+            render_rect: D2D1_ROUNDED_RECT {
+                rect: D2D_RECT_F {
+                    left: 10.0,
+                    top: 10.0,
+                    right: 110.0,
+                    bottom: 60.0,
+                },
+                radiusX: border_radius,
+                radiusY: border_radius,
+            },
+            ..Default::default()
+        }
+    }
+
+    fn get_test_outline(width: i32, gap: i32, is_inside_too: bool) -> Outline {
+        Outline {
+            width,
+            gap,
+            is_inside_too,
+            active_color: ColorBrush::default(),
+            inactive_color: ColorBrush::default(),
+        }
+    }
+
+    #[test]
+    fn test_get_outline_rect() {
+        let border_drawer = get_test_drawer(8.0);
+        let outline = get_test_outline(1, 2, false);
+
+        // Half the border + the gap + half the outline
+        let outline_rect = border_drawer.get_outline_rect(&outline, false).unwrap();
+        assert_eq!(
+            outline_rect.rect,
+            D2D_RECT_F {
+                left: 5.5,
+                top: 5.5,
+                right: 114.5,
+                bottom: 64.5,
+            }
+        );
+        assert_eq!(outline_rect.radiusX, 12.5);
+        assert_eq!(outline_rect.radiusY, 12.5);
+
+        // Square borders keep square outlines
+        let border_drawer = get_test_drawer(0.0);
+        let outline_rect = border_drawer.get_outline_rect(&outline, false).unwrap();
+        assert_eq!(outline_rect.radiusX, 0.0);
+    }
+
+    #[test]
+    fn test_get_inside_outline_rect() {
+        let border_drawer = get_test_drawer(8.0);
+
+        let outline_rect = border_drawer
+            .get_outline_rect(&get_test_outline(1, 2, true), true)
+            .unwrap();
+        assert_eq!(
+            outline_rect.rect,
+            D2D_RECT_F {
+                left: 14.5,
+                top: 14.5,
+                right: 105.5,
+                bottom: 55.5,
+            }
+        );
+        assert_eq!(outline_rect.radiusX, 3.5);
+
+        // The radius bottoms out at 0, and an outline that would turn inside out is skipped
+        let outline_rect = border_drawer
+            .get_outline_rect(&get_test_outline(1, 6, true), true)
+            .unwrap();
+        assert_eq!(outline_rect.radiusX, 0.0);
+        assert!(
+            border_drawer
+                .get_outline_rect(&get_test_outline(1, 30, true), true)
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_get_notch_rect() {
+        let mut border_drawer = get_test_drawer(8.0);
+        let notch = |edge, align, width, offset| Notch {
+            edge,
+            align,
+            width,
+            offset,
+        };
+
+        // The notch reaches from the border's inner edge to well past its outer edge
+        border_drawer.notch = Some(notch(BorderEdge::Top, NotchAlign::Center, 20.0, 0.0));
+        assert_eq!(
+            border_drawer.get_notch_rect(),
+            Some(D2D_RECT_F {
+                left: 50.0,
+                top: -NOTCH_REACH,
+                right: 70.0,
+                bottom: 12.0,
+            })
+        );
+
+        border_drawer.notch = Some(notch(BorderEdge::Left, NotchAlign::Start, 10.0, 5.0));
+        assert_eq!(
+            border_drawer.get_notch_rect(),
+            Some(D2D_RECT_F {
+                left: -NOTCH_REACH,
+                top: 15.0,
+                right: 12.0,
+                bottom: 25.0,
+            })
+        );
+
+        // An inside outline has to be cut through too
+        border_drawer.outline = Some(get_test_outline(1, 2, true));
+        border_drawer.notch = Some(notch(BorderEdge::Bottom, NotchAlign::End, 20.0, 5.0));
+        assert_eq!(
+            border_drawer.get_notch_rect(),
+            Some(D2D_RECT_F {
+                left: 85.0,
+                top: 55.0,
+                right: 105.0,
+                bottom: NOTCH_REACH,
+            })
+        );
+
+        border_drawer.notch = Some(notch(BorderEdge::Right, NotchAlign::Start, 0.0, 0.0));
+        assert_eq!(border_drawer.get_notch_rect(), None);
+    }
+}
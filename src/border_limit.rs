@@ -0,0 +1,71 @@
+// Caps how many borders exist at once (see the config's max_borders). We keep track of when each
+// window was last focused, and when a new border would go over the limit, the border of the least
+// recently focused window gets torn down to make room. That window gets its border back the next
+// time it's focused.
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+use windows::Win32::Foundation::HWND;
+
+use crate::APP_STATE;
+use crate::utils::{get_border_for_window, request_border_teardown, show_border_for_window};
+
+// Windows that have never been focused count as the least recently used
+static FOCUS_COUNTER: AtomicU64 = AtomicU64::new(1);
+static LAST_FOCUSED: LazyLock<Mutex<HashMap<isize, u64>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+
+pub fn get_max_borders() -> Option<usize> {
+    APP_STATE
+        .config
+        .read()
+        .unwrap()
+        .max_borders
+        .filter(|max_borders| *max_borders > 0)
+}
+
+// Called by the event hook whenever the foreground window changes
+pub fn handle_focus(hwnd: HWND) {
+    let tick = FOCUS_COUNTER.fetch_add(1, Ordering::SeqCst);
+    LAST_FOCUSED.lock().unwrap().insert(hwnd.0 as isize, tick);
+
+    // The window's border may have been evicted earlier
+    if get_max_borders().is_some() && get_border_for_window(hwnd).is_none() {
+        show_border_for_window(hwnd);
+    }
+}
+
+pub fn forget_window(hwnd: HWND) {
+    LAST_FOCUSED.lock().unwrap().remove(&(hwnd.0 as isize));
+}
+
+// Makes room for a border for 'tracking_window', evicting least recently focused borders as needed.
+// Returns false if every existing border was focused more recently, in which case the new border
+// shouldn't be created.
+pub fn make_room_for_border(
+    borders_hashmap: &mut MutexGuard<HashMap<isize, isize>>,
+    tracking_window: HWND,
+    max_borders: usize,
+) -> bool {
+    let last_focused = LAST_FOCUSED.lock().unwrap();
+    let get_last_focused = |hwnd_isize: &isize| last_focused.get(hwnd_isize).copied().unwrap_or(0);
+    let new_last_focused = get_last_focused(&(tracking_window.0 as isize));
+
+    while borders_hashmap.len() >= max_borders {
+        let Some(lru_tracking_isize) = borders_hashmap
+            .keys()
+            .copied()
+            .min_by_key(|tracking_isize| get_last_focused(tracking_isize))
+            .filter(|tracking_isize| get_last_focused(tracking_isize) < new_last_focused)
+        else {
+            return false;
+        };
+
+        debug!("evicting border for {lru_tracking_isize:#x} to stay within max_borders");
+        if let Some(border_isize) = borders_hashmap.remove(&lru_tracking_isize) {
+            request_border_teardown(borders_hashmap, border_isize, false);
+        }
+    }
+
+    true
+}
@@ -0,0 +1,54 @@
+// Window properties that we attach to every window we're drawing a border around, so that tiling
+// window managers (or anything else) can account for the border when computing gaps. They can be
+// read from any process using GetPropW(hwnd, "TackyBorders.Attached"), etc.
+//
+//   - TackyBorders.Attached: 1 while a border is attached. The property is absent otherwise.
+//   - TackyBorders.Width: border width in physical pixels (already scaled for the monitor's DPI),
+//     including the outline and its gap if there is one
+//   - TackyBorders.Offset: border offset in physical pixels. This can be negative. If the offset is
+//     set per-side or there's a screen_edge_offset, this is the largest one.
+//
+// The border extends (at most) (Width + Offset) pixels past the window's extended frame bounds.
+use windows::Win32::Foundation::{HANDLE, HWND};
+use windows::Win32::UI::WindowsAndMessaging::{GetPropW, RemovePropW, SetPropW};
+use windows::core::{PCWSTR, w};
+
+const ATTACHED_PROP: PCWSTR = w!("TackyBorders.Attached");
+const WIDTH_PROP: PCWSTR = w!("TackyBorders.Width");
+const OFFSET_PROP: PCWSTR = w!("TackyBorders.Offset");
+
+pub fn publish_border_props(
+    tracking_window: HWND,
+    border_width: i32,
+    border_offset: i32,
+) -> windows::core::Result<()> {
+    // Properties are HANDLE-sized, so we just store the numbers directly in them. We set Attached
+    // last so that readers never see it without the other two.
+    unsafe {
+        SetPropW(
+            tracking_window,
+            WIDTH_PROP,
+            Some(HANDLE(border_width as isize as _)),
+        )?;
+        SetPropW(
+            tracking_window,
+            OFFSET_PROP,
+            Some(HANDLE(border_offset as isize as _)),
+        )?;
+        SetPropW(tracking_window, ATTACHED_PROP, Some(HANDLE(1 as _)))
+    }
+}
+
+pub fn is_border_attached(tracking_window: HWND) -> bool {
+    !unsafe { GetPropW(tracking_window, ATTACHED_PROP) }.is_invalid()
+}
+
+// This fails if the tracking window has already been destroyed, in which case Windows already
+// cleaned up the properties for us, so errors are ignored.
+pub fn clear_border_props(tracking_window: HWND) {
+    unsafe {
+        let _ = RemovePropW(tracking_window, ATTACHED_PROP);
+        let _ = RemovePropW(tracking_window, WIDTH_PROP);
+        let _ = RemovePropW(tracking_window, OFFSET_PROP);
+    }
+}
@@ -0,0 +1,83 @@
+use std::env;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+pub static CLI_ARGS: LazyLock<CliArgs> = LazyLock::new(|| CliArgs::parse(env::args().skip(1)));
+
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct CliArgs {
+    // Same as setting 'read_only: True' in the config, except it can't be overridden by the config
+    pub read_only: bool,
+    // Set by --bench or any of the --bench-* options
+    pub bench: Option<BenchOptions>,
+    // Used internally by --bench to host the dummy windows in a separate process
+    pub bench_host: Option<u32>,
+    // Writes a zip for bug reports (see report.rs) and exits
+    pub report: bool,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchOptions {
+    pub windows: u32,
+    pub seconds: u64,
+    // Config file to take the border theme from. Defaults to the regular config.yaml.
+    pub theme: Option<PathBuf>,
+}
+
+impl Default for BenchOptions {
+    fn default() -> Self {
+        Self {
+            windows: 20,
+            seconds: 10,
+            theme: None,
+        }
+    }
+}
+
+impl CliArgs {
+    pub fn parse(args: impl Iterator<Item = String>) -> Self {
+        let mut cli_args = CliArgs::default();
+        let mut args = args;
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--read-only" => cli_args.read_only = true,
+                "--report" => cli_args.report = true,
+                "--bench" => {
+                    cli_args.bench.get_or_insert_default();
+                }
+                "--bench-windows" => {
+                    if let Some(windows) = parse_value(&arg, args.next()) {
+                        cli_args.bench.get_or_insert_default().windows = windows;
+                    }
+                }
+                "--bench-seconds" => {
+                    if let Some(seconds) = parse_value(&arg, args.next()) {
+                        cli_args.bench.get_or_insert_default().seconds = seconds;
+                    }
+                }
+                "--bench-theme" => {
+                    if let Some(theme) = parse_value(&arg, args.next()) {
+                        cli_args.bench.get_or_insert_default().theme = Some(theme);
+                    }
+                }
+                "--bench-host" => cli_args.bench_host = parse_value(&arg, args.next()),
+                _ => warn!("ignoring unrecognized command-line argument: {arg}"),
+            }
+        }
+
+        cli_args
+    }
+}
+
+fn parse_value<T: std::str::FromStr>(arg: &str, value: Option<String>) -> Option<T> {
+    let Some(value) = value else {
+        warn!("ignoring {arg} because it's missing a value");
+        return None;
+    };
+
+    value
+        .parse()
+        .inspect_err(|_| warn!("ignoring {arg} because '{value}' is not a valid value"))
+        .ok()
+}
@@ -1,25 +1,46 @@
 use anyhow::{Context, anyhow};
 use core::f32;
-use serde::{Deserialize, Serialize};
+use serde::{Deserialize, Deserializer, Serialize};
+use std::cell::Cell;
 use std::f32::consts::PI;
-use windows::Win32::Foundation::{FALSE, RECT};
-use windows::Win32::Graphics::Direct2D::Common::{D2D1_COLOR_F, D2D1_GRADIENT_STOP};
+use std::ptr;
+use std::sync::{Mutex, RwLock};
+use windows::UI::ViewManagement::{UIColorType, UISettings};
+use windows::Win32::Foundation::{COLORREF, FALSE, HWND, RECT};
+use windows::Win32::Graphics::Direct2D::Common::{
+    D2D_SIZE_U, D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_COLOR_F, D2D1_GRADIENT_STOP, D2D1_PIXEL_FORMAT,
+};
 use windows::Win32::Graphics::Direct2D::{
-    D2D1_BRUSH_PROPERTIES, D2D1_EXTEND_MODE_CLAMP, D2D1_GAMMA_2_2,
-    D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES, ID2D1Brush, ID2D1LinearGradientBrush, ID2D1RenderTarget,
-    ID2D1SolidColorBrush,
+    D2D1_BITMAP_BRUSH_PROPERTIES, D2D1_BITMAP_INTERPOLATION_MODE_LINEAR, D2D1_BITMAP_PROPERTIES,
+    D2D1_BRUSH_PROPERTIES, D2D1_EXTEND_MODE_CLAMP, D2D1_EXTEND_MODE_WRAP, D2D1_GAMMA_2_2,
+    D2D1_LINEAR_GRADIENT_BRUSH_PROPERTIES, ID2D1BitmapBrush, ID2D1Brush, ID2D1LinearGradientBrush,
+    ID2D1RenderTarget, ID2D1SolidColorBrush,
+};
+use windows::Win32::Graphics::Dwm::{
+    DWMWA_CAPTION_COLOR, DWMWA_COLOR_DEFAULT, DWMWA_COLOR_NONE, DwmGetColorizationColor,
+    DwmGetWindowAttribute,
+};
+use windows::Win32::Graphics::Dxgi::Common::DXGI_FORMAT_B8G8R8A8_UNORM;
+use windows::Win32::Graphics::Gdi::{
+    COLOR_BACKGROUND, COLOR_GRAYTEXT, COLOR_HIGHLIGHT, GetSysColor, SYS_COLOR_INDEX,
 };
-use windows::Win32::Graphics::Dwm::DwmGetColorizationColor;
 use windows::core::BOOL;
 use windows_numerics::{Matrix3x2, Vector2};
 
 use crate::LogIfErr;
+use crate::config::{resolve_palette_refs, serde_default_f32};
+use crate::utils::{get_window_process_name, lerp};
+use crate::wallpaper;
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, PartialEq)]
 #[serde(untagged)]
 pub enum ColorBrushConfig {
     Solid(String),
     Gradient(GradientBrushConfig),
+    Pattern(PatternBrushConfig),
+    // Outside of 'global', a gradient can leave out fields to inherit them from the corresponding
+    // global color (e.g. to only change the direction). These get merged when the config loads.
+    PartialGradient(PartialGradientConfig),
 }
 
 impl Default for ColorBrushConfig {
@@ -28,31 +49,316 @@ impl Default for ColorBrushConfig {
     }
 }
 
+// Palette references are resolved here so that they work wherever a color can go
+impl<'de> Deserialize<'de> for ColorBrushConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum RawColorBrushConfig {
+            Solid(String),
+            Gradient(GradientBrushConfig),
+            Pattern(PatternBrushConfig),
+            PartialGradient(PartialGradientConfig),
+        }
+
+        let mut color_config = match RawColorBrushConfig::deserialize(deserializer)? {
+            RawColorBrushConfig::Solid(color) => ColorBrushConfig::Solid(color),
+            RawColorBrushConfig::Gradient(gradient) => ColorBrushConfig::Gradient(gradient),
+            RawColorBrushConfig::Pattern(pattern) => ColorBrushConfig::Pattern(pattern),
+            RawColorBrushConfig::PartialGradient(partial) => {
+                ColorBrushConfig::PartialGradient(partial)
+            }
+        };
+
+        for color in color_config.colors_mut() {
+            *color = resolve_palette_refs(color).map_err(serde::de::Error::custom)?;
+        }
+
+        Ok(color_config)
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct GradientBrushConfig {
     pub colors: Vec<String>,
     pub direction: GradientDirection,
+    // Adds a bit of noise to reduce banding. Not supported for perimeter gradients.
+    #[serde(default)]
+    pub dither: bool,
 }
 
+// Two colors repeated in a pattern. The pattern is drawn into a small tile that gets repeated
+// across the border.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PatternBrushConfig {
+    pub pattern: PatternKind,
+    pub colors: [String; 2],
+    // Width of each stripe or square, in pixels
+    #[serde(default = "serde_default_f32::<4>")]
+    pub width: f32,
+    // Rotates the pattern clockwise, in degrees
+    #[serde(default = "serde_default_f32::<45>")]
+    pub angle: f32,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum PatternKind {
+    Stripes,
+    Checkerboard,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PartialGradientConfig {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub colors: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub direction: Option<GradientDirection>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dither: Option<bool>,
+}
+
+// NOTE: Angle also accepts "perimeter", which distributes the colors along the border's perimeter
+// instead of along a straight line
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(untagged)]
 pub enum GradientDirection {
     Angle(String),
-    Coordinates(GradientCoordinates),
+    Coordinates(GradientCoordinatesConfig),
+}
+
+impl GradientDirection {
+    pub fn angle(degrees: f32) -> Self {
+        GradientDirection::Angle(format!("{degrees}deg"))
+    }
+
+    pub fn perimeter() -> Self {
+        GradientDirection::Angle("perimeter".to_string())
+    }
+
+    // Coordinates are relative to the border, from 0.0 to 1.0
+    pub fn coordinates(start: [f32; 2], end: [f32; 2]) -> Self {
+        GradientDirection::Coordinates(GradientCoordinatesConfig {
+            start: start.map(GradientCoordinate::Number),
+            end: end.map(GradientCoordinate::Number),
+        })
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
+pub struct GradientCoordinatesConfig {
+    pub start: [GradientCoordinate; 2],
+    pub end: [GradientCoordinate; 2],
+}
+
+// Either a number from 0.0 to 1.0 or a percentage from 0% to 100%. We keep percentages as strings
+// here so that mistakes get reported by validate() instead of failing to parse the whole config.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(untagged)]
+pub enum GradientCoordinate {
+    Number(f32),
+    Percentage(String),
+}
+
+impl GradientCoordinate {
+    // Values that are only barely out of range (e.g. 1.0001 from rounding) get snapped into range
+    const SNAP_TOLERANCE: f32 = 0.001;
+
+    fn resolve(&self) -> anyhow::Result<f32> {
+        let value = match self {
+            GradientCoordinate::Number(number) => *number,
+            GradientCoordinate::Percentage(percentage) => percentage
+                .trim()
+                .strip_suffix('%')
+                .and_then(|p| p.trim().parse::<f32>().ok())
+                .map(|p| p / 100.0)
+                .with_context(|| {
+                    format!("invalid coordinate '{percentage}'; expected a number or a percentage like 50%")
+                })?,
+        };
+
+        if !(-Self::SNAP_TOLERANCE..=1.0 + Self::SNAP_TOLERANCE).contains(&value) {
+            return Err(anyhow!(
+                "coordinate {value} is out of range; expected 0.0 to 1.0 (or 0% to 100%)"
+            ));
+        }
+
+        Ok(value.clamp(0.0, 1.0))
+    }
+}
+
+impl GradientCoordinatesConfig {
+    fn resolve(&self) -> anyhow::Result<GradientCoordinates> {
+        let resolve_point = |point: &[GradientCoordinate; 2], name: &str| {
+            anyhow::Ok([
+                point[0].resolve().with_context(|| format!("{name}[0]"))?,
+                point[1].resolve().with_context(|| format!("{name}[1]"))?,
+            ])
+        };
+
+        let start = resolve_point(&self.start, "direction.start")?;
+        let end = resolve_point(&self.end, "direction.end")?;
+        if start == end {
+            return Err(anyhow!(
+                "direction.start and direction.end are the same point, so the gradient has no direction"
+            ));
+        }
+
+        Ok(GradientCoordinates { start, end })
+    }
+}
+
+// Coordinates after resolving percentages and snapping
+#[derive(Debug, Clone, PartialEq)]
 pub struct GradientCoordinates {
     pub start: [f32; 2],
     pub end: [f32; 2],
 }
 
+// Color space used when blending between two colors (e.g. during the fade animation)
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ColorInterpolation {
+    #[default]
+    Rgb,
+    Hsl,
+    Oklch,
+}
+
+// How translucent colors are blended by our own interpolation code (fades, perimeter gradients,
+// dithering). Direct2D always blends its own brushes and gradients with premultiplied alpha.
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum AlphaMode {
+    #[default]
+    Premultiplied,
+    // Blends the color channels without weighting them by alpha like older versions did. This
+    // makes colors darken as they approach transparent colors like "none" or "#00000000".
+    Straight,
+}
+
+// Keeps the accent color's lightness and saturation within a visible range, since near-black or
+// near-white accents can make borders disappear against most windows. All values are from 0 to 1.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AccentNormalizationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "serde_default_min_lightness")]
+    pub min_lightness: f32,
+    #[serde(default = "serde_default_max_lightness")]
+    pub max_lightness: f32,
+    #[serde(default = "serde_default_min_saturation")]
+    pub min_saturation: f32,
+    #[serde(default = "serde_default_f32::<1>")]
+    pub max_saturation: f32,
+}
+
+impl Default for AccentNormalizationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            min_lightness: serde_default_min_lightness(),
+            max_lightness: serde_default_max_lightness(),
+            min_saturation: serde_default_min_saturation(),
+            max_saturation: 1.0,
+        }
+    }
+}
+
+fn serde_default_min_lightness() -> f32 {
+    0.3
+}
+
+fn serde_default_max_lightness() -> f32 {
+    0.8
+}
+
+fn serde_default_min_saturation() -> f32 {
+    0.2
+}
+
+impl AccentNormalizationConfig {
+    pub fn normalize(&self, color: &D2D1_COLOR_F) -> D2D1_COLOR_F {
+        let (h, s, l) = rgb_to_hsl(color);
+
+        // Not using clamp() here because it panics if min > max
+        let l = l.max(self.min_lightness).min(self.max_lightness);
+        // Grays don't have a meaningful hue, so adding saturation would just turn them red
+        let s = match s > 0.0 {
+            true => s.max(self.min_saturation).min(self.max_saturation),
+            false => s,
+        };
+
+        hsl_to_rgb(h, s, l, color.a)
+    }
+}
+
+impl ColorInterpolation {
+    pub fn interpolate(self, start: &D2D1_COLOR_F, end: &D2D1_COLOR_F, t: f32) -> D2D1_COLOR_F {
+        let t = t.clamp(0.0, 1.0);
+        let a = lerp(t, start.a, end.a);
+
+        let use_straight_alpha = get_color_settings().alpha_mode == AlphaMode::Straight;
+
+        // A fully transparent color has no meaningful color channels (e.g. "none" is transparent
+        // black), so for the polar color spaces we borrow them from the other color instead
+        let (start, end) = match use_straight_alpha {
+            true => (*start, *end),
+            false => (
+                borrow_transparent_channels(start, end),
+                borrow_transparent_channels(end, start),
+            ),
+        };
+
+        match self {
+            ColorInterpolation::Rgb if use_straight_alpha => D2D1_COLOR_F {
+                r: lerp(t, start.r, end.r),
+                g: lerp(t, start.g, end.g),
+                b: lerp(t, start.b, end.b),
+                a,
+            },
+            ColorInterpolation::Rgb => {
+                // Blend in premultiplied space, then convert back since D2D1_COLOR_F is straight
+                let unpremultiply = |start_channel: f32, end_channel: f32| {
+                    let channel = lerp(t, start_channel * start.a, end_channel * end.a);
+                    match a > 0.0 {
+                        true => (channel / a).clamp(0.0, 1.0),
+                        false => 0.0,
+                    }
+                };
+
+                D2D1_COLOR_F {
+                    r: unpremultiply(start.r, end.r),
+                    g: unpremultiply(start.g, end.g),
+                    b: unpremultiply(start.b, end.b),
+                    a,
+                }
+            }
+            ColorInterpolation::Hsl => {
+                let (h1, s1, l1) = rgb_to_hsl(&start);
+                let (h2, s2, l2) = rgb_to_hsl(&end);
+                let (h1, h2) = fix_achromatic_hues(h1, s1, h2, s2);
+
+                hsl_to_rgb(lerp_hue(t, h1, h2), lerp(t, s1, s2), lerp(t, l1, l2), a)
+            }
+            ColorInterpolation::Oklch => {
+                let (l1, c1, h1) = rgb_to_oklch(&start);
+                let (l2, c2, h2) = rgb_to_oklch(&end);
+                let (h1, h2) = fix_achromatic_hues(h1, c1, h2, c2);
+
+                oklch_to_rgb(lerp(t, l1, l2), lerp(t, c1, c2), lerp_hue(t, h1, h2), a)
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum ColorBrush {
     Solid(SolidBrush),
     Gradient(GradientBrush),
+    Pattern(PatternBrush),
 }
 
 impl Default for ColorBrush {
@@ -60,6 +366,7 @@ impl Default for ColorBrush {
         ColorBrush::Solid(SolidBrush {
             color: D2D1_COLOR_F::default(),
             brush: None,
+            is_none: false,
         })
     }
 }
@@ -68,6 +375,19 @@ impl Default for ColorBrush {
 pub struct SolidBrush {
     color: D2D1_COLOR_F,
     brush: Option<ID2D1SolidColorBrush>,
+    // Set for the "none" keyword, which means the border shouldn't be drawn at all
+    is_none: bool,
+}
+
+#[derive(Debug, Clone)]
+pub struct PatternBrush {
+    kind: PatternKind,
+    colors: [D2D1_COLOR_F; 2],
+    width: f32,
+    angle: f32,
+    brush: Option<ID2D1BitmapBrush>,
+    // The transform set by animations. The brush itself also has the pattern's rotation applied.
+    transform: Cell<Matrix3x2>,
 }
 
 #[derive(Debug, Clone)]
@@ -75,44 +395,190 @@ pub struct GradientBrush {
     gradient_stops: Vec<D2D1_GRADIENT_STOP>,
     direction: GradientCoordinates,
     brush: Option<ID2D1LinearGradientBrush>,
+    is_perimeter: bool,
 }
 
 impl ColorBrushConfig {
-    pub fn to_color_brush(&self, is_active_color: bool) -> ColorBrush {
+    pub fn solid(color: impl Into<String>) -> Self {
+        ColorBrushConfig::Solid(color.into())
+    }
+
+    pub fn gradient<S: Into<String>>(
+        colors: impl IntoIterator<Item = S>,
+        direction: GradientDirection,
+    ) -> Self {
+        ColorBrushConfig::Gradient(GradientBrushConfig {
+            colors: colors.into_iter().map(Into::into).collect(),
+            direction,
+            dither: false,
+        })
+    }
+
+    // Only affects gradients; solid colors are returned as-is
+    pub fn with_dither(mut self, dither: bool) -> Self {
+        if let ColorBrushConfig::Gradient(ref mut gradient) = self {
+            gradient.dither = dither;
+        }
+        self
+    }
+
+    // Fills in a partial gradient's missing fields from 'base'. If 'base' isn't a gradient, it
+    // can't fill in anything, so the partial gradient must already specify colors and direction.
+    pub fn merge_with(&mut self, base: &ColorBrushConfig) {
+        let ColorBrushConfig::PartialGradient(partial) = self else {
+            return;
+        };
+
+        let base_gradient = match base {
+            ColorBrushConfig::Gradient(gradient) => Some(gradient),
+            _ => None,
+        };
+        let colors = partial
+            .colors
+            .clone()
+            .or_else(|| Some(base_gradient?.colors.clone()));
+        let direction = partial
+            .direction
+            .clone()
+            .or_else(|| Some(base_gradient?.direction.clone()));
+
+        // We'll leave it as-is so that validate() can report it
+        let (Some(colors), Some(direction)) = (colors, direction) else {
+            return;
+        };
+
+        let dither = partial
+            .dither
+            .or(base_gradient.map(|gradient| gradient.dither))
+            .unwrap_or_default();
+
+        *self = ColorBrushConfig::Gradient(GradientBrushConfig {
+            colors,
+            direction,
+            dither,
+        });
+    }
+
+    // Used when loading the config so that typos get reported instead of silently turning into
+    // transparent borders
+    pub fn validate(&self) -> anyhow::Result<()> {
         match self {
-            ColorBrushConfig::Solid(solid_config) => {
-                if solid_config == "accent" {
-                    ColorBrush::Solid(SolidBrush {
-                        color: get_accent_color(is_active_color),
-                        brush: None,
-                    })
-                } else {
-                    ColorBrush::Solid(SolidBrush {
-                        color: get_color_from_hex(solid_config.as_str()),
-                        brush: None,
-                    })
+            ColorBrushConfig::Solid(color) => validate_color(color),
+            ColorBrushConfig::Gradient(gradient_config) => {
+                if gradient_config.colors.len() < 2 {
+                    return Err(anyhow!("gradients need at least 2 colors"));
+                }
+
+                for color in gradient_config.colors.iter() {
+                    validate_color(color)?;
+                }
+
+                if let GradientDirection::Angle(ref angle) = gradient_config.direction
+                    && angle.trim() != "perimeter"
+                    && angle
+                        .strip_suffix("deg")
+                        .and_then(|d| d.trim().parse::<f32>().ok())
+                        .is_none()
+                {
+                    return Err(anyhow!(
+                        "invalid gradient direction '{angle}'; expected an angle like 45deg"
+                    ));
+                }
+
+                if let GradientDirection::Coordinates(ref coordinates) = gradient_config.direction {
+                    coordinates.resolve()?;
+                }
+
+                Ok(())
+            }
+            ColorBrushConfig::Pattern(pattern_config) => {
+                for color in pattern_config.colors.iter() {
+                    validate_color(color)?;
+                }
+
+                if pattern_config.width <= 0.0 {
+                    return Err(anyhow!("pattern width must be greater than 0"));
                 }
+
+                Ok(())
+            }
+            ColorBrushConfig::PartialGradient(_) => Err(anyhow!(
+                "gradient is missing 'colors' or 'direction', and there's no global gradient to inherit them from"
+            )),
+        }
+    }
+
+    fn colors_mut(&mut self) -> &mut [String] {
+        match self {
+            ColorBrushConfig::Solid(color) => std::slice::from_mut(color),
+            ColorBrushConfig::Gradient(gradient_config) => &mut gradient_config.colors,
+            ColorBrushConfig::Pattern(pattern_config) => &mut pattern_config.colors,
+            ColorBrushConfig::PartialGradient(partial_config) => {
+                partial_config.colors.as_deref_mut().unwrap_or_default()
+            }
+        }
+    }
+
+    // Whether any of the colors refer to the keyword, e.g. "wallpaper" in lighten(wallpaper, 20%)
+    pub fn uses_keyword(&self, keyword: &str) -> bool {
+        let colors: &[String] = match self {
+            ColorBrushConfig::Solid(color) => std::slice::from_ref(color),
+            ColorBrushConfig::Gradient(gradient_config) => &gradient_config.colors,
+            ColorBrushConfig::Pattern(pattern_config) => &pattern_config.colors,
+            ColorBrushConfig::PartialGradient(partial_config) => {
+                partial_config.colors.as_deref().unwrap_or_default()
             }
+        };
+
+        colors.iter().any(|color| {
+            let is_used = Cell::new(false);
+            let _ = parse_color(color, &|found_keyword| {
+                if found_keyword == keyword {
+                    is_used.set(true);
+                }
+                is_color_keyword(found_keyword).then(D2D1_COLOR_F::default)
+            });
+
+            is_used.get()
+        })
+    }
+
+    // The tracking window is only needed for the "window" color keyword
+    pub fn to_color_brush(&self, is_active_color: bool, tracking_window: HWND) -> ColorBrush {
+        match self {
+            ColorBrushConfig::Solid(solid_config) => ColorBrush::Solid(SolidBrush {
+                color: get_color(solid_config, is_active_color, tracking_window),
+                brush: None,
+                is_none: is_none_color(solid_config),
+            }),
             ColorBrushConfig::Gradient(gradient_config) => {
                 // We use 'step' to calculate the position of each color in the gradient below
                 let step = 1.0 / (gradient_config.colors.len() - 1) as f32;
 
-                let gradient_stops = gradient_config
+                let gradient_stops: Vec<D2D1_GRADIENT_STOP> = gradient_config
                     .clone()
                     .colors
                     .into_iter()
                     .enumerate()
                     .map(|(i, color)| D2D1_GRADIENT_STOP {
                         position: i as f32 * step,
-                        color: if color == "accent" {
-                            get_accent_color(is_active_color)
-                        } else {
-                            get_color_from_hex(color.as_str())
-                        },
+                        color: get_color(&color, is_active_color, tracking_window),
                     })
                     .collect();
 
+                let is_perimeter = matches!(
+                    gradient_config.direction,
+                    GradientDirection::Angle(ref angle) if angle.trim() == "perimeter"
+                );
+
                 let direction = match gradient_config.direction {
+                    // Perimeter gradients are drawn segment by segment, so the coordinates only
+                    // matter for the underlying linear gradient brush (which we still use to keep
+                    // track of opacity and such)
+                    GradientDirection::Angle(_) if is_perimeter => GradientCoordinates {
+                        start: [0.0, 0.0],
+                        end: [1.0, 0.0],
+                    },
                     // We'll convert an angle to coordinates by representing the angle as a linear
                     // line, then checking for collisions within the unit square bounded by (0.0,
                     // 0.0) and (1.0, 1.0)
@@ -190,15 +656,40 @@ impl ColorBrushConfig {
 
                         GradientCoordinates { start, end }
                     }
-                    GradientDirection::Coordinates(ref coordinates) => coordinates.clone(),
+                    // Invalid coordinates have already been reported by validate()
+                    GradientDirection::Coordinates(ref coordinates) => {
+                        coordinates.resolve().unwrap_or(GradientCoordinates {
+                            start: [0.0, 0.0],
+                            end: [1.0, 0.0],
+                        })
+                    }
+                };
+
+                let gradient_stops = match gradient_config.dither && !is_perimeter {
+                    true => dither_gradient_stops(&gradient_stops),
+                    false => gradient_stops,
                 };
 
                 ColorBrush::Gradient(GradientBrush {
                     gradient_stops,
                     direction,
                     brush: None,
+                    is_perimeter,
                 })
             }
+            ColorBrushConfig::Pattern(pattern_config) => ColorBrush::Pattern(PatternBrush {
+                kind: pattern_config.pattern,
+                colors: pattern_config
+                    .colors
+                    .each_ref()
+                    .map(|color| get_color(color, is_active_color, tracking_window)),
+                width: pattern_config.width,
+                angle: pattern_config.angle,
+                brush: None,
+                transform: Cell::new(Matrix3x2::identity()),
+            }),
+            // This has already been reported by validate()
+            ColorBrushConfig::PartialGradient(_) => ColorBrush::default(),
         }
     }
 }
@@ -216,6 +707,37 @@ impl Line {
 }
 
 impl ColorBrush {
+    // Desaturates and darkens the colors by 'amount' (0.0 to 1.0). This must be called before
+    // init_brush() because it doesn't touch the underlying ID2D1Brush.
+    pub fn dim(&mut self, amount: f32) {
+        if amount <= 0.0 {
+            return;
+        }
+
+        match self {
+            ColorBrush::Solid(solid) => solid.color = dim_color(&solid.color, amount),
+            ColorBrush::Gradient(gradient) => {
+                for stop in gradient.gradient_stops.iter_mut() {
+                    stop.color = dim_color(&stop.color, amount);
+                }
+            }
+            ColorBrush::Pattern(pattern) => {
+                for color in pattern.colors.iter_mut() {
+                    *color = dim_color(color, amount);
+                }
+            }
+        }
+    }
+
+    // Drops the ID2D1Brush (and with it, the brush's reference to the render target)
+    pub fn release_brush(&mut self) {
+        match self {
+            ColorBrush::Solid(solid) => solid.brush = None,
+            ColorBrush::Gradient(gradient) => gradient.brush = None,
+            ColorBrush::Pattern(pattern) => pattern.brush = None,
+        }
+    }
+
     // NOTE: ID2D1DeviceContext implements From<&ID2D1DeviceContext> for &ID2D1RenderTarget
     pub fn init_brush(
         &mut self,
@@ -263,11 +785,78 @@ impl ColorBrush {
 
                 gradient.brush = Some(id2d1_brush);
 
+                Ok(())
+            },
+            ColorBrush::Pattern(pattern) => unsafe {
+                let (tile_width, tile_height, pixels) = pattern.create_tile();
+
+                let bitmap_properties = D2D1_BITMAP_PROPERTIES {
+                    pixelFormat: D2D1_PIXEL_FORMAT {
+                        format: DXGI_FORMAT_B8G8R8A8_UNORM,
+                        alphaMode: D2D1_ALPHA_MODE_PREMULTIPLIED,
+                    },
+                    dpiX: 96.0,
+                    dpiY: 96.0,
+                };
+                let bitmap = renderer.CreateBitmap(
+                    D2D_SIZE_U {
+                        width: tile_width,
+                        height: tile_height,
+                    },
+                    Some(pixels.as_ptr() as _),
+                    tile_width * 4,
+                    &bitmap_properties,
+                )?;
+
+                let bitmap_brush_properties = D2D1_BITMAP_BRUSH_PROPERTIES {
+                    extendModeX: D2D1_EXTEND_MODE_WRAP,
+                    extendModeY: D2D1_EXTEND_MODE_WRAP,
+                    interpolationMode: D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
+                };
+
+                pattern.transform.set(brush_properties.transform);
+                let brush_properties = D2D1_BRUSH_PROPERTIES {
+                    opacity: brush_properties.opacity,
+                    transform: pattern.get_base_transform() * brush_properties.transform,
+                };
+
+                let id2d1_brush = renderer.CreateBitmapBrush(
+                    &bitmap,
+                    Some(&bitmap_brush_properties),
+                    Some(&brush_properties),
+                )?;
+
+                pattern.brush = Some(id2d1_brush);
+
                 Ok(())
             },
         }
     }
 
+    pub fn is_none(&self) -> bool {
+        matches!(self, ColorBrush::Solid(solid) if solid.is_none)
+    }
+
+    // Gradients will return their first color. This is for places that only support solid colors.
+    pub fn get_primary_color(&self) -> D2D1_COLOR_F {
+        match self {
+            ColorBrush::Solid(solid) => solid.color,
+            ColorBrush::Gradient(gradient) => gradient
+                .gradient_stops
+                .first()
+                .map(|stop| stop.color)
+                .unwrap_or_default(),
+            ColorBrush::Pattern(pattern) => pattern.colors[0],
+        }
+    }
+
+    pub fn get_solid_color(&self) -> Option<D2D1_COLOR_F> {
+        match self {
+            ColorBrush::Solid(solid) => Some(solid.color),
+            ColorBrush::Gradient(_) | ColorBrush::Pattern(_) => None,
+        }
+    }
+
     pub fn get_brush(&self) -> Option<&ID2D1Brush> {
         match self {
             ColorBrush::Solid(solid) => solid.brush.as_ref().map(|id2d1_brush| id2d1_brush.into()),
@@ -275,6 +864,9 @@ impl ColorBrush {
                 .brush
                 .as_ref()
                 .map(|id2d1_brush| id2d1_brush.into()),
+            ColorBrush::Pattern(pattern) => {
+                pattern.brush.as_ref().map(|id2d1_brush| id2d1_brush.into())
+            }
         }
     }
 
@@ -296,6 +888,14 @@ impl ColorBrush {
 
                 unsafe { id2d1_brush.SetOpacity(opacity) };
             }
+            ColorBrush::Pattern(pattern) => {
+                let id2d1_brush = pattern
+                    .brush
+                    .as_ref()
+                    .context("brush has not been created yet")?;
+
+                unsafe { id2d1_brush.SetOpacity(opacity) };
+            }
         }
 
         Ok(())
@@ -319,6 +919,14 @@ impl ColorBrush {
 
                 Ok(unsafe { id2d1_brush.GetOpacity() })
             }
+            ColorBrush::Pattern(pattern) => {
+                let id2d1_brush = pattern
+                    .brush
+                    .as_ref()
+                    .context("brush has not been created yet")?;
+
+                Ok(unsafe { id2d1_brush.GetOpacity() })
+            }
         }
     }
 
@@ -334,6 +942,14 @@ impl ColorBrush {
                     unsafe { id2d1_brush.SetTransform(transform) };
                 }
             }
+            ColorBrush::Pattern(pattern) => {
+                if let Some(ref id2d1_brush) = pattern.brush {
+                    pattern.transform.set(*transform);
+                    unsafe {
+                        id2d1_brush.SetTransform(&(pattern.get_base_transform() * transform))
+                    };
+                }
+            }
         }
     }
 
@@ -351,11 +967,80 @@ impl ColorBrush {
 
                 transform
             }),
+            // We leave out the pattern's own rotation so that this can be handed to other brushes
+            ColorBrush::Pattern(pattern) => pattern.brush.as_ref().map(|_| pattern.transform.get()),
+        }
+    }
+}
+
+impl PatternBrush {
+    fn get_base_transform(&self) -> Matrix3x2 {
+        Matrix3x2::rotation(self.angle)
+    }
+
+    // Returns the width, height, and premultiplied BGRA pixels of a single tile of the pattern
+    fn create_tile(&self) -> (u32, u32, Vec<u32>) {
+        let size = self.width.round().max(1.0) as u32;
+        let [first, second] = self.colors.map(|color| to_premultiplied_bgra(&color));
+
+        match self.kind {
+            // Since the stripes only vary horizontally, the tile only needs to be 1 pixel tall
+            PatternKind::Stripes => {
+                let pixels = (0..size * 2)
+                    .map(|x| if x < size { first } else { second })
+                    .collect();
+
+                (size * 2, 1, pixels)
+            }
+            PatternKind::Checkerboard => {
+                let pixels = (0..size * 2)
+                    .flat_map(|y| (0..size * 2).map(move |x| (x, y)))
+                    .map(|(x, y)| {
+                        if (x < size) == (y < size) {
+                            first
+                        } else {
+                            second
+                        }
+                    })
+                    .collect();
+
+                (size * 2, size * 2, pixels)
+            }
         }
     }
 }
 
+fn to_premultiplied_bgra(color: &D2D1_COLOR_F) -> u32 {
+    let to_byte = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u32;
+    let a = color.a.clamp(0.0, 1.0);
+
+    (to_byte(a) << 24)
+        | (to_byte(color.r * a) << 16)
+        | (to_byte(color.g * a) << 8)
+        | to_byte(color.b * a)
+}
+
 impl GradientBrush {
+    pub fn is_perimeter(&self) -> bool {
+        self.is_perimeter
+    }
+
+    // Samples the gradient at 't' (0.0 to 1.0) along the perimeter. The colors wrap back around to
+    // the first color so that there's no visible seam where the perimeter starts and ends.
+    pub fn sample_perimeter_color(&self, t: f32) -> D2D1_COLOR_F {
+        let num_colors = self.gradient_stops.len();
+        if num_colors == 0 {
+            return D2D1_COLOR_F::default();
+        }
+
+        let scaled = t.rem_euclid(1.0) * num_colors as f32;
+        let i = (scaled.floor() as usize).min(num_colors - 1);
+        let start = &self.gradient_stops[i].color;
+        let end = &self.gradient_stops[(i + 1) % num_colors].color;
+
+        ColorInterpolation::Rgb.interpolate(start, end, scaled - i as f32)
+    }
+
     pub fn update_start_end_points(&self, window_rect: &RECT) {
         let width = (window_rect.right - window_rect.left) as f32;
         let height = (window_rect.bottom - window_rect.top) as f32;
@@ -380,7 +1065,315 @@ impl GradientBrush {
     }
 }
 
+// Resamples the gradient into a lot of stops and adds a tiny bit of noise to each one. This breaks
+// up the visible bands that subtle gradients tend to have on 8-bit displays.
+fn dither_gradient_stops(stops: &[D2D1_GRADIENT_STOP]) -> Vec<D2D1_GRADIENT_STOP> {
+    const NUM_STOPS: usize = 256;
+    const NOISE_AMOUNT: f32 = 1.0 / 255.0; // About one step of an 8-bit color channel
+
+    // Xorshift with a fixed seed, so the noise doesn't change every time the brush is recreated
+    let mut state: u32 = 0x9E3779B9;
+    let mut next_noise = || {
+        state ^= state << 13;
+        state ^= state >> 17;
+        state ^= state << 5;
+
+        (state as f32 / u32::MAX as f32 - 0.5) * NOISE_AMOUNT
+    };
+
+    (0..NUM_STOPS)
+        .map(|i| {
+            let position = i as f32 / (NUM_STOPS - 1) as f32;
+            let color = sample_gradient_stops(stops, position);
+
+            D2D1_GRADIENT_STOP {
+                position,
+                color: D2D1_COLOR_F {
+                    r: (color.r + next_noise()).clamp(0.0, 1.0),
+                    g: (color.g + next_noise()).clamp(0.0, 1.0),
+                    b: (color.b + next_noise()).clamp(0.0, 1.0),
+                    a: color.a,
+                },
+            }
+        })
+        .collect()
+}
+
+fn sample_gradient_stops(stops: &[D2D1_GRADIENT_STOP], position: f32) -> D2D1_COLOR_F {
+    let (Some(first), Some(last)) = (stops.first(), stops.last()) else {
+        return D2D1_COLOR_F::default();
+    };
+
+    if position <= first.position {
+        return first.color;
+    }
+
+    for window in stops.windows(2) {
+        let [start, end] = window else {
+            continue;
+        };
+
+        if position <= end.position {
+            let t = (position - start.position) / (end.position - start.position);
+            return ColorInterpolation::Rgb.interpolate(&start.color, &end.color, t);
+        }
+    }
+
+    last.color
+}
+
+fn get_color(color: &str, is_active_color: bool, tracking_window: HWND) -> D2D1_COLOR_F {
+    let resolve_keyword = |keyword: &str| match keyword {
+        "accent" => Some(get_accent_color(is_active_color)),
+        "window" => Some(get_window_color(is_active_color, tracking_window)),
+        "auto" => Some(get_auto_color(is_active_color, tracking_window)),
+        "highlight" => Some(get_system_color(COLOR_HIGHLIGHT)),
+        "graytext" => Some(get_system_color(COLOR_GRAYTEXT)),
+        "wallpaper" => Some(get_wallpaper_color(is_active_color)),
+        // "none" is only special when used as an entire color; anywhere else (e.g. in a gradient),
+        // it's just a fully transparent color
+        "none" | "transparent" => Some(D2D1_COLOR_F::default()),
+        _ => get_accent_companion_offset(keyword)
+            .map(|hue_offset| rotate_hue(&get_accent_color(is_active_color), hue_offset)),
+    };
+
+    parse_color(color, &resolve_keyword).unwrap_or_else(|err| {
+        error!("could not parse color '{}': {err:#}", color.trim());
+        D2D1_COLOR_F::default()
+    })
+}
+
+// NOTE: keep this in sync with the keywords in get_color()
+fn is_color_keyword(color: &str) -> bool {
+    matches!(
+        color,
+        "accent"
+            | "window"
+            | "auto"
+            | "highlight"
+            | "graytext"
+            | "wallpaper"
+            | "none"
+            | "transparent"
+    ) || get_accent_companion_offset(color).is_some()
+}
+
+// Shorthands like "accent_complement" for "accent.complement"
+fn get_accent_companion_offset(keyword: &str) -> Option<f32> {
+    keyword.strip_prefix("accent_").and_then(get_harmony_offset)
+}
+
+// Color harmonies, as hue rotations around the color wheel. These can be applied to any color
+// using a suffix, e.g. "accent.complement" or "#ff0000.triad1".
+fn get_harmony_offset(harmony: &str) -> Option<f32> {
+    match harmony {
+        "complement" => Some(180.0),
+        "triad1" => Some(120.0),
+        "triad2" => Some(240.0),
+        "analogous1" => Some(-30.0),
+        "analogous2" => Some(30.0),
+        "split1" => Some(150.0),
+        "split2" => Some(210.0),
+        _ => None,
+    }
+}
+
+// Checks that a color can be parsed without resolving any keywords (those depend on the system
+// and the tracking window, so they're checked by name only)
+pub fn validate_color(color: &str) -> anyhow::Result<()> {
+    parse_color(color, &|keyword| {
+        is_color_keyword(keyword).then(D2D1_COLOR_F::default)
+    })
+    .map(|_| ())
+}
+
+fn parse_color(
+    color: &str,
+    resolve_keyword: &dyn Fn(&str) -> Option<D2D1_COLOR_F>,
+) -> anyhow::Result<D2D1_COLOR_F> {
+    let color = color.trim();
+
+    // Check for modifier functions like lighten(accent, 20%)
+    if let Some((name, args)) = color
+        .split_once('(')
+        .and_then(|(name, rest)| Some((name.trim(), rest.strip_suffix(')')?)))
+    {
+        return apply_color_function(name, args, resolve_keyword);
+    }
+
+    // Check for harmonies like accent.complement
+    if let Some((base_color, hue_offset)) = color
+        .rsplit_once('.')
+        .and_then(|(base_color, harmony)| Some((base_color, get_harmony_offset(harmony)?)))
+    {
+        return Ok(rotate_hue(
+            &parse_color(base_color, resolve_keyword)?,
+            hue_offset,
+        ));
+    }
+
+    if let Some(keyword_color) = resolve_keyword(color) {
+        return Ok(keyword_color);
+    }
+
+    let hex = color.strip_prefix('#').context(format!(
+        "unknown color '{color}'; expected a hex code (e.g. #ff0000) or a keyword like accent"
+    ))?;
+    parse_hex(hex)
+}
+
+fn is_none_color(color: &str) -> bool {
+    matches!(color.trim(), "none" | "transparent")
+}
+
+fn apply_color_function(
+    name: &str,
+    args: &str,
+    resolve_keyword: &dyn Fn(&str) -> Option<D2D1_COLOR_F>,
+) -> anyhow::Result<D2D1_COLOR_F> {
+    let args = split_color_args(args);
+    let [color_arg, amount_arg] = args[..] else {
+        return Err(anyhow!(
+            "{name}() expects 2 arguments but found {}",
+            args.len()
+        ));
+    };
+
+    // The color argument may itself be another function, e.g. alpha(lighten(accent, 10%), 0.5)
+    let color = parse_color(color_arg, resolve_keyword)?;
+    let amount = parse_amount(amount_arg)?;
+
+    match name {
+        "lighten" => Ok(adjust_lightness(&color, amount)),
+        "darken" => Ok(adjust_lightness(&color, -amount)),
+        "alpha" => Ok(D2D1_COLOR_F {
+            a: amount.clamp(0.0, 1.0),
+            ..color
+        }),
+        _ => Err(anyhow!("unknown color function '{name}'")),
+    }
+}
+
+// Splits on commas that aren't nested inside parentheses
+fn split_color_args(args: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut depth = 0;
+    let mut start = 0;
+
+    for (i, c) in args.char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => depth -= 1,
+            ',' if depth == 0 => {
+                result.push(args[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    result.push(args[start..].trim());
+
+    result
+}
+
+// Accepts either a percentage (e.g. "20%") or a fraction (e.g. "0.2")
+fn parse_amount(s: &str) -> anyhow::Result<f32> {
+    match s.strip_suffix('%') {
+        Some(percent) => percent.trim().parse::<f32>().map(|p| p / 100.0),
+        None => s.parse::<f32>(),
+    }
+    .map_err(|err| anyhow!("invalid amount '{s}': {err}"))
+}
+
+fn adjust_lightness(color: &D2D1_COLOR_F, amount: f32) -> D2D1_COLOR_F {
+    let (h, s, l) = rgb_to_hsl(color);
+    hsl_to_rgb(h, s, (l + amount).clamp(0.0, 1.0), color.a)
+}
+
+fn rotate_hue(color: &D2D1_COLOR_F, degrees: f32) -> D2D1_COLOR_F {
+    let (h, s, l) = rgb_to_hsl(color);
+    hsl_to_rgb((h + degrees).rem_euclid(360.0), s, l, color.a)
+}
+
+fn borrow_transparent_channels(color: &D2D1_COLOR_F, other: &D2D1_COLOR_F) -> D2D1_COLOR_F {
+    match color.a <= 0.0 && other.a > 0.0 {
+        true => D2D1_COLOR_F { a: 0.0, ..*other },
+        false => *color,
+    }
+}
+
+// The config options that change how colors get resolved. We keep a copy of these separately so
+// that resolving colors never has to lock the config.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ColorSettings {
+    pub alpha_mode: AlphaMode,
+    pub legacy_accent_color: bool,
+    // None if accent normalization is disabled
+    pub accent_normalization: Option<AccentNormalizationConfig>,
+}
+
+static COLOR_SETTINGS: RwLock<ColorSettings> = RwLock::new(ColorSettings {
+    alpha_mode: AlphaMode::Premultiplied,
+    legacy_accent_color: false,
+    accent_normalization: None,
+});
+
+pub fn set_color_settings(color_settings: ColorSettings) {
+    *COLOR_SETTINGS.write().unwrap() = color_settings;
+}
+
+fn get_color_settings() -> ColorSettings {
+    *COLOR_SETTINGS.read().unwrap()
+}
+
 fn get_accent_color(is_active_color: bool) -> D2D1_COLOR_F {
+    let color_settings = get_color_settings();
+    let accent_color = if color_settings.legacy_accent_color {
+        get_colorization_color()
+    } else {
+        get_ui_settings_accent_color().unwrap_or_else(|err| {
+            debug!("could not get accent color from UISettings; using colorization color: {err}");
+            get_colorization_color()
+        })
+    };
+    let accent_color = match color_settings.accent_normalization {
+        Some(normalization) => normalization.normalize(&accent_color),
+        None => accent_color,
+    };
+
+    if is_active_color {
+        accent_color
+    } else {
+        get_inactive_variant(&accent_color)
+    }
+}
+
+// Creating a UISettings is relatively expensive and every border resolves its colors on its own,
+// so we keep one around. It's agile, so any thread can use it.
+static UI_SETTINGS: Mutex<Option<UISettings>> = Mutex::new(None);
+
+// This is the actual accent color the user picked in Settings > Personalization > Colors
+fn get_ui_settings_accent_color() -> windows::core::Result<D2D1_COLOR_F> {
+    let ui_settings = {
+        let mut ui_settings_opt = UI_SETTINGS.lock().unwrap();
+        match ui_settings_opt.as_ref() {
+            Some(ui_settings) => ui_settings.clone(),
+            None => ui_settings_opt.insert(UISettings::new()?).clone(),
+        }
+    };
+    let color = ui_settings.GetColorValue(UIColorType::Accent)?;
+
+    Ok(D2D1_COLOR_F {
+        r: color.R as f32 / 255.0,
+        g: color.G as f32 / 255.0,
+        b: color.B as f32 / 255.0,
+        a: 1.0,
+    })
+}
+
+// The colorization color is what DWM uses for titlebars and window borders. On some systems it
+// differs from the accent color (e.g. with certain themes or when transparency is involved).
+fn get_colorization_color() -> D2D1_COLOR_F {
     let mut pcr_colorization: u32 = 0;
     let mut pf_opaqueblend: BOOL = FALSE;
 
@@ -393,31 +1386,127 @@ fn get_accent_color(is_active_color: bool) -> D2D1_COLOR_F {
     let accent_red = ((pcr_colorization & 0x00FF0000) >> 16) as f32 / 255.0;
     let accent_green = ((pcr_colorization & 0x0000FF00) >> 8) as f32 / 255.0;
     let accent_blue = (pcr_colorization & 0x000000FF) as f32 / 255.0;
-    let accent_avg = (accent_red + accent_green + accent_blue) / 3.0;
+
+    D2D1_COLOR_F {
+        r: accent_red,
+        g: accent_green,
+        b: accent_blue,
+        a: 1.0,
+    }
+}
+
+// Uses the caption color that the app chose for its titlebar. If the app didn't set one, we'll
+// just fall back to the accent color.
+fn get_window_color(is_active_color: bool, tracking_window: HWND) -> D2D1_COLOR_F {
+    let mut colorref = COLORREF::default();
+
+    let result = unsafe {
+        DwmGetWindowAttribute(
+            tracking_window,
+            DWMWA_CAPTION_COLOR,
+            ptr::addr_of_mut!(colorref) as _,
+            size_of::<COLORREF>() as u32,
+        )
+    };
+
+    // DWMWA_COLOR_DEFAULT and DWMWA_COLOR_NONE aren't actual colors
+    if result.is_err() || colorref.0 == DWMWA_COLOR_DEFAULT || colorref.0 == DWMWA_COLOR_NONE {
+        debug!("could not get caption color for {tracking_window:?}; using accent color instead");
+        return get_accent_color(is_active_color);
+    }
+
+    // COLORREF is in the format 0x00BBGGRR
+    let window_color = D2D1_COLOR_F {
+        r: (colorref.0 & 0x000000FF) as f32 / 255.0,
+        g: ((colorref.0 & 0x0000FF00) >> 8) as f32 / 255.0,
+        b: ((colorref.0 & 0x00FF0000) >> 16) as f32 / 255.0,
+        a: 1.0,
+    };
 
     if is_active_color {
-        D2D1_COLOR_F {
-            r: accent_red,
-            g: accent_green,
-            b: accent_blue,
-            a: 1.0,
-        }
+        window_color
     } else {
-        D2D1_COLOR_F {
-            r: accent_avg / 1.5 + accent_red / 10.0,
-            g: accent_avg / 1.5 + accent_green / 10.0,
-            b: accent_avg / 1.5 + accent_blue / 10.0,
-            a: 1.0,
+        get_inactive_variant(&window_color)
+    }
+}
+
+// The wallpaper's average color. Until it's been sampled (or if the desktop is a solid color), we
+// use the desktop's background color instead.
+fn get_wallpaper_color(is_active_color: bool) -> D2D1_COLOR_F {
+    let wallpaper_color =
+        wallpaper::get_wallpaper_color().unwrap_or_else(|| get_system_color(COLOR_BACKGROUND));
+
+    if is_active_color {
+        wallpaper_color
+    } else {
+        get_inactive_variant(&wallpaper_color)
+    }
+}
+
+// Mostly useful in high contrast mode, where these follow the high contrast theme's colors
+fn get_system_color(index: SYS_COLOR_INDEX) -> D2D1_COLOR_F {
+    // GetSysColor returns a COLORREF in the format 0x00BBGGRR
+    let colorref = unsafe { GetSysColor(index) };
+
+    D2D1_COLOR_F {
+        r: (colorref & 0x000000FF) as f32 / 255.0,
+        g: ((colorref & 0x0000FF00) >> 8) as f32 / 255.0,
+        b: ((colorref & 0x00FF0000) >> 16) as f32 / 255.0,
+        a: 1.0,
+    }
+}
+
+// Gives every process its own color by hashing the process name into a hue
+fn get_auto_color(is_active_color: bool, tracking_window: HWND) -> D2D1_COLOR_F {
+    let process_name = match get_window_process_name(tracking_window) {
+        Ok(process_name) => process_name,
+        Err(err) => {
+            debug!("could not get process name for {tracking_window:?}; using accent color: {err}");
+            return get_accent_color(is_active_color);
         }
+    };
+
+    let auto_color = get_color_from_name(&process_name);
+
+    if is_active_color {
+        auto_color
+    } else {
+        get_inactive_variant(&auto_color)
     }
 }
 
-fn get_color_from_hex(hex: &str) -> D2D1_COLOR_F {
-    let s = hex.strip_prefix("#").unwrap_or_default();
-    parse_hex(s).unwrap_or_else(|err| {
-        error!("could not parse hex: {err}");
-        D2D1_COLOR_F::default()
-    })
+fn get_color_from_name(name: &str) -> D2D1_COLOR_F {
+    // We use FNV-1a instead of std's DefaultHasher because DefaultHasher isn't guaranteed to give
+    // the same results across Rust versions, and we want the colors to stay the same
+    let hash = name
+        .to_lowercase()
+        .bytes()
+        .fold(0x811c9dc5_u32, |hash, byte| {
+            (hash ^ byte as u32).wrapping_mul(0x01000193)
+        });
+
+    let hue = (hash % 360) as f32;
+    hsl_to_rgb(hue, 0.65, 0.6, 1.0)
+}
+
+// Desaturates the color so that inactive windows are less eye-catching
+fn get_inactive_variant(color: &D2D1_COLOR_F) -> D2D1_COLOR_F {
+    let avg = (color.r + color.g + color.b) / 3.0;
+
+    D2D1_COLOR_F {
+        r: avg / 1.5 + color.r / 10.0,
+        g: avg / 1.5 + color.g / 10.0,
+        b: avg / 1.5 + color.b / 10.0,
+        a: color.a,
+    }
+}
+
+fn dim_color(color: &D2D1_COLOR_F, amount: f32) -> D2D1_COLOR_F {
+    let amount = amount.clamp(0.0, 1.0);
+    let (h, s, l) = rgb_to_hsl(color);
+
+    // We don't darken as much as we desaturate, otherwise the border becomes hard to see
+    hsl_to_rgb(h, s * (1.0 - amount), l * (1.0 - amount / 2.0), color.a)
 }
 
 fn parse_hex(s: &str) -> anyhow::Result<D2D1_COLOR_F> {
@@ -468,6 +1557,124 @@ fn parse_hex(s: &str) -> anyhow::Result<D2D1_COLOR_F> {
     }
 }
 
+// The hue is meaningless for grays, so we borrow the other color's hue to avoid sweeping
+// through unrelated hues when blending to or from a gray
+fn fix_achromatic_hues(h1: f32, chroma1: f32, h2: f32, chroma2: f32) -> (f32, f32) {
+    const EPSILON: f32 = 1e-4;
+
+    match (chroma1 < EPSILON, chroma2 < EPSILON) {
+        (true, false) => (h2, h2),
+        (false, true) => (h1, h1),
+        _ => (h1, h2),
+    }
+}
+
+// Interpolates between two hues (in degrees) along the shortest arc
+fn lerp_hue(t: f32, h1: f32, h2: f32) -> f32 {
+    let diff = (h2 - h1 + 540.0).rem_euclid(360.0) - 180.0;
+    (h1 + diff * t).rem_euclid(360.0)
+}
+
+fn rgb_to_hsl(color: &D2D1_COLOR_F) -> (f32, f32, f32) {
+    let max = color.r.max(color.g).max(color.b);
+    let min = color.r.min(color.g).min(color.b);
+    let l = (max + min) / 2.0;
+    let delta = max - min;
+
+    if delta == 0.0 {
+        return (0.0, 0.0, l);
+    }
+
+    let s = delta / (1.0 - (2.0 * l - 1.0).abs());
+    let h = if max == color.r {
+        60.0 * ((color.g - color.b) / delta).rem_euclid(6.0)
+    } else if max == color.g {
+        60.0 * ((color.b - color.r) / delta + 2.0)
+    } else {
+        60.0 * ((color.r - color.g) / delta + 4.0)
+    };
+
+    (h, s, l)
+}
+
+fn hsl_to_rgb(h: f32, s: f32, l: f32, a: f32) -> D2D1_COLOR_F {
+    let c = (1.0 - (2.0 * l - 1.0).abs()) * s;
+    let x = c * (1.0 - ((h / 60.0).rem_euclid(2.0) - 1.0).abs());
+    let m = l - c / 2.0;
+
+    let (r, g, b) = match h.rem_euclid(360.0) {
+        0.0..60.0 => (c, x, 0.0),
+        60.0..120.0 => (x, c, 0.0),
+        120.0..180.0 => (0.0, c, x),
+        180.0..240.0 => (0.0, x, c),
+        240.0..300.0 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+
+    D2D1_COLOR_F {
+        r: (r + m).clamp(0.0, 1.0),
+        g: (g + m).clamp(0.0, 1.0),
+        b: (b + m).clamp(0.0, 1.0),
+        a,
+    }
+}
+
+fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn linear_to_srgb(c: f32) -> f32 {
+    if c <= 0.0031308 {
+        12.92 * c
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// See https://bottosson.github.io/posts/oklab/ for the conversion matrices
+fn rgb_to_oklch(color: &D2D1_COLOR_F) -> (f32, f32, f32) {
+    let r = srgb_to_linear(color.r);
+    let g = srgb_to_linear(color.g);
+    let b = srgb_to_linear(color.b);
+
+    let l = (0.41222147 * r + 0.53633254 * g + 0.051445993 * b).cbrt();
+    let m = (0.2119035 * r + 0.6806995 * g + 0.10739696 * b).cbrt();
+    let s = (0.08830246 * r + 0.28171884 * g + 0.6299787 * b).cbrt();
+
+    let lightness = 0.21045426 * l + 0.7936178 * m - 0.004072047 * s;
+    let ok_a = 1.9779985 * l - 2.4285922 * m + 0.4505937 * s;
+    let ok_b = 0.025904037 * l + 0.78277177 * m - 0.80867577 * s;
+
+    let chroma = (ok_a * ok_a + ok_b * ok_b).sqrt();
+    let hue = ok_b.atan2(ok_a).to_degrees().rem_euclid(360.0);
+
+    (lightness, chroma, hue)
+}
+
+fn oklch_to_rgb(lightness: f32, chroma: f32, hue: f32, a: f32) -> D2D1_COLOR_F {
+    let ok_a = chroma * hue.to_radians().cos();
+    let ok_b = chroma * hue.to_radians().sin();
+
+    let l = (lightness + 0.39633778 * ok_a + 0.21580376 * ok_b).powi(3);
+    let m = (lightness - 0.105561346 * ok_a - 0.06385417 * ok_b).powi(3);
+    let s = (lightness - 0.08948418 * ok_a - 1.2914855 * ok_b).powi(3);
+
+    let r = 4.0767417 * l - 3.3077116 * m + 0.23096994 * s;
+    let g = -1.268438 * l + 2.6097574 * m - 0.34131938 * s;
+    let b = -0.0041960863 * l - 0.7034186 * m + 1.7076147 * s;
+
+    D2D1_COLOR_F {
+        r: linear_to_srgb(r).clamp(0.0, 1.0),
+        g: linear_to_srgb(g).clamp(0.0, 1.0),
+        b: linear_to_srgb(b).clamp(0.0, 1.0),
+        a,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -477,8 +1684,9 @@ mod tests {
         let color_brush_config = ColorBrushConfig::Gradient(GradientBrushConfig {
             colors: vec!["#ffffff".to_string(), "#000000".to_string()],
             direction: GradientDirection::Angle("90deg".to_string()),
+            dither: false,
         });
-        let color_brush = color_brush_config.to_color_brush(true);
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
 
         if let ColorBrush::Gradient(ref gradient) = color_brush {
             assert!(gradient.direction.start == [0.5, 1.0]);
@@ -495,8 +1703,9 @@ mod tests {
         let color_brush_config = ColorBrushConfig::Gradient(GradientBrushConfig {
             colors: vec!["#ffffff".to_string(), "#000000".to_string()],
             direction: GradientDirection::Angle("-90deg".to_string()),
+            dither: false,
         });
-        let color_brush = color_brush_config.to_color_brush(true);
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
 
         if let ColorBrush::Gradient(ref gradient) = color_brush {
             assert!(gradient.direction.start == [0.5, 0.0]);
@@ -513,8 +1722,9 @@ mod tests {
         let color_brush_config = ColorBrushConfig::Gradient(GradientBrushConfig {
             colors: vec!["#ffffff".to_string(), "#000000".to_string()],
             direction: GradientDirection::Angle("-540deg".to_string()),
+            dither: false,
         });
-        let color_brush = color_brush_config.to_color_brush(true);
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
 
         if let ColorBrush::Gradient(ref gradient) = color_brush {
             assert!(gradient.direction.start == [1.0, 0.5]);
@@ -529,7 +1739,7 @@ mod tests {
     #[test]
     fn test_color_parser_translucent() -> anyhow::Result<()> {
         let color_brush_config = ColorBrushConfig::Solid("#ffffff80".to_string());
-        let color_brush = color_brush_config.to_color_brush(true);
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
 
         if let ColorBrush::Solid(ref solid) = color_brush {
             assert!(
@@ -547,4 +1757,363 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_color_modifier_functions() {
+        let hwnd = HWND::default();
+
+        // #ff0000 has a lightness of 50%
+        assert_color_eq(
+            get_color("lighten(#ff0000, 25%)", true, hwnd),
+            D2D1_COLOR_F {
+                r: 1.0,
+                g: 0.5,
+                b: 0.5,
+                a: 1.0,
+            },
+        );
+        assert_color_eq(
+            get_color("darken(#ff0000, 0.25)", true, hwnd),
+            D2D1_COLOR_F {
+                r: 0.5,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0,
+            },
+        );
+        assert_color_eq(
+            get_color("alpha(darken(#ff0000, 25%), 0.6)", true, hwnd),
+            D2D1_COLOR_F {
+                r: 0.5,
+                g: 0.0,
+                b: 0.0,
+                a: 0.6,
+            },
+        );
+
+        // Invalid functions fall back to the default color
+        assert_color_eq(
+            get_color("saturate(#ff0000, 10%)", true, hwnd),
+            D2D1_COLOR_F::default(),
+        );
+    }
+
+    #[test]
+    fn test_auto_color_is_stable() {
+        let firefox = get_color_from_name("firefox");
+
+        // Process names on Windows are case-insensitive, so the colors should be too
+        assert_color_eq(get_color_from_name("Firefox"), firefox);
+        assert!(get_color_from_name("chrome") != firefox);
+
+        let (_, s, l) = rgb_to_hsl(&firefox);
+        assert!((s - 0.65).abs() < 0.01 && (l - 0.6).abs() < 0.01);
+    }
+
+    fn assert_color_eq(actual: D2D1_COLOR_F, expected: D2D1_COLOR_F) {
+        let close = |x: f32, y: f32| (x - y).abs() < 0.01;
+        assert!(
+            close(actual.r, expected.r)
+                && close(actual.g, expected.g)
+                && close(actual.b, expected.b)
+                && close(actual.a, expected.a),
+            "expected {expected:?}, got {actual:?}"
+        );
+    }
+
+    #[test]
+    fn test_color_space_round_trip() {
+        let color = D2D1_COLOR_F {
+            r: 0.384,
+            g: 0.455,
+            b: 0.906,
+            a: 1.0,
+        };
+
+        let (h, s, l) = rgb_to_hsl(&color);
+        assert_color_eq(hsl_to_rgb(h, s, l, 1.0), color);
+
+        let (l, c, h) = rgb_to_oklch(&color);
+        assert_color_eq(oklch_to_rgb(l, c, h, 1.0), color);
+    }
+
+    #[test]
+    fn test_hsl_interpolation_keeps_saturation() {
+        let red = D2D1_COLOR_F {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let blue = D2D1_COLOR_F {
+            r: 0.0,
+            g: 0.0,
+            b: 1.0,
+            a: 1.0,
+        };
+
+        // RGB passes through a dark purple, whereas HSL should take the short way around the
+        // color wheel and land on a fully saturated magenta
+        assert_color_eq(
+            ColorInterpolation::Rgb.interpolate(&red, &blue, 0.5),
+            D2D1_COLOR_F {
+                r: 0.5,
+                g: 0.0,
+                b: 0.5,
+                a: 1.0,
+            },
+        );
+        assert_color_eq(
+            ColorInterpolation::Hsl.interpolate(&red, &blue, 0.5),
+            D2D1_COLOR_F {
+                r: 1.0,
+                g: 0.0,
+                b: 1.0,
+                a: 1.0,
+            },
+        );
+
+        // Endpoints should be unchanged
+        assert_color_eq(ColorInterpolation::Oklch.interpolate(&red, &blue, 0.0), red);
+        assert_color_eq(
+            ColorInterpolation::Oklch.interpolate(&red, &blue, 1.0),
+            blue,
+        );
+    }
+
+    #[test]
+    fn test_premultiplied_interpolation() {
+        let red = D2D1_COLOR_F {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        let transparent = D2D1_COLOR_F::default();
+
+        // Fading to transparent black should only reduce the alpha, not darken the red
+        let half_red = D2D1_COLOR_F { a: 0.5, ..red };
+        assert_color_eq(
+            ColorInterpolation::Rgb.interpolate(&red, &transparent, 0.5),
+            half_red,
+        );
+        assert_color_eq(
+            ColorInterpolation::Hsl.interpolate(&transparent, &red, 0.5),
+            half_red,
+        );
+        assert_color_eq(
+            ColorInterpolation::Oklch.interpolate(&red, &transparent, 0.5),
+            half_red,
+        );
+
+        // Alpha-weighted: the more opaque color should dominate
+        let translucent_blue = D2D1_COLOR_F {
+            r: 0.0,
+            g: 0.0,
+            b: 1.0,
+            a: 0.25,
+        };
+        let color = ColorInterpolation::Rgb.interpolate(&red, &translucent_blue, 0.5);
+        assert!((color.r - 0.8).abs() < 0.001 && (color.b - 0.2).abs() < 0.001);
+        assert!((color.a - 0.625).abs() < 0.001);
+    }
+
+    #[test]
+    fn test_gradient_dithering() {
+        let color_brush_config = ColorBrushConfig::Gradient(GradientBrushConfig {
+            colors: vec!["#000000".to_string(), "#ffffff".to_string()],
+            direction: GradientDirection::Angle("90deg".to_string()),
+            dither: true,
+        });
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
+
+        let ColorBrush::Gradient(ref gradient) = color_brush else {
+            panic!("created incorrect color brush");
+        };
+
+        // The stops should still cover the whole gradient in order, and the noise should never
+        // push a color more than one step away from the original
+        let stops = &gradient.gradient_stops;
+        assert!(stops.first().unwrap().position == 0.0);
+        assert!(stops.last().unwrap().position == 1.0);
+        assert!(stops.windows(2).all(|w| w[0].position < w[1].position));
+        assert!(
+            stops
+                .iter()
+                .all(|stop| (stop.color.r - stop.position).abs() <= 1.0 / 255.0)
+        );
+    }
+
+    #[test]
+    fn test_color_brush_config_round_trip() -> anyhow::Result<()> {
+        let color_brush_configs = [
+            ColorBrushConfig::solid("darken(accent, 10%)"),
+            ColorBrushConfig::gradient(["#89b4fa", "#cba6f7"], GradientDirection::angle(45.0))
+                .with_dither(true),
+            ColorBrushConfig::gradient(
+                ["#89b4fa", "#cba6f7"],
+                GradientDirection::coordinates([0.0, 0.0], [1.0, 1.0]),
+            ),
+        ];
+
+        for color_brush_config in color_brush_configs {
+            color_brush_config.validate()?;
+
+            let yaml = serde_yml::to_string(&color_brush_config)?;
+            let deserialized: ColorBrushConfig = serde_yml::from_str(&yaml)?;
+            assert_eq!(deserialized, color_brush_config);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_partial_gradient_merge() {
+        let base =
+            ColorBrushConfig::gradient(["#89b4fa", "#cba6f7"], GradientDirection::angle(45.0))
+                .with_dither(true);
+
+        let mut color_brush_config = ColorBrushConfig::PartialGradient(PartialGradientConfig {
+            direction: Some(GradientDirection::perimeter()),
+            ..Default::default()
+        });
+        color_brush_config.merge_with(&base);
+        assert_eq!(
+            color_brush_config,
+            ColorBrushConfig::gradient(["#89b4fa", "#cba6f7"], GradientDirection::perimeter())
+                .with_dither(true)
+        );
+
+        // A solid color has nothing to inherit from, so this should be reported by validate()
+        let mut color_brush_config = ColorBrushConfig::PartialGradient(PartialGradientConfig {
+            direction: Some(GradientDirection::perimeter()),
+            ..Default::default()
+        });
+        color_brush_config.merge_with(&ColorBrushConfig::solid("accent"));
+        assert!(color_brush_config.validate().is_err());
+    }
+
+    #[test]
+    fn test_pattern_brush_tile() -> anyhow::Result<()> {
+        let color_brush_config: ColorBrushConfig = serde_yml::from_str(
+            "pattern: Checkerboard\ncolors: [\"#ff0000\", \"#0000ff80\"]\nwidth: 2",
+        )?;
+        color_brush_config.validate()?;
+
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
+        let ColorBrush::Pattern(ref pattern) = color_brush else {
+            panic!("created incorrect color brush");
+        };
+
+        let red = 0xffff0000;
+        let blue = 0x80000080; // premultiplied by its alpha
+        let (width, height, pixels) = pattern.create_tile();
+        assert_eq!((width, height), (4, 4));
+        assert_eq!(pixels[0..4], [red, red, blue, blue]);
+        assert_eq!(pixels[8..12], [blue, blue, red, red]);
+
+        let invalid_config: ColorBrushConfig =
+            serde_yml::from_str("pattern: Stripes\ncolors: [\"#ff0000\", \"#0000ff\"]\nwidth: 0")?;
+        assert!(invalid_config.validate().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_accent_normalization() {
+        let normalization = AccentNormalizationConfig {
+            enabled: true,
+            ..Default::default()
+        };
+
+        // Near-black gets lifted to min_lightness, keeping its hue and saturation
+        let (h, s, l) = rgb_to_hsl(&normalization.normalize(&hsl_to_rgb(210.0, 0.5, 0.05, 1.0)));
+        assert!((h - 210.0).abs() < 1.0 && (s - 0.5).abs() < 0.01 && (l - 0.3).abs() < 0.01);
+
+        // Near-white gets brought down to max_lightness
+        let (_, _, l) = rgb_to_hsl(&normalization.normalize(&hsl_to_rgb(30.0, 0.5, 0.98, 1.0)));
+        assert!((l - 0.8).abs() < 0.01);
+
+        // Colors that are already in range are left alone
+        let color = hsl_to_rgb(120.0, 0.6, 0.5, 1.0);
+        assert_color_eq(normalization.normalize(&color), color);
+
+        // Grays stay gray instead of picking up a hue
+        let gray = normalization.normalize(&hsl_to_rgb(0.0, 0.0, 0.02, 1.0));
+        assert_color_eq(gray, hsl_to_rgb(0.0, 0.0, 0.3, 1.0));
+    }
+
+    #[test]
+    fn test_accent_companions() {
+        let red = D2D1_COLOR_F {
+            r: 1.0,
+            g: 0.0,
+            b: 0.0,
+            a: 1.0,
+        };
+        assert_color_eq(
+            rotate_hue(&red, 180.0),
+            D2D1_COLOR_F {
+                r: 0.0,
+                g: 1.0,
+                b: 1.0,
+                a: 1.0,
+            },
+        );
+        assert_color_eq(
+            rotate_hue(&red, -120.0),
+            D2D1_COLOR_F {
+                r: 0.0,
+                g: 0.0,
+                b: 1.0,
+                a: 1.0,
+            },
+        );
+
+        assert!(validate_color("lighten(accent_complement, 10%)").is_ok());
+        assert!(validate_color("accent_triad3").is_err());
+
+        let hwnd = HWND::default();
+        assert_color_eq(
+            get_color("#ff0000.complement", true, hwnd),
+            rotate_hue(&red, 180.0),
+        );
+        assert_color_eq(
+            get_color("darken(#ff0000, 25%).split1", true, hwnd),
+            rotate_hue(&get_color("darken(#ff0000, 25%)", true, hwnd), 150.0),
+        );
+        assert!(validate_color("alpha(accent.triad2, 0.5)").is_ok());
+        assert!(validate_color("accent.triad3").is_err());
+    }
+
+    #[test]
+    fn test_gradient_coordinates_validation() -> anyhow::Result<()> {
+        let color_brush_config: ColorBrushConfig = serde_yml::from_str(
+            "colors: [\"#000000\", \"#ffffff\"]\ndirection:\n  start: [\"50%\", 1.0001]\n  end: [0.5, \"0%\"]",
+        )?;
+        color_brush_config.validate()?;
+
+        let color_brush = color_brush_config.to_color_brush(true, HWND::default());
+        let ColorBrush::Gradient(ref gradient) = color_brush else {
+            panic!("created incorrect color brush");
+        };
+        // 1.0001 should have been snapped to 1.0
+        assert!(gradient.direction.start == [0.5, 1.0]);
+        assert!(gradient.direction.end == [0.5, 0.0]);
+
+        let out_of_range = ColorBrushConfig::gradient(
+            ["#000000", "#ffffff"],
+            GradientDirection::coordinates([0.0, 0.0], [1.5, 1.0]),
+        );
+        let err = out_of_range.validate().unwrap_err();
+        assert!(format!("{err:#}").starts_with("direction.end[0]"));
+
+        let same_point = ColorBrushConfig::gradient(
+            ["#000000", "#ffffff"],
+            GradientDirection::coordinates([0.5, 0.5], [0.5, 0.5]),
+        );
+        assert!(same_point.validate().is_err());
+
+        Ok(())
+    }
 }
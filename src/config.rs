@@ -1,21 +1,43 @@
-use crate::animations::AnimationsConfig;
-use crate::colors::ColorBrushConfig;
+use crate::animations::{AnimationsConfig, RestoreAnimConfig};
+use crate::autodetect::{IntegrationsConfig, start_detector_if_enabled};
+use crate::border_drawer::{BorderOffset, Notch};
+use crate::cli::CLI_ARGS;
+use crate::colors::{
+    AccentNormalizationConfig, AlphaMode, ColorBrushConfig, ColorSettings, set_color_settings,
+};
 use crate::effects::EffectsConfig;
-use crate::komorebi::KomorebiColorsConfig;
+use crate::expression::{Expression, ExpressionVars};
+use crate::focus_commands::FocusCommandsConfig;
+use crate::hot_corner::{HotCornerConfig, start_hot_corner_if_enabled};
+use crate::komorebi::{KomorebiColorsConfig, KomorebiGapsConfig};
+use crate::monitor_layout::{MonitorLayout, MonitorLayoutConfig};
+use crate::proximity::{ProximityRevealConfig, start_tracker_if_enabled};
 use crate::render_backend::RenderBackendConfig;
-use crate::utils::{LogIfErr, get_adjusted_radius, get_window_corner_preference};
-use crate::{APP_STATE, DirectXDevices, IS_WINDOWS_11, display_error_box, reload_borders};
+use crate::renderer_core::DirectXDevices;
+use crate::schedule::{ProfileScheduleConfig, ScheduleConfig, TimeOfDay, Weekday};
+use crate::utils::{
+    LogIfErr, PlacementState, does_build_match, get_adjusted_radius, get_monitor_device_name,
+    get_monitor_index, get_window_corner_preference, is_high_contrast_on,
+};
+use crate::{APP_STATE, IS_WINDOWS_11, OS_BUILD, display_error_box, reload_borders};
 use anyhow::{Context, anyhow};
 use dirs::home_dir;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::cell::RefCell;
+use std::collections::HashMap;
 use std::fs::{self, DirBuilder};
 use std::os::windows::ffi::OsStrExt;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
 use std::{env, iter, ptr, slice, thread, time};
 use windows::Win32::Foundation::{CloseHandle, HANDLE, HWND};
 use windows::Win32::Graphics::Dwm::{
-    DWMWCP_DEFAULT, DWMWCP_DONOTROUND, DWMWCP_ROUND, DWMWCP_ROUNDSMALL,
+    DWM_WINDOW_CORNER_PREFERENCE, DWMWCP_DEFAULT, DWMWCP_DONOTROUND, DWMWCP_ROUND,
+    DWMWCP_ROUNDSMALL,
 };
+use windows::Win32::Graphics::Gdi::HMONITOR;
 use windows::Win32::Storage::FileSystem::{
     CreateFileW, FILE_FLAG_BACKUP_SEMANTICS, FILE_LIST_DIRECTORY, FILE_NOTIFY_CHANGE_LAST_WRITE,
     FILE_NOTIFY_INFORMATION, FILE_SHARE_DELETE, FILE_SHARE_READ, FILE_SHARE_WRITE, OPEN_EXISTING,
@@ -24,13 +46,26 @@ use windows::Win32::Storage::FileSystem::{
 use windows::Win32::System::IO::CancelIoEx;
 use windows::core::PCWSTR;
 
-const DEFAULT_CONFIG: &str = include_str!("resources/config.yaml");
+pub const DEFAULT_CONFIG: &str = include_str!("resources/config.yaml");
 
-#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
     #[serde(default)]
     pub watch_config_changes: bool,
+    // Prevents tacky-borders itself from opening or watching the config (e.g. for managed installs)
+    #[serde(default)]
+    pub read_only: bool,
+    // Hides border windows from UI Automation's control and content views
+    #[serde(default)]
+    pub hide_from_uia: bool,
+    #[serde(default)]
+    pub high_contrast: HighContrastConfig,
+    #[serde(default)]
+    pub desktop_widgets: DesktopWidgetsConfig,
+    // The look that presentation mode (toggled from the tray) switches to
+    #[serde(default)]
+    pub presentation: PresentationConfig,
     #[serde(default)]
     #[serde(alias = "rendering_backend")]
     pub render_backend: RenderBackendConfig,
@@ -38,44 +73,159 @@ pub struct Config {
     pub global: Global,
     #[serde(default)]
     pub window_rules: Vec<WindowRule>,
+    // Keyed by monitor device name (e.g. "DISPLAY1") or index (e.g. "0")
+    #[serde(default)]
+    pub monitors: HashMap<String, MonitorConfig>,
+    // Named colors that can be referenced elsewhere using "palette.<name>"
+    #[serde(default)]
+    pub palette: HashMap<String, String>,
+    // Time-of-day color overrides. The first schedule containing the current time is used.
+    #[serde(default)]
+    pub schedules: Vec<ScheduleConfig>,
+    // Named partial configs that get merged on top of the rest of the config while scheduled
+    #[serde(default)]
+    pub profiles: HashMap<String, serde_yml::Value>,
+    // When each profile is active. The first entry containing the current day and time is used.
+    #[serde(default)]
+    pub profile_schedule: Vec<ProfileScheduleConfig>,
+    // Which profile to use for each monitor layout. The first matching entry takes precedence over
+    // profile_schedule.
+    #[serde(default)]
+    pub monitor_layouts: Vec<MonitorLayoutConfig>,
+    // Resolve "accent" using DwmGetColorizationColor like older versions did
+    #[serde(default)]
+    pub legacy_accent_color: bool,
+    // Clamp the accent color's lightness/saturation so it stays visible
+    #[serde(default)]
+    pub accent_normalization: AccentNormalizationConfig,
+    // How translucent colors are blended when we interpolate them ourselves
+    #[serde(default)]
+    pub alpha_mode: AlphaMode,
+    // Drop window rules that contain invalid colors instead of applying them with fallback colors
+    #[serde(default)]
+    pub skip_invalid_rules: bool,
+    // How window titles show up in logs and in the border windows' own titles. Window rules still
+    // match against the real title.
+    #[serde(default)]
+    pub title_privacy: TitlePrivacy,
+    // How Title rules treat windows without a title (or whose title can't be retrieved)
+    #[serde(default)]
+    pub empty_titles: EmptyTitleMatching,
+    // Enables integrations for window managers that are running (see autodetect.rs)
+    #[serde(default)]
+    pub integrations: IntegrationsConfig,
+    // Window classes of other tools' overlays (e.g. FancyZones) that borders never put themselves on
+    // top of
+    #[serde(default)]
+    pub friendly_overlays: Vec<String>,
+    // The most borders that can exist at once. The least recently focused windows lose theirs first.
+    #[serde(default)]
+    pub max_borders: Option<usize>,
+    // Hides/shows the borders or cycles through profiles when the cursor is pushed into a corner
+    #[serde(default)]
+    pub hot_corner: HotCornerConfig,
+    // Keeps borders faded out until the cursor comes near their window's edges
+    #[serde(default)]
+    pub proximity_reveal: ProximityRevealConfig,
+    // Filled in by validate_colors() after the config is loaded
+    #[serde(skip)]
+    pub color_errors: Vec<String>,
+    // The profile that was merged in when the config was loaded
+    #[serde(skip)]
+    pub active_profile: Option<String>,
+    // The yaml from before any profile was merged in. Switching profiles starts over from this
+    // instead of reading config.yaml again, which embedders may not even use.
+    #[serde(skip)]
+    pub base_yaml: Option<serde_yml::Value>,
 }
 
 // Show borders even if the config.yaml is completely empty
 // NOTE: this is just for serde and is intentionally kept separate from the Default trait
 // because I still want the width and offset zeroed out when I call Config::default()
-fn serde_default_global() -> Global {
+pub(crate) fn serde_default_global() -> Global {
     Global {
-        border_width: serde_default_f32::<4>(),
-        border_offset: serde_default_i32::<-1>(),
+        border_width: Length::dip(4.0),
+        border_offset: OffsetConfig::Uniform(Length::dip(-1.0)),
+        bracket_length: serde_default_f32::<20>(),
         ..Default::default()
     }
 }
 
-#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct Global {
-    #[serde(default = "serde_default_f32::<4>")]
-    pub border_width: f32,
-    #[serde(default = "serde_default_i32::<-1>")]
-    pub border_offset: i32,
+    #[serde(default = "serde_default_border_width")]
+    pub border_width: Length,
+    // Bounds for border_width after it's been scaled for the monitor's DPI (in physical pixels)
+    pub min_border_width: Option<f32>,
+    pub max_border_width: Option<f32>,
+    // Replaces border_width while the window is maximized, snapped, or floating
+    #[serde(default)]
+    pub state_widths: StateWidthsConfig,
+    #[serde(default = "serde_default_offset")]
+    pub border_offset: OffsetConfig,
+    // Replaces border_offset for the sides of a snapped window that touch the monitor's work area
+    pub screen_edge_offset: Option<Length>,
     #[serde(default)]
     pub border_radius: RadiusConfig,
+    // How much gets added to border_radius so the border's curve lines up with the window's
+    #[serde(default)]
+    pub radius_compensation: RadiusCompensation,
+    #[serde(default)]
+    pub border_style: BorderStyle,
+    // Custom dash and gap lengths, in multiples of border_width. Replaces border_style's dashes.
+    pub dash_pattern: Option<Vec<f32>>,
+    // Only draw these edges of the border. All of them are drawn if this is left out.
+    pub edges: Option<Vec<BorderEdge>>,
+    #[serde(default)]
+    pub z_order: ZOrder,
+    // Draw a flat border inside maximized windows instead of hiding it
+    #[serde(default)]
+    pub show_when_maximized: bool,
+    // Length of each arm of the corner brackets (in pixels), for border_style: Brackets
+    #[serde(default = "serde_default_f32::<20>")]
+    pub bracket_length: f32,
+    // Fades the outer edge of the border out to transparent over this many pixels. 0 turns it off.
+    #[serde(default)]
+    pub feather: f32,
     #[serde(default)]
     pub active_color: ColorBrushConfig,
     #[serde(default)]
     pub inactive_color: ColorBrushConfig,
     #[serde(default)]
     pub komorebi_colors: KomorebiColorsConfig,
+    // Global only since komorebi's gaps are per-workspace rather than per-window
+    #[serde(default)]
+    pub komorebi_gaps: KomorebiGapsConfig,
     #[serde(default)]
     pub animations: AnimationsConfig,
     #[serde(default)]
     pub effects: EffectsConfig,
+    #[serde(default)]
+    pub idle_dim: IdleDimConfig,
+    #[serde(default)]
+    pub outline: OutlineConfig,
+    #[serde(default)]
+    pub acrylic: AcrylicConfig,
+    #[serde(default)]
+    pub titlebar_strip: TitlebarStripConfig,
+    #[serde(default)]
+    pub pixel_snapping: PixelSnappingConfig,
+    #[serde(default)]
+    pub dim_overlay: DimOverlayConfig,
     #[serde(alias = "init_delay")]
     #[serde(default = "serde_default_u64::<250>")]
     pub initialize_delay: u64, // Adjust delay when creating new windows/borders
     #[serde(alias = "restore_delay")]
     #[serde(default = "serde_default_u64::<200>")]
     pub unminimize_delay: u64, // Adjust delay when restoring minimized windows
+    // What the border does when a minimized window is restored. Anything but None replaces
+    // unminimize_delay.
+    #[serde(default)]
+    pub restore_animation: RestoreAnimConfig,
+    // Rect changes smaller than this (in pixels) are only applied once they've settled
+    #[serde(default)]
+    pub rect_hysteresis: i32,
 }
 
 pub fn serde_default_u64<const V: u64>() -> u64 {
@@ -91,85 +241,977 @@ pub fn serde_default_f32<const V: i32>() -> f32 {
     V as f32
 }
 
-pub fn serde_default_bool<const V: bool>() -> bool {
-    V
+pub fn serde_default_bool<const V: bool>() -> bool {
+    V
+}
+
+// Colors to use while Windows' high contrast mode is on. These take precedence over everything else.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HighContrastConfig {
+    #[serde(default = "serde_default_bool::<true>")]
+    pub enabled: bool,
+    #[serde(default = "serde_default_high_contrast_active")]
+    pub active_color: ColorBrushConfig,
+    #[serde(default = "serde_default_high_contrast_inactive")]
+    pub inactive_color: ColorBrushConfig,
+}
+
+impl Default for HighContrastConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            active_color: serde_default_high_contrast_active(),
+            inactive_color: serde_default_high_contrast_inactive(),
+        }
+    }
+}
+
+// Windows pinned to the desktop (e.g. Rainmeter skins or desktop clocks) usually shouldn't have a
+// border, so they're left out unless this is enabled. Window rules that match them take priority.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DesktopWidgetsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+}
+
+impl DesktopWidgetsConfig {
+    // The rule that desktop widgets get when no other window rule matches them
+    pub fn to_window_rule(&self) -> WindowRule {
+        WindowRule {
+            id: Some("desktop_widgets".to_string()),
+            enabled: (!self.enabled).then_some(EnableMode::Bool(false)),
+            active_color: self.active_color.clone(),
+            inactive_color: self.inactive_color.clone(),
+            ..Default::default()
+        }
+    }
+}
+
+// Set from the tray's "Presentation Mode" or set_presentation_mode(). This isn't part of the config
+// so that it stays on across reloads.
+pub static IS_PRESENTATION_MODE: AtomicBool = AtomicBool::new(false);
+
+// A profile picked with cycle_profile(), which takes precedence over monitor_layouts and
+// profile_schedule. Like presentation mode, this stays in effect across reloads.
+pub static PROFILE_OVERRIDE: Mutex<Option<String>> = Mutex::new(None);
+
+// Ids of window rules that were turned off from the tray's "Window Rules" menu. Rules without an
+// id can't be turned off this way. Like presentation mode, this stays in effect across reloads.
+pub static DISABLED_RULE_IDS: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+// A high-visibility look for screen sharing and demos. While presentation mode is on, these replace
+// the width and colors from everywhere else (except high_contrast), and animations are turned off.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PresentationConfig {
+    #[serde(default = "serde_default_f32::<6>")]
+    pub border_width: f32,
+    #[serde(default = "serde_default_presentation_active")]
+    pub active_color: ColorBrushConfig,
+    #[serde(default = "serde_default_presentation_inactive")]
+    pub inactive_color: ColorBrushConfig,
+}
+
+impl Default for PresentationConfig {
+    fn default() -> Self {
+        Self {
+            border_width: 6.0,
+            active_color: serde_default_presentation_active(),
+            inactive_color: serde_default_presentation_inactive(),
+        }
+    }
+}
+
+fn serde_default_presentation_active() -> ColorBrushConfig {
+    ColorBrushConfig::Solid("#ffd400".to_string())
+}
+
+fn serde_default_presentation_inactive() -> ColorBrushConfig {
+    ColorBrushConfig::Solid("#ffffff".to_string())
+}
+
+// These follow the system colors chosen by the high contrast theme
+fn serde_default_high_contrast_active() -> ColorBrushConfig {
+    ColorBrushConfig::Solid("highlight".to_string())
+}
+
+fn serde_default_high_contrast_inactive() -> ColorBrushConfig {
+    ColorBrushConfig::Solid("graytext".to_string())
+}
+
+// Gradually dims the inactive border of windows that haven't been focused in a while
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct IdleDimConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "serde_default_u64::<30>")]
+    pub after: u64, // Minutes without focus before the dimming starts
+    #[serde(default = "serde_default_u64::<30>")]
+    pub duration: u64, // Minutes it takes to reach the full strength
+    #[serde(default = "serde_default_idle_dim_strength")]
+    pub strength: f32, // How much to desaturate and darken the colors, from 0.0 to 1.0
+}
+
+impl Default for IdleDimConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after: 30,
+            duration: 30,
+            strength: serde_default_idle_dim_strength(),
+        }
+    }
+}
+
+// A second stroke drawn around the outside of the border (and optionally the inside too), e.g. a
+// thin dark outline so that the border still stands out against light backgrounds
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct OutlineConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "serde_default_f32::<1>")]
+    pub width: f32,
+    // Space between the border and the outline (in pixels)
+    #[serde(default)]
+    pub gap: f32,
+    #[serde(default)]
+    pub sides: OutlineSides,
+    #[serde(default = "serde_default_outline_color")]
+    pub active_color: ColorBrushConfig,
+    // Defaults to active_color
+    pub inactive_color: Option<ColorBrushConfig>,
+}
+
+impl Default for OutlineConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            width: 1.0,
+            gap: 0.0,
+            sides: OutlineSides::default(),
+            active_color: serde_default_outline_color(),
+            inactive_color: None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum OutlineSides {
+    #[default]
+    Outside,
+    // Outlines the inner edge of the border as well, so it stands out against the window too
+    Both,
+}
+
+// Blurs whatever is behind the border, like the taskbar does. This only shows through where the
+// border color is translucent, so it's best paired with something like "#ffffff40".
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct AcrylicConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Color mixed into the blur. Only solid colors are supported here.
+    #[serde(default = "serde_default_acrylic_tint")]
+    pub tint: ColorBrushConfig,
+}
+
+impl Default for AcrylicConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            tint: serde_default_acrylic_tint(),
+        }
+    }
+}
+
+// Lines thin borders up with the physical pixel grid so they don't get smeared across two pixels
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct PixelSnappingConfig {
+    #[serde(default = "serde_default_bool::<true>")]
+    pub enabled: bool,
+    // Turns off anti-aliasing for borders that are 1px wide or thinner
+    #[serde(default)]
+    pub aliased: bool,
+}
+
+impl Default for PixelSnappingConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            aliased: false,
+        }
+    }
+}
+
+// Fills a strip along the top of the window with the border's color, so the border doubles as an
+// accent for the window's caption
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TitlebarStripConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Measured from the inner edge of the border (in pixels)
+    #[serde(default = "serde_default_f32::<4>")]
+    pub height: f32,
+}
+
+impl Default for TitlebarStripConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            height: 4.0,
+        }
+    }
+}
+
+// Covers inactive windows with a translucent color so that they recede into the background
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct DimOverlayConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // Only solid colors are supported here
+    #[serde(default = "serde_default_dim_overlay_color")]
+    pub color: ColorBrushConfig,
+    #[serde(default = "serde_default_dim_overlay_opacity")]
+    pub opacity: f32,
+}
+
+impl Default for DimOverlayConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            color: serde_default_dim_overlay_color(),
+            opacity: serde_default_dim_overlay_opacity(),
+        }
+    }
+}
+
+fn serde_default_dim_overlay_color() -> ColorBrushConfig {
+    ColorBrushConfig::solid("#000000")
+}
+
+fn serde_default_dim_overlay_opacity() -> f32 {
+    0.3
+}
+
+fn serde_default_acrylic_tint() -> ColorBrushConfig {
+    ColorBrushConfig::solid("#20202040")
+}
+
+fn serde_default_outline_color() -> ColorBrushConfig {
+    ColorBrushConfig::solid("#000000")
+}
+
+fn serde_default_idle_dim_strength() -> f32 {
+    0.6
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorConfig {
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct WindowRule {
+    // Optional identifier for the rule. NOTE: 'name' is already used for the match pattern.
+    pub id: Option<String>,
+    #[serde(rename = "match")]
+    pub kind: Option<MatchKind>,
+    pub name: Option<String>,
+    pub strategy: Option<MatchStrategy>,
+    // Also match against the window's direct children (e.g. embedded browser frames)
+    pub match_children: Option<bool>,
+    // Always hash the titles of matching windows, regardless of title_privacy
+    pub private: Option<bool>,
+    // Only use this rule on machines that meet these conditions
+    pub when: Option<ConditionConfig>,
+    pub border_width: Option<Length>,
+    pub min_border_width: Option<f32>,
+    pub max_border_width: Option<f32>,
+    pub state_widths: Option<StateWidthsConfig>,
+    pub border_offset: Option<OffsetConfig>,
+    pub screen_edge_offset: Option<Length>,
+    pub border_radius: Option<RadiusConfig>,
+    pub radius_compensation: Option<RadiusCompensation>,
+    pub border_style: Option<BorderStyle>,
+    pub dash_pattern: Option<Vec<f32>>,
+    pub edges: Option<Vec<BorderEdge>>,
+    pub notch: Option<NotchConfig>,
+    pub z_order: Option<ZOrder>,
+    pub show_when_maximized: Option<bool>,
+    // Keep drawing the border over borderless fullscreen windows (e.g. games and video players)
+    pub show_when_fullscreen: Option<bool>,
+    // Makes the window itself use these corners, so that they match the border (Windows 11 only)
+    pub window_corner: Option<WindowCorner>,
+    pub bracket_length: Option<f32>,
+    pub feather: Option<f32>,
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+    pub komorebi_colors: Option<KomorebiColorsConfig>,
+    pub enabled: Option<EnableMode>,
+    pub animations: Option<AnimationsConfig>,
+    pub effects: Option<EffectsConfig>,
+    pub idle_dim: Option<IdleDimConfig>,
+    pub outline: Option<OutlineConfig>,
+    pub acrylic: Option<AcrylicConfig>,
+    pub titlebar_strip: Option<TitlebarStripConfig>,
+    pub pixel_snapping: Option<PixelSnappingConfig>,
+    pub dim_overlay: Option<DimOverlayConfig>,
+    // Commands to run when the window gains or loses focus
+    pub on_focus: Option<FocusCommandsConfig>,
+    #[serde(alias = "init_delay")]
+    pub initialize_delay: Option<u64>,
+    #[serde(alias = "restore_delay")]
+    pub unminimize_delay: Option<u64>,
+    pub restore_animation: Option<RestoreAnimConfig>,
+    pub rect_hysteresis: Option<i32>,
+    pub resource_colors: Option<ResourceColorsConfig>,
+    #[serde(alias = "rendering_backend")]
+    pub render_backend: Option<RenderBackendConfig>,
+}
+
+impl WindowRule {
+    fn get_color_errors(&self) -> Vec<String> {
+        let mut colors = vec![
+            ("active_color".to_string(), self.active_color.as_ref()),
+            ("inactive_color".to_string(), self.inactive_color.as_ref()),
+        ];
+
+        if let Some(ref outline) = self.outline {
+            colors.extend([
+                (
+                    "outline.active_color".to_string(),
+                    Some(&outline.active_color),
+                ),
+                (
+                    "outline.inactive_color".to_string(),
+                    outline.inactive_color.as_ref(),
+                ),
+            ]);
+        }
+
+        if let Some(ref acrylic) = self.acrylic {
+            colors.push(("acrylic.tint".to_string(), Some(&acrylic.tint)));
+        }
+
+        if let Some(ref dim_overlay) = self.dim_overlay {
+            colors.push(("dim_overlay.color".to_string(), Some(&dim_overlay.color)));
+        }
+
+        if let Some(ref komorebi_colors) = self.komorebi_colors {
+            colors.extend([
+                (
+                    "komorebi_colors.stack_color".to_string(),
+                    komorebi_colors.stack_color.as_ref(),
+                ),
+                (
+                    "komorebi_colors.monocle_color".to_string(),
+                    komorebi_colors.monocle_color.as_ref(),
+                ),
+                (
+                    "komorebi_colors.floating_color".to_string(),
+                    komorebi_colors.floating_color.as_ref(),
+                ),
+                (
+                    "komorebi_colors.focused_monitor_color".to_string(),
+                    komorebi_colors.focused_monitor_color.as_ref(),
+                ),
+            ]);
+        }
+
+        if let Some(ref resource_colors) = self.resource_colors {
+            for (i, threshold) in resource_colors.thresholds.iter().enumerate() {
+                colors.extend([
+                    (
+                        format!("resource_colors.thresholds[{i}].active_color"),
+                        threshold.active_color.as_ref(),
+                    ),
+                    (
+                        format!("resource_colors.thresholds[{i}].inactive_color"),
+                        threshold.inactive_color.as_ref(),
+                    ),
+                ]);
+            }
+        }
+
+        get_color_errors(&colors)
+    }
+
+    // Used to refer to the rule in logs. We prefer the user-provided id because, unlike the rule's
+    // index, it stays the same when other rules are added or removed.
+    pub fn display_name(&self) -> String {
+        match (&self.id, &self.kind, &self.name) {
+            (Some(id), _, _) => id.clone(),
+            (None, Some(kind), Some(name)) => format!("{kind:?} \"{name}\""),
+            _ => "<unnamed rule>".to_string(),
+        }
+    }
+}
+
+// Changes the border's colors based on the CPU/memory usage of the window's process
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceColorsConfig {
+    #[serde(default = "serde_default_u64::<3000>")]
+    pub interval: u64, // How often to sample the process, in milliseconds
+    #[serde(default)]
+    pub thresholds: Vec<ResourceThresholdConfig>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ResourceThresholdConfig {
+    pub cpu: Option<f32>,    // Percent of total CPU usage
+    pub memory: Option<f64>, // Working set in MB
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MatchKind {
+    Title,
+    Class,
+    Process,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub enum MatchStrategy {
+    Equals,
+    Contains,
+    Regex,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(from = "RadiusConfigRepr")]
+pub enum RadiusConfig {
+    // Follows the tracking window's own DWM corner preference
+    #[default]
+    Auto,
+    Square,
+    Round,
+    RoundSmall,
+    #[serde(untagged)]
+    Custom(f32),
+}
+
+// What's actually accepted in the config. Besides the variant names, this takes the shorter
+// lowercase names, and -1 (which is what we used for Auto before it existed).
+#[derive(Deserialize)]
+enum RadiusConfigRepr {
+    #[serde(alias = "system", alias = "System")]
+    Auto,
+    #[serde(alias = "none", alias = "None")]
+    Square,
+    #[serde(alias = "round")]
+    Round,
+    #[serde(alias = "small", alias = "Small")]
+    RoundSmall,
+    #[serde(untagged)]
+    Custom(f32),
+}
+
+impl From<RadiusConfigRepr> for RadiusConfig {
+    fn from(repr: RadiusConfigRepr) -> Self {
+        match repr {
+            RadiusConfigRepr::Auto => RadiusConfig::Auto,
+            RadiusConfigRepr::Square => RadiusConfig::Square,
+            RadiusConfigRepr::Round => RadiusConfig::Round,
+            RadiusConfigRepr::RoundSmall => RadiusConfig::RoundSmall,
+            RadiusConfigRepr::Custom(-1.0) => RadiusConfig::Auto,
+            RadiusConfigRepr::Custom(radius) => RadiusConfig::Custom(radius),
+        }
+    }
+}
+
+impl RadiusConfig {
+    // The compensation is in physical pixels (see RadiusCompensation::to_compensation())
+    pub fn to_radius(&self, compensation: f32, dpi: u32, tracking_window: HWND) -> f32 {
+        match self {
+            RadiusConfig::Auto => get_system_radius(compensation, dpi, tracking_window),
+            RadiusConfig::Square => 0.0,
+            RadiusConfig::Round => get_adjusted_radius(8.0, dpi, compensation),
+            RadiusConfig::RoundSmall => get_adjusted_radius(4.0, dpi, compensation),
+            RadiusConfig::Custom(radius) => get_adjusted_radius(*radius, dpi, compensation),
+        }
+    }
+}
+
+// border_radius is the radius of the window's corners, but the border is drawn along the middle of
+// its stroke, which generally isn't where the window's edge is
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq)]
+pub enum RadiusCompensation {
+    // Adds half of the border width, which is what we've always done
+    #[default]
+    HalfWidth,
+    // Follows the window's corners exactly, taking the border width and offset into account
+    Concentric,
+    // Uses border_radius as-is for the middle of the stroke
+    None,
+    // Adds this many pixels (scaled for the DPI like border_radius)
+    #[serde(untagged)]
+    Custom(f32),
+}
+
+impl RadiusCompensation {
+    pub fn to_compensation(
+        &self,
+        border_width: i32,
+        border_offset: &BorderOffset,
+        dpi: u32,
+    ) -> f32 {
+        match self {
+            RadiusCompensation::HalfWidth => border_width as f32 / 2.0,
+            // The middle of the stroke sits (border_width / 2 + offset) outside the window's edge.
+            // The corners touch two sides each, so we go with the average offset.
+            RadiusCompensation::Concentric => {
+                let average_offset = (border_offset.top
+                    + border_offset.left
+                    + border_offset.right
+                    + border_offset.bottom) as f32
+                    / 4.0;
+                border_width as f32 / 2.0 + average_offset
+            }
+            RadiusCompensation::None => 0.0,
+            RadiusCompensation::Custom(compensation) => compensation * dpi as f32 / 96.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BorderStyle {
+    #[default]
+    Solid,
+    Dashed,
+    Dotted,
+    // Short L-shaped brackets at each corner (see bracket_length)
+    Brackets,
+    // Lighter top/left and darker bottom/right edges, so the window looks raised (or sunken)
+    Raised,
+    Sunken,
+}
+
+impl BorderStyle {
+    // Alternating dash and gap lengths, in multiples of the border width (which is how Direct2D
+    // measures them). Dotted uses zero-length dashes with round caps, so each dot is a circle.
+    pub fn get_dashes(self) -> Option<&'static [f32]> {
+        match self {
+            BorderStyle::Solid
+            | BorderStyle::Brackets
+            | BorderStyle::Raised
+            | BorderStyle::Sunken => None,
+            BorderStyle::Dashed => Some(&[3.0, 2.0]),
+            BorderStyle::Dotted => Some(&[0.0, 2.0]),
+        }
+    }
+}
+
+// Direct2D rejects negative lengths, and a pattern without any length at all would never advance
+fn validate_dash_pattern(dash_pattern: &[f32]) -> anyhow::Result<()> {
+    if dash_pattern.is_empty() {
+        return Err(anyhow!("dash_pattern cannot be empty"));
+    }
+    if dash_pattern
+        .iter()
+        .any(|len| !len.is_finite() || *len < 0.0)
+    {
+        return Err(anyhow!("dash_pattern cannot contain negative lengths"));
+    }
+    if dash_pattern.iter().sum::<f32>() <= 0.0 {
+        return Err(anyhow!(
+            "dash_pattern must have at least one non-zero length"
+        ));
+    }
+
+    Ok(())
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct StateWidthsConfig {
+    pub maximized: Option<Length>,
+    pub snapped: Option<Length>,
+    pub floating: Option<Length>,
+}
+
+impl StateWidthsConfig {
+    pub fn get(&self, placement: PlacementState) -> Option<Length> {
+        match placement {
+            PlacementState::Maximized => self.maximized.clone(),
+            PlacementState::Snapped => self.snapped.clone(),
+            PlacementState::Floating => self.floating.clone(),
+        }
+    }
+}
+
+// Scales border_width (or the width for the window's placement) for the DPI, then clamps it to
+// min/max_border_width, which are already in physical pixels. If the bounds conflict,
+// max_border_width wins.
+//
+// A window rule's border_width takes priority over the global state_widths, so rules that only set
+// border_width keep their width in every placement.
+pub fn get_border_width(
+    window_rule: &WindowRule,
+    config: &Config,
+    dpi: u32,
+    placement: PlacementState,
+) -> i32 {
+    // Presentation mode is meant to look the same everywhere, so it skips the bounds too
+    if config.is_presentation_mode_active() {
+        return (config.presentation.border_width * dpi as f32 / 96.0).round() as i32;
+    }
+
+    let global = &config.global;
+    let width_config = window_rule
+        .state_widths
+        .as_ref()
+        .and_then(|state_widths| state_widths.get(placement))
+        .or_else(|| window_rule.border_width.clone())
+        .or_else(|| global.state_widths.get(placement))
+        .unwrap_or_else(|| global.border_width.clone());
+    let mut border_width = width_config.to_physical(dpi);
+
+    if let Some(min_width) = window_rule.min_border_width.or(global.min_border_width) {
+        border_width = border_width.max(min_width);
+    }
+    if let Some(max_width) = window_rule.max_border_width.or(global.max_border_width) {
+        border_width = border_width.min(max_width);
+    }
+
+    border_width.round() as i32
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum TitlePrivacy {
+    #[default]
+    Full,
+    // Only keep the first few characters
+    Truncate,
+    // Replace the title with a short hash, so the same window can still be followed across lines
+    Hash,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum EmptyTitleMatching {
+    // Only rules whose name is also empty match, regardless of the strategy. Otherwise, an empty
+    // title would match any Contains rule and most regexes.
+    #[default]
+    OnlyEmptyPattern,
+    NeverMatch,
+}
+
+impl EmptyTitleMatching {
+    pub fn does_match(&self, match_name: &str) -> bool {
+        match self {
+            EmptyTitleMatching::OnlyEmptyPattern => match_name.is_empty(),
+            EmptyTitleMatching::NeverMatch => false,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum BorderEdge {
+    #[default]
+    Top,
+    Left,
+    Right,
+    Bottom,
+}
+
+// A stretch of one edge where the border isn't drawn, e.g. to leave room for an app's tab strip
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct NotchConfig {
+    #[serde(default)]
+    pub edge: BorderEdge,
+    #[serde(default)]
+    pub align: NotchAlign,
+    // How long the notch is along the edge (in pixels)
+    #[serde(default = "serde_default_f32::<200>")]
+    pub width: f32,
+    // Moves the notch away from where it's aligned to, towards the middle of the edge (in pixels)
+    #[serde(default)]
+    pub offset: f32,
+}
+
+impl NotchConfig {
+    pub fn to_notch(&self, dpi: u32) -> Notch {
+        Notch {
+            edge: self.edge,
+            align: self.align,
+            width: self.width.max(0.0) * dpi as f32 / 96.0,
+            offset: self.offset * dpi as f32 / 96.0,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum NotchAlign {
+    // The left end of top/bottom edges and the top end of left/right edges
+    Start,
+    #[default]
+    Center,
+    End,
+}
+
+// Lets one config behave differently across machines. Every condition that's set has to be met.
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ConditionConfig {
+    // Compared against the Windows build number, e.g. ">=22621" for Windows 11 22H2 and up
+    pub os: Option<String>,
+}
+
+impl ConditionConfig {
+    pub fn is_met(&self) -> bool {
+        self.check().unwrap_or_else(|err| {
+            error!("could not check condition: {err:#}");
+            false
+        })
+    }
+
+    fn check(&self) -> anyhow::Result<bool> {
+        if let Some(os) = &self.os
+            && !does_build_match(os, *OS_BUILD)?
+        {
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum WindowCorner {
+    #[serde(alias = "round")]
+    Round,
+    #[serde(alias = "small")]
+    Small,
+    #[serde(alias = "donotround")]
+    DoNotRound,
+    // Clips the window to the same radius as the border using a window region, since DWM only
+    // offers the two radii above
+    #[serde(alias = "match")]
+    Match,
+}
+
+impl WindowCorner {
+    // Match doesn't have a corner preference of its own
+    pub fn to_corner_preference(self) -> Option<DWM_WINDOW_CORNER_PREFERENCE> {
+        match self {
+            WindowCorner::Round => Some(DWMWCP_ROUND),
+            WindowCorner::Small => Some(DWMWCP_ROUNDSMALL),
+            WindowCorner::DoNotRound => Some(DWMWCP_DONOTROUND),
+            WindowCorner::Match => None,
+        }
+    }
+
+    // The radius to clip the window to when we can't use a corner preference (before DPI scaling)
+    pub fn get_region_radius(self, radius_config: &RadiusConfig, tracking_window: HWND) -> f32 {
+        match self {
+            WindowCorner::Round => 8.0,
+            WindowCorner::Small => 4.0,
+            WindowCorner::DoNotRound => 0.0,
+            WindowCorner::Match => radius_config.to_radius(0.0, 96, tracking_window),
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum ZOrder {
+    #[default]
+    Above,
+    // Keeps the border beneath the window, so it never covers the app's own popups and menus
+    Below,
+}
+
+// These are the radii that DWM uses for each corner preference on Windows 11
+fn get_system_radius(compensation: f32, dpi: u32, tracking_window: HWND) -> f32 {
+    // I believe this will error on Windows 10, so we'll just use a default
+    match get_window_corner_preference(tracking_window).unwrap_or(DWMWCP_DEFAULT) {
+        DWMWCP_DEFAULT if *IS_WINDOWS_11 => get_adjusted_radius(8.0, dpi, compensation),
+        DWMWCP_DEFAULT => 0.0,
+        DWMWCP_DONOTROUND => 0.0,
+        DWMWCP_ROUND => get_adjusted_radius(8.0, dpi, compensation),
+        DWMWCP_ROUNDSMALL => get_adjusted_radius(4.0, dpi, compensation),
+        _ => 0.0,
+    }
+}
+
+// A width or offset that's either scaled for the monitor's DPI (4 or "4dip") or used as-is
+// ("4px"), so that borders can be the same physical thickness on every monitor. It can also be an
+// expression like "max(2, monitor_dpi / 48)", which works out to physical pixels.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(try_from = "LengthRepr", into = "LengthRepr")]
+pub struct Length {
+    pub value: f32,
+    pub unit: LengthUnit,
+    // Replaces value and unit when set
+    pub expression: Option<Expression>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum LengthUnit {
+    // Device-independent pixels, which get scaled for the DPI (this is what plain numbers use)
+    #[default]
+    Dip,
+    // Physical pixels
+    Px,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LengthRepr {
+    Number(f32),
+    Text(String),
+}
+
+impl TryFrom<LengthRepr> for Length {
+    type Error = String;
+
+    fn try_from(repr: LengthRepr) -> Result<Self, Self::Error> {
+        let text = match repr {
+            LengthRepr::Number(value) => return Ok(Length::dip(value)),
+            LengthRepr::Text(text) => text,
+        };
+
+        let trimmed = text.trim();
+        let (number, unit) = match (trimmed.strip_suffix("px"), trimmed.strip_suffix("dip")) {
+            (Some(number), _) => (number, LengthUnit::Px),
+            (_, Some(number)) => (number, LengthUnit::Dip),
+            _ => (trimmed, LengthUnit::Dip),
+        };
+
+        if let Ok(value) = number.trim().parse::<f32>()
+            && value.is_finite()
+        {
+            return Ok(Length {
+                value,
+                unit,
+                expression: None,
+            });
+        }
+
+        // Anything else has to be an expression
+        match Expression::parse(trimmed) {
+            Ok(expression) => Ok(Length {
+                expression: Some(expression),
+                ..Default::default()
+            }),
+            Err(err) => Err(format!(
+                "invalid length '{text}'; expected a number, \"<number>px\", \"<number>dip\", or \
+                an expression ({err})"
+            )),
+        }
+    }
+}
+
+impl From<Length> for LengthRepr {
+    fn from(length: Length) -> Self {
+        if let Some(expression) = length.expression {
+            return LengthRepr::Text(expression.source().to_string());
+        }
+
+        match length.unit {
+            LengthUnit::Dip => LengthRepr::Number(length.value),
+            LengthUnit::Px => LengthRepr::Text(format!("{}px", length.value)),
+        }
+    }
+}
+
+impl Length {
+    pub fn dip(value: f32) -> Self {
+        Self {
+            value,
+            unit: LengthUnit::Dip,
+            expression: None,
+        }
+    }
+
+    pub fn to_physical(&self, dpi: u32) -> f32 {
+        if let Some(ref expression) = self.expression {
+            let value = expression.eval(&ExpressionVars { monitor_dpi: dpi });
+            // e.g. dividing by zero
+            return match value.is_finite() {
+                true => value,
+                false => 0.0,
+            };
+        }
+
+        match self.unit {
+            LengthUnit::Dip => self.value * dpi as f32 / 96.0,
+            LengthUnit::Px => self.value,
+        }
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
-#[serde(deny_unknown_fields)]
-pub struct WindowRule {
-    #[serde(rename = "match")]
-    pub kind: Option<MatchKind>,
-    pub name: Option<String>,
-    pub strategy: Option<MatchStrategy>,
-    pub border_width: Option<f32>,
-    pub border_offset: Option<i32>,
-    pub border_radius: Option<RadiusConfig>,
-    pub active_color: Option<ColorBrushConfig>,
-    pub inactive_color: Option<ColorBrushConfig>,
-    pub komorebi_colors: Option<KomorebiColorsConfig>,
-    pub enabled: Option<EnableMode>,
-    pub animations: Option<AnimationsConfig>,
-    pub effects: Option<EffectsConfig>,
-    #[serde(alias = "init_delay")]
-    pub initialize_delay: Option<u64>,
-    #[serde(alias = "restore_delay")]
-    pub unminimize_delay: Option<u64>,
+fn serde_default_border_width() -> Length {
+    Length::dip(4.0)
 }
 
+// Either a single offset for all sides, or a separate offset for each side
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum MatchKind {
-    Title,
-    Class,
-    Process,
+#[serde(untagged)]
+pub enum OffsetConfig {
+    Uniform(Length),
+    Sides(SideOffsetsConfig),
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
-pub enum MatchStrategy {
-    Equals,
-    Contains,
-    Regex,
+impl Default for OffsetConfig {
+    fn default() -> Self {
+        OffsetConfig::Uniform(Length::default())
+    }
 }
 
-#[derive(Clone, Debug, Default, Deserialize, PartialEq)]
-pub enum RadiusConfig {
-    #[default]
-    Auto,
-    Square,
-    Round,
-    RoundSmall,
-    #[serde(untagged)]
-    Custom(f32),
+// Sides that aren't specified use the same default as border_offset
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct SideOffsetsConfig {
+    #[serde(default = "serde_default_side_offset")]
+    pub top: Length,
+    #[serde(default = "serde_default_side_offset")]
+    pub left: Length,
+    #[serde(default = "serde_default_side_offset")]
+    pub right: Length,
+    #[serde(default = "serde_default_side_offset")]
+    pub bottom: Length,
 }
 
-impl RadiusConfig {
-    pub fn to_radius(&self, border_width: i32, dpi: u32, tracking_window: HWND) -> f32 {
+impl OffsetConfig {
+    pub fn to_offset(&self, dpi: u32) -> BorderOffset {
+        let scale = |offset: &Length| offset.to_physical(dpi).round() as i32;
+
         match self {
-            // We also check Custom(-1.0) for legacy reasons (don't wanna break anyone's old config)
-            RadiusConfig::Auto | RadiusConfig::Custom(-1.0) => {
-                // I believe this will error on Windows 10, so we'll just use a default
-                match get_window_corner_preference(tracking_window).unwrap_or(DWMWCP_DEFAULT) {
-                    DWMWCP_DEFAULT => {
-                        if *IS_WINDOWS_11 {
-                            get_adjusted_radius(8.0, dpi, border_width)
-                        } else {
-                            0.0
-                        }
-                    }
-                    DWMWCP_DONOTROUND => 0.0,
-                    DWMWCP_ROUND => get_adjusted_radius(8.0, dpi, border_width),
-                    DWMWCP_ROUNDSMALL => get_adjusted_radius(4.0, dpi, border_width),
-                    _ => 0.0,
-                }
-            }
-            RadiusConfig::Square => 0.0,
-            RadiusConfig::Round => get_adjusted_radius(8.0, dpi, border_width),
-            RadiusConfig::RoundSmall => get_adjusted_radius(4.0, dpi, border_width),
-            RadiusConfig::Custom(radius) => get_adjusted_radius(*radius, dpi, border_width),
+            OffsetConfig::Uniform(offset) => BorderOffset::uniform(scale(offset)),
+            OffsetConfig::Sides(sides) => BorderOffset {
+                top: scale(&sides.top),
+                left: scale(&sides.left),
+                right: scale(&sides.right),
+                bottom: scale(&sides.bottom),
+            },
         }
     }
 }
-#[derive(Debug, Clone, Default, Deserialize, PartialEq)]
+
+fn serde_default_offset() -> OffsetConfig {
+    OffsetConfig::Uniform(Length::dip(-1.0))
+}
+
+fn serde_default_side_offset() -> Length {
+    Length::dip(-1.0)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub enum EnableMode {
     #[default]
     Auto,
@@ -216,8 +1258,437 @@ impl Config {
             }
         }
 
-        // Deserialize the config.yaml file
-        serde_yml::from_str(&contents).map_err(anyhow::Error::new)
+        Self::from_yaml(&contents)
+    }
+
+    // Parses a config from a yaml string. This is what create() uses under the hood, but it's also
+    // useful for embedders that want to supply their own config instead of reading config.yaml.
+    pub fn from_yaml(contents: &str) -> anyhow::Result<Self> {
+        let mut value: serde_yml::Value =
+            serde_yml::from_str(contents).map_err(anyhow::Error::new)?;
+        let base_yaml = value.clone();
+
+        // Like palette references below, 'conditional' sections are handled on the raw yaml, so
+        // they can override anything in the config
+        let mut config: Config = match apply_conditional_sections(&mut value)? {
+            true => serde_yml::from_value(value.clone()).map_err(anyhow::Error::new)?,
+            // Deserializing from the string keeps line numbers in the error messages
+            false => serde_yml::from_str(contents).map_err(anyhow::Error::new)?,
+        };
+
+        // Catch typos in window rules' conditions now rather than whenever a window gets matched
+        for rule in config.window_rules.iter() {
+            if let Some(when) = &rule.when {
+                when.check().with_context(|| {
+                    format!("invalid 'when' in window rule '{}'", rule.display_name())
+                })?;
+            }
+            if let Some(dash_pattern) = &rule.dash_pattern {
+                validate_dash_pattern(dash_pattern).with_context(|| {
+                    format!(
+                        "invalid 'dash_pattern' in window rule '{}'",
+                        rule.display_name()
+                    )
+                })?;
+            }
+        }
+        if let Some(dash_pattern) = &config.global.dash_pattern {
+            validate_dash_pattern(dash_pattern).context("invalid 'dash_pattern' in global")?;
+        }
+
+        // The scheduled profile gets merged on top of everything else, including conditional
+        // sections, so it has to be handled on the raw yaml as well
+        for entry in config.profile_schedule.iter() {
+            if !config.profiles.contains_key(&entry.profile) {
+                return Err(anyhow!(
+                    "profile_schedule refers to unknown profile '{}'",
+                    entry.profile
+                ));
+            }
+        }
+        for entry in config.monitor_layouts.iter() {
+            if !config.profiles.contains_key(&entry.profile) {
+                return Err(anyhow!(
+                    "monitor_layouts refers to unknown profile '{}'",
+                    entry.profile
+                ));
+            }
+            entry.check().context("invalid entry in monitor_layouts")?;
+        }
+        if let Some(profile) = config.get_scheduled_profile() {
+            debug!("applying profile '{profile}'");
+            apply_profile(&mut value, &profile)?;
+
+            config = serde_yml::from_value(value.clone())
+                .map_err(anyhow::Error::new)
+                .with_context(|| format!("invalid profile '{profile}'"))?;
+            config.active_profile = Some(profile);
+        }
+
+        // We need the palette before we can resolve references to it, so if there is one, we
+        // deserialize again with it in place
+        if !config.palette.is_empty() {
+            let palette = config.palette.clone();
+            let active_profile = config.active_profile.take();
+            config = with_palette(&palette, || serde_yml::from_value(value))
+                .map_err(anyhow::Error::new)?;
+            config.active_profile = active_profile;
+        }
+
+        config.merge_partial_gradients();
+        config.validate_colors();
+        config.base_yaml = Some(base_yaml);
+
+        Ok(config)
+    }
+
+    // Serializes the config back into yaml that from_yaml() (and config.yaml) will accept. Unset
+    // optional fields are left out instead of being written as nulls.
+    pub fn to_yaml(&self) -> anyhow::Result<String> {
+        let mut value = serde_yml::to_value(self).map_err(anyhow::Error::new)?;
+        strip_nulls(&mut value);
+
+        serde_yml::to_string(&value).map_err(anyhow::Error::new)
+    }
+
+    // Rules, monitors, and schedules can override just part of the corresponding global gradient
+    pub(crate) fn merge_partial_gradients(&mut self) {
+        let global = &self.global;
+        let merge = |color_opt: &mut Option<ColorBrushConfig>, base: &ColorBrushConfig| {
+            if let Some(color) = color_opt.as_mut() {
+                color.merge_with(base);
+            }
+        };
+
+        for rule in self.window_rules.iter_mut() {
+            merge(&mut rule.active_color, &global.active_color);
+            merge(&mut rule.inactive_color, &global.inactive_color);
+
+            if let Some(ref mut komorebi_colors) = rule.komorebi_colors {
+                let global_komorebi = &global.komorebi_colors;
+                merge(
+                    &mut komorebi_colors.stack_color,
+                    global_komorebi
+                        .stack_color
+                        .as_ref()
+                        .unwrap_or(&global.active_color),
+                );
+                merge(
+                    &mut komorebi_colors.monocle_color,
+                    global_komorebi
+                        .monocle_color
+                        .as_ref()
+                        .unwrap_or(&global.active_color),
+                );
+                merge(
+                    &mut komorebi_colors.floating_color,
+                    global_komorebi
+                        .floating_color
+                        .as_ref()
+                        .unwrap_or(&global.active_color),
+                );
+                merge(
+                    &mut komorebi_colors.focused_monitor_color,
+                    global_komorebi
+                        .focused_monitor_color
+                        .as_ref()
+                        .unwrap_or(&global.inactive_color),
+                );
+            }
+
+            if let Some(ref mut resource_colors) = rule.resource_colors {
+                for threshold in resource_colors.thresholds.iter_mut() {
+                    merge(&mut threshold.active_color, &global.active_color);
+                    merge(&mut threshold.inactive_color, &global.inactive_color);
+                }
+            }
+        }
+
+        for monitor in self.monitors.values_mut() {
+            merge(&mut monitor.active_color, &global.active_color);
+            merge(&mut monitor.inactive_color, &global.inactive_color);
+        }
+
+        for schedule in self.schedules.iter_mut() {
+            merge(&mut schedule.active_color, &global.active_color);
+            merge(&mut schedule.inactive_color, &global.inactive_color);
+        }
+    }
+
+    // Invalid colors would otherwise just fall back to transparent, which makes it look like
+    // tacky-borders is broken, so we collect them here along with where they came from
+    pub(crate) fn validate_colors(&mut self) {
+        let global = &self.global;
+        let mut errors = get_color_errors(&[
+            ("active_color", Some(&global.active_color)),
+            ("inactive_color", Some(&global.inactive_color)),
+            (
+                "komorebi_colors.stack_color",
+                global.komorebi_colors.stack_color.as_ref(),
+            ),
+            (
+                "komorebi_colors.monocle_color",
+                global.komorebi_colors.monocle_color.as_ref(),
+            ),
+            (
+                "komorebi_colors.floating_color",
+                global.komorebi_colors.floating_color.as_ref(),
+            ),
+            (
+                "komorebi_colors.focused_monitor_color",
+                global.komorebi_colors.focused_monitor_color.as_ref(),
+            ),
+            ("outline.active_color", Some(&global.outline.active_color)),
+            (
+                "outline.inactive_color",
+                global.outline.inactive_color.as_ref(),
+            ),
+            ("acrylic.tint", Some(&global.acrylic.tint)),
+            ("dim_overlay.color", Some(&global.dim_overlay.color)),
+        ])
+        .into_iter()
+        .map(|err| format!("global: {err}"))
+        .collect::<Vec<_>>();
+
+        errors.extend(
+            get_color_errors(&[
+                ("active_color", Some(&self.high_contrast.active_color)),
+                ("inactive_color", Some(&self.high_contrast.inactive_color)),
+            ])
+            .into_iter()
+            .map(|err| format!("high_contrast: {err}")),
+        );
+        errors.extend(
+            get_color_errors(&[
+                ("active_color", Some(&self.presentation.active_color)),
+                ("inactive_color", Some(&self.presentation.inactive_color)),
+            ])
+            .into_iter()
+            .map(|err| format!("presentation: {err}")),
+        );
+        errors.extend(
+            get_color_errors(&[
+                ("active_color", self.desktop_widgets.active_color.as_ref()),
+                (
+                    "inactive_color",
+                    self.desktop_widgets.inactive_color.as_ref(),
+                ),
+            ])
+            .into_iter()
+            .map(|err| format!("desktop_widgets: {err}")),
+        );
+
+        for (name, monitor) in self.monitors.iter() {
+            errors.extend(
+                get_color_errors(&[
+                    ("active_color", monitor.active_color.as_ref()),
+                    ("inactive_color", monitor.inactive_color.as_ref()),
+                ])
+                .into_iter()
+                .map(|err| format!("monitor '{name}': {err}")),
+            );
+        }
+
+        for (i, schedule) in self.schedules.iter().enumerate() {
+            errors.extend(
+                get_color_errors(&[
+                    ("active_color", schedule.active_color.as_ref()),
+                    ("inactive_color", schedule.inactive_color.as_ref()),
+                ])
+                .into_iter()
+                .map(|err| format!("schedule {i}: {err}")),
+            );
+        }
+
+        let mut invalid_rules = Vec::new();
+        for (i, rule) in self.window_rules.iter().enumerate() {
+            let rule_errors = rule.get_color_errors();
+            if !rule_errors.is_empty() {
+                invalid_rules.push(i);
+            }
+
+            errors.extend(
+                rule_errors
+                    .into_iter()
+                    .map(|err| format!("window rule '{}': {err}", rule.display_name())),
+            );
+        }
+
+        if self.skip_invalid_rules && !invalid_rules.is_empty() {
+            let mut i = 0;
+            self.window_rules.retain(|_| {
+                i += 1;
+                !invalid_rules.contains(&(i - 1))
+            });
+        }
+
+        self.color_errors = errors;
+    }
+
+    // Logs the errors found by validate_colors() and shows them in a message box
+    pub fn report_color_errors(&self) {
+        if self.color_errors.is_empty() {
+            return;
+        }
+
+        for err in self.color_errors.iter() {
+            error!("invalid color in config: {err}");
+        }
+
+        let skipped_note = match self.skip_invalid_rules {
+            true => "\n\nWindow rules with invalid colors have been skipped.",
+            false => "",
+        };
+        display_error_box(format!(
+            "found invalid colors in config.yaml:\n\n{}{skipped_note}",
+            self.color_errors.join("\n")
+        ));
+    }
+
+    // Read-only mode can be enabled in the config or with the --read-only command-line switch. In
+    // this mode, the config can still be reloaded through the tray icon.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only || CLI_ARGS.read_only
+    }
+
+    // Window rules can override the render backend, so we check those too. This is used to figure
+    // out whether we need to create the DirectX devices for the V2 render backend.
+    pub fn uses_render_backend(&self, render_backend: RenderBackendConfig) -> bool {
+        self.render_backend == render_backend
+            || self
+                .window_rules
+                .iter()
+                .any(|rule| rule.render_backend == Some(render_backend))
+    }
+
+    pub fn get_monitor_config(&self, hmonitor: HMONITOR) -> Option<&MonitorConfig> {
+        if self.monitors.is_empty() {
+            return None;
+        }
+
+        // Device names look like "\\.\DISPLAY1", but we'll also accept just "DISPLAY1"
+        let matches_device_name = |key: &str| {
+            get_monitor_device_name(hmonitor).is_ok_and(|device_name| {
+                device_name.eq_ignore_ascii_case(key)
+                    || device_name
+                        .trim_start_matches(r"\\.\")
+                        .eq_ignore_ascii_case(key)
+            })
+        };
+        let matches_index = |key: &str| {
+            get_monitor_index(hmonitor).is_some_and(|index| key.parse::<usize>() == Ok(index))
+        };
+
+        // Device names take priority over indices
+        self.monitors
+            .iter()
+            .find(|(key, _)| matches_device_name(key.trim()))
+            .or_else(|| {
+                self.monitors
+                    .iter()
+                    .find(|(key, _)| matches_index(key.trim()))
+            })
+            .map(|(_, monitor_config)| monitor_config)
+    }
+
+    pub fn is_high_contrast_active(&self) -> bool {
+        self.high_contrast.enabled && is_high_contrast_on()
+    }
+
+    pub fn is_presentation_mode_active(&self) -> bool {
+        IS_PRESENTATION_MODE.load(Ordering::Relaxed)
+    }
+
+    pub fn get_active_schedule_index(&self) -> Option<usize> {
+        if self.schedules.is_empty() {
+            return None;
+        }
+
+        let now = TimeOfDay::now();
+        self.schedules
+            .iter()
+            .position(|schedule| schedule.contains(now))
+    }
+
+    pub fn get_color_settings(&self) -> ColorSettings {
+        ColorSettings {
+            alpha_mode: self.alpha_mode,
+            legacy_accent_color: self.legacy_accent_color,
+            accent_normalization: Some(self.accent_normalization)
+                .filter(|normalization| normalization.enabled),
+        }
+    }
+
+    pub fn get_scheduled_profile(&self) -> Option<String> {
+        if let Some(profile) = PROFILE_OVERRIDE.lock().unwrap().as_ref()
+            && self.profiles.contains_key(profile)
+        {
+            return Some(profile.clone());
+        }
+
+        if !self.monitor_layouts.is_empty() {
+            let layout = MonitorLayout::current();
+            if let Some(entry) = self
+                .monitor_layouts
+                .iter()
+                .find(|entry| entry.matches(&layout))
+            {
+                return Some(entry.profile.clone());
+            }
+        }
+
+        if self.profile_schedule.is_empty() {
+            return None;
+        }
+
+        let (today, now) = (Weekday::today(), TimeOfDay::now());
+        self.profile_schedule
+            .iter()
+            .find(|entry| entry.contains(today, now))
+            .map(|entry| entry.profile.clone())
+    }
+
+    // Resolves a window's active and inactive colors. In order of precedence, we check the high
+    // contrast colors (if high contrast mode is on), the presentation colors (if presentation mode
+    // is on), the window rule, the active schedule, then the window's monitor, then the global
+    // config.
+    pub fn get_color_configs<'a>(
+        &'a self,
+        window_rule: &'a WindowRule,
+        hmonitor: HMONITOR,
+    ) -> (&'a ColorBrushConfig, &'a ColorBrushConfig) {
+        if self.is_high_contrast_active() {
+            return (
+                &self.high_contrast.active_color,
+                &self.high_contrast.inactive_color,
+            );
+        }
+        if self.is_presentation_mode_active() {
+            return (
+                &self.presentation.active_color,
+                &self.presentation.inactive_color,
+            );
+        }
+
+        let monitor_config = self.get_monitor_config(hmonitor);
+        let schedule = self
+            .get_active_schedule_index()
+            .map(|index| &self.schedules[index]);
+
+        let active_color_config = window_rule
+            .active_color
+            .as_ref()
+            .or(schedule.and_then(|schedule| schedule.active_color.as_ref()))
+            .or(monitor_config.and_then(|config| config.active_color.as_ref()))
+            .unwrap_or(&self.global.active_color);
+        let inactive_color_config = window_rule
+            .inactive_color
+            .as_ref()
+            .or(schedule.and_then(|schedule| schedule.inactive_color.as_ref()))
+            .or(monitor_config.and_then(|config| config.inactive_color.as_ref()))
+            .unwrap_or(&self.global.inactive_color);
+
+        (active_color_config, inactive_color_config)
     }
 
     pub fn get_dir() -> anyhow::Result<PathBuf> {
@@ -240,62 +1711,270 @@ impl Config {
     }
 
     pub fn reload() {
-        let new_config = match Self::create() {
+        match Self::create() {
             Ok(config) => {
-                {
-                    let mut config_watcher = APP_STATE.config_watcher.lock().unwrap();
-
-                    if config_watcher.is_enabled(&config) && !config_watcher.is_running() {
-                        config_watcher.start().log_if_err();
-                    } else if !config_watcher.is_enabled(&config) && config_watcher.is_running() {
-                        config_watcher.stop().log_if_err();
-                    }
-                }
+                config.report_color_errors();
+                config.apply();
+            }
+            Err(err) => {
+                error!("could not reload config: {err:#}");
+                display_error_box(format!("could not reload config: {err:#}"));
 
-                {
-                    let mut komorebi_integration = APP_STATE.komorebi_integration.lock().unwrap();
-
-                    if komorebi_integration.is_enabled(&config)
-                        && !komorebi_integration.is_running()
-                    {
-                        komorebi_integration.start().log_if_err();
-                    } else if !komorebi_integration.is_enabled(&config)
-                        && komorebi_integration.is_running()
-                    {
-                        komorebi_integration.stop().log_if_err();
-                    }
-                }
+                let config = Config::default();
+                set_color_settings(config.get_color_settings());
+                *APP_STATE.config.write().unwrap() = config;
+            }
+        }
+    }
 
-                {
-                    let mut directx_devices_opt = APP_STATE.directx_devices.write().unwrap();
-
-                    if config.render_backend == RenderBackendConfig::V2
-                        && directx_devices_opt.is_none()
-                    {
-                        let direct_x_devices = DirectXDevices::new(&APP_STATE.render_factory)
-                            .unwrap_or_else(|err| {
-                                error!("could not create directx devices: {err}");
-                                panic!("could not create directx devices: {err}");
-                            });
-
-                        *directx_devices_opt = Some(direct_x_devices);
-                    } else if config.render_backend == RenderBackendConfig::Legacy
-                        && directx_devices_opt.is_some()
-                    {
-                        *directx_devices_opt = None;
-                    }
-                }
+    // Rebuilds the active config with whichever profile get_scheduled_profile() picks now. Unlike
+    // reload(), this doesn't read config.yaml, so it also works for configs passed to
+    // start_engine(). Like reload(), existing borders are left alone.
+    pub fn switch_profile() {
+        let base_yaml_res = {
+            let config = APP_STATE.config.read().unwrap();
+            match config.base_yaml {
+                Some(ref base_yaml) => serde_yml::to_string(base_yaml).map_err(anyhow::Error::new),
+                // Configs built in code never had a profile merged in
+                None => config.to_yaml(),
+            }
+        };
 
-                config
+        match base_yaml_res.and_then(|base_yaml| Self::from_yaml(&base_yaml)) {
+            Ok(config) => {
+                config.report_color_errors();
+                config.apply();
             }
             Err(err) => {
-                error!("could not reload config: {err:#}");
-                display_error_box(format!("could not reload config: {err:#}"));
+                error!("could not switch profile: {err:#}");
+                display_error_box(format!("could not switch profile: {err:#}"));
+            }
+        }
+    }
+
+    // Makes this the active config, starting or stopping whichever services it needs. Note that
+    // this doesn't touch existing borders; use reload_borders() for that.
+    pub fn apply(self) {
+        {
+            let mut config_watcher = APP_STATE.config_watcher.lock().unwrap();
+
+            if config_watcher.is_enabled(&self) && !config_watcher.is_running() {
+                config_watcher.start().log_if_err();
+            } else if !config_watcher.is_enabled(&self) && config_watcher.is_running() {
+                config_watcher.stop().log_if_err();
+            }
+        }
+
+        start_detector_if_enabled(&self);
+        start_hot_corner_if_enabled(&self);
+        start_tracker_if_enabled(&self);
+
+        {
+            let mut komorebi_integration = APP_STATE.komorebi_integration.lock().unwrap();
 
-                Config::default()
+            if komorebi_integration.is_enabled(&self) && !komorebi_integration.is_running() {
+                komorebi_integration.start().log_if_err();
+            } else if !komorebi_integration.is_enabled(&self) && komorebi_integration.is_running() {
+                komorebi_integration.stop().log_if_err();
             }
+        }
+
+        {
+            let mut directx_devices_opt = APP_STATE.directx_devices.write().unwrap();
+
+            if self.uses_render_backend(RenderBackendConfig::V2) && directx_devices_opt.is_none() {
+                let direct_x_devices = DirectXDevices::new(&APP_STATE.render_factory)
+                    .unwrap_or_else(|err| {
+                        error!("could not create directx devices: {err}");
+                        panic!("could not create directx devices: {err}");
+                    });
+
+                *directx_devices_opt = Some(direct_x_devices);
+            } else if !self.uses_render_backend(RenderBackendConfig::V2)
+                && directx_devices_opt.is_some()
+            {
+                *directx_devices_opt = None;
+            }
+        }
+
+        set_color_settings(self.get_color_settings());
+        *APP_STATE.config.write().unwrap() = self;
+    }
+}
+
+fn get_color_errors<S: AsRef<str>>(colors: &[(S, Option<&ColorBrushConfig>)]) -> Vec<String> {
+    colors
+        .iter()
+        .filter_map(|(field, color_opt)| {
+            let err = (*color_opt)?.validate().err()?;
+            Some(format!("{}: {err:#}", field.as_ref()))
+        })
+        .collect()
+}
+
+// References can also be nested in color functions, e.g. lighten(palette.rose, 10%)
+static PALETTE_REF_REGEX: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"palette\.([\w-]+)").unwrap());
+
+thread_local! {
+    // The palette of the config that's being deserialized on this thread, if it has one
+    static PALETTE: RefCell<Option<HashMap<String, String>>> = const { RefCell::new(None) };
+}
+
+// Palette references get resolved as each ColorBrushConfig is deserialized, so this has to wrap
+// the deserialization. Other strings (e.g. a window rule's 'name' pattern) are never touched.
+fn with_palette<T>(palette: &HashMap<String, String>, f: impl FnOnce() -> T) -> T {
+    PALETTE.set(Some(palette.clone()));
+    let result = f();
+    PALETTE.set(None);
+
+    result
+}
+
+// Returns the color as-is if we're not inside with_palette()
+pub fn resolve_palette_refs(color: &str) -> anyhow::Result<String> {
+    PALETTE.with_borrow(|palette| {
+        let Some(palette) = palette else {
+            return Ok(color.to_string());
+        };
+
+        let mut missing: Option<String> = None;
+        let resolved = PALETTE_REF_REGEX.replace_all(color, |captures: &regex::Captures| {
+            let name = &captures[1];
+            palette.get(name).cloned().unwrap_or_else(|| {
+                missing.get_or_insert_with(|| name.to_string());
+                String::new()
+            })
+        });
+
+        match missing {
+            Some(name) => Err(anyhow!("could not find '{name}' in the palette")),
+            None => Ok(resolved.into_owned()),
+        }
+    })
+}
+
+// Merges each entry under the top-level 'conditional' key into the rest of the config if its 'when'
+// is met, e.g.:
+//
+//   conditional:
+//     - when: { os: ">=22621" }
+//       global:
+//         border_radius: Auto
+//
+// Mappings are merged key by key and other values are replaced, except for window_rules, which get
+// placed in front of the existing rules so they take priority. Returns false if there's no
+// 'conditional' key at all.
+fn apply_conditional_sections(value: &mut serde_yml::Value) -> anyhow::Result<bool> {
+    let Some(root) = value.as_mapping_mut() else {
+        return Ok(false);
+    };
+    let Some(sections) = root.remove("conditional") else {
+        return Ok(false);
+    };
+    let serde_yml::Value::Sequence(sections) = sections else {
+        return Err(anyhow!("'conditional' must be a list"));
+    };
+
+    for (i, section) in sections.into_iter().enumerate() {
+        let serde_yml::Value::Mapping(mut section) = section else {
+            return Err(anyhow!("conditional section {i} must be a mapping"));
         };
-        *APP_STATE.config.write().unwrap() = new_config;
+
+        let when: ConditionConfig = section
+            .remove("when")
+            .map(serde_yml::from_value)
+            .transpose()
+            .map_err(anyhow::Error::new)
+            .with_context(|| format!("invalid 'when' in conditional section {i}"))?
+            .ok_or_else(|| anyhow!("conditional section {i} is missing 'when'"))?;
+        if !when
+            .check()
+            .with_context(|| format!("invalid 'when' in conditional section {i}"))?
+        {
+            continue;
+        }
+
+        debug!("applying conditional section {i}");
+        merge_section(root, section);
+    }
+
+    Ok(true)
+}
+
+// Merges the named profile on top of the rest of the config, the same way conditional sections
+// are merged. Profiles can't contain other profiles or their own schedule.
+fn apply_profile(value: &mut serde_yml::Value, profile: &str) -> anyhow::Result<()> {
+    let Some(root) = value.as_mapping_mut() else {
+        return Ok(());
+    };
+    let overlay = root
+        .get("profiles")
+        .and_then(|profiles| profiles.get(profile))
+        .cloned()
+        .ok_or_else(|| anyhow!("could not find profile '{profile}'"))?;
+    let serde_yml::Value::Mapping(overlay) = overlay else {
+        return Err(anyhow!("profile '{profile}' must be a mapping"));
+    };
+    if overlay.contains_key("profiles")
+        || overlay.contains_key("profile_schedule")
+        || overlay.contains_key("monitor_layouts")
+    {
+        return Err(anyhow!(
+            "profile '{profile}' cannot contain 'profiles', 'profile_schedule', or \
+            'monitor_layouts'"
+        ));
+    }
+
+    merge_section(root, overlay);
+
+    Ok(())
+}
+
+fn merge_section(root: &mut serde_yml::Mapping, section: serde_yml::Mapping) {
+    for (key, item) in section {
+        match (key.as_str(), root.get_mut(&key), item) {
+            (
+                Some("window_rules"),
+                Some(serde_yml::Value::Sequence(rules)),
+                serde_yml::Value::Sequence(mut new_rules),
+            ) => {
+                new_rules.append(rules);
+                *rules = new_rules;
+            }
+            (_, Some(existing), item) => merge_yaml(existing, item),
+            (_, None, item) => {
+                root.insert(key, item);
+            }
+        }
+    }
+}
+
+fn merge_yaml(base: &mut serde_yml::Value, overlay: serde_yml::Value) {
+    match (base, overlay) {
+        (serde_yml::Value::Mapping(base), serde_yml::Value::Mapping(overlay)) => {
+            for (key, item) in overlay {
+                match base.get_mut(&key) {
+                    Some(existing) => merge_yaml(existing, item),
+                    None => {
+                        base.insert(key, item);
+                    }
+                }
+            }
+        }
+        (base, overlay) => *base = overlay,
+    }
+}
+
+fn strip_nulls(value: &mut serde_yml::Value) {
+    match value {
+        serde_yml::Value::Sequence(sequence) => sequence.iter_mut().for_each(strip_nulls),
+        serde_yml::Value::Mapping(mapping) => {
+            mapping.retain(|_, item| !item.is_null());
+            mapping.values_mut().for_each(strip_nulls);
+        }
+        serde_yml::Value::Tagged(tagged) => strip_nulls(&mut tagged.value),
+        _ => {}
     }
 }
 
@@ -318,7 +1997,7 @@ impl ConfigWatcher {
     }
 
     pub fn is_enabled(&mut self, config: &Config) -> bool {
-        config.watch_config_changes
+        config.watch_config_changes && !config.is_read_only()
     }
 
     pub fn start(&mut self) -> anyhow::Result<()> {
@@ -477,3 +2156,101 @@ pub fn config_watcher_callback() {
         reload_borders();
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concentric_radius_compensation() {
+        // The default border_width and border_offset
+        let compensation =
+            RadiusCompensation::Concentric.to_compensation(4, &BorderOffset::uniform(-1), 96);
+        assert_eq!(compensation, 1.0);
+
+        let border_offset = BorderOffset {
+            top: 2,
+            left: 0,
+            right: 0,
+            bottom: 2,
+        };
+        let compensation = RadiusCompensation::Concentric.to_compensation(6, &border_offset, 96);
+        assert_eq!(compensation, 4.0);
+    }
+
+    #[test]
+    fn test_palette_refs_only_resolve_in_colors() -> anyhow::Result<()> {
+        let config = Config::from_yaml(
+            r##"
+palette:
+  rose: "#f5a0b0"
+global:
+  active_color: "lighten(palette.rose, 10%)"
+  inactive_color:
+    colors: ["palette.rose", "#000000"]
+    direction: 90deg
+  outline:
+    enabled: true
+    inactive_color: "palette.rose.complement"
+window_rules:
+  - match: Title
+    name: "palette.rose"
+    active_color: "palette.rose"
+"##,
+        )?;
+
+        assert_eq!(
+            config.global.active_color,
+            ColorBrushConfig::Solid("lighten(#f5a0b0, 10%)".to_string())
+        );
+        let ColorBrushConfig::Gradient(ref gradient) = config.global.inactive_color else {
+            panic!("inactive_color should be a gradient");
+        };
+        assert_eq!(gradient.colors[0], "#f5a0b0");
+        assert_eq!(
+            config.global.outline.inactive_color,
+            Some(ColorBrushConfig::Solid("#f5a0b0.complement".to_string()))
+        );
+
+        let rule = &config.window_rules[0];
+        assert_eq!(rule.name.as_deref(), Some("palette.rose"));
+        assert_eq!(
+            rule.active_color,
+            Some(ColorBrushConfig::Solid("#f5a0b0".to_string()))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_yaml_round_trip() -> anyhow::Result<()> {
+        let custom_config = r##"
+global:
+  border_width: "3px"
+  border_offset: "max(-1, 0 - scale)"
+  active_color:
+    colors: ["accent", "#89b4fa"]
+    direction: 45deg
+  inactive_color: "darken(accent, 20%)"
+window_rules:
+  - match: Process
+    name: "firefox"
+    strategy: Contains
+    border_width: 2
+    active_color:
+      direction: 180deg
+"##;
+
+        for contents in [DEFAULT_CONFIG, custom_config] {
+            let mut config = Config::from_yaml(contents)?;
+            let mut round_tripped = Config::from_yaml(&config.to_yaml()?)?;
+
+            // The yaml itself is bound to look different
+            config.base_yaml = None;
+            round_tripped.base_yaml = None;
+            assert_eq!(config, round_tripped);
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,236 @@
+use anyhow::{Context, anyhow};
+use regex::Regex;
+
+use crate::colors::ColorBrushConfig;
+use crate::config::{
+    AcrylicConfig, Config, EnableMode, Length, MatchKind, MatchStrategy, MonitorConfig,
+    OffsetConfig, OutlineConfig, RadiusConfig, WindowRule, serde_default_global,
+};
+use crate::render_backend::RenderBackendConfig;
+use crate::schedule::ScheduleConfig;
+
+// Builders for tools that generate configs (e.g. theme generators). Everything here ends up as a
+// plain Config/WindowRule, so the result can also be written out with Config::to_yaml().
+#[derive(Debug, Clone)]
+pub struct ConfigBuilder {
+    config: Config,
+}
+
+impl Config {
+    // Starts from the same values that an empty config.yaml would give you
+    pub fn builder() -> ConfigBuilder {
+        ConfigBuilder {
+            config: Config {
+                global: serde_default_global(),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl ConfigBuilder {
+    pub fn watch_config_changes(mut self, watch_config_changes: bool) -> Self {
+        self.config.watch_config_changes = watch_config_changes;
+        self
+    }
+
+    pub fn hide_from_uia(mut self, hide_from_uia: bool) -> Self {
+        self.config.hide_from_uia = hide_from_uia;
+        self
+    }
+
+    pub fn render_backend(mut self, render_backend: RenderBackendConfig) -> Self {
+        self.config.render_backend = render_backend;
+        self
+    }
+
+    pub fn border_width(mut self, border_width: f32) -> Self {
+        self.config.global.border_width = Length::dip(border_width);
+        self
+    }
+
+    pub fn border_offset(mut self, border_offset: OffsetConfig) -> Self {
+        self.config.global.border_offset = border_offset;
+        self
+    }
+
+    pub fn border_radius(mut self, border_radius: RadiusConfig) -> Self {
+        self.config.global.border_radius = border_radius;
+        self
+    }
+
+    pub fn active_color(mut self, active_color: ColorBrushConfig) -> Self {
+        self.config.global.active_color = active_color;
+        self
+    }
+
+    pub fn inactive_color(mut self, inactive_color: ColorBrushConfig) -> Self {
+        self.config.global.inactive_color = inactive_color;
+        self
+    }
+
+    pub fn outline(mut self, outline: OutlineConfig) -> Self {
+        self.config.global.outline = outline;
+        self
+    }
+
+    pub fn acrylic(mut self, acrylic: AcrylicConfig) -> Self {
+        self.config.global.acrylic = acrylic;
+        self
+    }
+
+    pub fn initialize_delay(mut self, initialize_delay: u64) -> Self {
+        self.config.global.initialize_delay = initialize_delay;
+        self
+    }
+
+    pub fn unminimize_delay(mut self, unminimize_delay: u64) -> Self {
+        self.config.global.unminimize_delay = unminimize_delay;
+        self
+    }
+
+    // Rules are matched in the order they're added
+    pub fn window_rule(mut self, window_rule: WindowRule) -> Self {
+        self.config.window_rules.push(window_rule);
+        self
+    }
+
+    // 'monitor' is either a device name (e.g. "DISPLAY1") or an index (e.g. "0")
+    pub fn monitor(mut self, monitor: impl Into<String>, monitor_config: MonitorConfig) -> Self {
+        self.config.monitors.insert(monitor.into(), monitor_config);
+        self
+    }
+
+    pub fn palette_color(mut self, name: impl Into<String>, color: impl Into<String>) -> Self {
+        self.config.palette.insert(name.into(), color.into());
+        self
+    }
+
+    pub fn schedule(mut self, schedule: ScheduleConfig) -> Self {
+        self.config.schedules.push(schedule);
+        self
+    }
+
+    // Fails if any color in the config is invalid. Palette references are resolved the same way
+    // they are in config.yaml, so we just round-trip through yaml here.
+    pub fn build(self) -> anyhow::Result<Config> {
+        let config = if self.config.palette.is_empty() {
+            let mut config = self.config;
+            config.merge_partial_gradients();
+            config.validate_colors();
+            config
+        } else {
+            Config::from_yaml(&self.config.to_yaml()?)?
+        };
+
+        if !config.color_errors.is_empty() {
+            return Err(anyhow!(
+                "found invalid colors: {}",
+                config.color_errors.join("; ")
+            ));
+        }
+
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WindowRuleBuilder {
+    window_rule: WindowRule,
+}
+
+impl WindowRule {
+    pub fn builder(kind: MatchKind, name: impl Into<String>) -> WindowRuleBuilder {
+        WindowRuleBuilder {
+            window_rule: WindowRule {
+                kind: Some(kind),
+                name: Some(name.into()),
+                ..Default::default()
+            },
+        }
+    }
+}
+
+impl WindowRuleBuilder {
+    pub fn id(mut self, id: impl Into<String>) -> Self {
+        self.window_rule.id = Some(id.into());
+        self
+    }
+
+    pub fn strategy(mut self, strategy: MatchStrategy) -> Self {
+        self.window_rule.strategy = Some(strategy);
+        self
+    }
+
+    pub fn match_children(mut self, match_children: bool) -> Self {
+        self.window_rule.match_children = Some(match_children);
+        self
+    }
+
+    pub fn enabled(mut self, enabled: EnableMode) -> Self {
+        self.window_rule.enabled = Some(enabled);
+        self
+    }
+
+    pub fn border_width(mut self, border_width: f32) -> Self {
+        self.window_rule.border_width = Some(Length::dip(border_width));
+        self
+    }
+
+    pub fn border_offset(mut self, border_offset: OffsetConfig) -> Self {
+        self.window_rule.border_offset = Some(border_offset);
+        self
+    }
+
+    pub fn border_radius(mut self, border_radius: RadiusConfig) -> Self {
+        self.window_rule.border_radius = Some(border_radius);
+        self
+    }
+
+    pub fn active_color(mut self, active_color: ColorBrushConfig) -> Self {
+        self.window_rule.active_color = Some(active_color);
+        self
+    }
+
+    pub fn inactive_color(mut self, inactive_color: ColorBrushConfig) -> Self {
+        self.window_rule.inactive_color = Some(inactive_color);
+        self
+    }
+
+    pub fn outline(mut self, outline: OutlineConfig) -> Self {
+        self.window_rule.outline = Some(outline);
+        self
+    }
+
+    pub fn acrylic(mut self, acrylic: AcrylicConfig) -> Self {
+        self.window_rule.acrylic = Some(acrylic);
+        self
+    }
+
+    pub fn initialize_delay(mut self, initialize_delay: u64) -> Self {
+        self.window_rule.initialize_delay = Some(initialize_delay);
+        self
+    }
+
+    pub fn unminimize_delay(mut self, unminimize_delay: u64) -> Self {
+        self.window_rule.unminimize_delay = Some(unminimize_delay);
+        self
+    }
+
+    pub fn render_backend(mut self, render_backend: RenderBackendConfig) -> Self {
+        self.window_rule.render_backend = Some(render_backend);
+        self
+    }
+
+    // Colors may reference the palette, so they're validated by ConfigBuilder::build() instead
+    pub fn build(self) -> anyhow::Result<WindowRule> {
+        let window_rule = self.window_rule;
+
+        if window_rule.strategy == Some(MatchStrategy::Regex) {
+            let pattern = window_rule.name.as_deref().unwrap_or_default();
+            Regex::new(pattern).with_context(|| format!("invalid regex '{pattern}'"))?;
+        }
+
+        Ok(window_rule)
+    }
+}
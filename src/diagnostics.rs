@@ -0,0 +1,292 @@
+// A small window (opened from the tray) for troubleshooting without having to find the log file.
+// It shows a tail of the log that can be filtered by level, along with a count of the errors that
+// usually explain missing borders (window creation, DWM, and Direct2D failures).
+use anyhow::{Context, anyhow};
+use log::LevelFilter;
+use std::sync::atomic::{AtomicIsize, AtomicU64, Ordering};
+use std::thread;
+use windows::Win32::Foundation::{ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::Graphics::Gdi::{DEFAULT_GUI_FONT, GetStockObject};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CB_ADDSTRING, CB_GETCURSEL, CB_SETCURSEL, CBN_SELCHANGE, CBS_DROPDOWNLIST, CW_USEDEFAULT,
+    CreateWindowExW, DefWindowProcW, DispatchMessageW, ES_AUTOVSCROLL, ES_MULTILINE, ES_READONLY,
+    GetDlgItem, GetMessageW, HMENU, IDC_ARROW, KillTimer, LoadCursorW, MSG, MoveWindow,
+    PostQuitMessage, RegisterClassExW, SW_RESTORE, SetForegroundWindow, SetTimer, SetWindowTextW,
+    ShowWindow, TranslateMessage, WINDOW_EX_STYLE, WINDOW_STYLE, WM_COMMAND, WM_CREATE, WM_DESTROY,
+    WM_SETFONT, WM_SIZE, WM_TIMER, WNDCLASSEXW, WS_BORDER, WS_CHILD, WS_OVERLAPPEDWINDOW,
+    WS_VISIBLE, WS_VSCROLL,
+};
+use windows::core::{PCWSTR, w};
+
+use crate::error::get_error_summary;
+use crate::log_buffer::{get_log_generation, get_recent_log_lines};
+use crate::memory_trim::get_trim_summary;
+use crate::utils::{LogIfErr, get_last_error, hiword, loword, send_message_w};
+
+// These live in Win32_UI_Controls, which we don't otherwise need
+const EM_SETSEL: u32 = 0x00B1;
+const EM_SCROLLCARET: u32 = 0x00B7;
+const EM_SETLIMITTEXT: u32 = 0x00C5;
+
+const LEVEL_COMBO_ID: i32 = 1;
+const LOG_EDIT_ID: i32 = 2;
+const REFRESH_TIMER_ID: usize = 1;
+const REFRESH_INTERVAL_MS: u32 = 500;
+const MAX_SHOWN_LINES: usize = 500;
+const COMBO_HEIGHT: i32 = 200; // Includes the dropdown list
+const COMBO_WIDTH: i32 = 120;
+const MARGIN: i32 = 8;
+
+const LEVELS: [(PCWSTR, LevelFilter); 4] = [
+    (w!("Error"), LevelFilter::Error),
+    (w!("Warn"), LevelFilter::Warn),
+    (w!("Info"), LevelFilter::Info),
+    (w!("Debug"), LevelFilter::Debug),
+];
+const DEFAULT_LEVEL_IDX: usize = 2;
+
+// Only one diagnostics window is open at a time
+static DIAGNOSTICS_WINDOW: AtomicIsize = AtomicIsize::new(0);
+// The log generation that the window last showed, so we only refresh when something was logged
+static SHOWN_GENERATION: AtomicU64 = AtomicU64::new(u64::MAX);
+
+pub fn show_diagnostics_window() {
+    let existing_window = DIAGNOSTICS_WINDOW.load(Ordering::SeqCst);
+    if existing_window != 0 {
+        let hwnd = HWND(existing_window as _);
+        unsafe {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+            let _ = SetForegroundWindow(hwnd);
+        }
+        return;
+    }
+
+    // The window gets its own thread and message loop, like the borders do
+    let _ = thread::spawn(|| run_diagnostics_window().log_if_err());
+}
+
+fn run_diagnostics_window() -> anyhow::Result<()> {
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+
+        let window_class = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(diagnostics_wnd_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: w!("tacky-diagnostics"),
+            hCursor: LoadCursorW(None, IDC_ARROW)?,
+            ..Default::default()
+        };
+        if RegisterClassExW(&window_class) == 0 {
+            let last_error = get_last_error();
+            if last_error != ERROR_CLASS_ALREADY_EXISTS {
+                return Err(anyhow!(
+                    "could not register diagnostics window class: {last_error:?}"
+                ));
+            }
+        }
+
+        let hwnd = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("tacky-diagnostics"),
+            w!("tacky-borders diagnostics"),
+            WS_OVERLAPPEDWINDOW | WS_VISIBLE,
+            CW_USEDEFAULT,
+            CW_USEDEFAULT,
+            800,
+            500,
+            None,
+            None,
+            Some(hinstance.into()),
+            None,
+        )
+        .context("could not create diagnostics window")?;
+        DIAGNOSTICS_WINDOW.store(hwnd.0 as isize, Ordering::SeqCst);
+
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, None, 0, 0).into() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn diagnostics_wnd_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        WM_CREATE => {
+            create_controls(hwnd).log_if_err();
+            SHOWN_GENERATION.store(u64::MAX, Ordering::SeqCst);
+            refresh_log(hwnd);
+
+            unsafe { SetTimer(Some(hwnd), REFRESH_TIMER_ID, REFRESH_INTERVAL_MS, None) };
+        }
+        WM_SIZE => {
+            let width = loword(lparam.0 as usize) as i32;
+            let height = hiword(lparam.0 as usize) as i32;
+
+            unsafe {
+                if let Ok(combo) = GetDlgItem(Some(hwnd), LEVEL_COMBO_ID) {
+                    let _ = MoveWindow(combo, MARGIN, MARGIN, COMBO_WIDTH, COMBO_HEIGHT, true);
+                }
+                if let Ok(edit) = GetDlgItem(Some(hwnd), LOG_EDIT_ID) {
+                    // The combo box's closed height is roughly the height of a line of text
+                    let top = MARGIN * 2 + 24;
+                    let _ = MoveWindow(
+                        edit,
+                        MARGIN,
+                        top,
+                        (width - MARGIN * 2).max(0),
+                        (height - top - MARGIN).max(0),
+                        true,
+                    );
+                }
+            }
+        }
+        WM_COMMAND
+            if loword(wparam.0) as i32 == LEVEL_COMBO_ID
+                && hiword(wparam.0) as u32 == CBN_SELCHANGE =>
+        {
+            SHOWN_GENERATION.store(u64::MAX, Ordering::SeqCst);
+            refresh_log(hwnd);
+        }
+        WM_TIMER if wparam.0 == REFRESH_TIMER_ID => {
+            refresh_log(hwnd);
+        }
+        WM_DESTROY => {
+            unsafe {
+                let _ = KillTimer(Some(hwnd), REFRESH_TIMER_ID);
+                PostQuitMessage(0);
+            }
+            DIAGNOSTICS_WINDOW.store(0, Ordering::SeqCst);
+        }
+        _ => return unsafe { DefWindowProcW(hwnd, message, wparam, lparam) },
+    }
+
+    LRESULT(0)
+}
+
+fn create_controls(hwnd: HWND) -> anyhow::Result<()> {
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+        let font = GetStockObject(DEFAULT_GUI_FONT);
+
+        let combo = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("COMBOBOX"),
+            None,
+            WS_CHILD | WS_VISIBLE | WS_VSCROLL | WINDOW_STYLE(CBS_DROPDOWNLIST as u32),
+            MARGIN,
+            MARGIN,
+            COMBO_WIDTH,
+            COMBO_HEIGHT,
+            Some(hwnd),
+            Some(HMENU(LEVEL_COMBO_ID as _)),
+            Some(hinstance.into()),
+            None,
+        )
+        .context("could not create level combo box")?;
+        send_message_w(
+            combo,
+            WM_SETFONT,
+            Some(WPARAM(font.0 as _)),
+            Some(LPARAM(1)),
+        );
+        for (name, _) in LEVELS {
+            send_message_w(
+                combo,
+                CB_ADDSTRING,
+                Some(WPARAM(0)),
+                Some(LPARAM(name.0 as _)),
+            );
+        }
+        send_message_w(
+            combo,
+            CB_SETCURSEL,
+            Some(WPARAM(DEFAULT_LEVEL_IDX)),
+            Some(LPARAM(0)),
+        );
+
+        let edit = CreateWindowExW(
+            WINDOW_EX_STYLE::default(),
+            w!("EDIT"),
+            None,
+            WS_CHILD
+                | WS_VISIBLE
+                | WS_VSCROLL
+                | WS_BORDER
+                | WINDOW_STYLE((ES_MULTILINE | ES_READONLY | ES_AUTOVSCROLL) as u32),
+            0,
+            0,
+            0,
+            0,
+            Some(hwnd),
+            Some(HMENU(LOG_EDIT_ID as _)),
+            Some(hinstance.into()),
+            None,
+        )
+        .context("could not create log edit control")?;
+        send_message_w(edit, WM_SETFONT, Some(WPARAM(font.0 as _)), Some(LPARAM(1)));
+        // Multiline edit controls are limited to 32K characters by default
+        send_message_w(edit, EM_SETLIMITTEXT, Some(WPARAM(0)), Some(LPARAM(0)));
+    }
+
+    Ok(())
+}
+
+fn refresh_log(hwnd: HWND) {
+    let generation = get_log_generation();
+    if SHOWN_GENERATION.swap(generation, Ordering::SeqCst) == generation {
+        return;
+    }
+
+    unsafe {
+        let (Ok(combo), Ok(edit)) = (
+            GetDlgItem(Some(hwnd), LEVEL_COMBO_ID),
+            GetDlgItem(Some(hwnd), LOG_EDIT_ID),
+        ) else {
+            return;
+        };
+
+        let level_idx =
+            send_message_w(combo, CB_GETCURSEL, Some(WPARAM(0)), Some(LPARAM(0))).0 as usize;
+        let level = LEVELS
+            .get(level_idx)
+            .unwrap_or(&LEVELS[DEFAULT_LEVEL_IDX])
+            .1;
+
+        // Put the error counts and memory stats up top so they don't get lost in the log
+        let mut lines = get_recent_log_lines(level, MAX_SHOWN_LINES);
+        let summaries: Vec<String> = [get_error_summary(), get_trim_summary()]
+            .into_iter()
+            .flatten()
+            .collect();
+        if !summaries.is_empty() {
+            lines.splice(0..0, summaries.into_iter().chain([String::new()]));
+        }
+
+        let text: Vec<u16> = lines
+            .join("\r\n")
+            .encode_utf16()
+            .chain(std::iter::once(0))
+            .collect();
+        let _ = SetWindowTextW(edit, PCWSTR(text.as_ptr()));
+
+        // Scroll to the newest line
+        let len = text.len() - 1;
+        send_message_w(
+            edit,
+            EM_SETSEL,
+            Some(WPARAM(len)),
+            Some(LPARAM(len as isize)),
+        );
+        send_message_w(edit, EM_SCROLLCARET, Some(WPARAM(0)), Some(LPARAM(0)));
+    }
+}
@@ -0,0 +1,149 @@
+// When none of the borders have been showing for a while (e.g. every window is filtered out or in
+// borderless fullscreen), we go dormant: the borders stop their timers and drop their renderers,
+// and we release the DirectX devices. Only the event hook keeps running. Everything gets recreated
+// once a border needs to show again.
+use anyhow::Context;
+use std::collections::HashSet;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::{thread, time};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+
+use crate::APP_STATE;
+use crate::render_backend::RenderBackendConfig;
+use crate::renderer_core::DirectXDevices;
+use crate::utils::{LogIfErr, WM_APP_DORMANT, post_message_w};
+
+const DORMANT_DELAY: time::Duration = time::Duration::from_secs(10);
+
+struct DormancyState {
+    // Border windows that are currently showing (NativeDwm borders count as long as they exist)
+    visible_borders: HashSet<isize>,
+    // A border that just called wake() hasn't made itself visible yet, so we hold off on going
+    // dormant again for a bit
+    last_wake: Option<time::Instant>,
+}
+
+// Going dormant and waking both happen under this lock, so that a border can't wake us up halfway
+// through going dormant (and then find the DirectX devices gone)
+static DORMANCY_STATE: LazyLock<Mutex<DormancyState>> = LazyLock::new(|| {
+    Mutex::new(DormancyState {
+        visible_borders: HashSet::new(),
+        last_wake: None,
+    })
+});
+// Only changed while DORMANCY_STATE is locked, but can be read without it
+static IS_DORMANT: AtomicBool = AtomicBool::new(false);
+static IS_CHECK_PENDING: AtomicBool = AtomicBool::new(false);
+
+pub fn set_border_visible(border_window: HWND, is_visible: bool) {
+    let mut dormancy_state = DORMANCY_STATE.lock().unwrap();
+    let visible_borders = &mut dormancy_state.visible_borders;
+
+    match is_visible {
+        true => {
+            visible_borders.insert(border_window.0 as isize);
+        }
+        false => {
+            if visible_borders.remove(&(border_window.0 as isize)) && visible_borders.is_empty() {
+                queue_dormancy_check();
+            }
+        }
+    }
+}
+
+pub fn shrink_visible_borders() {
+    DORMANCY_STATE
+        .lock()
+        .unwrap()
+        .visible_borders
+        .shrink_to_fit();
+}
+
+pub fn is_dormant() -> bool {
+    IS_DORMANT.load(Ordering::SeqCst)
+}
+
+// Goes dormant if there still aren't any visible borders after DORMANT_DELAY
+pub fn queue_dormancy_check() {
+    if IS_CHECK_PENDING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    let _ = thread::spawn(|| {
+        thread::sleep(DORMANT_DELAY);
+        IS_CHECK_PENDING.store(false, Ordering::SeqCst);
+
+        let dormancy_state = DORMANCY_STATE.lock().unwrap();
+        if !dormancy_state.visible_borders.is_empty() || is_dormant() {
+            return;
+        }
+
+        // Give the border that woke us up the rest of its DORMANT_DELAY to show itself
+        if dormancy_state
+            .last_wake
+            .is_some_and(|last_wake| last_wake.elapsed() < DORMANT_DELAY)
+        {
+            drop(dormancy_state);
+            queue_dormancy_check();
+            return;
+        }
+
+        info!("no borders are visible; going dormant");
+
+        IS_DORMANT.store(true, Ordering::SeqCst);
+        *APP_STATE.directx_devices.write().unwrap() = None;
+        drop(dormancy_state);
+
+        notify_borders_of_dormancy();
+    });
+}
+
+// Borders release their own references to the devices as they handle WM_APP_DORMANT. If one of
+// them wakes us up before the others get to it, they'll see that we aren't dormant anymore and
+// ignore the message.
+fn notify_borders_of_dormancy() {
+    for border_isize in APP_STATE.borders.lock().unwrap().values() {
+        post_message_w(
+            Some(HWND(*border_isize as _)),
+            WM_APP_DORMANT,
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("could not post WM_APP_DORMANT")
+        .log_if_err();
+    }
+}
+
+// Recreates whatever going dormant released. Borders call this before they show or initialize
+// their renderer, so it has to be done by the time this returns.
+pub fn wake() {
+    let mut dormancy_state = DORMANCY_STATE.lock().unwrap();
+    dormancy_state.last_wake = Some(time::Instant::now());
+
+    if !is_dormant() {
+        return;
+    }
+
+    info!("waking up from dormancy");
+
+    let uses_v2 = APP_STATE
+        .config
+        .read()
+        .unwrap()
+        .uses_render_backend(RenderBackendConfig::V2);
+    let mut directx_devices_opt = APP_STATE.directx_devices.write().unwrap();
+    if uses_v2 && directx_devices_opt.is_none() {
+        match DirectXDevices::new(&APP_STATE.render_factory) {
+            Ok(directx_devices) => *directx_devices_opt = Some(directx_devices),
+            Err(err) => error!("could not recreate directx devices: {err:#}"),
+        }
+    }
+    drop(directx_devices_opt);
+
+    IS_DORMANT.store(false, Ordering::SeqCst);
+    drop(dormancy_state);
+
+    // In case the border that woke us up doesn't end up showing after all
+    queue_dormancy_check();
+}
@@ -1,5 +1,5 @@
 use anyhow::{Context, anyhow};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::slice;
 use windows::Win32::Graphics::Direct2D::Common::{
     D2D1_COMPOSITE_MODE_DESTINATION_OUT, D2D1_COMPOSITE_MODE_SOURCE_OVER,
@@ -19,7 +19,7 @@ use crate::config::{serde_default_bool, serde_default_f32};
 use crate::render_backend::RenderBackend;
 use crate::window_border::WindowState;
 
-#[derive(Debug, Default, Deserialize, Clone, PartialEq)]
+#[derive(Debug, Default, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct EffectsConfig {
     #[serde(default)]
@@ -93,6 +93,11 @@ impl Effects {
         }
     }
 
+    pub fn release_command_lists(&mut self) {
+        self.active_command_list = None;
+        self.inactive_command_list = None;
+    }
+
     pub fn init_command_lists_if_enabled(
         &mut self,
         render_backend: &RenderBackend,
@@ -285,7 +290,7 @@ impl Effects {
     }
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct EffectParamsConfig {
     #[serde(alias = "type")]
@@ -321,13 +326,13 @@ pub struct EffectParams {
     pub translation: Translation,
 }
 
-#[derive(Debug, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum EffectType {
     Glow,
     Shadow,
 }
 
-#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub struct Translation {
     pub x: f32,
     pub y: f32,
@@ -0,0 +1,57 @@
+// The failures we want to tell apart when troubleshooting. These still travel through anyhow like
+// everything else (so '?' and context() work as usual), but can be picked back out of the chain
+// with downcast_ref() to be counted and summarized in the diagnostics window.
+use std::sync::atomic::{AtomicU32, Ordering};
+
+pub type BoxedError = Box<dyn std::error::Error + Send + Sync>;
+
+#[derive(Debug, thiserror::Error)]
+pub enum BorderError {
+    #[error("could not create border window")]
+    WindowCreation(#[source] windows::core::Error),
+    // The &str describes which DWM call failed
+    #[error("{0}")]
+    Dwm(&'static str, #[source] windows::core::Error),
+    // Likewise, the &str describes what we were doing with Direct2D
+    #[error("{0}")]
+    Direct2D(&'static str, #[source] BoxedError),
+}
+
+static WINDOW_CREATION_ERRORS: AtomicU32 = AtomicU32::new(0);
+static DWM_ERRORS: AtomicU32 = AtomicU32::new(0);
+static DIRECT2D_ERRORS: AtomicU32 = AtomicU32::new(0);
+
+impl BorderError {
+    fn counter(&self) -> &'static AtomicU32 {
+        match self {
+            BorderError::WindowCreation(_) => &WINDOW_CREATION_ERRORS,
+            BorderError::Dwm(..) => &DWM_ERRORS,
+            BorderError::Direct2D(..) => &DIRECT2D_ERRORS,
+        }
+    }
+}
+
+// Counts the first BorderError in the chain, if there is one
+pub fn record_border_error(err: &anyhow::Error) {
+    if let Some(border_err) = err
+        .chain()
+        .find_map(|cause| cause.downcast_ref::<BorderError>())
+    {
+        border_err.counter().fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+// Returns None if nothing has failed yet
+pub fn get_error_summary() -> Option<String> {
+    let window_creation = WINDOW_CREATION_ERRORS.load(Ordering::Relaxed);
+    let dwm = DWM_ERRORS.load(Ordering::Relaxed);
+    let direct2d = DIRECT2D_ERRORS.load(Ordering::Relaxed);
+
+    if window_creation + dwm + direct2d == 0 {
+        return None;
+    }
+
+    Some(format!(
+        "Errors since startup: {window_creation} window creation, {dwm} DWM, {direct2d} Direct2D"
+    ))
+}
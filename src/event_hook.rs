@@ -6,8 +6,8 @@ use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
 use windows::Win32::UI::WindowsAndMessaging::{
     CHILDID_SELF, EVENT_OBJECT_CLOAKED, EVENT_OBJECT_DESTROY, EVENT_OBJECT_HIDE,
     EVENT_OBJECT_LOCATIONCHANGE, EVENT_OBJECT_REORDER, EVENT_OBJECT_SHOW, EVENT_OBJECT_UNCLOAKED,
-    EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MINIMIZEEND, EVENT_SYSTEM_MINIMIZESTART, OBJID_CURSOR,
-    OBJID_WINDOW,
+    EVENT_SYSTEM_DESKTOPSWITCH, EVENT_SYSTEM_FOREGROUND, EVENT_SYSTEM_MINIMIZEEND,
+    EVENT_SYSTEM_MINIMIZESTART, OBJID_CURSOR, OBJID_WINDOW,
 };
 
 use crate::APP_STATE;
@@ -17,6 +17,7 @@ use crate::utils::{
     hide_border_for_window, is_window_visible, post_message_w, send_notify_message_w,
     show_border_for_window,
 };
+use crate::{border_limit, focus_commands, position_batch};
 
 pub extern "system" fn process_win_event(
     _h_win_event_hook: HWINEVENTHOOK,
@@ -99,6 +100,8 @@ pub extern "system" fn process_win_event(
                     .log_if_err();
             }
         }
+        // Switching desktops cloaks and uncloaks lots of windows at once, so we batch the moves
+        EVENT_SYSTEM_DESKTOPSWITCH => position_batch::begin_batch(),
         EVENT_OBJECT_DESTROY => {
             if _id_object == OBJID_WINDOW.0 && _id_child == CHILDID_SELF as i32 {
                 destroy_border_for_window(_hwnd);
@@ -138,6 +141,10 @@ fn handle_foreground_event(best_hwnd_guess: HWND, other_hwnd_guess: HWND) {
         false => other_hwnd_guess,
     };
     *APP_STATE.active_window.lock().unwrap() = new_active_hwnd.0 as isize;
+    focus_commands::handle_focus_change(new_active_hwnd);
+    if !new_active_hwnd.is_invalid() {
+        border_limit::handle_focus(new_active_hwnd);
+    }
 
     // Send foreground messages to all the border windows
     for (key, val) in APP_STATE.borders.lock().unwrap().iter() {
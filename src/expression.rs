@@ -0,0 +1,468 @@
+// A tiny expression language for lengths in the config (border widths and offsets), e.g.
+// "max(2, monitor_dpi / 48)". Lengths get resolved once per monitor rather than per frame, so the
+// only variables are about the monitor, not the window's state. There's no way to loop, define
+// anything, or touch anything outside of the variables below, so evaluating an expression always
+// terminates, and anything we don't know about is rejected when the config loads rather than when
+// the border gets drawn.
+//
+// Everything is an f32. Comparisons, &&, ||, and ! treat 0 as false and anything else as true, and
+// return 1 or 0.
+use std::fmt;
+use std::iter::Peekable;
+use std::str::Chars;
+
+// Keeps pathological configs from overflowing the stack while parsing
+const MAX_DEPTH: usize = 32;
+const MAX_LENGTH: usize = 256;
+
+// Two-character symbols come first so that e.g. "<=" doesn't get read as "<" and "="
+const SYMBOLS: [&str; 19] = [
+    "==", "!=", "<=", ">=", "&&", "||", "+", "-", "*", "/", "%", "<", ">", "!", "?", ":", "(", ")",
+    ",",
+];
+
+#[derive(Clone)]
+pub struct Expression {
+    source: String,
+    node: Node,
+}
+
+// What an expression can refer to. These get filled in whenever the value is resolved.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ExpressionVars {
+    pub monitor_dpi: u32,
+}
+
+#[derive(Debug, Clone)]
+enum Node {
+    Number(f32),
+    Variable(Variable),
+    Negate(Box<Node>),
+    Not(Box<Node>),
+    Binary(BinaryOp, Box<Node>, Box<Node>),
+    Conditional(Box<Node>, Box<Node>, Box<Node>),
+    Call(Function, Vec<Node>),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Variable {
+    MonitorDpi,
+    // monitor_dpi / 96, e.g. 1.5 at 150%
+    Scale,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum BinaryOp {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Remainder,
+    Equal,
+    NotEqual,
+    Less,
+    LessEqual,
+    Greater,
+    GreaterEqual,
+    And,
+    Or,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Function {
+    Min,
+    Max,
+    Clamp,
+    Round,
+    Floor,
+    Ceil,
+    Abs,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(f32),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+impl Expression {
+    pub fn parse(source: &str) -> Result<Self, String> {
+        if source.len() > MAX_LENGTH {
+            return Err(format!("expression is longer than {MAX_LENGTH} characters"));
+        }
+
+        let tokens = tokenize(source)?;
+        let mut parser = Parser {
+            tokens: &tokens,
+            pos: 0,
+            depth: 0,
+        };
+        let node = parser.parse_conditional()?;
+        if let Some(token) = parser.peek() {
+            return Err(format!("unexpected {} in '{source}'", describe(token)));
+        }
+
+        Ok(Self {
+            source: source.to_string(),
+            node,
+        })
+    }
+
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    pub fn eval(&self, vars: &ExpressionVars) -> f32 {
+        eval_node(&self.node, vars)
+    }
+}
+
+impl fmt::Debug for Expression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Expression({:?})", self.source)
+    }
+}
+
+// Two expressions are the same if they were written the same way
+impl PartialEq for Expression {
+    fn eq(&self, other: &Self) -> bool {
+        self.source == other.source
+    }
+}
+
+fn eval_node(node: &Node, vars: &ExpressionVars) -> f32 {
+    let as_f32 = |is_true: bool| if is_true { 1.0 } else { 0.0 };
+
+    match node {
+        Node::Number(value) => *value,
+        Node::Variable(Variable::MonitorDpi) => vars.monitor_dpi as f32,
+        Node::Variable(Variable::Scale) => vars.monitor_dpi as f32 / 96.0,
+        Node::Negate(node) => -eval_node(node, vars),
+        Node::Not(node) => as_f32(eval_node(node, vars) == 0.0),
+        Node::Binary(op, lhs, rhs) => {
+            let lhs = eval_node(lhs, vars);
+            // && and || short-circuit like they usually do
+            match op {
+                BinaryOp::And if lhs == 0.0 => return 0.0,
+                BinaryOp::Or if lhs != 0.0 => return 1.0,
+                _ => {}
+            }
+            let rhs = eval_node(rhs, vars);
+
+            match op {
+                BinaryOp::Add => lhs + rhs,
+                BinaryOp::Subtract => lhs - rhs,
+                BinaryOp::Multiply => lhs * rhs,
+                BinaryOp::Divide => lhs / rhs,
+                BinaryOp::Remainder => lhs % rhs,
+                BinaryOp::Equal => as_f32(lhs == rhs),
+                BinaryOp::NotEqual => as_f32(lhs != rhs),
+                BinaryOp::Less => as_f32(lhs < rhs),
+                BinaryOp::LessEqual => as_f32(lhs <= rhs),
+                BinaryOp::Greater => as_f32(lhs > rhs),
+                BinaryOp::GreaterEqual => as_f32(lhs >= rhs),
+                BinaryOp::And | BinaryOp::Or => as_f32(rhs != 0.0),
+            }
+        }
+        Node::Conditional(condition, if_true, if_false) => match eval_node(condition, vars) {
+            0.0 => eval_node(if_false, vars),
+            _ => eval_node(if_true, vars),
+        },
+        Node::Call(function, args) => {
+            let args: Vec<f32> = args.iter().map(|arg| eval_node(arg, vars)).collect();
+            match function {
+                Function::Min => args.into_iter().fold(f32::INFINITY, f32::min),
+                Function::Max => args.into_iter().fold(f32::NEG_INFINITY, f32::max),
+                Function::Clamp => args[0].max(args[1]).min(args[2]),
+                Function::Round => args[0].round(),
+                Function::Floor => args[0].floor(),
+                Function::Ceil => args[0].ceil(),
+                Function::Abs => args[0].abs(),
+            }
+        }
+    }
+}
+
+fn tokenize(source: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars: Peekable<Chars> = source.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() || c == '.' {
+            let mut number = String::new();
+            while let Some(&c) = chars.peek()
+                && (c.is_ascii_digit() || c == '.')
+            {
+                number.push(c);
+                chars.next();
+            }
+            let value = number
+                .parse::<f32>()
+                .map_err(|_| format!("invalid number '{number}'"))?;
+            tokens.push(Token::Number(value));
+        } else if c.is_ascii_alphabetic() || c == '_' {
+            let mut identifier = String::new();
+            while let Some(&c) = chars.peek()
+                && (c.is_ascii_alphanumeric() || c == '_')
+            {
+                identifier.push(c);
+                chars.next();
+            }
+            tokens.push(Token::Identifier(identifier));
+        } else {
+            let rest: String = chars.clone().take(2).collect();
+            let Some(symbol) = SYMBOLS.iter().find(|symbol| rest.starts_with(**symbol)) else {
+                return Err(format!("unexpected character '{c}'"));
+            };
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push(Token::Symbol(symbol));
+        }
+    }
+
+    Ok(tokens)
+}
+
+fn describe(token: &Token) -> String {
+    match token {
+        Token::Number(value) => format!("number '{value}'"),
+        Token::Identifier(identifier) => format!("name '{identifier}'"),
+        Token::Symbol(symbol) => format!("'{symbol}'"),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        let token = self.tokens.get(self.pos);
+        self.pos += 1;
+        token
+    }
+
+    fn eat(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(s)) if *s == symbol) {
+            self.pos += 1;
+            return true;
+        }
+        false
+    }
+
+    fn expect(&mut self, symbol: &str) -> Result<(), String> {
+        match self.eat(symbol) {
+            true => Ok(()),
+            false => Err(match self.peek() {
+                Some(token) => format!("expected '{symbol}' but found {}", describe(token)),
+                None => format!("expected '{symbol}' but the expression ended"),
+            }),
+        }
+    }
+
+    // condition ? if_true : if_false
+    fn parse_conditional(&mut self) -> Result<Node, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".to_string());
+        }
+
+        let condition = self.parse_binary(0)?;
+        let node = match self.eat("?") {
+            true => {
+                let if_true = self.parse_conditional()?;
+                self.expect(":")?;
+                let if_false = self.parse_conditional()?;
+                Node::Conditional(Box::new(condition), Box::new(if_true), Box::new(if_false))
+            }
+            false => condition,
+        };
+
+        self.depth -= 1;
+        Ok(node)
+    }
+
+    // Operators from loosest to tightest. Everything at the same level is left-associative.
+    fn parse_binary(&mut self, level: usize) -> Result<Node, String> {
+        const LEVELS: [&[(&str, BinaryOp)]; 5] = [
+            &[("||", BinaryOp::Or)],
+            &[("&&", BinaryOp::And)],
+            &[
+                ("==", BinaryOp::Equal),
+                ("!=", BinaryOp::NotEqual),
+                ("<=", BinaryOp::LessEqual),
+                (">=", BinaryOp::GreaterEqual),
+                ("<", BinaryOp::Less),
+                (">", BinaryOp::Greater),
+            ],
+            &[("+", BinaryOp::Add), ("-", BinaryOp::Subtract)],
+            &[
+                ("*", BinaryOp::Multiply),
+                ("/", BinaryOp::Divide),
+                ("%", BinaryOp::Remainder),
+            ],
+        ];
+
+        let Some(ops) = LEVELS.get(level) else {
+            return self.parse_unary();
+        };
+
+        let mut lhs = self.parse_binary(level + 1)?;
+        'outer: loop {
+            for (symbol, op) in ops.iter() {
+                if self.eat(symbol) {
+                    let rhs = self.parse_binary(level + 1)?;
+                    lhs = Node::Binary(*op, Box::new(lhs), Box::new(rhs));
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn parse_unary(&mut self) -> Result<Node, String> {
+        if self.eat("-") {
+            return Ok(Node::Negate(Box::new(self.parse_nested_unary()?)));
+        }
+        if self.eat("!") {
+            return Ok(Node::Not(Box::new(self.parse_nested_unary()?)));
+        }
+
+        self.parse_primary()
+    }
+
+    fn parse_nested_unary(&mut self) -> Result<Node, String> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err("expression is nested too deeply".to_string());
+        }
+        let node = self.parse_unary();
+        self.depth -= 1;
+
+        node
+    }
+
+    fn parse_primary(&mut self) -> Result<Node, String> {
+        match self.next().cloned() {
+            Some(Token::Number(value)) => Ok(Node::Number(value)),
+            Some(Token::Symbol("(")) => {
+                let node = self.parse_conditional()?;
+                self.expect(")")?;
+                Ok(node)
+            }
+            Some(Token::Identifier(name)) if self.eat("(") => self.parse_call(&name),
+            Some(Token::Identifier(name)) => match name.as_str() {
+                "monitor_dpi" => Ok(Node::Variable(Variable::MonitorDpi)),
+                "scale" => Ok(Node::Variable(Variable::Scale)),
+                "true" => Ok(Node::Number(1.0)),
+                "false" => Ok(Node::Number(0.0)),
+                _ => Err(format!(
+                    "unknown variable '{name}'; expected monitor_dpi or scale"
+                )),
+            },
+            Some(token) => Err(format!("unexpected {}", describe(&token))),
+            None => Err("the expression ended too early".to_string()),
+        }
+    }
+
+    // The opening parenthesis has already been eaten
+    fn parse_call(&mut self, name: &str) -> Result<Node, String> {
+        let (function, min_args, max_args) = match name {
+            "min" => (Function::Min, 1, usize::MAX),
+            "max" => (Function::Max, 1, usize::MAX),
+            "clamp" => (Function::Clamp, 3, 3),
+            "round" => (Function::Round, 1, 1),
+            "floor" => (Function::Floor, 1, 1),
+            "ceil" => (Function::Ceil, 1, 1),
+            "abs" => (Function::Abs, 1, 1),
+            _ => return Err(format!("unknown function '{name}'")),
+        };
+
+        let mut args = Vec::new();
+        if !self.eat(")") {
+            loop {
+                args.push(self.parse_conditional()?);
+                if self.eat(")") {
+                    break;
+                }
+                self.expect(",")?;
+            }
+        }
+
+        if args.len() < min_args || args.len() > max_args {
+            return Err(match min_args == max_args {
+                true => format!("{name}() takes {min_args} argument(s)"),
+                false => format!("{name}() takes at least {min_args} argument(s)"),
+            });
+        }
+
+        Ok(Node::Call(function, args))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(source: &str, monitor_dpi: u32) -> f32 {
+        Expression::parse(source)
+            .unwrap()
+            .eval(&ExpressionVars { monitor_dpi })
+    }
+
+    #[test]
+    fn test_precedence() {
+        assert_eq!(eval("1 + 2 * 3", 96), 7.0);
+        assert_eq!(eval("(1 + 2) * 3", 96), 9.0);
+        assert_eq!(eval("10 - 4 - 3", 96), 3.0);
+        assert_eq!(eval("-2 * -3", 96), 6.0);
+        assert_eq!(eval("1 + 1 == 2 && !(3 < 2)", 96), 1.0);
+    }
+
+    #[test]
+    fn test_variables_and_functions() {
+        assert_eq!(eval("max(2, monitor_dpi / 48)", 96), 2.0);
+        assert_eq!(eval("max(2, monitor_dpi / 48)", 288), 6.0);
+        assert_eq!(eval("round(3 * scale)", 144), 5.0);
+        assert_eq!(eval("clamp(monitor_dpi, 100, 150)", 192), 150.0);
+        assert_eq!(eval("min(4, 2, 3)", 96), 2.0);
+    }
+
+    #[test]
+    fn test_conditional() {
+        assert_eq!(eval("scale > 1 ? 3 : 2", 96), 2.0);
+        assert_eq!(eval("scale > 1 ? 3 : 2", 120), 3.0);
+        assert_eq!(eval("false ? 1 : true ? 2 : 3", 96), 2.0);
+    }
+
+    #[test]
+    fn test_invalid_expressions() {
+        for source in [
+            "",
+            "1 +",
+            "(1",
+            "1 2",
+            "foo",
+            "foo(1)",
+            "clamp(1, 2)",
+            "max()",
+            "1 $ 2",
+            "1.2.3",
+        ] {
+            assert!(Expression::parse(source).is_err(), "{source}");
+        }
+
+        let deeply_nested = format!("{}1{}", "(".repeat(100), ")".repeat(100));
+        assert!(Expression::parse(&deeply_nested).is_err());
+    }
+}
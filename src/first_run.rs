@@ -0,0 +1,132 @@
+use anyhow::{Context, anyhow};
+use regex::Regex;
+use std::{env, fs};
+use windows::Win32::Foundation::ERROR_SUCCESS;
+use windows::Win32::System::Registry::{HKEY_CURRENT_USER, REG_SZ, RegSetKeyValueW};
+use windows::Win32::UI::WindowsAndMessaging::{
+    IDYES, MB_ICONQUESTION, MB_SETFOREGROUND, MB_TOPMOST, MB_YESNO, MessageBoxW,
+};
+use windows::core::{HSTRING, w};
+
+use crate::cli::CLI_ARGS;
+use crate::config::{Config, DEFAULT_CONFIG};
+use crate::utils::LogIfErr;
+
+const AUTOSTART_KEY: &str = r"Software\Microsoft\Windows\CurrentVersion\Run";
+
+// Walks first-time users through creating their config. This only runs if there's no config.yaml
+// yet, so installers (MSI, winget, etc.) don't need to do anything special.
+pub fn run_first_run_wizard_if_needed() {
+    // Managed installs are expected to provide their own config
+    if CLI_ARGS.read_only {
+        return;
+    }
+
+    let config_path = match Config::get_dir() {
+        Ok(dir) => dir.join("config.yaml"),
+        Err(err) => {
+            error!("could not get config dir for first-run wizard: {err}");
+            return;
+        }
+    };
+    if config_path.exists() {
+        return;
+    }
+
+    info!("no config found; starting first-run wizard");
+
+    let use_accent = ask_yes_no(
+        "Welcome to tacky-borders!\n\n\
+        Would you like your borders to use your Windows accent color?\n\n\
+        Yes: use the accent color\n\
+        No: use the default gradient theme",
+    );
+    let contents = match use_accent {
+        true => get_accent_preset(DEFAULT_CONFIG),
+        false => DEFAULT_CONFIG.to_string(),
+    };
+
+    match fs::write(&config_path, contents) {
+        Ok(()) => info!("created config in {}", config_path.display()),
+        Err(err) => error!("could not create config in first-run wizard: {err}"),
+    }
+
+    if ask_yes_no("Start tacky-borders automatically when you sign in?") {
+        register_autostart()
+            .context("could not register autostart")
+            .log_if_err();
+    }
+}
+
+fn ask_yes_no(text: &str) -> bool {
+    let result = unsafe {
+        MessageBoxW(
+            None,
+            &HSTRING::from(text),
+            w!("tacky-borders"),
+            MB_YESNO | MB_ICONQUESTION | MB_SETFOREGROUND | MB_TOPMOST,
+        )
+    };
+
+    result == IDYES
+}
+
+// Swaps out the default config's global active/inactive colors for the accent color, keeping all
+// the comments intact. The config may have been checked out with CRLF line endings, so we keep
+// whichever ones it uses.
+fn get_accent_preset(default_config: &str) -> String {
+    let color_regex =
+        Regex::new(r"(?m)^  (active_color|inactive_color):(\r?\n)(?:    .*\n)*").unwrap();
+
+    color_regex
+        .replace_all(default_config, "  $1: \"accent\"$2")
+        .into_owned()
+}
+
+// Adds tacky-borders to the current user's Run key, which doesn't need admin rights or a service
+fn register_autostart() -> anyhow::Result<()> {
+    let exe_path = env::current_exe().context("could not get exe path")?;
+    let value: Vec<u16> = format!("\"{}\"", exe_path.display())
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+
+    let result = unsafe {
+        RegSetKeyValueW(
+            HKEY_CURRENT_USER,
+            &HSTRING::from(AUTOSTART_KEY),
+            w!("tacky-borders"),
+            REG_SZ.0,
+            Some(value.as_ptr() as _),
+            (value.len() * size_of::<u16>()) as u32,
+        )
+    };
+
+    if result != ERROR_SUCCESS {
+        return Err(anyhow!("RegSetKeyValueW returned {result:?}"));
+    }
+
+    info!("registered autostart for {}", exe_path.display());
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_accent_preset() {
+        let default_config = "global:\n  active_color:\n    colors: [\"#6274e7\", \"#8752a3\"]\n    direction: 45deg\n\n  inactive_color: \"#30304f\"\n";
+        assert_eq!(
+            get_accent_preset(default_config),
+            "global:\n  active_color: \"accent\"\n\n  inactive_color: \"#30304f\"\n"
+        );
+
+        let default_config = default_config.replace('\n', "\r\n");
+        assert_eq!(
+            get_accent_preset(&default_config),
+            "global:\r\n  active_color: \"accent\"\r\n\r\n  inactive_color: \"#30304f\"\r\n"
+        );
+    }
+}
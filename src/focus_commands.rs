@@ -0,0 +1,134 @@
+// Lets a window rule run a command when its window gains or loses focus, e.g. to play a sound or to
+// switch keyboard layouts. Commands go through cmd.exe so scripts and shell built-ins work too.
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::os::windows::process::CommandExt;
+use std::process::Command;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::sync::{LazyLock, Mutex};
+use std::{thread, time};
+use windows::Win32::Foundation::HWND;
+use windows::Win32::System::Threading::CREATE_NO_WINDOW;
+
+use crate::APP_STATE;
+use crate::config::serde_default_u64;
+use crate::job_object::spawn_in_job;
+use crate::utils::{get_window_process_name, get_window_rule};
+
+// The last window we ran focus commands for, since the foreground event can fire more than once
+// for the same window
+static LAST_FOCUSED_WINDOW: AtomicIsize = AtomicIsize::new(0);
+// When each command last ran, so that rapidly switching windows doesn't spawn a pile of processes
+static LAST_RUN_TIMES: LazyLock<Mutex<HashMap<String, time::Instant>>> =
+    LazyLock::new(|| Mutex::new(HashMap::new()));
+// The on_focus of the last window that gained focus, so we don't have to match its rule again once
+// it loses focus
+static LAST_ON_FOCUS: Mutex<Option<(isize, Option<FocusCommandsConfig>)>> = Mutex::new(None);
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct FocusCommandsConfig {
+    // Runs when the window gains focus
+    pub gained: Option<String>,
+    // Runs when the window loses focus
+    pub lost: Option<String>,
+    // How long a command has to wait before it can run again (in milliseconds)
+    #[serde(default = "serde_default_u64::<1000>")]
+    pub cooldown: u64,
+}
+
+impl Default for FocusCommandsConfig {
+    fn default() -> Self {
+        Self {
+            gained: None,
+            lost: None,
+            cooldown: 1000,
+        }
+    }
+}
+
+pub fn handle_focus_change(new_active_hwnd: HWND) {
+    let new_isize = new_active_hwnd.0 as isize;
+    let old_isize = LAST_FOCUSED_WINDOW.swap(new_isize, Ordering::SeqCst);
+    if old_isize == new_isize {
+        return;
+    }
+
+    // Most configs don't use on_focus at all, so don't bother matching rules for them
+    let has_focus_commands = APP_STATE
+        .config
+        .read()
+        .unwrap()
+        .window_rules
+        .iter()
+        .any(|rule| rule.on_focus.is_some());
+    if !has_focus_commands {
+        return;
+    }
+
+    // Matching the window rules and spawning processes is too slow for the event hook
+    let _ = thread::spawn(move || {
+        let old_hwnd = HWND(old_isize as _);
+        let new_hwnd = HWND(new_isize as _);
+
+        let new_on_focus = match new_hwnd.is_invalid() {
+            true => None,
+            false => get_window_rule(new_hwnd).on_focus,
+        };
+        let last_on_focus = LAST_ON_FOCUS
+            .lock()
+            .unwrap()
+            .replace((new_isize, new_on_focus.clone()));
+
+        // Another focus change may have gotten in first, in which case we have to match the rule
+        let old_on_focus = match last_on_focus {
+            Some((last_isize, on_focus)) if last_isize == old_isize => on_focus,
+            _ if !old_hwnd.is_invalid() => get_window_rule(old_hwnd).on_focus,
+            _ => None,
+        };
+
+        if let Some(on_focus) = old_on_focus
+            && let Some(ref command) = on_focus.lost
+        {
+            run_command(command, old_hwnd, on_focus.cooldown);
+        }
+
+        if let Some(on_focus) = new_on_focus
+            && let Some(ref command) = on_focus.gained
+        {
+            run_command(command, new_hwnd, on_focus.cooldown);
+        }
+    });
+}
+
+fn run_command(command: &str, hwnd: HWND, cooldown: u64) {
+    {
+        let mut last_run_times = LAST_RUN_TIMES.lock().unwrap();
+        let now = time::Instant::now();
+        if let Some(last_run_time) = last_run_times.get(command)
+            && now.duration_since(*last_run_time) < time::Duration::from_millis(cooldown)
+        {
+            debug!("skipping focus command (still cooling down): {command}");
+            return;
+        }
+        last_run_times.insert(command.to_string(), now);
+    }
+
+    debug!("running focus command: {command}");
+
+    // The command can tell which window it's for through these
+    let spawn_res = spawn_in_job(
+        Command::new("cmd")
+            .arg("/C")
+            .raw_arg(command)
+            .env("TACKY_HWND", format!("{:#x}", hwnd.0 as isize))
+            .env(
+                "TACKY_PROCESS",
+                get_window_process_name(hwnd).unwrap_or_default(),
+            )
+            .creation_flags(CREATE_NO_WINDOW.0),
+    );
+    if let Err(err) = spawn_res {
+        error!("could not run focus command '{command}': {err:#}");
+    }
+}
@@ -0,0 +1,134 @@
+// Lets the user hide/show the borders or switch profiles by pushing the mouse into a corner of the
+// screen. We just poll the cursor position every so often, which is cheap enough and doesn't need
+// any extra windows or hooks.
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{thread, time};
+use windows::Win32::Foundation::POINT;
+use windows::Win32::Graphics::Gdi::{MONITOR_DEFAULTTONEAREST, MonitorFromPoint};
+use windows::Win32::UI::WindowsAndMessaging::GetCursorPos;
+
+use crate::config::{Config, serde_default_i32, serde_default_u64};
+use crate::utils::get_monitor_info;
+use crate::{APP_STATE, are_borders_hidden, cycle_profile, set_borders_hidden};
+
+const POLL_INTERVAL: time::Duration = time::Duration::from_millis(100);
+
+static IS_HOT_CORNER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct HotCornerConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub corner: Corner,
+    #[serde(default)]
+    pub action: HotCornerAction,
+    // How close to the corner the cursor has to be (in pixels)
+    #[serde(default = "serde_default_i32::<2>")]
+    pub size: i32,
+    // How long the cursor has to stay in the corner before the action fires (in milliseconds)
+    #[serde(default = "serde_default_u64::<300>")]
+    pub delay: u64,
+}
+
+impl Default for HotCornerConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            corner: Corner::default(),
+            action: HotCornerAction::default(),
+            size: 2,
+            delay: 300,
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    #[default]
+    BottomLeft,
+    BottomRight,
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum HotCornerAction {
+    #[default]
+    ToggleBorders,
+    // Goes through the config's profiles in alphabetical order, then back to the scheduled one
+    CycleProfiles,
+}
+
+// Like the integration detector, the polling thread stops by itself once the hot corner gets
+// disabled in the config
+pub fn start_hot_corner_if_enabled(config: &Config) {
+    if !config.hot_corner.enabled || IS_HOT_CORNER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    debug!("starting hot corner");
+
+    let _ = thread::spawn(|| {
+        // When the cursor entered the corner, and whether we've already fired for this visit
+        let mut entered_at: Option<time::Instant> = None;
+        let mut has_fired = false;
+
+        loop {
+            thread::sleep(POLL_INTERVAL);
+
+            let hot_corner = APP_STATE.config.read().unwrap().hot_corner.clone();
+            if !hot_corner.enabled {
+                debug!("stopping hot corner");
+                IS_HOT_CORNER_RUNNING.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            if !is_cursor_in_corner(&hot_corner) {
+                entered_at = None;
+                has_fired = false;
+                continue;
+            }
+
+            let entered_at = *entered_at.get_or_insert_with(time::Instant::now);
+            if has_fired || entered_at.elapsed() < time::Duration::from_millis(hot_corner.delay) {
+                continue;
+            }
+            has_fired = true;
+
+            match hot_corner.action {
+                HotCornerAction::ToggleBorders => set_borders_hidden(!are_borders_hidden()),
+                HotCornerAction::CycleProfiles => cycle_profile(),
+            }
+        }
+    });
+}
+
+// Every monitor has its own corners, so we check the corners of whichever monitor the cursor is on
+fn is_cursor_in_corner(hot_corner: &HotCornerConfig) -> bool {
+    let mut point = POINT::default();
+    if unsafe { GetCursorPos(&mut point) }.is_err() {
+        return false;
+    }
+
+    let hmonitor = unsafe { MonitorFromPoint(point, MONITOR_DEFAULTTONEAREST) };
+    let Ok(monitor_info) = get_monitor_info(hmonitor) else {
+        return false;
+    };
+    let rect = monitor_info.rcMonitor;
+    let size = hot_corner.size.max(1);
+
+    let is_near_left = point.x < rect.left + size;
+    let is_near_right = point.x >= rect.right - size;
+    let is_near_top = point.y < rect.top + size;
+    let is_near_bottom = point.y >= rect.bottom - size;
+
+    match hot_corner.corner {
+        Corner::TopLeft => is_near_top && is_near_left,
+        Corner::TopRight => is_near_top && is_near_right,
+        Corner::BottomLeft => is_near_bottom && is_near_left,
+        Corner::BottomRight => is_near_bottom && is_near_right,
+    }
+}
@@ -0,0 +1,70 @@
+use anyhow::Context;
+use std::os::windows::io::AsRawHandle;
+use std::process::{Child, Command};
+use std::sync::LazyLock;
+use windows::Win32::Foundation::{CloseHandle, HANDLE};
+use windows::Win32::System::JobObjects::{
+    AssignProcessToJobObject, CreateJobObjectW, JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE,
+    JOBOBJECT_EXTENDED_LIMIT_INFORMATION, JobObjectExtendedLimitInformation,
+    SetInformationJobObject,
+};
+use windows::core::PCWSTR;
+
+use crate::utils::LogIfErr;
+
+// Any child processes we spawn get placed into this job. The handle is intentionally never closed;
+// Windows will close it for us when tacky-borders exits (even if it gets killed or crashes), and
+// because of JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE, that also terminates everything in the job.
+//
+// NOTE: we can't put helper threads in here, but those already die along with the process anyway.
+static CHILD_PROCESS_JOB: LazyLock<Option<isize>> = LazyLock::new(|| {
+    create_kill_on_close_job()
+        .map(|hjob| hjob.0 as isize)
+        .inspect_err(|err| error!("could not create job object for child processes: {err:#}"))
+        .ok()
+});
+
+fn create_kill_on_close_job() -> anyhow::Result<HANDLE> {
+    let hjob =
+        unsafe { CreateJobObjectW(None, PCWSTR::null()) }.context("could not create job object")?;
+
+    let mut limit_info = JOBOBJECT_EXTENDED_LIMIT_INFORMATION::default();
+    limit_info.BasicLimitInformation.LimitFlags = JOB_OBJECT_LIMIT_KILL_ON_JOB_CLOSE;
+
+    if let Err(err) = unsafe {
+        SetInformationJobObject(
+            hjob,
+            JobObjectExtendedLimitInformation,
+            &limit_info as *const _ as _,
+            size_of::<JOBOBJECT_EXTENDED_LIMIT_INFORMATION>() as u32,
+        )
+    } {
+        let _ = unsafe { CloseHandle(hjob) };
+        return Err(err).context("could not set job object limits");
+    }
+
+    Ok(hjob)
+}
+
+// Spawns the command and ties the child's lifetime to ours. If the child can't be placed into the
+// job, we still return it, since that only matters if tacky-borders exits unexpectedly.
+pub fn spawn_in_job(command: &mut Command) -> anyhow::Result<Child> {
+    let child = command.spawn().context("could not spawn child process")?;
+
+    match *CHILD_PROCESS_JOB {
+        Some(hjob_isize) => unsafe {
+            AssignProcessToJobObject(HANDLE(hjob_isize as _), HANDLE(child.as_raw_handle()))
+        }
+        .context(format!(
+            "could not assign child process {} to job object",
+            child.id()
+        ))
+        .log_if_err(),
+        None => warn!(
+            "job object is unavailable; child process {} may outlive tacky-borders",
+            child.id()
+        ),
+    }
+
+    Ok(child)
+}
@@ -1,6 +1,6 @@
 use anyhow::{Context, anyhow};
 use dirs::home_dir;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, VecDeque};
 use std::os::windows::process::CommandExt;
 use std::path::PathBuf;
@@ -13,40 +13,106 @@ use windows::Win32::System::IO::OVERLAPPED_ENTRY;
 use windows::Win32::System::Threading::CREATE_NO_WINDOW;
 
 use crate::APP_STATE;
+use crate::autodetect::is_komorebi_detected;
 use crate::colors::ColorBrushConfig;
 use crate::config::{Config, serde_default_bool};
 use crate::iocp::{CompletionPort, UnixDomainSocket, UnixListener, UnixStream};
-use crate::utils::{LogIfErr, WM_APP_KOMOREBI, get_foreground_window, post_message_w};
+use crate::job_object::spawn_in_job;
+use crate::utils::{
+    LogIfErr, WM_APP_KOMOREBI, WM_APP_KOMOREBI_GAPS, WM_APP_SCHEDULE, get_foreground_window,
+    post_message_w,
+};
 
 const BUFFER_POOL_REFRESH_INTERVAL: time::Duration = time::Duration::from_secs(600);
 const BUFFER_SIZE: usize = 32768;
-
-#[derive(Debug, Default, Clone, Deserialize, PartialEq)]
+const WATCHDOG_INTERVAL: time::Duration = time::Duration::from_secs(1);
+// How long komorebi gets to notify us after one of its windows gains focus before we consider our
+// state stale. Focus changes always produce a notification while we're subscribed.
+const STALE_TIMEOUT: time::Duration = time::Duration::from_secs(3);
+const MIN_RECONNECT_BACKOFF: time::Duration = time::Duration::from_secs(1);
+const MAX_RECONNECT_BACKOFF: time::Duration = time::Duration::from_secs(60);
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(deny_unknown_fields)]
 pub struct KomorebiColorsConfig {
     pub stack_color: Option<ColorBrushConfig>,
     pub monocle_color: Option<ColorBrushConfig>,
     pub floating_color: Option<ColorBrushConfig>,
+    // Inactive color for windows on the monitor that komorebi considers focused
+    pub focused_monitor_color: Option<ColorBrushConfig>,
     #[serde(default = "serde_default_bool::<true>")]
     pub enabled: bool,
 }
 
+#[derive(Debug, Default, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct KomorebiGapsConfig {
+    #[serde(default)]
+    pub mode: KomorebiGapMode,
+}
+
+// What to do when a border would reach into the gap between two of komorebi's tiles far enough to
+// overlap the neighboring tile's border
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
+pub enum KomorebiGapMode {
+    #[default]
+    Off,
+    Warn,
+    // Shrink border_offset so that the border fits within half of the gap
+    Adjust,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum KomorebiStatus {
+    #[default]
+    Stopped,
+    Connected,
+    // We're not getting notifications (e.g. komorebi restarted and forgot about our subscription),
+    // so we keep trying to resubscribe. Borders use the foreground window in the meantime.
+    Reconnecting,
+    // Our own socket worker died, so the whole integration needs to be restarted
+    Disconnected,
+}
+
+impl KomorebiStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            KomorebiStatus::Stopped => "stopped",
+            KomorebiStatus::Connected => "connected",
+            KomorebiStatus::Reconnecting => "reconnecting",
+            KomorebiStatus::Disconnected => "disconnected",
+        }
+    }
+}
+
 pub struct KomorebiIntegration {
     // NOTE: in komorebi it's <Border HWND, WindowKind>, but here it's <Tracking HWND, WindowKind>
     pub focus_state: Arc<Mutex<HashMap<isize, WindowKind>>>,
+    // <Tracking HWND, whether the window is on komorebi's focused monitor>
+    pub monitor_focus: Arc<Mutex<HashMap<isize, bool>>>,
+    // <Tracking HWND, container_padding> for tiled windows that have at least one neighbor
+    pub container_padding: Arc<Mutex<HashMap<isize, i32>>>,
     pub listen_socket: Option<UnixDomainSocket>,
+    pub status: Arc<Mutex<KomorebiStatus>>,
+    last_notification_time: Arc<Mutex<Option<time::Instant>>>,
 }
 
 impl KomorebiIntegration {
     pub fn new() -> Self {
         Self {
             focus_state: Arc::new(Mutex::new(HashMap::new())),
+            monitor_focus: Arc::new(Mutex::new(HashMap::new())),
+            container_padding: Arc::new(Mutex::new(HashMap::new())),
             listen_socket: None,
+            status: Arc::new(Mutex::new(KomorebiStatus::Stopped)),
+            last_notification_time: Arc::new(Mutex::new(None)),
         }
     }
 
     pub fn is_enabled(&mut self, config: &Config) -> bool {
         config.global.komorebi_colors.enabled
+            || is_komorebi_detected(config)
+            || config.global.komorebi_gaps.mode != KomorebiGapMode::Off
             || config.window_rules.iter().any(|rule| {
                 rule.komorebi_colors
                     .as_ref()
@@ -73,7 +139,8 @@ impl KomorebiIntegration {
         let socket_file = socket_path
             .file_name()
             .and_then(|file| file.to_str())
-            .context("could not get komorebic socket name")?;
+            .context("could not get komorebic socket name")?
+            .to_string();
 
         // If the socket file already exists, we cannot bind to it, so we must delete it first
         if fs::exists(&socket_path).context("could not check if komorebic socket exists")? {
@@ -92,9 +159,16 @@ impl KomorebiIntegration {
         self.listen_socket = Some(listener.socket.clone());
 
         let focus_state = self.focus_state.clone();
+        let monitor_focus = self.monitor_focus.clone();
+        let container_padding = self.container_padding.clone();
+        // Threads from a previous run may still be winding down, so they keep the old status
+        // (which stop() set to Stopped) while we start over with a new one
+        self.status = Arc::new(Mutex::new(KomorebiStatus::Connected));
+        let status = self.status.clone();
+        let last_notification_time = self.last_notification_time.clone();
 
         let _ = thread::spawn(move || {
-            move || -> anyhow::Result<()> {
+            let worker_res = || -> anyhow::Result<()> {
                 let mut entries = vec![OVERLAPPED_ENTRY::default(); 8];
                 let mut buffer_pool = VecDeque::<Vec<u8>>::new();
                 let mut streams_queue = VecDeque::<(usize, Box<UnixStream>)>::new();
@@ -145,8 +219,19 @@ impl KomorebiIntegration {
                                 .context("could not remove stream from queue")?
                                 .1;
 
+                            *last_notification_time.lock().unwrap() = Some(time::Instant::now());
+                            {
+                                let mut status = status.lock().unwrap();
+                                if *status == KomorebiStatus::Reconnecting {
+                                    info!("komorebi integration has reconnected");
+                                    *status = KomorebiStatus::Connected;
+                                }
+                            }
+
                             Self::process_komorebi_notification(
                                 focus_state.clone(),
+                                monitor_focus.clone(),
+                                container_padding.clone(),
                                 &stream.buffer,
                                 entry.dwNumberOfBytesTransferred,
                             );
@@ -156,29 +241,202 @@ impl KomorebiIntegration {
                         }
                     }
                 }
-            }()
-            .log_if_err();
+            }();
+
+            // stop() closes the socket out from under us, so errors are expected in that case
+            if let Err(err) = worker_res {
+                let mut status = status.lock().unwrap();
+                if *status == KomorebiStatus::Stopped {
+                    debug!("komorebi socket worker exited: {err:#}");
+                } else {
+                    error!("komorebi socket worker exited unexpectedly: {err:#}");
+                    *status = KomorebiStatus::Disconnected;
+                }
+            }
         });
 
-        // Attempt to subscribe to komorebic, stopping integration if subscription fails
-        if !Command::new("komorebic")
-            .arg("subscribe-socket")
-            .arg(socket_file)
-            .creation_flags(CREATE_NO_WINDOW.0)
-            .status()
-            .context("could not get komorebic subscribe-socket exit status")?
-            .success()
-        {
-            error!("could not subscribe to komorebic socket; stopping integration");
-            self.stop().context("could not stop komorebi integration")?;
-        }
+        self.spawn_watchdog(socket_file);
 
         Ok(())
     }
 
+    fn subscribe(socket_file: &str) -> anyhow::Result<bool> {
+        Ok(spawn_in_job(
+            Command::new("komorebic")
+                .arg("subscribe-socket")
+                .arg(socket_file)
+                .creation_flags(CREATE_NO_WINDOW.0),
+        )?
+        .wait()
+        .context("could not get komorebic subscribe-socket exit status")?
+        .success())
+    }
+
+    // Watches for komorebi going quiet (or our socket worker dying) and tries to recover from it
+    fn spawn_watchdog(&self, socket_file: String) {
+        let focus_state = self.focus_state.clone();
+        let monitor_focus = self.monitor_focus.clone();
+        let container_padding = self.container_padding.clone();
+        let status = self.status.clone();
+        let last_notification_time = self.last_notification_time.clone();
+
+        let _ = thread::spawn(move || {
+            // Subscribing waits for komorebic to exit, so we do it here instead of in start(),
+            // which runs while APP_STATE.komorebi_integration is locked. If komorebi isn't running
+            // yet, we'll just keep trying below.
+            let is_subscribed = Self::subscribe(&socket_file)
+                .inspect_err(|err| error!("could not run komorebic: {err:#}"))
+                .unwrap_or(false);
+            if !is_subscribed {
+                warn!("could not subscribe to komorebic socket; retrying in the background");
+
+                let mut status = status.lock().unwrap();
+                if *status == KomorebiStatus::Connected {
+                    *status = KomorebiStatus::Reconnecting;
+                }
+            }
+
+            let mut last_foreground = get_foreground_window().0 as isize;
+            // When we first noticed one of komorebi's windows gaining focus
+            let mut pending_focus_time: Option<time::Instant> = None;
+            let mut backoff = MIN_RECONNECT_BACKOFF;
+            let mut next_attempt_time = time::Instant::now();
+
+            loop {
+                thread::sleep(WATCHDOG_INTERVAL);
+
+                let current_status = *status.lock().unwrap();
+                match current_status {
+                    KomorebiStatus::Stopped => return,
+                    KomorebiStatus::Connected => {
+                        backoff = MIN_RECONNECT_BACKOFF;
+
+                        let foreground = get_foreground_window().0 as isize;
+                        if foreground != last_foreground {
+                            last_foreground = foreground;
+                            if focus_state.lock().unwrap().contains_key(&foreground) {
+                                pending_focus_time = Some(time::Instant::now());
+                            }
+                        }
+
+                        let Some(focus_time) = pending_focus_time else {
+                            continue;
+                        };
+
+                        // We only poll once per interval, so the notification may have arrived a
+                        // bit before we noticed the focus change
+                        let last_notification = *last_notification_time.lock().unwrap();
+                        if last_notification.is_some_and(|last_notification| {
+                            last_notification + WATCHDOG_INTERVAL >= focus_time
+                        }) {
+                            pending_focus_time = None;
+                        } else if focus_time.elapsed() > STALE_TIMEOUT {
+                            warn!(
+                                "komorebi has stopped sending notifications; falling back to the \
+                                foreground window and trying to reconnect"
+                            );
+                            pending_focus_time = None;
+                            *status.lock().unwrap() = KomorebiStatus::Reconnecting;
+                            next_attempt_time = time::Instant::now();
+
+                            Self::clear_state(&focus_state, &monitor_focus, &container_padding);
+                        }
+                    }
+                    KomorebiStatus::Reconnecting => {
+                        if time::Instant::now() < next_attempt_time {
+                            continue;
+                        }
+
+                        match Self::subscribe(&socket_file) {
+                            Ok(true) => {
+                                debug!("resubscribed to komorebic socket");
+                                // We'll only switch back to Connected once komorebi actually
+                                // sends us something, so make sure the next attempt isn't
+                                // immediate in case it never does
+                                next_attempt_time = time::Instant::now() + MAX_RECONNECT_BACKOFF;
+                                backoff = MIN_RECONNECT_BACKOFF;
+                            }
+                            Ok(false) => {
+                                debug!(
+                                    "could not resubscribe to komorebic socket; retrying in {backoff:?}"
+                                );
+                                next_attempt_time = time::Instant::now() + backoff;
+                                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                            }
+                            Err(err) => {
+                                error!("could not run komorebic: {err:#}");
+                                next_attempt_time = time::Instant::now() + backoff;
+                                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                            }
+                        }
+                    }
+                    KomorebiStatus::Disconnected => {
+                        if time::Instant::now() < next_attempt_time {
+                            continue;
+                        }
+
+                        Self::clear_state(&focus_state, &monitor_focus, &container_padding);
+
+                        // A successful start() spawns its own watchdog, so this one can exit
+                        let mut komorebi_integration =
+                            APP_STATE.komorebi_integration.lock().unwrap();
+                        komorebi_integration.stop().log_if_err();
+                        match komorebi_integration.start() {
+                            Ok(()) => return,
+                            Err(err) => {
+                                error!("could not restart komorebi integration: {err:#}");
+                                *status.lock().unwrap() = KomorebiStatus::Disconnected;
+                                next_attempt_time = time::Instant::now() + backoff;
+                                backoff = (backoff * 2).min(MAX_RECONNECT_BACKOFF);
+                            }
+                        }
+                    }
+                }
+            }
+        });
+    }
+
+    // Forgets everything komorebi told us and has the borders fall back to their regular colors
+    fn clear_state(
+        focus_state: &Mutex<HashMap<isize, WindowKind>>,
+        monitor_focus: &Mutex<HashMap<isize, bool>>,
+        container_padding: &Mutex<HashMap<isize, i32>>,
+    ) {
+        focus_state.lock().unwrap().clear();
+        monitor_focus.lock().unwrap().clear();
+        container_padding.lock().unwrap().clear();
+
+        // WM_APP_SCHEDULE makes the borders rebuild their colors from the config
+        for border in APP_STATE.borders.lock().unwrap().values() {
+            let border_hwnd = HWND(*border as _);
+            post_message_w(Some(border_hwnd), WM_APP_SCHEDULE, WPARAM(0), LPARAM(0))
+                .context("WM_APP_SCHEDULE")
+                .log_if_err();
+            post_message_w(
+                Some(border_hwnd),
+                WM_APP_KOMOREBI_GAPS,
+                WPARAM(0),
+                LPARAM(0),
+            )
+            .context("WM_APP_KOMOREBI_GAPS")
+            .log_if_err();
+        }
+    }
+
+    pub fn reset_borders(&self) {
+        Self::clear_state(
+            &self.focus_state,
+            &self.monitor_focus,
+            &self.container_padding,
+        );
+    }
+
     pub fn stop(&mut self) -> anyhow::Result<()> {
         debug!("stopping komorebi integration");
 
+        // This also tells the watchdog thread to exit
+        *self.status.lock().unwrap() = KomorebiStatus::Stopped;
+
         // If this is Some, it means WinSock is (most likely) running, so we need to cleanup. Doing
         // so should also cause the socket worker thread to automatically fail and exit.
         if let Some(ref socket) = self.listen_socket {
@@ -195,6 +453,10 @@ impl KomorebiIntegration {
         self.listen_socket.is_some()
     }
 
+    pub fn get_status(&self) -> KomorebiStatus {
+        *self.status.lock().unwrap()
+    }
+
     pub fn get_komorebic_socket_path() -> anyhow::Result<PathBuf> {
         let home_dir = home_dir().context("could not get home dir")?;
 
@@ -208,6 +470,8 @@ impl KomorebiIntegration {
     // Largely adapted from komorebi's own border implementation. Thanks @LGUG2Z
     pub fn process_komorebi_notification(
         focus_state_mutex: Arc<Mutex<HashMap<isize, WindowKind>>>,
+        monitor_focus_mutex: Arc<Mutex<HashMap<isize, bool>>>,
+        container_padding_mutex: Arc<Mutex<HashMap<isize, i32>>>,
         buffer: &[u8],
         bytes_received: u32,
     ) {
@@ -221,6 +485,10 @@ impl KomorebiIntegration {
             };
 
         let previous_focus_state = (*focus_state_mutex.lock().unwrap()).clone();
+        let previous_container_padding = (*container_padding_mutex.lock().unwrap()).clone();
+        let mut new_container_padding = HashMap::new();
+        let previous_monitor_focus = (*monitor_focus_mutex.lock().unwrap()).clone();
+        let mut new_monitor_focus = HashMap::new();
 
         let monitors = notification.get("state").get("monitors");
         let focused_monitor_idx = monitors.get("focused").as_u64().unwrap() as usize;
@@ -241,6 +509,24 @@ impl KomorebiIntegration {
                 .unwrap()
                 .get(m.get("workspaces").get("focused").as_u64().unwrap() as usize)
             {
+                let is_monitor_focused = monitor_idx == focused_monitor_idx;
+                let monocle_windows = ws.get("monocle_container").get("windows").get("elements");
+                for window in ws
+                    .get("containers")
+                    .get("elements")
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .flat_map(|c| c.get("windows").get("elements").as_array().unwrap())
+                    .chain(monocle_windows.as_array().unwrap_or_default())
+                    .chain(ws.get("floating_windows").as_array().unwrap())
+                {
+                    new_monitor_focus.insert(
+                        window.get("hwnd").as_i64().unwrap() as isize,
+                        is_monitor_focused,
+                    );
+                }
+
                 // Handle the monocle container separately
                 let monocle = ws.get("monocle_container");
                 if !monocle.is_null() {
@@ -262,6 +548,23 @@ impl KomorebiIntegration {
                     }
                 }
 
+                // Gaps only matter between tiles, so we skip workspaces with a single container.
+                // container_padding is null when komorebi's padding is disabled for the workspace.
+                let containers = ws.get("containers").get("elements").as_array().unwrap();
+                if let Some(padding) = ws.get("container_padding").as_i64()
+                    && containers.len() > 1
+                {
+                    for window in containers
+                        .iter()
+                        .flat_map(|c| c.get("windows").get("elements").as_array().unwrap())
+                    {
+                        new_container_padding.insert(
+                            window.get("hwnd").as_i64().unwrap() as isize,
+                            padding as i32,
+                        );
+                    }
+                }
+
                 let foreground_hwnd = get_foreground_window();
 
                 for (idx, c) in ws
@@ -328,26 +631,41 @@ impl KomorebiIntegration {
             }
         }
 
+        *container_padding_mutex.lock().unwrap() = new_container_padding.clone();
+        *monitor_focus_mutex.lock().unwrap() = new_monitor_focus.clone();
+
         let new_focus_state = focus_state_mutex.lock().unwrap();
 
         for (tracking, border) in APP_STATE.borders.lock().unwrap().iter() {
+            if previous_container_padding.get(tracking) != new_container_padding.get(tracking) {
+                post_message_w(
+                    Some(HWND(*border as _)),
+                    WM_APP_KOMOREBI_GAPS,
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("WM_APP_KOMOREBI_GAPS")
+                .log_if_err();
+            }
+
             let previous_window_kind = previous_focus_state.get(tracking);
             let new_window_kind = new_focus_state.get(tracking);
 
-            // Only post update messages when the window kind has actually changed
-            if previous_window_kind != new_window_kind {
-                // If the window kinds were just Single and Unfocused, then we can just rely on
-                // tacky-borders' internal logic to update border colors
-                if matches!(
+            // If the window kinds were just Single and Unfocused, then we can just rely on
+            // tacky-borders' internal logic to update border colors
+            let is_window_kind_changed = previous_window_kind != new_window_kind
+                && !(matches!(
                     previous_window_kind,
                     Some(WindowKind::Single) | Some(WindowKind::Unfocused)
                 ) && matches!(
                     new_window_kind,
                     Some(WindowKind::Single) | Some(WindowKind::Unfocused)
-                ) {
-                    continue;
-                }
+                ));
+            let is_monitor_focus_changed =
+                previous_monitor_focus.get(tracking) != new_monitor_focus.get(tracking);
 
+            // Only post update messages when something has actually changed
+            if is_window_kind_changed || is_monitor_focus_changed {
                 let border_hwnd = HWND(*border as _);
                 post_message_w(Some(border_hwnd), WM_APP_KOMOREBI, WPARAM(0), LPARAM(0))
                     .context("WM_APP_KOMOREBI")
@@ -1,62 +1,99 @@
+//! The border engine behind tacky-borders. The binary is just a thin wrapper around this crate,
+//! so other programs (tiling window managers, shells, etc.) can embed borders the same way:
+//!
+//! 1. Call [`start_engine`] on a thread, optionally passing a [`config::Config`] (e.g. from
+//!    [`config::Config::from_yaml`]) instead of reading config.yaml.
+//! 2. Run [`run_message_loop`] on that same thread.
+//! 3. Use [`update_config`] to swap configs at runtime, and [`stop_engine`] when you're done.
+
 #[macro_use]
 extern crate log;
 extern crate sp_log;
 
 use anyhow::{Context, anyhow};
-use config::{Config, ConfigWatcher, EnableMode, config_watcher_callback};
+use autodetect::start_detector_if_enabled;
+use colors::set_color_settings;
+use config::{
+    Config, ConfigWatcher, DISABLED_RULE_IDS, EnableMode, IS_PRESENTATION_MODE, PROFILE_OVERRIDE,
+    config_watcher_callback,
+};
 use core::time;
+use hot_corner::start_hot_corner_if_enabled;
 use komorebi::KomorebiIntegration;
+use log_buffer::LogBuffer;
+use proximity::start_tracker_if_enabled;
 use render_backend::RenderBackendConfig;
+use renderer_core::{DirectXDevices, RenderFactory};
 use sp_log::{ColorChoice, CombinedLogger, FileLogger, LevelFilter, TermLogger, TerminalMode};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::{LazyLock, Mutex, RwLock};
+use std::sync::{Condvar, LazyLock, Mutex, RwLock};
 use std::thread;
 use utils::{
-    LogIfErr, create_border_for_window, get_foreground_window, get_last_error, get_window_rule,
+    LogIfErr, WM_APP_SETCOLOR, create_border_for_window, destroy_orphaned_borders,
+    get_border_for_window, get_foreground_window, get_last_error, get_window_rule,
     has_filtered_style, is_window_cloaked, is_window_top_level, is_window_visible, post_message_w,
-    send_message_w,
+    request_border_teardown,
 };
+use window_border::ColorOverride;
 use windows::Wdk::System::SystemServices::RtlGetVersion;
-use windows::Win32::Foundation::{ERROR_CLASS_ALREADY_EXISTS, HMODULE, HWND, LPARAM, TRUE};
-use windows::Win32::Graphics::Direct2D::{
-    D2D1_FACTORY_TYPE_MULTI_THREADED, D2D1CreateFactory, ID2D1Device, ID2D1Factory1,
-};
-use windows::Win32::Graphics::Direct3D::{
-    D3D_DRIVER_TYPE_HARDWARE, D3D_FEATURE_LEVEL, D3D_FEATURE_LEVEL_9_1, D3D_FEATURE_LEVEL_9_2,
-    D3D_FEATURE_LEVEL_9_3, D3D_FEATURE_LEVEL_10_0, D3D_FEATURE_LEVEL_10_1, D3D_FEATURE_LEVEL_11_0,
-    D3D_FEATURE_LEVEL_11_1,
-};
-use windows::Win32::Graphics::Direct3D11::{
-    D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_SDK_VERSION, D3D11CreateDevice, ID3D11Device,
-};
-use windows::Win32::Graphics::Dxgi::IDXGIDevice;
+use windows::Win32::Foundation::{ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, TRUE, WPARAM};
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::System::SystemInformation::OSVERSIONINFOW;
-use windows::Win32::UI::Accessibility::{HWINEVENTHOOK, SetWinEventHook};
+use windows::Win32::UI::Accessibility::{HWINEVENTHOOK, SetWinEventHook, UnhookWinEvent};
 use windows::Win32::UI::WindowsAndMessaging::{
-    EVENT_MAX, EVENT_MIN, EnumWindows, IDC_ARROW, LoadCursorW, MB_ICONERROR, MB_OK,
-    MB_SETFOREGROUND, MB_TOPMOST, MessageBoxW, RegisterClassExW, WINEVENT_OUTOFCONTEXT,
-    WINEVENT_SKIPOWNPROCESS, WM_NCDESTROY, WNDCLASSEXW,
+    DispatchMessageW, EVENT_MAX, EVENT_MIN, EnumWindows, GetMessageW, IDC_ARROW, LoadCursorW,
+    MB_ICONERROR, MB_OK, MB_SETFOREGROUND, MB_TOPMOST, MESSAGEBOX_STYLE, MSG, MessageBoxW,
+    RegisterClassExW, SetTimer, TranslateMessage, WINEVENT_OUTOFCONTEXT, WINEVENT_SKIPOWNPROCESS,
+    WNDCLASSEXW,
 };
-use windows::core::{BOOL, Interface, PCWSTR, w};
+use windows::core::{BOOL, PCWSTR, w};
 
+pub mod acrylic;
 pub mod anim_timer;
 pub mod animations;
+pub mod autodetect;
+pub mod bench;
 pub mod border_drawer;
+pub mod border_limit;
+pub mod border_props;
+pub mod cli;
 pub mod colors;
 pub mod config;
+pub mod config_builder;
+pub mod diagnostics;
+pub mod dormancy;
 pub mod effects;
+pub mod error;
 pub mod event_hook;
+pub mod expression;
+pub mod first_run;
+pub mod focus_commands;
+pub mod hot_corner;
 pub mod iocp;
+pub mod job_object;
 pub mod komorebi;
+pub mod log_buffer;
+pub mod memory_trim;
+pub mod monitor_layout;
+pub mod position_batch;
+pub mod proximity;
 pub mod render_backend;
+pub mod renderer_core;
+pub mod report;
+pub mod resource_sampler;
+pub mod rule_picker;
+pub mod schedule;
+pub mod snapshot;
 pub mod sys_tray_icon;
+pub mod uia;
 pub mod utils;
+pub mod wallpaper;
 pub mod window_border;
 
-static IS_WINDOWS_11: LazyLock<bool> = LazyLock::new(|| {
+// Checked against 'when: { os: ... }' in the config
+static OS_BUILD: LazyLock<u32> = LazyLock::new(|| {
     let mut version_info = OSVERSIONINFOW {
         dwOSVersionInfoSize: size_of::<OSVERSIONINFOW>() as u32,
         ..Default::default()
@@ -70,25 +107,37 @@ static IS_WINDOWS_11: LazyLock<bool> = LazyLock::new(|| {
         version_info.dwMajorVersion, version_info.dwMinorVersion, version_info.dwBuildNumber
     );
 
-    version_info.dwBuildNumber >= 22000
+    version_info.dwBuildNumber
 });
+static IS_WINDOWS_11: LazyLock<bool> = LazyLock::new(|| *OS_BUILD >= 22000);
 static APP_STATE: LazyLock<AppState> = LazyLock::new(AppState::new);
 
+// How often we look for borders whose tracking window has disappeared without us noticing
+const ORPHAN_SWEEP_INTERVAL_MS: u32 = 5_000;
+
+// Config passed to start_engine() by embedders. AppState::new() takes this instead of reading
+// config.yaml if it's set.
+static INITIAL_CONFIG: Mutex<Option<Config>> = Mutex::new(None);
+
+// Set by set_borders_hidden(), e.g. from the hot corner
+static ARE_BORDERS_HIDDEN: AtomicBool = AtomicBool::new(false);
+
 struct AppState {
     borders: Mutex<HashMap<isize, isize>>,
+    // Border windows that have been asked to tear down but haven't confirmed it yet. To avoid
+    // deadlocks, always lock 'borders' before locking this.
+    closing_borders: Mutex<HashSet<isize>>,
+    borders_closed: Condvar,
     initial_windows: Mutex<Vec<isize>>,
     active_window: Mutex<isize>,
     is_polling_active_window: AtomicBool,
     config: RwLock<Config>,
     config_watcher: Mutex<ConfigWatcher>,
-    render_factory: ID2D1Factory1,
+    render_factory: RenderFactory,
     directx_devices: RwLock<Option<DirectXDevices>>,
     komorebi_integration: Mutex<KomorebiIntegration>,
 }
 
-unsafe impl Send for AppState {}
-unsafe impl Sync for AppState {}
-
 impl AppState {
     fn new() -> Self {
         let active_window = get_foreground_window().0 as isize;
@@ -106,12 +155,19 @@ impl AppState {
 
         let mut komorebi_integration = KomorebiIntegration::new();
 
-        let config = match Config::create() {
+        let initial_config = INITIAL_CONFIG.lock().unwrap().take();
+        let config = match initial_config.map(Ok).unwrap_or_else(Config::create) {
             Ok(config) => {
+                config.report_color_errors();
+
                 if config_watcher.is_enabled(&config) {
                     config_watcher.start().log_if_err();
                 }
 
+                start_detector_if_enabled(&config);
+                start_hot_corner_if_enabled(&config);
+                start_tracker_if_enabled(&config);
+
                 if komorebi_integration.is_enabled(&config) {
                     komorebi_integration.start().log_if_err();
                 }
@@ -125,16 +181,15 @@ impl AppState {
                 Config::default()
             }
         };
+        set_color_settings(config.get_color_settings());
 
-        let render_factory: ID2D1Factory1 = unsafe {
-            D2D1CreateFactory(D2D1_FACTORY_TYPE_MULTI_THREADED, None).unwrap_or_else(|err| {
-                error!("could not create ID2D1Factory: {err}");
-                panic!()
-            })
-        };
+        let render_factory = RenderFactory::new().unwrap_or_else(|err| {
+            error!("could not create render factory: {err:#}");
+            panic!()
+        });
 
-        let directx_devices_opt = match config.render_backend {
-            RenderBackendConfig::V2 => {
+        let directx_devices_opt = match config.uses_render_backend(RenderBackendConfig::V2) {
+            true => {
                 // I think I have to just panic if .unwrap() fails tbh; don't know what else I could do.
                 let directx_devices = DirectXDevices::new(&render_factory).unwrap_or_else(|err| {
                     error!("could not create directx devices: {err}");
@@ -143,11 +198,13 @@ impl AppState {
 
                 Some(directx_devices)
             }
-            RenderBackendConfig::Legacy => None,
+            false => None,
         };
 
         AppState {
             borders: Mutex::new(HashMap::new()),
+            closing_borders: Mutex::new(HashSet::new()),
+            borders_closed: Condvar::new(),
             initial_windows: Mutex::new(Vec::new()),
             active_window: Mutex::new(active_window),
             is_polling_active_window: AtomicBool::new(false),
@@ -168,57 +225,6 @@ impl AppState {
     }
 }
 
-struct DirectXDevices {
-    d3d11_device: ID3D11Device,
-    dxgi_device: IDXGIDevice,
-    d2d_device: ID2D1Device,
-}
-
-impl DirectXDevices {
-    fn new(factory: &ID2D1Factory1) -> anyhow::Result<Self> {
-        let creation_flags = D3D11_CREATE_DEVICE_BGRA_SUPPORT;
-
-        let feature_levels = [
-            D3D_FEATURE_LEVEL_11_1,
-            D3D_FEATURE_LEVEL_11_0,
-            D3D_FEATURE_LEVEL_10_1,
-            D3D_FEATURE_LEVEL_10_0,
-            D3D_FEATURE_LEVEL_9_3,
-            D3D_FEATURE_LEVEL_9_2,
-            D3D_FEATURE_LEVEL_9_1,
-        ];
-
-        let mut device_opt: Option<ID3D11Device> = None;
-        let mut feature_level: D3D_FEATURE_LEVEL = D3D_FEATURE_LEVEL::default();
-
-        unsafe {
-            D3D11CreateDevice(
-                None,
-                D3D_DRIVER_TYPE_HARDWARE,
-                HMODULE::default(),
-                creation_flags,
-                Some(&feature_levels),
-                D3D11_SDK_VERSION,
-                Some(&mut device_opt),
-                Some(&mut feature_level),
-                None,
-            )
-        }?;
-
-        debug!("directx feature_level: {feature_level:X?}");
-
-        let d3d11_device = device_opt.context("could not get d3d11_device")?;
-        let dxgi_device = d3d11_device.cast().context("dxgi_device")?;
-        let d2d_device = unsafe { factory.CreateDevice(&dxgi_device) }.context("d2d_device")?;
-
-        Ok(Self {
-            d3d11_device,
-            dxgi_device,
-            d2d_device,
-        })
-    }
-}
-
 pub fn create_logger() -> anyhow::Result<()> {
     // NOTE: there are two Config structs in this function: tacky-borders' and sp_log's
     let log_path = crate::Config::get_dir()?.join("tacky-borders.log");
@@ -240,6 +246,8 @@ pub fn create_logger() -> anyhow::Result<()> {
             // 1 MB
             Some(1024 * 1024),
         ),
+        // Feeds the log viewer in the diagnostics window
+        LogBuffer::new(LevelFilter::Debug),
     ])?;
 
     Ok(())
@@ -269,6 +277,16 @@ pub fn register_border_window_class() -> anyhow::Result<()> {
 }
 
 pub fn set_event_hook() -> HWINEVENTHOOK {
+    // One sweep for every border rather than a timer per border
+    unsafe {
+        SetTimer(
+            None,
+            0,
+            ORPHAN_SWEEP_INTERVAL_MS,
+            Some(destroy_orphaned_borders),
+        )
+    };
+
     unsafe {
         SetWinEventHook(
             EVENT_MIN,
@@ -282,6 +300,199 @@ pub fn set_event_hook() -> HWINEVENTHOOK {
     }
 }
 
+// Everything needed to get borders going on the current thread. The caller must run a message loop
+// on this same thread afterwards (see run_message_loop()), since the event hook relies on it.
+//
+// If 'config' is None, the config is read from config.yaml like the tacky-borders binary does.
+pub fn start_engine(config: Option<Config>) -> anyhow::Result<HWINEVENTHOOK> {
+    if let Some(config) = config {
+        *INITIAL_CONFIG.lock().unwrap() = Some(config);
+
+        // If APP_STATE was already initialized, then it didn't pick up our config, so we have to
+        // apply it ourselves
+        LazyLock::force(&APP_STATE);
+        if let Some(config) = INITIAL_CONFIG.lock().unwrap().take() {
+            config.apply();
+        }
+    }
+
+    let hwineventhook = set_event_hook();
+    if hwineventhook.is_invalid() {
+        return Err(anyhow!("could not set event hook: {:?}", get_last_error()));
+    }
+
+    // Without the event hook, nothing would ever happen, but these just leave us without some (or
+    // all) borders until the next reload, so we keep going
+    register_border_window_class().log_if_err();
+    monitor_layout::create_display_change_window().log_if_err();
+    create_borders_for_existing_windows()
+        .context("could not create borders")
+        .log_if_err();
+    schedule::start_schedule_timer();
+    // In case there weren't any windows that needed a border to begin with
+    dormancy::queue_dormancy_check();
+
+    Ok(hwineventhook)
+}
+
+// Undoes start_engine(). This doesn't post WM_QUIT, so the caller is responsible for leaving its
+// message loop.
+pub fn stop_engine(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<()> {
+    let unhook_bool = unsafe { UnhookWinEvent(hwineventhook) }.as_bool();
+    let stop_res = APP_STATE.config_watcher.lock().unwrap().stop();
+    let close_res = APP_STATE.komorebi_integration.lock().unwrap().stop();
+
+    destroy_borders();
+    monitor_layout::destroy_display_change_window();
+
+    if !unhook_bool || stop_res.is_err() || close_res.is_err() {
+        return Err(anyhow!(
+            "attempt to unhook win event: {unhook_bool:?}; attempt to stop config watcher: {stop_res:?}; attempt to close socket: {close_res:?}"
+        ));
+    }
+
+    Ok(())
+}
+
+// Overrides the colors of the border for 'tracking_window' (e.g. for scripting or profiles). Pass
+// a ColorOverride without any colors to go back to the configured colors.
+pub fn set_color_override(
+    tracking_window: HWND,
+    color_override: ColorOverride,
+) -> anyhow::Result<()> {
+    let border_window = get_border_for_window(tracking_window)
+        .with_context(|| format!("could not find border for {tracking_window:?}"))?;
+
+    // The border takes ownership of this when it handles the message
+    let color_override_ptr = Box::into_raw(Box::new(color_override));
+    post_message_w(
+        Some(border_window),
+        WM_APP_SETCOLOR,
+        WPARAM(0),
+        LPARAM(color_override_ptr as isize),
+    )
+    .inspect_err(|_| drop(unsafe { Box::from_raw(color_override_ptr) }))
+    .context("could not post WM_APP_SETCOLOR message")
+}
+
+// Swaps in a new config and recreates all borders with it
+pub fn update_config(config: Config) {
+    config.apply();
+    reload_borders();
+}
+
+// Switches every border to the config's 'presentation' look, or back to the regular config
+pub fn set_presentation_mode(enabled: bool) {
+    if IS_PRESENTATION_MODE.swap(enabled, Ordering::Relaxed) == enabled {
+        return;
+    }
+
+    match enabled {
+        true => info!("presentation mode enabled"),
+        false => info!("presentation mode disabled"),
+    }
+    reload_borders();
+}
+
+pub fn is_presentation_mode() -> bool {
+    IS_PRESENTATION_MODE.load(Ordering::Relaxed)
+}
+
+// Turns the window rule with the given id off (or back on) without touching the config file. The
+// windows it matched fall through to the next matching rule while it's off.
+pub fn set_window_rule_enabled(id: &str, enabled: bool) {
+    {
+        let mut disabled_ids = DISABLED_RULE_IDS.lock().unwrap();
+        if disabled_ids.iter().any(|disabled_id| disabled_id == id) != enabled {
+            return;
+        }
+
+        match enabled {
+            true => disabled_ids.retain(|disabled_id| disabled_id != id),
+            false => disabled_ids.push(id.to_string()),
+        }
+    }
+
+    match enabled {
+        true => info!("window rule '{id}' enabled"),
+        false => info!("window rule '{id}' disabled"),
+    }
+    reload_borders();
+}
+
+pub fn is_window_rule_enabled(id: &str) -> bool {
+    !DISABLED_RULE_IDS
+        .lock()
+        .unwrap()
+        .iter()
+        .any(|disabled_id| disabled_id == id)
+}
+
+// While the borders are hidden, no borders get created at all, so they don't cost anything
+pub fn set_borders_hidden(hidden: bool) {
+    if ARE_BORDERS_HIDDEN.swap(hidden, Ordering::Relaxed) == hidden {
+        return;
+    }
+
+    match hidden {
+        true => {
+            info!("hiding borders");
+            destroy_borders();
+        }
+        false => {
+            info!("showing borders");
+            reload_borders();
+        }
+    }
+}
+
+pub fn are_borders_hidden() -> bool {
+    ARE_BORDERS_HIDDEN.load(Ordering::Relaxed)
+}
+
+// Switches to the next profile in alphabetical order. After the last one, we go back to whichever
+// profile is scheduled (if any).
+pub fn cycle_profile() {
+    let next_profile = {
+        let config = APP_STATE.config.read().unwrap();
+        let mut profiles: Vec<&String> = config.profiles.keys().collect();
+        if profiles.is_empty() {
+            warn!("there are no profiles to cycle through");
+            return;
+        }
+        profiles.sort();
+
+        // We go by the override rather than the active profile, since the scheduled profile could
+        // be the last one, which would keep us from ever getting past it
+        let profile_override = PROFILE_OVERRIDE.lock().unwrap();
+        let current_idx = profile_override
+            .as_ref()
+            .and_then(|current| profiles.iter().position(|profile| *profile == current));
+        match current_idx {
+            Some(idx) => profiles.get(idx + 1).map(|profile| profile.to_string()),
+            None => Some(profiles[0].clone()),
+        }
+    };
+
+    match next_profile {
+        Some(ref profile) => info!("switching to profile '{profile}'"),
+        None => info!("switching back to the scheduled profile"),
+    }
+    *PROFILE_OVERRIDE.lock().unwrap() = next_profile;
+    Config::switch_profile();
+    reload_borders();
+}
+
+pub fn run_message_loop() {
+    unsafe {
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, None, 0, 0).as_bool() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+}
+
 pub fn create_borders_for_existing_windows() -> windows::core::Result<()> {
     unsafe { EnumWindows(Some(create_borders_callback), LPARAM::default()) }?;
     debug!("windows have been enumerated!");
@@ -290,64 +501,63 @@ pub fn create_borders_for_existing_windows() -> windows::core::Result<()> {
 }
 
 pub fn destroy_borders() {
-    const MAX_ATTEMPTS: u32 = 3;
-
-    for i in 0..MAX_ATTEMPTS {
-        // Copy the hashmap's values to prevent mutex deadlocks
-        let border_hwnds: Vec<HWND> = APP_STATE
-            .borders
-            .lock()
-            .unwrap()
-            .values()
-            .map(|hwnd_isize| HWND(*hwnd_isize as _))
-            .collect();
+    const TEARDOWN_TIMEOUT: time::Duration = time::Duration::from_millis(500);
 
-        for hwnd in border_hwnds {
-            let _ = send_message_w(hwnd, WM_NCDESTROY, None, None);
-        }
+    // Take every border out of the hashmap so that no new messages are routed to them, then ask
+    // each one to tear itself down
+    {
+        let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
+        let border_isizes: Vec<isize> = borders_hashmap.drain().map(|(_, val)| val).collect();
 
-        // SendMessageW ensures that the border windows have processed their messages, but it
-        // does not guarantee that the thread has exited, so we still must wait a few ms
-        thread::sleep(time::Duration::from_millis(5));
-
-        let remaining_borders = APP_STATE.borders.lock().unwrap();
-        if remaining_borders.is_empty() {
-            break;
-        } else if i == MAX_ATTEMPTS - 1 {
-            error!(
-                "could not successfully destroy all borders (still remaining: {:?})",
-                *remaining_borders
-            );
+        for border_isize in border_isizes {
+            request_border_teardown(&borders_hashmap, border_isize, false);
         }
     }
 
-    // NOTE: we will rely on each border thread to remove themselves from the hashmap, so we won't
-    // do any manual cleanup here
+    // Each border thread confirms its teardown only after its window has been destroyed, so once
+    // 'closing_borders' is empty, we know there are no border windows left
+    let closing_borders = APP_STATE.closing_borders.lock().unwrap();
+    let (remaining_borders, wait_result) = APP_STATE
+        .borders_closed
+        .wait_timeout_while(closing_borders, TEARDOWN_TIMEOUT, |closing_borders| {
+            !closing_borders.is_empty()
+        })
+        .unwrap();
+
+    if wait_result.timed_out() {
+        error!(
+            "could not successfully destroy all borders (still remaining: {:?})",
+            *remaining_borders
+        );
+    }
 }
 
 pub fn reload_borders() {
     destroy_borders();
     APP_STATE.initial_windows.lock().unwrap().clear();
+    position_batch::begin_batch();
     create_borders_for_existing_windows().log_if_err();
 }
 
 pub fn display_error_box<T: std::fmt::Display>(err: T) {
-    let error_vec: Vec<u16> = err
-        .to_string()
-        .encode_utf16()
-        .chain(std::iter::once(0))
-        .collect();
-
-    let _ = thread::spawn(move || {
-        let _ = unsafe {
-            MessageBoxW(
-                None,
-                PCWSTR(error_vec.as_ptr()),
-                w!("Error!"),
-                MB_OK | MB_ICONERROR | MB_SETFOREGROUND | MB_TOPMOST,
-            )
-        };
-    });
+    let err = err.to_string();
+
+    let _ = thread::spawn(move || show_message_box(&err, w!("Error!"), MB_ICONERROR));
+}
+
+// Unlike display_error_box(), this blocks until the box is closed, so it's fine to call right
+// before exiting (the release build has no console, so this is how one-off commands report back)
+pub fn show_message_box(text: &str, caption: PCWSTR, icon: MESSAGEBOX_STYLE) {
+    let text_vec: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+
+    let _ = unsafe {
+        MessageBoxW(
+            None,
+            PCWSTR(text_vec.as_ptr()),
+            caption,
+            MB_OK | icon | MB_SETFOREGROUND | MB_TOPMOST,
+        )
+    };
 }
 
 unsafe extern "system" fn create_borders_callback(_hwnd: HWND, _lparam: LPARAM) -> BOOL {
@@ -357,7 +567,10 @@ unsafe extern "system" fn create_borders_callback(_hwnd: HWND, _lparam: LPARAM)
             let window_rule = get_window_rule(_hwnd);
 
             if window_rule.enabled == Some(EnableMode::Bool(false)) {
-                info!("border is disabled for {_hwnd:?}");
+                info!(
+                    "border is disabled for {_hwnd:?} by window rule '{}'",
+                    window_rule.display_name()
+                );
             } else if window_rule.enabled == Some(EnableMode::Bool(true))
                 || !has_filtered_style(_hwnd)
             {
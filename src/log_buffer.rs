@@ -0,0 +1,97 @@
+// Keeps the most recent log lines in memory so that they can be shown in the diagnostics window
+// (and attached to bug reports) without having to dig up the log file.
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use sp_log::SharedLogger;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicU64, Ordering};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+const MAX_LOG_LINES: usize = 1000;
+
+static LOG_LINES: Mutex<VecDeque<LogLine>> = Mutex::new(VecDeque::new());
+// Bumped on every new line, so readers can tell whether anything changed since they last looked
+static LOG_GENERATION: AtomicU64 = AtomicU64::new(0);
+
+#[derive(Debug, Clone)]
+pub struct LogLine {
+    pub level: Level,
+    pub text: String,
+}
+
+pub struct LogBuffer {
+    level: LevelFilter,
+}
+
+impl LogBuffer {
+    pub fn new(level: LevelFilter) -> Box<Self> {
+        Box::new(Self { level })
+    }
+}
+
+impl Log for LogBuffer {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        let time = unsafe { GetLocalTime() };
+        let text = format!(
+            "{:02}:{:02}:{:02} [{}] {}",
+            time.wHour,
+            time.wMinute,
+            time.wSecond,
+            record.level(),
+            record.args()
+        );
+
+        let mut log_lines = LOG_LINES.lock().unwrap();
+        if log_lines.len() >= MAX_LOG_LINES {
+            log_lines.pop_front();
+        }
+        log_lines.push_back(LogLine {
+            level: record.level(),
+            text,
+        });
+        LOG_GENERATION.fetch_add(1, Ordering::SeqCst);
+    }
+
+    fn flush(&self) {}
+}
+
+impl SharedLogger for LogBuffer {
+    fn level(&self) -> LevelFilter {
+        self.level
+    }
+
+    fn config(&self) -> Option<&sp_log::Config> {
+        None
+    }
+
+    fn as_log(self: Box<Self>) -> Box<dyn Log> {
+        Box::new(*self)
+    }
+}
+
+pub fn get_log_generation() -> u64 {
+    LOG_GENERATION.load(Ordering::SeqCst)
+}
+
+// Returns up to max_lines of the most recent lines at or above the given level, oldest first
+pub fn get_recent_log_lines(level: LevelFilter, max_lines: usize) -> Vec<String> {
+    let log_lines = LOG_LINES.lock().unwrap();
+    let mut recent_lines: Vec<String> = log_lines
+        .iter()
+        .rev()
+        .filter(|line| line.level <= level)
+        .take(max_lines)
+        .map(|line| line.text.clone())
+        .collect();
+    recent_lines.reverse();
+
+    recent_lines
+}
@@ -8,16 +8,16 @@ extern crate log;
 extern crate sp_log;
 
 use anyhow::Context;
+use tacky_borders::bench::{run_bench, run_bench_host};
+use tacky_borders::cli::CLI_ARGS;
+use tacky_borders::first_run::run_first_run_wizard_if_needed;
+use tacky_borders::report::create_report;
 use tacky_borders::sys_tray_icon::create_tray_icon;
 use tacky_borders::utils::{LogIfErr, imm_disable_ime, set_process_dpi_awareness_context};
-use tacky_borders::{
-    create_borders_for_existing_windows, create_logger, register_border_window_class,
-    set_event_hook,
-};
+use tacky_borders::{create_logger, run_message_loop, show_message_box, start_engine};
 use windows::Win32::UI::HiDpi::DPI_AWARENESS_CONTEXT_PER_MONITOR_AWARE_V2;
-use windows::Win32::UI::WindowsAndMessaging::{
-    DispatchMessageW, GetMessageW, MSG, TranslateMessage,
-};
+use windows::Win32::UI::WindowsAndMessaging::MB_ICONERROR;
+use windows::core::w;
 
 fn main() {
     if let Err(err) = create_logger() {
@@ -26,6 +26,10 @@ fn main() {
 
     info!("starting tacky-borders");
 
+    if CLI_ARGS.read_only {
+        info!("running in read-only mode");
+    }
+
     // xFFFFFFFF (-1) is used to disable IME windows for all threads in the current process.
     imm_disable_ime(0xFFFFFFFF)
         .ok()
@@ -36,7 +40,59 @@ fn main() {
         .context("could not make process dpi aware")
         .log_if_err();
 
-    let hwineventhook = set_event_hook();
+    // The dummy windows for --bench are hosted in a separate process (see bench.rs)
+    if let Some(count) = CLI_ARGS.bench_host {
+        run_bench_host(count).log_if_err();
+        return;
+    }
+
+    if let Some(ref bench_options) = CLI_ARGS.bench {
+        if let Err(err) = run_bench(bench_options) {
+            error!("could not run benchmark: {err:#}");
+            show_message_box(
+                &format!("could not run benchmark: {err:#}"),
+                w!("Error!"),
+                MB_ICONERROR,
+            );
+        }
+        return;
+    }
+
+    if CLI_ARGS.report {
+        match create_report() {
+            Ok(report_path) => {
+                info!("saved report to {}", report_path.display());
+                if let Some(dir) = report_path.parent() {
+                    open::that(dir).log_if_err();
+                }
+            }
+            Err(err) => {
+                error!("could not create report: {err:#}");
+                show_message_box(
+                    &format!("could not create report: {err:#}"),
+                    w!("Error!"),
+                    MB_ICONERROR,
+                );
+            }
+        }
+        return;
+    }
+
+    // This needs to run before anything loads the config
+    run_first_run_wizard_if_needed();
+
+    let hwineventhook = match start_engine(None) {
+        Ok(hwineventhook) => hwineventhook,
+        Err(err) => {
+            error!("could not start tacky-borders: {err:#}");
+            show_message_box(
+                &format!("could not start tacky-borders: {err:#}"),
+                w!("Error!"),
+                MB_ICONERROR,
+            );
+            return;
+        }
+    };
 
     // This is responsible for the tray icon window, so it must be kept in scope
     let tray_icon_res = create_tray_icon(hwineventhook);
@@ -44,16 +100,7 @@ fn main() {
         error!("could not create tray icon: {err}");
     }
 
-    register_border_window_class().log_if_err();
-    create_borders_for_existing_windows().log_if_err();
-
-    unsafe {
-        let mut message = MSG::default();
-        while GetMessageW(&mut message, None, 0, 0).as_bool() {
-            let _ = TranslateMessage(&message);
-            DispatchMessageW(&message);
-        }
-    }
+    run_message_loop();
 
     info!("exiting tacky-borders");
 }
@@ -0,0 +1,98 @@
+// After a burst of windows opening and closing (e.g. a workspace full of windows getting closed),
+// the border maps keep their grown capacity and the freed border thread stacks and renderers stay
+// in the heap. Once things have calmed down, we shrink everything back and hand the memory back to
+// the OS.
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::Win32::System::Memory::{GetProcessHeap, HEAP_FLAGS, HeapCompact};
+use windows::Win32::System::Threading::GetCurrentProcess;
+
+use crate::APP_STATE;
+use crate::dormancy::shrink_visible_borders;
+use crate::resource_sampler::get_process_private_mb;
+
+const TRIM_DELAY: Duration = Duration::from_secs(30);
+// Small maps aren't worth reallocating, so we only trim once the capacity is well past what's used
+const MIN_WASTED_CAPACITY: usize = 64;
+
+// When the pending trim should happen, or None if no trim is pending
+static TRIM_DEADLINE: Mutex<Option<Instant>> = Mutex::new(None);
+// The private bytes (in MB) before and after the last trim, shown in the diagnostics window
+static LAST_TRIM: Mutex<Option<(Instant, f64, f64)>> = Mutex::new(None);
+
+// Called whenever a border finishes tearing down. Each call pushes the deadline back, so the trim
+// itself only happens once no other borders have closed for TRIM_DELAY.
+pub fn queue_memory_trim() {
+    let mut trim_deadline = TRIM_DEADLINE.lock().unwrap();
+    let is_trim_pending = trim_deadline.is_some();
+    *trim_deadline = Some(Instant::now() + TRIM_DELAY);
+    drop(trim_deadline);
+
+    if is_trim_pending {
+        return;
+    }
+
+    let _ = thread::spawn(|| {
+        loop {
+            let mut trim_deadline = TRIM_DEADLINE.lock().unwrap();
+            let remaining = trim_deadline
+                .map(|deadline| deadline.saturating_duration_since(Instant::now()))
+                .unwrap_or_default();
+
+            if remaining.is_zero() {
+                *trim_deadline = None;
+                break;
+            }
+
+            drop(trim_deadline);
+            thread::sleep(remaining);
+        }
+
+        let borders_hashmap = APP_STATE.borders.lock().unwrap();
+        let wasted_capacity = borders_hashmap.capacity() - borders_hashmap.len();
+        drop(borders_hashmap);
+
+        if wasted_capacity >= MIN_WASTED_CAPACITY {
+            trim_memory();
+        }
+    });
+}
+
+// Returns the process's private bytes (in MB) before and after trimming
+pub fn trim_memory() -> (Option<f64>, Option<f64>) {
+    let memory_before = get_process_private_mb(unsafe { GetCurrentProcess() });
+
+    // Remember to lock 'borders' before 'closing_borders'
+    let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
+    borders_hashmap.shrink_to_fit();
+    APP_STATE.closing_borders.lock().unwrap().shrink_to_fit();
+    drop(borders_hashmap);
+
+    APP_STATE.initial_windows.lock().unwrap().shrink_to_fit();
+    shrink_visible_borders();
+
+    unsafe {
+        // Rust's allocator uses the process heap on Windows
+        if let Ok(hheap) = GetProcessHeap() {
+            HeapCompact(hheap, HEAP_FLAGS(0));
+        }
+    }
+
+    let memory_after = get_process_private_mb(unsafe { GetCurrentProcess() });
+    if let (Some(before), Some(after)) = (memory_before, memory_after) {
+        info!("trimmed memory: {before:.1} MB -> {after:.1} MB private");
+        *LAST_TRIM.lock().unwrap() = Some((Instant::now(), before, after));
+    }
+
+    (memory_before, memory_after)
+}
+
+pub fn get_trim_summary() -> Option<String> {
+    let (trimmed_at, before, after) = (*LAST_TRIM.lock().unwrap())?;
+
+    Some(format!(
+        "Last memory trim ({}s ago): {before:.1} MB -> {after:.1} MB private",
+        trimmed_at.elapsed().as_secs()
+    ))
+}
@@ -0,0 +1,219 @@
+// Lets the config switch profiles depending on which monitors are connected, e.g. thicker borders
+// while docked to a few big monitors and thinner ones on a laptop's own screen. We check the layout
+// again whenever the display settings change.
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicIsize, Ordering};
+use std::{thread, time};
+use windows::Win32::Foundation::{ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, RegisterClassExW, WM_DISPLAYCHANGE,
+    WNDCLASSEXW, WS_EX_TOOLWINDOW, WS_POPUP,
+};
+use windows::core::w;
+
+use crate::config::Config;
+use crate::utils::{get_last_error, get_monitor_resolution, get_monitors};
+use crate::{APP_STATE, position_batch, reload_borders};
+
+// Docking and undocking can change the display settings several times in a row, so we wait for
+// things to settle before checking the layout
+const SETTLE_DELAY: time::Duration = time::Duration::from_secs(1);
+
+static IS_CHECK_PENDING: AtomicBool = AtomicBool::new(false);
+
+// Hidden window that listens for WM_DISPLAYCHANGE, since there might not be any borders around to
+// hear about it (e.g. while they're hidden or dormant)
+static DISPLAY_CHANGE_WINDOW: AtomicIsize = AtomicIsize::new(0);
+
+// Switches to one of the config's named profiles while the connected monitors match. Leaving out
+// both 'monitor_count' and 'resolutions' matches every layout.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct MonitorLayoutConfig {
+    pub profile: String,
+    pub monitor_count: Option<usize>,
+    // Resolutions (e.g. "2560x1440") that all have to be connected. Listing the same resolution
+    // twice means two monitors with that resolution.
+    pub resolutions: Option<Vec<String>>,
+}
+
+impl MonitorLayoutConfig {
+    pub fn check(&self) -> anyhow::Result<()> {
+        for resolution in self.resolutions.iter().flatten() {
+            if parse_resolution(resolution).is_none() {
+                return Err(anyhow!(
+                    "invalid resolution '{resolution}' (expected something like \"1920x1080\")"
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn matches(&self, layout: &MonitorLayout) -> bool {
+        if let Some(monitor_count) = self.monitor_count
+            && monitor_count != layout.resolutions.len()
+        {
+            return false;
+        }
+
+        // Each listed resolution has to be matched by a different monitor
+        let mut remaining = layout.resolutions.clone();
+        self.resolutions.iter().flatten().all(|resolution| {
+            match parse_resolution(resolution)
+                .and_then(|resolution| remaining.iter().position(|other| *other == resolution))
+            {
+                Some(idx) => {
+                    remaining.swap_remove(idx);
+                    true
+                }
+                None => false,
+            }
+        })
+    }
+}
+
+// The resolutions of the connected monitors, in EnumDisplayMonitors order
+#[derive(Clone, Debug, PartialEq)]
+pub struct MonitorLayout {
+    pub resolutions: Vec<(u32, u32)>,
+}
+
+impl MonitorLayout {
+    pub fn current() -> Self {
+        let resolutions = get_monitors()
+            .into_iter()
+            .filter_map(|hmonitor| get_monitor_resolution(hmonitor).ok())
+            .collect();
+
+        Self { resolutions }
+    }
+}
+
+impl fmt::Display for MonitorLayout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let resolutions: Vec<String> = self
+            .resolutions
+            .iter()
+            .map(|(width, height)| format!("{width}x{height}"))
+            .collect();
+
+        write!(
+            f,
+            "{} monitor(s): {}",
+            self.resolutions.len(),
+            resolutions.join(", ")
+        )
+    }
+}
+
+fn parse_resolution(resolution: &str) -> Option<(u32, u32)> {
+    let (width, height) = resolution.trim().split_once(['x', 'X'])?;
+
+    Some((width.trim().parse().ok()?, height.trim().parse().ok()?))
+}
+
+// Creates the hidden window on the current thread, which needs to run a message loop afterwards.
+// WM_DISPLAYCHANGE is only broadcast to top-level windows, so this can't be a message-only window.
+pub fn create_display_change_window() -> anyhow::Result<()> {
+    if DISPLAY_CHANGE_WINDOW.load(Ordering::SeqCst) != 0 {
+        return Ok(());
+    }
+
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+
+        let window_class = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(display_change_wnd_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: w!("tacky-display-change"),
+            ..Default::default()
+        };
+        if RegisterClassExW(&window_class) == 0 {
+            let last_error = get_last_error();
+            if last_error != ERROR_CLASS_ALREADY_EXISTS {
+                return Err(anyhow!(
+                    "could not register display change window class: {last_error:?}"
+                ));
+            }
+        }
+
+        let hwnd = CreateWindowExW(
+            WS_EX_TOOLWINDOW,
+            w!("tacky-display-change"),
+            w!("tacky-borders display change"),
+            WS_POPUP,
+            0,
+            0,
+            0,
+            0,
+            None,
+            None,
+            Some(hinstance.into()),
+            None,
+        )
+        .context("could not create display change window")?;
+        DISPLAY_CHANGE_WINDOW.store(hwnd.0 as isize, Ordering::SeqCst);
+    }
+
+    Ok(())
+}
+
+// Has to be called from the thread that created the window
+pub fn destroy_display_change_window() {
+    let hwnd_isize = DISPLAY_CHANGE_WINDOW.swap(0, Ordering::SeqCst);
+    if hwnd_isize != 0 {
+        let _ = unsafe { DestroyWindow(HWND(hwnd_isize as _)) };
+    }
+}
+
+unsafe extern "system" fn display_change_wnd_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    if message == WM_DISPLAYCHANGE {
+        // Every border is about to move or resize, so we apply those moves in one go
+        position_batch::begin_batch();
+        queue_layout_check();
+    }
+
+    unsafe { DefWindowProcW(hwnd, message, wparam, lparam) }
+}
+
+// Called whenever the display settings change. Like the profile schedule, we switch profiles if a
+// different one should be active now.
+pub fn queue_layout_check() {
+    if APP_STATE.config.read().unwrap().monitor_layouts.is_empty()
+        || IS_CHECK_PENDING.swap(true, Ordering::SeqCst)
+    {
+        return;
+    }
+
+    let _ = thread::spawn(|| {
+        thread::sleep(SETTLE_DELAY);
+        IS_CHECK_PENDING.store(false, Ordering::SeqCst);
+
+        let (active_profile, scheduled_profile) = {
+            let config = APP_STATE.config.read().unwrap();
+            (
+                config.active_profile.clone(),
+                config.get_scheduled_profile(),
+            )
+        };
+        if active_profile != scheduled_profile {
+            info!(
+                "monitor layout changed to {}; switching from profile {active_profile:?} to \
+                {scheduled_profile:?}",
+                MonitorLayout::current()
+            );
+            Config::switch_profile();
+            reload_borders();
+        }
+    });
+}
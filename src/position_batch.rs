@@ -0,0 +1,164 @@
+// Batches border moves into one DeferWindowPos transaction when lots of borders move at once
+// (desktop switches, display changes, and reloads). The borders queue their moves from their own
+// threads, but only the batch's thread ever touches the HDWP, so the transaction is applied in one
+// go. Each border then gets WM_APP_POSITIONED so that it can render at its new size.
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{thread, time};
+use windows::Win32::Foundation::{HWND, LPARAM, RECT, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{
+    BeginDeferWindowPos, DeferWindowPos, EndDeferWindowPos, IsWindow, SET_WINDOW_POS_FLAGS,
+    SWP_HIDEWINDOW, SWP_SHOWWINDOW, SetWindowPos,
+};
+
+use crate::utils::{LogIfErr, WM_APP_POSITIONED, post_message_w};
+
+// How long a batch collects border moves before they're all applied. The borders receive their
+// LOCATIONCHANGE messages on their own threads, so we give them a moment to catch up.
+const BATCH_WINDOW_MS: u64 = 30;
+
+// HWNDs are stored as isize so that this can be shared across the border threads
+#[derive(Debug)]
+struct PendingPosition {
+    border_window: isize,
+    insert_after: isize,
+    rect: RECT,
+    flags: SET_WINDOW_POS_FLAGS,
+}
+
+// Lets regular moves skip the lock entirely while no batch is open
+static IS_BATCH_OPEN: AtomicBool = AtomicBool::new(false);
+static PENDING_POSITIONS: Mutex<Option<Vec<PendingPosition>>> = Mutex::new(None);
+
+// Opens a batch if there isn't already one open. It's applied automatically after BATCH_WINDOW_MS.
+pub fn begin_batch() {
+    let mut pending_positions = PENDING_POSITIONS.lock().unwrap();
+    if pending_positions.is_some() {
+        return;
+    }
+    *pending_positions = Some(Vec::new());
+    IS_BATCH_OPEN.store(true, Ordering::SeqCst);
+    drop(pending_positions);
+
+    let _ = thread::spawn(|| {
+        thread::sleep(time::Duration::from_millis(BATCH_WINDOW_MS));
+        apply_batch();
+    });
+}
+
+// Returns false if the position wasn't queued, in which case the caller should call SetWindowPos
+// itself. Otherwise, the caller should hold off on rendering until it gets WM_APP_POSITIONED.
+// Hiding is never batched because we'd rather not leave borders floating around.
+pub fn queue_position(
+    border_window: HWND,
+    insert_after: HWND,
+    rect: &RECT,
+    flags: SET_WINDOW_POS_FLAGS,
+) -> bool {
+    if !IS_BATCH_OPEN.load(Ordering::SeqCst) {
+        return false;
+    }
+
+    let mut pending_positions = PENDING_POSITIONS.lock().unwrap();
+    let Some(ref mut positions) = *pending_positions else {
+        return false;
+    };
+
+    // Only the latest position for each border matters, but if an earlier one was going to show
+    // the border, we still need to do that
+    let mut flags = flags;
+    if let Some(index) = positions
+        .iter()
+        .position(|position| position.border_window == border_window.0 as isize)
+    {
+        let previous = positions.remove(index);
+        if !flags.contains(SWP_HIDEWINDOW) {
+            flags |= previous.flags & SWP_SHOWWINDOW;
+        }
+    }
+
+    if flags.contains(SWP_HIDEWINDOW) {
+        return false;
+    }
+
+    positions.push(PendingPosition {
+        border_window: border_window.0 as isize,
+        insert_after: insert_after.0 as isize,
+        rect: *rect,
+        flags,
+    });
+
+    true
+}
+
+fn apply_batch() {
+    // The lock is released before we move anything, since EndDeferWindowPos waits on the border
+    // threads, which may be trying to queue their own moves in the meantime
+    let positions = {
+        let mut pending_positions = PENDING_POSITIONS.lock().unwrap();
+        IS_BATCH_OPEN.store(false, Ordering::SeqCst);
+        pending_positions.take().unwrap_or_default()
+    };
+
+    // Borders may have been destroyed while the batch was open, which would fail the whole batch
+    let positions: Vec<PendingPosition> = positions
+        .into_iter()
+        .filter(|position| unsafe { IsWindow(Some(HWND(position.border_window as _))) }.as_bool())
+        .collect();
+
+    if positions.is_empty() {
+        return;
+    }
+
+    if let Err(err) = defer_positions(&positions) {
+        debug!("could not batch border positions; moving them one by one instead: {err}");
+
+        for position in positions.iter() {
+            unsafe {
+                SetWindowPos(
+                    HWND(position.border_window as _),
+                    Some(HWND(position.insert_after as _)),
+                    position.rect.left,
+                    position.rect.top,
+                    position.rect.right - position.rect.left,
+                    position.rect.bottom - position.rect.top,
+                    position.flags,
+                )
+            }
+            .log_if_err();
+        }
+    }
+
+    // Now that every border is where it belongs, they can render at their new sizes
+    for position in positions.iter() {
+        let _ = post_message_w(
+            Some(HWND(position.border_window as _)),
+            WM_APP_POSITIONED,
+            WPARAM(0),
+            LPARAM(0),
+        );
+    }
+}
+
+fn defer_positions(positions: &[PendingPosition]) -> windows::core::Result<()> {
+    unsafe {
+        let mut hdwp = BeginDeferWindowPos(positions.len() as i32)?;
+
+        // If DeferWindowPos fails, the whole operation is abandoned and we must not call
+        // EndDeferWindowPos, so just returning the error here is fine
+        for position in positions.iter() {
+            hdwp = DeferWindowPos(
+                hdwp,
+                HWND(position.border_window as _),
+                Some(HWND(position.insert_after as _)),
+                position.rect.left,
+                position.rect.top,
+                position.rect.right - position.rect.left,
+                position.rect.bottom - position.rect.top,
+                position.flags,
+            )?;
+        }
+
+        EndDeferWindowPos(hdwp)
+    }
+}
@@ -0,0 +1,157 @@
+// Keeps borders nearly invisible until the cursor gets close to their window's edges, then fades
+// them in. Like the hot corner, we just poll the cursor position. Each border's opacity is eased
+// here and then sent to the border, which applies it to its whole window (see WM_APP_PROXIMITY).
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::{thread, time};
+use windows::Win32::Foundation::{HWND, LPARAM, POINT, RECT, WPARAM};
+use windows::Win32::UI::WindowsAndMessaging::{GetCursorPos, GetWindowRect};
+
+use crate::APP_STATE;
+use crate::config::{Config, serde_default_i32, serde_default_u64};
+use crate::utils::{LogIfErr, WM_APP_PROXIMITY, post_message_w};
+
+// We poll faster while something is fading
+const IDLE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(50);
+const FADE_POLL_INTERVAL: time::Duration = time::Duration::from_millis(16);
+// WM_APP_PROXIMITY sends the opacity as an integer out of this
+pub const OPACITY_SCALE: f32 = 1000.0;
+
+static IS_TRACKER_RUNNING: AtomicBool = AtomicBool::new(false);
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProximityRevealConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    // How close to a window's edge the cursor has to be before its border starts fading in (in
+    // pixels). Borders are fully visible once the cursor is on the edge itself.
+    #[serde(default = "serde_default_i32::<120>")]
+    pub distance: i32,
+    // How visible borders are while the cursor is far away
+    #[serde(default = "serde_default_min_opacity")]
+    pub min_opacity: f32,
+    // How long a border takes to fade all the way in or out (in milliseconds)
+    #[serde(default = "serde_default_u64::<150>")]
+    pub fade_duration: u64,
+}
+
+impl Default for ProximityRevealConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            distance: 120,
+            min_opacity: serde_default_min_opacity(),
+            fade_duration: 150,
+        }
+    }
+}
+
+fn serde_default_min_opacity() -> f32 {
+    0.05
+}
+
+// The tracker stops by itself once proximity_reveal gets disabled in the config. Disabling it
+// reloads the borders anyways, so they come back fully visible.
+pub fn start_tracker_if_enabled(config: &Config) {
+    if !config.proximity_reveal.enabled || IS_TRACKER_RUNNING.swap(true, Ordering::SeqCst) {
+        return;
+    }
+
+    debug!("starting proximity tracker");
+
+    let _ = thread::spawn(|| {
+        // The opacity that we last sent to each border, keyed by the border's hwnd
+        let mut opacities: HashMap<isize, f32> = HashMap::new();
+        let mut last_poll = time::Instant::now();
+        let mut is_fading = false;
+
+        loop {
+            thread::sleep(match is_fading {
+                true => FADE_POLL_INTERVAL,
+                false => IDLE_POLL_INTERVAL,
+            });
+            let elapsed = last_poll.elapsed();
+            last_poll = time::Instant::now();
+
+            let proximity_reveal = APP_STATE.config.read().unwrap().proximity_reveal.clone();
+            if !proximity_reveal.enabled {
+                debug!("stopping proximity tracker");
+                IS_TRACKER_RUNNING.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            let mut cursor = POINT::default();
+            if unsafe { GetCursorPos(&mut cursor) }.is_err() {
+                continue;
+            }
+
+            let borders: Vec<(isize, isize)> = APP_STATE
+                .borders
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(tracking, border)| (*tracking, *border))
+                .collect();
+            opacities.retain(|border, _| borders.iter().any(|(_, other)| other == border));
+
+            // How far the opacity can move during this poll
+            let max_step = match proximity_reveal.fade_duration {
+                0 => 1.0,
+                fade_duration => elapsed.as_secs_f32() * 1000.0 / fade_duration as f32,
+            };
+
+            is_fading = false;
+            for (tracking_isize, border_isize) in borders {
+                let mut rect = RECT::default();
+                if unsafe { GetWindowRect(HWND(tracking_isize as _), &mut rect) }.is_err() {
+                    continue;
+                }
+
+                let target = get_target_opacity(&proximity_reveal, &rect, cursor);
+                let opacity = match opacities.get(&border_isize) {
+                    Some(&opacity) => opacity + (target - opacity).clamp(-max_step, max_step),
+                    // New borders start out where they should be without fading
+                    None => target,
+                };
+                if opacities.get(&border_isize) == Some(&opacity) {
+                    continue;
+                }
+
+                is_fading |= opacity != target;
+                opacities.insert(border_isize, opacity);
+                post_message_w(
+                    Some(HWND(border_isize as _)),
+                    WM_APP_PROXIMITY,
+                    WPARAM((opacity * OPACITY_SCALE).round() as usize),
+                    LPARAM(0),
+                )
+                .context("could not post WM_APP_PROXIMITY message")
+                .log_if_err();
+            }
+        }
+    });
+}
+
+fn get_target_opacity(proximity_reveal: &ProximityRevealConfig, rect: &RECT, cursor: POINT) -> f32 {
+    let min_opacity = proximity_reveal.min_opacity.clamp(0.0, 1.0);
+    let distance = proximity_reveal.distance.max(1) as f32;
+
+    // Distance from the cursor to the nearest edge, whether the cursor is inside or outside
+    let (x, y) = (cursor.x, cursor.y);
+    let edge_distance = if x >= rect.left && x < rect.right && y >= rect.top && y < rect.bottom {
+        (x - rect.left)
+            .min(rect.right - x)
+            .min(y - rect.top)
+            .min(rect.bottom - y) as f32
+    } else {
+        let dx = (rect.left - x).max(x - rect.right).max(0) as f32;
+        let dy = (rect.top - y).max(y - rect.bottom).max(0) as f32;
+        dx.hypot(dy)
+    };
+
+    let closeness = (1.0 - edge_distance / distance).clamp(0.0, 1.0);
+    min_opacity + (1.0 - min_opacity) * closeness
+}
@@ -1,7 +1,7 @@
 use anyhow::{Context, anyhow};
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::mem::ManuallyDrop;
-use windows::Win32::Foundation::{FALSE, HWND};
+use windows::Win32::Foundation::{COLORREF, FALSE, HWND};
 use windows::Win32::Graphics::Direct2D::Common::{
     D2D_SIZE_U, D2D1_ALPHA_MODE_PREMULTIPLIED, D2D1_PIXEL_FORMAT,
 };
@@ -10,12 +10,15 @@ use windows::Win32::Graphics::Direct2D::{
     D2D1_BITMAP_PROPERTIES1, D2D1_DEVICE_CONTEXT_OPTIONS_NONE, D2D1_HWND_RENDER_TARGET_PROPERTIES,
     D2D1_PRESENT_OPTIONS_IMMEDIATELY, D2D1_PRESENT_OPTIONS_RETAIN_CONTENTS,
     D2D1_RENDER_TARGET_PROPERTIES, D2D1_RENDER_TARGET_TYPE_DEFAULT, ID2D1Bitmap1,
-    ID2D1DeviceContext, ID2D1HwndRenderTarget,
+    ID2D1DeviceContext, ID2D1HwndRenderTarget, ID2D1RenderTarget,
 };
 use windows::Win32::Graphics::DirectComposition::{
     DCompositionCreateDevice3, IDCompositionDesktopDevice, IDCompositionDevice3,
     IDCompositionTarget, IDCompositionVisual2,
 };
+use windows::Win32::Graphics::Dwm::{
+    DWMWA_BORDER_COLOR, DWMWA_COLOR_DEFAULT, DWMWA_COLOR_NONE, DwmSetWindowAttribute,
+};
 use windows::Win32::Graphics::Dxgi::Common::{
     DXGI_ALPHA_MODE_PREMULTIPLIED, DXGI_FORMAT_B8G8R8A8_UNORM, DXGI_FORMAT_UNKNOWN,
     DXGI_SAMPLE_DESC,
@@ -27,19 +30,30 @@ use windows::Win32::Graphics::Dxgi::{
 };
 use windows::core::Interface;
 
-use crate::APP_STATE;
+use crate::colors::ColorBrush;
+use crate::error::BorderError;
+use crate::{APP_STATE, IS_WINDOWS_11};
 
-#[derive(Debug, Default, Clone, Copy, Deserialize, PartialEq)]
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, PartialEq)]
 pub enum RenderBackendConfig {
     #[default]
+    #[serde(alias = "d2d")]
     V2,
     Legacy,
+    // Colors the tracking window's own DWM border instead of drawing an overlay window. It's much
+    // cheaper, but only supports solid colors and requires Windows 11.
+    #[serde(alias = "native-dwm")]
+    NativeDwm,
+    // Doesn't draw anything at all. This is mostly meant for window rules.
+    #[serde(alias = "none")]
+    None,
 }
 
 #[derive(Debug, Default, Clone)]
 pub enum RenderBackend {
     V2(V2RenderBackend),
     Legacy(LegacyRenderBackend),
+    NativeDwm(NativeDwmRenderBackend),
     #[default]
     None,
 }
@@ -64,24 +78,37 @@ pub struct LegacyRenderBackend {
     pub render_target: ID2D1HwndRenderTarget,
 }
 
+#[derive(Debug, Clone)]
+pub struct NativeDwmRenderBackend {
+    pub tracking_window: HWND,
+}
+
 impl RenderBackendConfig {
     pub fn to_render_backend(
         self,
         width: u32,
         height: u32,
         border_window: HWND,
+        tracking_window: HWND,
         create_extra_bitmaps: bool,
     ) -> anyhow::Result<RenderBackend> {
         match self {
-            RenderBackendConfig::V2 => Ok(RenderBackend::V2(V2RenderBackend::new(
-                width,
-                height,
-                border_window,
-                create_extra_bitmaps,
-            )?)),
-            RenderBackendConfig::Legacy => Ok(RenderBackend::Legacy(LegacyRenderBackend::new(
-                border_window,
-            )?)),
+            RenderBackendConfig::V2 => Ok(RenderBackend::V2(
+                V2RenderBackend::new(width, height, border_window, create_extra_bitmaps).map_err(
+                    |err| {
+                        BorderError::Direct2D("could not initialize direct2d renderer", err.into())
+                    },
+                )?,
+            )),
+            RenderBackendConfig::Legacy => Ok(RenderBackend::Legacy(
+                LegacyRenderBackend::new(border_window).map_err(|err| {
+                    BorderError::Direct2D("could not initialize direct2d renderer", err.into())
+                })?,
+            )),
+            RenderBackendConfig::NativeDwm => Ok(RenderBackend::NativeDwm(
+                NativeDwmRenderBackend::new(tracking_window)?,
+            )),
+            RenderBackendConfig::None => Err(anyhow!("cannot create a None render backend")),
         }
     }
 }
@@ -100,6 +127,8 @@ impl RenderBackend {
             // TODO: We already update/resize the buffers of the Legacy renderer within
             // BorderDrawer::render(), but I might want to move it here instead?
             RenderBackend::Legacy(_) => return Ok(()),
+            // There are no buffers; Windows draws the native border itself
+            RenderBackend::NativeDwm(_) => return Ok(()),
             RenderBackend::None => return Err(anyhow!("render backend is None")),
         }
 
@@ -122,12 +151,67 @@ impl RenderBackend {
 
                 Ok(pixel_size)
             }
+            RenderBackend::NativeDwm(_) => Err(anyhow!("NativeDwm render backend has no size")),
             RenderBackend::None => Err(anyhow!("render backend is None")),
         }
     }
 
+    // NOTE: ID2D1DeviceContext implements From<&ID2D1DeviceContext> for &ID2D1RenderTarget
+    pub fn get_renderer(&self) -> Option<&ID2D1RenderTarget> {
+        match self {
+            RenderBackend::V2(backend) => Some((&backend.d2d_context).into()),
+            RenderBackend::Legacy(backend) => Some((&backend.render_target).into()),
+            RenderBackend::NativeDwm(_) | RenderBackend::None => None,
+        }
+    }
+
     pub fn supports_effects(&self) -> bool {
-        !matches!(self, RenderBackend::Legacy(_) | RenderBackend::None)
+        matches!(self, RenderBackend::V2(_))
+    }
+}
+
+impl NativeDwmRenderBackend {
+    pub fn new(tracking_window: HWND) -> anyhow::Result<Self> {
+        // DWMWA_BORDER_COLOR is only supported on Windows 11
+        if !*IS_WINDOWS_11 {
+            return Err(anyhow!("the NativeDwm render backend requires Windows 11"));
+        }
+
+        Ok(Self { tracking_window })
+    }
+
+    // The native border only supports solid colors, so gradients will just use their first color
+    pub fn set_border_color(&self, color: &ColorBrush) -> windows::core::Result<()> {
+        let colorref = match color.is_none() {
+            true => DWMWA_COLOR_NONE,
+            false => {
+                let color = color.get_primary_color();
+                let r = (color.r * 255.0).round() as u32;
+                let g = (color.g * 255.0).round() as u32;
+                let b = (color.b * 255.0).round() as u32;
+
+                // COLORREF is in 0x00BBGGRR format
+                r | (g << 8) | (b << 16)
+            }
+        };
+
+        self.set_border_colorref(COLORREF(colorref))
+    }
+
+    // Gives the native border back to Windows
+    pub fn reset(&self) -> windows::core::Result<()> {
+        self.set_border_colorref(COLORREF(DWMWA_COLOR_DEFAULT))
+    }
+
+    fn set_border_colorref(&self, colorref: COLORREF) -> windows::core::Result<()> {
+        unsafe {
+            DwmSetWindowAttribute(
+                self.tracking_window,
+                DWMWA_BORDER_COLOR,
+                &colorref as *const _ as _,
+                size_of::<COLORREF>() as u32,
+            )
+        }
     }
 }
 
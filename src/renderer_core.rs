@@ -0,0 +1,122 @@
+// Owns the Direct2D factory and the DirectX devices that are shared by every border.
+//
+// Each border runs on its own thread, so anything in here is used from many threads at once. The
+// rules are:
+//   - RenderFactory and DirectXDevices are the only rendering objects that may cross threads.
+//     They're marked Send + Sync below, and nothing else is. windows-rs doesn't implement Send or
+//     Sync for Direct2D/DXGI interfaces, so the compiler keeps everything else on its thread.
+//   - Everything created from them (device contexts, render targets, brushes, swap chains, etc.)
+//     belongs to the border thread that created it and must be dropped on that thread.
+//
+// This only holds up because the factory is created with D2D1_FACTORY_TYPE_MULTI_THREADED, which
+// makes Direct2D serialize access to the factory and to the D3D11 device underneath it.
+use anyhow::{Context, anyhow};
+use std::ops::Deref;
+use windows::Win32::Foundation::HMODULE;
+use windows::Win32::Graphics::Direct2D::{
+    D2D1_FACTORY_TYPE_MULTI_THREADED, D2D1CreateFactory, ID2D1Device, ID2D1Factory1,
+    ID2D1Multithread,
+};
+use windows::Win32::Graphics::Direct3D::{
+    D3D_DRIVER_TYPE_HARDWARE, D3D_FEATURE_LEVEL, D3D_FEATURE_LEVEL_9_1, D3D_FEATURE_LEVEL_9_2,
+    D3D_FEATURE_LEVEL_9_3, D3D_FEATURE_LEVEL_10_0, D3D_FEATURE_LEVEL_10_1, D3D_FEATURE_LEVEL_11_0,
+    D3D_FEATURE_LEVEL_11_1,
+};
+use windows::Win32::Graphics::Direct3D11::{
+    D3D11_CREATE_DEVICE_BGRA_SUPPORT, D3D11_SDK_VERSION, D3D11CreateDevice, ID3D11Device,
+};
+use windows::Win32::Graphics::Dxgi::IDXGIDevice;
+use windows::core::Interface;
+
+pub struct RenderFactory(ID2D1Factory1);
+
+// SAFETY: the factory is multi-threaded (checked in new()), so Direct2D handles the locking
+unsafe impl Send for RenderFactory {}
+unsafe impl Sync for RenderFactory {}
+
+impl RenderFactory {
+    pub fn new() -> anyhow::Result<Self> {
+        let factory: ID2D1Factory1 =
+            unsafe { D2D1CreateFactory(D2D1_FACTORY_TYPE_MULTI_THREADED, None) }
+                .context("could not create ID2D1Factory")?;
+
+        // This should never fail, but everything else in this module depends on it
+        let is_multithread_protected = unsafe {
+            factory
+                .cast::<ID2D1Multithread>()
+                .context("could not get ID2D1Multithread")?
+                .GetMultithreadProtected()
+        };
+        if !is_multithread_protected.as_bool() {
+            return Err(anyhow!("ID2D1Factory is not multithread protected"));
+        }
+
+        Ok(Self(factory))
+    }
+}
+
+impl Deref for RenderFactory {
+    type Target = ID2D1Factory1;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+pub struct DirectXDevices {
+    pub d3d11_device: ID3D11Device,
+    pub dxgi_device: IDXGIDevice,
+    pub d2d_device: ID2D1Device,
+}
+
+// SAFETY: D3D11 devices are thread-safe as long as they aren't created with
+// D3D11_CREATE_DEVICE_SINGLETHREADED, and the D2D device inherits the factory's locking. We never
+// touch the D3D11 immediate context ourselves; Direct2D does that while holding its lock.
+unsafe impl Send for DirectXDevices {}
+unsafe impl Sync for DirectXDevices {}
+
+impl DirectXDevices {
+    pub fn new(factory: &RenderFactory) -> anyhow::Result<Self> {
+        // NOTE: don't add D3D11_CREATE_DEVICE_SINGLETHREADED here (see the SAFETY comment above)
+        let creation_flags = D3D11_CREATE_DEVICE_BGRA_SUPPORT;
+
+        let feature_levels = [
+            D3D_FEATURE_LEVEL_11_1,
+            D3D_FEATURE_LEVEL_11_0,
+            D3D_FEATURE_LEVEL_10_1,
+            D3D_FEATURE_LEVEL_10_0,
+            D3D_FEATURE_LEVEL_9_3,
+            D3D_FEATURE_LEVEL_9_2,
+            D3D_FEATURE_LEVEL_9_1,
+        ];
+
+        let mut device_opt: Option<ID3D11Device> = None;
+        let mut feature_level: D3D_FEATURE_LEVEL = D3D_FEATURE_LEVEL::default();
+
+        unsafe {
+            D3D11CreateDevice(
+                None,
+                D3D_DRIVER_TYPE_HARDWARE,
+                HMODULE::default(),
+                creation_flags,
+                Some(&feature_levels),
+                D3D11_SDK_VERSION,
+                Some(&mut device_opt),
+                Some(&mut feature_level),
+                None,
+            )
+        }?;
+
+        debug!("directx feature_level: {feature_level:X?}");
+
+        let d3d11_device = device_opt.context("could not get d3d11_device")?;
+        let dxgi_device = d3d11_device.cast().context("dxgi_device")?;
+        let d2d_device = unsafe { factory.CreateDevice(&dxgi_device) }.context("d2d_device")?;
+
+        Ok(Self {
+            d3d11_device,
+            dxgi_device,
+            d2d_device,
+        })
+    }
+}
@@ -0,0 +1,333 @@
+// Builds the zip for --report, which has everything we usually end up asking for in bug reports:
+// the version, OS build, and GPUs, the config, the log, and a table of every visible window along
+// with the rule that matched it and whether it should have a border. Unlike the diagnostic snapshot,
+// this runs in its own process, so it works even if tacky-borders itself won't start.
+use anyhow::Context;
+use regex::Regex;
+use std::fmt::Write;
+use std::fs::{self, DirBuilder};
+use std::path::PathBuf;
+use windows::Win32::Foundation::{HWND, LPARAM, TRUE};
+use windows::Win32::Graphics::Dxgi::{
+    CreateDXGIFactory1, DXGI_ADAPTER_FLAG_SOFTWARE, IDXGIFactory1,
+};
+use windows::Win32::System::Registry::{
+    HKEY_LOCAL_MACHINE, RRF_RT_REG_DWORD, RRF_RT_REG_SZ, RegGetValueW,
+};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+use windows::Win32::UI::WindowsAndMessaging::EnumWindows;
+use windows::core::{BOOL, PCWSTR, w};
+
+use crate::border_limit::get_max_borders;
+use crate::border_props::is_border_attached;
+use crate::config::{Config, EnableMode};
+use crate::snapshot::create_zip;
+use crate::utils::{
+    get_window_class, get_window_process_name, get_window_rule, get_window_title_for_display,
+    has_filtered_style, is_window_cloaked, is_window_top_level, is_window_visible,
+};
+use crate::{IS_WINDOWS_11, OS_BUILD};
+
+const CURRENT_VERSION_KEY: PCWSTR = w!(r"SOFTWARE\Microsoft\Windows NT\CurrentVersion");
+// The end of the log is what matters, and whole logs can get too big to attach to an issue
+const MAX_LOG_LINES: usize = 2000;
+
+pub fn create_report() -> anyhow::Result<PathBuf> {
+    let config_dir = Config::get_dir()?;
+
+    // Missing files just get left out, since a broken config or log is often why there's a report
+    let config = fs::read_to_string(config_dir.join("config.yaml"))
+        .map(|config| sanitize(&config))
+        .unwrap_or_else(|err| format!("# could not read config.yaml: {err}\n"));
+    let logs = fs::read_to_string(config_dir.join("tacky-borders.log"))
+        .map(|logs| sanitize(&get_log_tail(&logs, MAX_LOG_LINES)))
+        .unwrap_or_else(|err| format!("could not read tacky-borders.log: {err}\r\n"));
+
+    let zip = create_zip(&[
+        ("system.txt", get_system_info().as_bytes()),
+        ("config.yaml", config.as_bytes()),
+        ("tacky-borders.log", logs.as_bytes()),
+        ("windows.txt", get_window_audit().as_bytes()),
+    ]);
+
+    let report_dir = config_dir.join("reports");
+    if !report_dir.exists() {
+        DirBuilder::new()
+            .recursive(true)
+            .create(&report_dir)
+            .context("could not create reports directory")?;
+    }
+
+    let time = unsafe { GetLocalTime() };
+    let report_path = report_dir.join(format!(
+        "report-{:04}{:02}{:02}-{:02}{:02}{:02}.zip",
+        time.wYear, time.wMonth, time.wDay, time.wHour, time.wMinute, time.wSecond
+    ));
+    fs::write(&report_path, zip).context("could not write report")?;
+
+    Ok(report_path)
+}
+
+// Paths in the config and log usually contain the user's name, which has no business in an issue
+fn sanitize(text: &str) -> String {
+    let mut text = text.to_string();
+
+    if let Some(home_dir) = dirs::home_dir()
+        && let Some(home_dir) = home_dir.to_str()
+        && !home_dir.is_empty()
+    {
+        text = text.replace(home_dir, "~");
+    }
+
+    if let Ok(user_name) = std::env::var("USERNAME")
+        && !user_name.is_empty()
+    {
+        text = redact_user_name(&text, &user_name);
+    }
+
+    text
+}
+
+// Only redacts the name where it's a folder under Users, since short names like "win" or "Al" would
+// otherwise mangle unrelated text
+fn redact_user_name(text: &str, user_name: &str) -> String {
+    let pattern = format!(
+        r#"(?i)([\\/]+Users[\\/]+){}([\\/"'\s]|$)"#,
+        regex::escape(user_name)
+    );
+    let Ok(regex) = Regex::new(&pattern) else {
+        return text.to_string();
+    };
+
+    regex.replace_all(text, "${1}<user>${2}").into_owned()
+}
+
+fn get_log_tail(logs: &str, max_lines: usize) -> String {
+    let lines: Vec<&str> = logs.lines().collect();
+    if lines.len() <= max_lines {
+        return logs.to_string();
+    }
+
+    let skipped = lines.len() - max_lines;
+    format!(
+        "[{skipped} older lines left out]\r\n{}\r\n",
+        lines[skipped..].join("\r\n")
+    )
+}
+
+fn get_system_info() -> String {
+    let mut info = format!("tacky-borders {}\n\n", env!("CARGO_PKG_VERSION"));
+
+    let _ = writeln!(
+        info,
+        "Windows {} (build {}.{}, version {})",
+        match *IS_WINDOWS_11 {
+            true => 11,
+            false => 10,
+        },
+        *OS_BUILD,
+        get_registry_dword(CURRENT_VERSION_KEY, w!("UBR")).unwrap_or_default(),
+        get_registry_string(CURRENT_VERSION_KEY, w!("DisplayVersion")).unwrap_or_default(),
+    );
+
+    info += "\nGPUs:\n";
+    match get_gpus() {
+        Ok(gpus) => gpus.iter().for_each(|gpu| {
+            let _ = writeln!(info, "  - {gpu}");
+        }),
+        Err(err) => {
+            let _ = writeln!(info, "  could not enumerate GPUs: {err:#}");
+        }
+    }
+
+    info
+}
+
+fn get_gpus() -> anyhow::Result<Vec<String>> {
+    let factory: IDXGIFactory1 = unsafe { CreateDXGIFactory1() }.context("dxgi_factory")?;
+    let mut gpus = Vec::new();
+
+    // EnumAdapters1 returns DXGI_ERROR_NOT_FOUND once we run out of adapters
+    while let Ok(adapter) = unsafe { factory.EnumAdapters1(gpus.len() as u32) } {
+        let desc = unsafe { adapter.GetDesc1() }.context("adapter desc")?;
+        let len = desc.Description.iter().position(|&c| c == 0);
+        let name = String::from_utf16_lossy(&desc.Description[..len.unwrap_or(128)]);
+
+        gpus.push(format!(
+            "{name} (vendor {:#06x}, device {:#06x}, {} MB dedicated{})",
+            desc.VendorId,
+            desc.DeviceId,
+            desc.DedicatedVideoMemory / (1024 * 1024),
+            match desc.Flags & DXGI_ADAPTER_FLAG_SOFTWARE.0 as u32 != 0 {
+                true => ", software",
+                false => "",
+            }
+        ));
+    }
+
+    Ok(gpus)
+}
+
+fn get_registry_string(key: PCWSTR, value: PCWSTR) -> Option<String> {
+    let mut buffer = [0u16; 256];
+    let mut size = size_of_val(&buffer) as u32;
+    unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_SZ,
+            None,
+            Some(buffer.as_mut_ptr() as _),
+            Some(&mut size),
+        )
+    }
+    .ok()
+    .ok()?;
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    Some(String::from_utf16_lossy(&buffer[..len]))
+}
+
+fn get_registry_dword(key: PCWSTR, value: PCWSTR) -> Option<u32> {
+    let mut dword = 0u32;
+    let mut size = size_of::<u32>() as u32;
+    unsafe {
+        RegGetValueW(
+            HKEY_LOCAL_MACHINE,
+            key,
+            value,
+            RRF_RT_REG_DWORD,
+            None,
+            Some(&mut dword as *mut u32 as _),
+            Some(&mut size),
+        )
+    }
+    .ok()
+    .ok()?;
+
+    Some(dword)
+}
+
+// One line per visible top-level window, mirroring the checks in create_borders_callback()
+fn get_window_audit() -> String {
+    let mut hwnds: Vec<HWND> = Vec::new();
+    if let Err(err) = unsafe {
+        EnumWindows(
+            Some(collect_windows_callback),
+            LPARAM(&mut hwnds as *mut Vec<HWND> as isize),
+        )
+    } {
+        return format!("could not enumerate windows: {err}\n");
+    }
+
+    let max_borders = get_max_borders();
+    let mut rows = Vec::new();
+    for hwnd in hwnds {
+        let class = get_window_class(hwnd).unwrap_or_default();
+        // Skip our own border windows
+        if class == "border" {
+            continue;
+        }
+
+        let window_rule = get_window_rule(hwnd);
+        let expected = if is_window_cloaked(hwnd) {
+            "no (cloaked)"
+        } else if window_rule.enabled == Some(EnableMode::Bool(false))
+            && window_rule.id.as_deref() == Some("desktop_widgets")
+        {
+            "no (desktop_widgets disabled)"
+        } else if window_rule.enabled == Some(EnableMode::Bool(false)) {
+            "no (disabled by rule)"
+        } else if window_rule.enabled == Some(EnableMode::Bool(true)) || !has_filtered_style(hwnd) {
+            "yes"
+        } else {
+            "no (filtered style)"
+        };
+        let rule = match window_rule.kind {
+            Some(_) => window_rule.display_name(),
+            None => "-".to_string(),
+        };
+
+        rows.push((hwnd, expected, rule, class));
+    }
+
+    // With max_borders, only the most recently focused windows get a border, and we can't tell
+    // which ones those are from here
+    let expected_count = rows.iter().filter(|row| row.1 == "yes").count();
+    let is_over_max_borders = max_borders.is_some_and(|max_borders| expected_count > max_borders);
+
+    let mut audit = String::from("hwnd | attached | expected | rule | process | class | title\n");
+    for (hwnd, expected, rule, class) in rows {
+        let expected = match expected == "yes" && is_over_max_borders {
+            true => "maybe (max_borders)",
+            false => expected,
+        };
+
+        // Titles are always hashed here since the report is meant to be shared
+        let _ = writeln!(
+            audit,
+            "{:#x} | {} | {expected} | {rule} | {} | {class} | {}",
+            hwnd.0 as isize,
+            match is_border_attached(hwnd) {
+                true => "yes",
+                false => "no",
+            },
+            get_window_process_name(hwnd).unwrap_or_default(),
+            get_window_title_for_display(hwnd, true),
+        );
+    }
+
+    if let Some(max_borders) = max_borders.filter(|_| is_over_max_borders) {
+        let _ = writeln!(
+            audit,
+            "{expected_count} windows could get a border, but max_borders is {max_borders}"
+        );
+    }
+
+    audit
+}
+
+unsafe extern "system" fn collect_windows_callback(hwnd: HWND, lparam: LPARAM) -> BOOL {
+    if is_window_top_level(hwnd) && is_window_visible(hwnd) {
+        let hwnds = unsafe { &mut *(lparam.0 as *mut Vec<HWND>) };
+        hwnds.push(hwnd);
+    }
+
+    TRUE
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redact_user_name() {
+        assert_eq!(
+            redact_user_name(
+                r"loaded C:\Users\al\.config\tacky-borders\config.yaml",
+                "al"
+            ),
+            r"loaded C:\Users\<user>\.config\tacky-borders\config.yaml"
+        );
+        assert_eq!(
+            redact_user_name(r#"path: "C:\\users\\Al""#, "al"),
+            r#"path: "C:\\users\\<user>""#
+        );
+
+        // Outside of a path (or as part of a longer name), the name is left alone
+        assert_eq!(
+            redact_user_name(r"window: Calculator (C:\Users\alex)", "al"),
+            r"window: Calculator (C:\Users\alex)"
+        );
+    }
+
+    #[test]
+    fn test_get_log_tail() {
+        assert_eq!(get_log_tail("a\r\nb\r\n", 2), "a\r\nb\r\n");
+        assert_eq!(
+            get_log_tail("a\r\nb\r\nc\r\n", 2),
+            "[1 older lines left out]\r\nb\r\nc\r\n"
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use windows::Win32::Foundation::{CloseHandle, FILETIME, HANDLE, HWND, LPARAM, WPARAM};
+use windows::Win32::System::ProcessStatus::{
+    GetProcessMemoryInfo, PROCESS_MEMORY_COUNTERS, PROCESS_MEMORY_COUNTERS_EX,
+};
+use windows::Win32::System::Threading::{
+    GetProcessTimes, OpenProcess, PROCESS_QUERY_LIMITED_INFORMATION, PROCESS_VM_READ,
+};
+use windows::Win32::UI::WindowsAndMessaging::GetWindowThreadProcessId;
+
+use crate::config::ResourceThresholdConfig;
+use crate::post_message_w;
+use crate::utils::WM_APP_RESOURCES;
+
+// Periodically samples the CPU and memory usage of the tracking window's process, and notifies the
+// border window whenever a different threshold gets exceeded. The index of the exceeded threshold
+// is sent in the WPARAM (offset by 1, so 0 means that no threshold is exceeded).
+#[derive(Debug, Clone)]
+pub struct ResourceSampler {
+    stop_flag: Arc<Mutex<bool>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct ResourceUsage {
+    cpu_percent: f32,
+    memory_mb: f64,
+}
+
+impl ResourceSampler {
+    pub fn start(
+        border_window: HWND,
+        tracking_window: HWND,
+        thresholds: Vec<ResourceThresholdConfig>,
+        interval_ms: u64,
+    ) -> Self {
+        let stop_flag = Arc::new(Mutex::new(false));
+        let stop_flag_clone = stop_flag.clone();
+
+        // Convert the hwnds to isizes so we can pass them into the thread
+        let border_isize = border_window.0 as isize;
+        let tracking_isize = tracking_window.0 as isize;
+
+        thread::spawn(move || {
+            let border_window = HWND(border_isize as _);
+            let tracking_window = HWND(tracking_isize as _);
+            let interval = Duration::from_millis(interval_ms);
+
+            let mut process_id = 0;
+            unsafe { GetWindowThreadProcessId(tracking_window, Some(&mut process_id)) };
+
+            let hprocess = match unsafe {
+                OpenProcess(
+                    PROCESS_QUERY_LIMITED_INFORMATION | PROCESS_VM_READ,
+                    false,
+                    process_id,
+                )
+            } {
+                Ok(hprocess) => hprocess,
+                Err(err) => {
+                    error!(
+                        "could not open process for resource sampling of {tracking_window:?}: {err}"
+                    );
+                    return;
+                }
+            };
+
+            let num_cpus = thread::available_parallelism()
+                .map(|n| n.get())
+                .unwrap_or(1) as f32;
+
+            let mut last_level = 0;
+            let mut last_sample: Option<(u64, Instant)> = None;
+
+            while !*stop_flag_clone.lock().unwrap() {
+                let Some(cpu_time) = get_process_cpu_time(hprocess) else {
+                    break;
+                };
+                let now = Instant::now();
+
+                // We need two samples before we can calculate the CPU usage
+                if let Some((last_cpu_time, last_instant)) = last_sample {
+                    // CPU times are measured in 100-nanosecond intervals
+                    let cpu_elapsed = cpu_time.saturating_sub(last_cpu_time) as f64 / 10_000_000.0;
+                    let wall_elapsed = now.duration_since(last_instant).as_secs_f64();
+
+                    let usage = ResourceUsage {
+                        cpu_percent: (cpu_elapsed / wall_elapsed * 100.0) as f32 / num_cpus,
+                        memory_mb: get_process_memory_mb(hprocess).unwrap_or_default(),
+                    };
+
+                    // The last exceeded threshold wins, so users should order them by severity
+                    let level = thresholds
+                        .iter()
+                        .rposition(|threshold| is_threshold_exceeded(threshold, &usage))
+                        .map(|index| index + 1)
+                        .unwrap_or(0);
+
+                    if level != last_level {
+                        debug!("resource usage for {tracking_window:?} is now {usage:?}");
+
+                        if let Err(err) = post_message_w(
+                            Some(border_window),
+                            WM_APP_RESOURCES,
+                            WPARAM(level),
+                            LPARAM(0),
+                        ) {
+                            error!(
+                                "could not send resource usage message for {border_window:?}: {err}"
+                            );
+                            break;
+                        }
+                        last_level = level;
+                    }
+                }
+
+                last_sample = Some((cpu_time, now));
+                thread::sleep(interval);
+            }
+
+            let _ = unsafe { CloseHandle(hprocess) };
+        });
+
+        Self { stop_flag }
+    }
+
+    pub fn stop(&mut self) {
+        if let Ok(mut stop_flag) = self.stop_flag.lock() {
+            *stop_flag = true;
+        }
+    }
+}
+
+fn is_threshold_exceeded(threshold: &ResourceThresholdConfig, usage: &ResourceUsage) -> bool {
+    // Thresholds without any limits never get exceeded
+    if threshold.cpu.is_none() && threshold.memory.is_none() {
+        return false;
+    }
+
+    threshold.cpu.is_none_or(|cpu| usage.cpu_percent > cpu)
+        && threshold
+            .memory
+            .is_none_or(|memory| usage.memory_mb > memory)
+}
+
+// Returns the total kernel + user time of the process in 100-nanosecond intervals
+fn get_process_cpu_time(hprocess: HANDLE) -> Option<u64> {
+    let mut creation_time = FILETIME::default();
+    let mut exit_time = FILETIME::default();
+    let mut kernel_time = FILETIME::default();
+    let mut user_time = FILETIME::default();
+
+    unsafe {
+        GetProcessTimes(
+            hprocess,
+            &mut creation_time,
+            &mut exit_time,
+            &mut kernel_time,
+            &mut user_time,
+        )
+    }
+    .inspect_err(|err| debug!("could not get process times: {err}"))
+    .ok()?;
+
+    let filetime_to_u64 =
+        |ft: FILETIME| ((ft.dwHighDateTime as u64) << 32) | ft.dwLowDateTime as u64;
+
+    Some(filetime_to_u64(kernel_time) + filetime_to_u64(user_time))
+}
+
+pub fn get_process_memory_mb(hprocess: HANDLE) -> Option<f64> {
+    let mut counters = PROCESS_MEMORY_COUNTERS {
+        cb: size_of::<PROCESS_MEMORY_COUNTERS>() as u32,
+        ..Default::default()
+    };
+
+    unsafe { GetProcessMemoryInfo(hprocess, &mut counters, counters.cb) }
+        .inspect_err(|err| debug!("could not get process memory info: {err}"))
+        .ok()?;
+
+    Some(counters.WorkingSetSize as f64 / (1024.0 * 1024.0))
+}
+
+// Unlike the working set, private bytes don't drop just because Windows paged some memory out, so
+// this is what tells us whether memory was actually freed.
+pub fn get_process_private_mb(hprocess: HANDLE) -> Option<f64> {
+    let mut counters = PROCESS_MEMORY_COUNTERS_EX {
+        cb: size_of::<PROCESS_MEMORY_COUNTERS_EX>() as u32,
+        ..Default::default()
+    };
+
+    unsafe {
+        GetProcessMemoryInfo(
+            hprocess,
+            &mut counters as *mut PROCESS_MEMORY_COUNTERS_EX as *mut PROCESS_MEMORY_COUNTERS,
+            counters.cb,
+        )
+    }
+    .inspect_err(|err| debug!("could not get process memory info: {err}"))
+    .ok()?;
+
+    Some(counters.PrivateUsage as f64 / (1024.0 * 1024.0))
+}
@@ -0,0 +1,225 @@
+// "Create Rule from Window" in the tray. We cover the screen with a nearly invisible window that
+// shows a crosshair, and whichever window the user clicks gets a window rule at the top of the
+// config's window_rules. The config is then opened so the user can review and fill in the rule.
+use anyhow::{Context, anyhow};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicIsize, Ordering};
+use std::{fs, thread};
+use windows::Win32::Foundation::{
+    COLORREF, ERROR_CLASS_ALREADY_EXISTS, HWND, LPARAM, LRESULT, POINT, WPARAM,
+};
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
+use windows::Win32::UI::WindowsAndMessaging::{
+    CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW, GA_ROOT, GetAncestor,
+    GetCursorPos, GetMessageW, GetSystemMetrics, IDC_CROSS, LWA_ALPHA, LoadCursorW, MSG,
+    PostQuitMessage, RegisterClassExW, SM_CXVIRTUALSCREEN, SM_CYVIRTUALSCREEN, SM_XVIRTUALSCREEN,
+    SM_YVIRTUALSCREEN, SetForegroundWindow, SetLayeredWindowAttributes, TranslateMessage,
+    WM_DESTROY, WM_KEYDOWN, WM_LBUTTONDOWN, WM_RBUTTONDOWN, WNDCLASSEXW, WS_EX_LAYERED,
+    WS_EX_TOOLWINDOW, WS_EX_TOPMOST, WS_POPUP, WS_VISIBLE, WindowFromPoint,
+};
+use windows::core::w;
+
+use crate::APP_STATE;
+use crate::config::Config;
+use crate::utils::{LogIfErr, get_last_error, get_window_class, get_window_process_name};
+
+// This lives in Win32_UI_Input_KeyboardAndMouse, which we don't otherwise need
+const VK_ESCAPE: usize = 0x1B;
+
+// Only one picker is open at a time
+static PICKER_WINDOW: AtomicIsize = AtomicIsize::new(0);
+
+pub fn start_rule_picker() {
+    if PICKER_WINDOW.load(Ordering::SeqCst) != 0 {
+        return;
+    }
+
+    if APP_STATE.config.read().unwrap().is_read_only() {
+        warn!("cannot create window rules in read-only mode");
+        return;
+    }
+
+    info!("click the window to create a rule for (right-click or escape to cancel)");
+
+    // Like the diagnostics window, the picker gets its own thread and message loop
+    let _ = thread::spawn(|| run_picker_window().log_if_err());
+}
+
+fn run_picker_window() -> anyhow::Result<()> {
+    unsafe {
+        let hinstance = GetModuleHandleW(None)?;
+
+        let window_class = WNDCLASSEXW {
+            cbSize: size_of::<WNDCLASSEXW>() as u32,
+            lpfnWndProc: Some(picker_wnd_proc),
+            hInstance: hinstance.into(),
+            lpszClassName: w!("tacky-rule-picker"),
+            hCursor: LoadCursorW(None, IDC_CROSS)?,
+            ..Default::default()
+        };
+        if RegisterClassExW(&window_class) == 0 {
+            let last_error = get_last_error();
+            if last_error != ERROR_CLASS_ALREADY_EXISTS {
+                return Err(anyhow!(
+                    "could not register rule picker window class: {last_error:?}"
+                ));
+            }
+        }
+
+        // Cover every monitor
+        let hwnd = CreateWindowExW(
+            WS_EX_LAYERED | WS_EX_TOPMOST | WS_EX_TOOLWINDOW,
+            w!("tacky-rule-picker"),
+            w!("tacky-borders rule picker"),
+            WS_POPUP | WS_VISIBLE,
+            GetSystemMetrics(SM_XVIRTUALSCREEN),
+            GetSystemMetrics(SM_YVIRTUALSCREEN),
+            GetSystemMetrics(SM_CXVIRTUALSCREEN),
+            GetSystemMetrics(SM_CYVIRTUALSCREEN),
+            None,
+            None,
+            Some(hinstance.into()),
+            None,
+        )
+        .context("could not create rule picker window")?;
+        PICKER_WINDOW.store(hwnd.0 as isize, Ordering::SeqCst);
+
+        // Fully transparent windows don't get mouse input, so this is as invisible as it gets
+        SetLayeredWindowAttributes(hwnd, COLORREF(0), 1, LWA_ALPHA)
+            .context("could not make rule picker transparent")?;
+        let _ = SetForegroundWindow(hwnd);
+
+        let mut message = MSG::default();
+        while GetMessageW(&mut message, None, 0, 0).into() {
+            let _ = TranslateMessage(&message);
+            DispatchMessageW(&message);
+        }
+    }
+
+    Ok(())
+}
+
+unsafe extern "system" fn picker_wnd_proc(
+    hwnd: HWND,
+    message: u32,
+    wparam: WPARAM,
+    lparam: LPARAM,
+) -> LRESULT {
+    match message {
+        WM_LBUTTONDOWN => unsafe {
+            let mut point = POINT::default();
+            let cursor_res = GetCursorPos(&mut point);
+
+            // Get the picker out of the way first so that it isn't the window under the cursor
+            let _ = DestroyWindow(hwnd);
+
+            if let Err(err) = cursor_res {
+                error!("could not get cursor position for rule picker: {err}");
+                return LRESULT(0);
+            }
+
+            let picked_window = GetAncestor(WindowFromPoint(point), GA_ROOT);
+            match add_rule_for_window(picked_window) {
+                Ok(config_path) => {
+                    info!("added a window rule for {picked_window:?} to the config");
+                    open::that(config_path).log_if_err();
+                }
+                Err(err) => error!("could not create window rule: {err:#}"),
+            }
+        },
+        WM_RBUTTONDOWN => unsafe {
+            info!("rule picker cancelled");
+            let _ = DestroyWindow(hwnd);
+        },
+        WM_KEYDOWN if wparam.0 == VK_ESCAPE => unsafe {
+            info!("rule picker cancelled");
+            let _ = DestroyWindow(hwnd);
+        },
+        WM_DESTROY => {
+            PICKER_WINDOW.store(0, Ordering::SeqCst);
+            unsafe { PostQuitMessage(0) };
+        }
+        _ => return unsafe { DefWindowProcW(hwnd, message, wparam, lparam) },
+    }
+
+    LRESULT(0)
+}
+
+fn add_rule_for_window(hwnd: HWND) -> anyhow::Result<PathBuf> {
+    if hwnd.is_invalid() {
+        return Err(anyhow!("there is no window under the cursor"));
+    }
+
+    let process_name = get_window_process_name(hwnd)?;
+    let class = get_window_class(hwnd).unwrap_or_default();
+
+    // The process is usually what people want to match, but the class is there in case it isn't
+    let rule = [
+        format!(
+            "# Added by \"Create Rule from Window\". To match by class instead, use: match: Class, name: {}",
+            to_yaml_string(&class)
+        ),
+        "- match: Process".to_string(),
+        format!("  name: {}", to_yaml_string(&process_name)),
+        "  enabled: True".to_string(),
+    ];
+
+    let config_path = Config::get_dir()?.join("config.yaml");
+    let contents = fs::read_to_string(&config_path).context("could not read config")?;
+    fs::write(&config_path, insert_window_rule(&contents, &rule)?)
+        .context("could not write config")?;
+
+    Ok(config_path)
+}
+
+fn to_yaml_string(value: &str) -> String {
+    serde_yml::to_string(value)
+        .map(|yaml| yaml.trim_end().to_string())
+        .unwrap_or_else(|_| format!("{value:?}"))
+}
+
+// Puts the rule at the top of window_rules so that it takes priority over the existing rules. We
+// edit the text instead of re-serializing the config so that the user's comments stay intact.
+fn insert_window_rule(contents: &str, rule: &[String]) -> anyhow::Result<String> {
+    let line_ending = match contents.contains("\r\n") {
+        true => "\r\n",
+        false => "\n",
+    };
+    let mut lines: Vec<String> = contents.lines().map(str::to_string).collect();
+
+    let Some(rules_idx) = lines
+        .iter()
+        .position(|line| line.starts_with("window_rules:"))
+    else {
+        // There's no window_rules yet, so add it at the end
+        lines.push("window_rules:".to_string());
+        lines.extend(rule.iter().map(|line| format!("  {line}")));
+        return Ok(lines.join(line_ending) + line_ending);
+    };
+
+    let value = lines[rules_idx]["window_rules:".len()..]
+        .split('#')
+        .next()
+        .unwrap_or_default()
+        .trim();
+    match value {
+        "" => {}
+        "[]" => lines[rules_idx] = "window_rules:".to_string(),
+        _ => return Err(anyhow!("window_rules isn't written as a block list")),
+    }
+
+    // Match the indentation of the existing rules
+    let indent = lines[rules_idx + 1..]
+        .iter()
+        .find(|line| !line.trim().is_empty() && !line.trim_start().starts_with('#'))
+        .filter(|line| line.trim_start().starts_with('-'))
+        .map(|line| line[..line.len() - line.trim_start().len()].to_string())
+        .unwrap_or_else(|| "  ".to_string());
+
+    lines.splice(
+        rules_idx + 1..rules_idx + 1,
+        rule.iter().map(|line| format!("{indent}{line}")),
+    );
+
+    Ok(lines.join(line_ending) + line_ending)
+}
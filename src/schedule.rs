@@ -0,0 +1,181 @@
+use anyhow::{Context, anyhow};
+use serde::{Deserialize, Serialize};
+use std::{thread, time};
+use windows::Win32::Foundation::{HWND, LPARAM, WPARAM};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+
+use crate::colors::ColorBrushConfig;
+use crate::config::Config;
+use crate::utils::{LogIfErr, WM_APP_SCHEDULE, post_message_w};
+use crate::{APP_STATE, reload_borders};
+
+// Colors that only apply during a certain time of day (e.g. warmer colors in the evening)
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleConfig {
+    pub from: TimeOfDay,
+    pub to: TimeOfDay,
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+}
+
+// Minutes since midnight, parsed from "HH:MM" (24-hour time)
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, PartialOrd)]
+#[serde(try_from = "String", into = "String")]
+pub struct TimeOfDay(pub u32);
+
+impl From<TimeOfDay> for String {
+    fn from(value: TimeOfDay) -> Self {
+        format!("{:02}:{:02}", value.0 / 60, value.0 % 60)
+    }
+}
+
+impl TryFrom<String> for TimeOfDay {
+    type Error = anyhow::Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let (hours, minutes) = value
+            .trim()
+            .split_once(':')
+            .context(format!("invalid time '{value}'; expected HH:MM"))?;
+        let hours: u32 = hours
+            .parse()
+            .context(format!("invalid hours in '{value}'"))?;
+        let minutes: u32 = minutes
+            .parse()
+            .context(format!("invalid minutes in '{value}'"))?;
+
+        // We allow 24:00 so that ranges can end at midnight
+        if minutes >= 60 || hours > 24 || (hours == 24 && minutes != 0) {
+            return Err(anyhow!("time '{value}' is out of range"));
+        }
+
+        Ok(TimeOfDay(hours * 60 + minutes))
+    }
+}
+
+impl TimeOfDay {
+    pub fn now() -> Self {
+        let local_time = unsafe { GetLocalTime() };
+        TimeOfDay(local_time.wHour as u32 * 60 + local_time.wMinute as u32)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq)]
+pub enum Weekday {
+    #[serde(alias = "Sun")]
+    Sunday,
+    #[serde(alias = "Mon")]
+    Monday,
+    #[serde(alias = "Tue")]
+    Tuesday,
+    #[serde(alias = "Wed")]
+    Wednesday,
+    #[serde(alias = "Thu")]
+    Thursday,
+    #[serde(alias = "Fri")]
+    Friday,
+    #[serde(alias = "Sat")]
+    Saturday,
+}
+
+impl Weekday {
+    pub fn today() -> Self {
+        // GetLocalTime() counts the days of the week from Sunday
+        match unsafe { GetLocalTime() }.wDayOfWeek {
+            0 => Weekday::Sunday,
+            1 => Weekday::Monday,
+            2 => Weekday::Tuesday,
+            3 => Weekday::Wednesday,
+            4 => Weekday::Thursday,
+            5 => Weekday::Friday,
+            _ => Weekday::Saturday,
+        }
+    }
+}
+
+// Switches to one of the config's named profiles during a certain time of day and/or on certain
+// days of the week. Leaving out 'from' and 'to' means the whole day.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct ProfileScheduleConfig {
+    pub profile: String,
+    #[serde(default)]
+    pub from: TimeOfDay,
+    #[serde(default)]
+    pub to: TimeOfDay,
+    // Leaving this out means every day
+    pub days: Option<Vec<Weekday>>,
+}
+
+impl ProfileScheduleConfig {
+    pub fn contains(&self, day: Weekday, time: TimeOfDay) -> bool {
+        self.days.as_ref().is_none_or(|days| days.contains(&day))
+            && is_time_in_range(self.from, self.to, time)
+    }
+}
+
+impl ScheduleConfig {
+    pub fn contains(&self, time: TimeOfDay) -> bool {
+        is_time_in_range(self.from, self.to, time)
+    }
+}
+
+fn is_time_in_range(from: TimeOfDay, to: TimeOfDay, time: TimeOfDay) -> bool {
+    let (from, to) = (from.0, to.0 % (24 * 60));
+
+    match from.cmp(&to) {
+        std::cmp::Ordering::Less => from <= time.0 && time.0 < to,
+        // The range wraps around midnight (e.g. 20:00 to 07:00)
+        std::cmp::Ordering::Greater => time.0 >= from || time.0 < to,
+        std::cmp::Ordering::Equal => true,
+    }
+}
+
+// Checks the schedules at the start of every minute. When a different profile is scheduled, we
+// switch to it and reload the borders just like when config.yaml changes. Otherwise, we tell the
+// borders to re-resolve their colors whenever a different color schedule becomes active.
+pub fn start_schedule_timer() {
+    let _ = thread::spawn(|| {
+        let mut last_schedule = APP_STATE.config.read().unwrap().get_active_schedule_index();
+
+        loop {
+            let seconds = unsafe { GetLocalTime() }.wSecond as u64;
+            thread::sleep(time::Duration::from_secs(60 - seconds.min(59)));
+
+            let (active_profile, scheduled_profile) = {
+                let config = APP_STATE.config.read().unwrap();
+                (
+                    config.active_profile.clone(),
+                    config.get_scheduled_profile(),
+                )
+            };
+            if active_profile != scheduled_profile {
+                info!("switching from profile {active_profile:?} to {scheduled_profile:?}");
+                Config::switch_profile();
+                reload_borders();
+
+                last_schedule = APP_STATE.config.read().unwrap().get_active_schedule_index();
+                continue;
+            }
+
+            let active_schedule = APP_STATE.config.read().unwrap().get_active_schedule_index();
+            if active_schedule == last_schedule {
+                continue;
+            }
+            last_schedule = active_schedule;
+
+            debug!("color schedule has changed to {active_schedule:?}");
+            for border_isize in APP_STATE.borders.lock().unwrap().values() {
+                post_message_w(
+                    Some(HWND(*border_isize as _)),
+                    WM_APP_SCHEDULE,
+                    WPARAM(0),
+                    LPARAM(0),
+                )
+                .context("could not post WM_APP_SCHEDULE")
+                .log_if_err();
+            }
+        }
+    });
+}
@@ -0,0 +1,280 @@
+// Bundles up what we usually end up asking for in bug reports: a screenshot of the window and its
+// border, the config that actually applies to it, and the recent logs. Everything goes into a
+// single zip in the config directory so it can be dragged straight into an issue.
+use anyhow::{Context, anyhow};
+use log::LevelFilter;
+use std::fs::{self, DirBuilder};
+use std::path::PathBuf;
+use std::{thread, time};
+use windows::Win32::Foundation::{HWND, RECT};
+use windows::Win32::Graphics::Gdi::{
+    BI_RGB, BITMAPINFO, BITMAPINFOHEADER, BitBlt, CAPTUREBLT, CreateCompatibleBitmap,
+    CreateCompatibleDC, DIB_RGB_COLORS, DeleteDC, DeleteObject, GetDC, GetDIBits, ReleaseDC,
+    SRCCOPY, SelectObject,
+};
+use windows::Win32::System::SystemInformation::GetLocalTime;
+use windows::Win32::UI::WindowsAndMessaging::GetWindowRect;
+
+use crate::APP_STATE;
+use crate::config::Config;
+use crate::log_buffer::get_recent_log_lines;
+use crate::utils::{
+    LogIfErr, crc32, get_border_for_window, get_foreground_window, get_window_class,
+    get_window_process_name, get_window_rule, get_window_title_for_display,
+};
+
+// Clicking the tray menu focuses the taskbar, so give the user a moment to focus the window they
+// actually want to capture
+const CAPTURE_DELAY: time::Duration = time::Duration::from_secs(3);
+
+pub fn capture_snapshot_after_delay() {
+    info!(
+        "capturing diagnostic snapshot in {} seconds; focus the window you want to capture",
+        CAPTURE_DELAY.as_secs()
+    );
+
+    let _ = thread::spawn(|| {
+        thread::sleep(CAPTURE_DELAY);
+
+        match capture_snapshot(get_foreground_window()) {
+            Ok(snapshot_path) => {
+                info!("saved diagnostic snapshot to {}", snapshot_path.display());
+                if let Some(dir) = snapshot_path.parent() {
+                    open::that(dir).log_if_err();
+                }
+            }
+            Err(err) => error!("could not capture diagnostic snapshot: {err:#}"),
+        }
+    });
+}
+
+pub fn capture_snapshot(hwnd: HWND) -> anyhow::Result<PathBuf> {
+    if hwnd.is_invalid() {
+        return Err(anyhow!("there is no focused window"));
+    }
+
+    // Capture the tracking window and its border window together, since the border extends past
+    // the tracking window's edges
+    let mut capture_rect = get_rect(hwnd)?;
+    if let Some(border_window) = get_border_for_window(hwnd)
+        && let Ok(border_rect) = get_rect(border_window)
+    {
+        capture_rect = RECT {
+            left: capture_rect.left.min(border_rect.left),
+            top: capture_rect.top.min(border_rect.top),
+            right: capture_rect.right.max(border_rect.right),
+            bottom: capture_rect.bottom.max(border_rect.bottom),
+        };
+    }
+
+    let screenshot = capture_screen_rect(&capture_rect).context("could not take screenshot")?;
+    let effective_config = get_effective_config(hwnd)?;
+    let logs = get_recent_log_lines(LevelFilter::Debug, usize::MAX).join("\r\n");
+
+    let zip = create_zip(&[
+        ("screenshot.bmp", &screenshot),
+        ("effective_config.yaml", effective_config.as_bytes()),
+        ("tacky-borders.log", logs.as_bytes()),
+    ]);
+
+    let snapshot_dir = Config::get_dir()?.join("snapshots");
+    if !snapshot_dir.exists() {
+        DirBuilder::new()
+            .recursive(true)
+            .create(&snapshot_dir)
+            .context("could not create snapshots directory")?;
+    }
+
+    let time = unsafe { GetLocalTime() };
+    let snapshot_path = snapshot_dir.join(format!(
+        "snapshot-{:04}{:02}{:02}-{:02}{:02}{:02}.zip",
+        time.wYear, time.wMonth, time.wDay, time.wHour, time.wMinute, time.wSecond
+    ));
+    fs::write(&snapshot_path, zip).context("could not write snapshot")?;
+
+    Ok(snapshot_path)
+}
+
+fn get_rect(hwnd: HWND) -> anyhow::Result<RECT> {
+    let mut rect = RECT::default();
+    unsafe { GetWindowRect(hwnd, &mut rect) }.context("could not get window rect")?;
+
+    Ok(rect)
+}
+
+fn get_effective_config(hwnd: HWND) -> anyhow::Result<String> {
+    // get_window_rule() locks the config, so call it first
+    let window_rule = get_window_rule(hwnd);
+    let is_private = window_rule.private.unwrap_or(false);
+
+    let mut text = format!(
+        "# title: {}\n# class: {}\n# process: {}\n",
+        get_window_title_for_display(hwnd, is_private),
+        get_window_class(hwnd).unwrap_or_default(),
+        get_window_process_name(hwnd).unwrap_or_default(),
+    );
+
+    let config = APP_STATE.config.read().unwrap();
+    text += "\n# The window rule that matched (if any):\nwindow_rule:\n";
+    text += &indent(&serde_yml::to_string(&window_rule)?);
+    text += "\n# The global settings that the window rule falls back on:\nglobal:\n";
+    text += &indent(&serde_yml::to_string(&config.global)?);
+
+    Ok(text)
+}
+
+fn indent(yaml: &str) -> String {
+    yaml.lines().map(|line| format!("  {line}\n")).collect()
+}
+
+// Returns the screen contents within the given rect as a 32-bit .bmp file
+fn capture_screen_rect(rect: &RECT) -> anyhow::Result<Vec<u8>> {
+    let width = rect.right - rect.left;
+    let height = rect.bottom - rect.top;
+    if width <= 0 || height <= 0 {
+        return Err(anyhow!("window has an empty rect"));
+    }
+
+    let mut bitmap_info = BITMAPINFO {
+        bmiHeader: BITMAPINFOHEADER {
+            biSize: size_of::<BITMAPINFOHEADER>() as u32,
+            biWidth: width,
+            // Negative height means the rows go from top to bottom
+            biHeight: -height,
+            biPlanes: 1,
+            biBitCount: 32,
+            biCompression: BI_RGB.0,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let mut pixels = vec![0u8; (width * height * 4) as usize];
+
+    unsafe {
+        let screen_dc = GetDC(None);
+        let memory_dc = CreateCompatibleDC(Some(screen_dc));
+        let bitmap = CreateCompatibleBitmap(screen_dc, width, height);
+        let old_bitmap = SelectObject(memory_dc, bitmap.into());
+
+        // CAPTUREBLT includes layered windows, which is what the border windows are
+        let blt_res = BitBlt(
+            memory_dc,
+            0,
+            0,
+            width,
+            height,
+            Some(screen_dc),
+            rect.left,
+            rect.top,
+            SRCCOPY | CAPTUREBLT,
+        );
+
+        SelectObject(memory_dc, old_bitmap);
+        let rows = GetDIBits(
+            memory_dc,
+            bitmap,
+            0,
+            height as u32,
+            Some(pixels.as_mut_ptr() as _),
+            &mut bitmap_info,
+            DIB_RGB_COLORS,
+        );
+
+        let _ = DeleteObject(bitmap.into());
+        let _ = DeleteDC(memory_dc);
+        ReleaseDC(None, screen_dc);
+
+        blt_res.context("could not copy screen contents")?;
+        if rows == 0 {
+            return Err(anyhow!("could not read screenshot pixels"));
+        }
+    }
+
+    let header_size = 14 + size_of::<BITMAPINFOHEADER>() as u32;
+    let file_size = header_size + pixels.len() as u32;
+
+    let mut bmp = Vec::with_capacity(file_size as usize);
+    bmp.extend_from_slice(b"BM");
+    bmp.extend_from_slice(&file_size.to_le_bytes());
+    bmp.extend_from_slice(&0u32.to_le_bytes());
+    bmp.extend_from_slice(&header_size.to_le_bytes());
+
+    let header = &bitmap_info.bmiHeader;
+    bmp.extend_from_slice(&header.biSize.to_le_bytes());
+    bmp.extend_from_slice(&header.biWidth.to_le_bytes());
+    bmp.extend_from_slice(&header.biHeight.to_le_bytes());
+    bmp.extend_from_slice(&header.biPlanes.to_le_bytes());
+    bmp.extend_from_slice(&header.biBitCount.to_le_bytes());
+    bmp.extend_from_slice(&header.biCompression.to_le_bytes());
+    bmp.extend_from_slice(&(pixels.len() as u32).to_le_bytes());
+    bmp.extend_from_slice(&header.biXPelsPerMeter.to_le_bytes());
+    bmp.extend_from_slice(&header.biYPelsPerMeter.to_le_bytes());
+    bmp.extend_from_slice(&header.biClrUsed.to_le_bytes());
+    bmp.extend_from_slice(&header.biClrImportant.to_le_bytes());
+    bmp.extend_from_slice(&pixels);
+
+    Ok(bmp)
+}
+
+// Writes an uncompressed ("stored") zip archive. The files are small enough that compression isn't
+// worth pulling in another dependency for.
+pub fn create_zip(files: &[(&str, &[u8])]) -> Vec<u8> {
+    let mut zip = Vec::new();
+    let mut central_directory = Vec::new();
+
+    for (name, data) in files {
+        let offset = zip.len() as u32;
+        let crc = crc32(data);
+        let size = data.len() as u32;
+
+        // Local file header
+        zip.extend_from_slice(&0x0403_4B50u32.to_le_bytes());
+        zip.extend_from_slice(&20u16.to_le_bytes()); // Version needed to extract
+        zip.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        zip.extend_from_slice(&0u16.to_le_bytes()); // Compression method (stored)
+        zip.extend_from_slice(&0u16.to_le_bytes()); // Modification time
+        zip.extend_from_slice(&0x21u16.to_le_bytes()); // Modification date (1980-01-01)
+        zip.extend_from_slice(&crc.to_le_bytes());
+        zip.extend_from_slice(&size.to_le_bytes()); // Compressed size
+        zip.extend_from_slice(&size.to_le_bytes()); // Uncompressed size
+        zip.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        zip.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        zip.extend_from_slice(name.as_bytes());
+        zip.extend_from_slice(data);
+
+        // Central directory entry
+        central_directory.extend_from_slice(&0x0201_4B50u32.to_le_bytes());
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version made by
+        central_directory.extend_from_slice(&20u16.to_le_bytes()); // Version needed to extract
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Flags
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Compression method
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Modification time
+        central_directory.extend_from_slice(&0x21u16.to_le_bytes()); // Modification date
+        central_directory.extend_from_slice(&crc.to_le_bytes());
+        central_directory.extend_from_slice(&size.to_le_bytes());
+        central_directory.extend_from_slice(&size.to_le_bytes());
+        central_directory.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Extra field length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+        central_directory.extend_from_slice(&0u16.to_le_bytes()); // Internal attributes
+        central_directory.extend_from_slice(&0u32.to_le_bytes()); // External attributes
+        central_directory.extend_from_slice(&offset.to_le_bytes());
+        central_directory.extend_from_slice(name.as_bytes());
+    }
+
+    let central_directory_offset = zip.len() as u32;
+    zip.extend_from_slice(&central_directory);
+
+    // End of central directory record
+    zip.extend_from_slice(&0x0605_4B50u32.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // Disk number
+    zip.extend_from_slice(&0u16.to_le_bytes()); // Disk with the central directory
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(files.len() as u16).to_le_bytes());
+    zip.extend_from_slice(&(central_directory.len() as u32).to_le_bytes());
+    zip.extend_from_slice(&central_directory_offset.to_le_bytes());
+    zip.extend_from_slice(&0u16.to_le_bytes()); // Comment length
+
+    zip
+}
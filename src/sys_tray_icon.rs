@@ -1,12 +1,107 @@
 use anyhow::Context;
-use tray_icon::menu::{Menu, MenuEvent, MenuItem};
+use std::cell::RefCell;
+use tray_icon::menu::{CheckMenuItem, Menu, MenuEvent, MenuItem, Submenu};
 use tray_icon::{Icon, TrayIcon, TrayIconBuilder};
-use windows::Win32::UI::Accessibility::{HWINEVENTHOOK, UnhookWinEvent};
-use windows::Win32::UI::WindowsAndMessaging::PostQuitMessage;
+use windows::Win32::Foundation::HWND;
+use windows::Win32::UI::Accessibility::HWINEVENTHOOK;
+use windows::Win32::UI::WindowsAndMessaging::{PostQuitMessage, SetTimer};
 
 use crate::config::Config;
+use crate::diagnostics::show_diagnostics_window;
+use crate::komorebi::KomorebiStatus;
+use crate::rule_picker::start_rule_picker;
+use crate::snapshot::capture_snapshot_after_delay;
 use crate::utils::LogIfErr;
-use crate::{APP_STATE, reload_borders};
+use crate::{
+    APP_STATE, is_presentation_mode, is_window_rule_enabled, reload_borders, set_presentation_mode,
+    set_window_rule_enabled, stop_engine,
+};
+
+const TRAY_STATUS_INTERVAL_MS: u32 = 1000;
+// Menu ids for the "Window Rules" submenu are this followed by the rule's id
+const RULE_MENU_ID_PREFIX: &str = "rule:";
+
+struct TrayStatus {
+    tray_icon: TrayIcon,
+    komorebi_status: KomorebiStatus,
+    rules_menu: Submenu,
+    // The rule ids that 'rules_menu' was last filled with
+    rule_ids: Vec<String>,
+}
+
+thread_local! {
+    // The tray icon isn't Send, so we keep a handle to it on the thread that created it and poll
+    // for status changes from a timer on that same thread
+    static TRAY_STATUS: RefCell<Option<TrayStatus>> = const { RefCell::new(None) };
+}
+
+// Only rules with an 'id' show up in the menu, since that's what we remember them by
+fn get_rule_ids() -> Vec<String> {
+    APP_STATE
+        .config
+        .read()
+        .unwrap()
+        .window_rules
+        .iter()
+        .filter_map(|rule| rule.id.clone())
+        .collect()
+}
+
+fn fill_rules_menu(rules_menu: &Submenu, rule_ids: &[String]) -> anyhow::Result<()> {
+    for _ in 0..rules_menu.items().len() {
+        rules_menu.remove_at(0);
+    }
+
+    for rule_id in rule_ids {
+        rules_menu.append(&CheckMenuItem::with_id(
+            format!("{RULE_MENU_ID_PREFIX}{rule_id}"),
+            rule_id,
+            true,
+            is_window_rule_enabled(rule_id),
+            None,
+        ))?;
+    }
+    rules_menu.set_enabled(!rule_ids.is_empty());
+
+    Ok(())
+}
+
+fn get_tooltip(komorebi_status: KomorebiStatus) -> String {
+    let tooltip = format!("{}{}", "tacky-borders v", env!("CARGO_PKG_VERSION"));
+
+    match komorebi_status {
+        KomorebiStatus::Stopped => tooltip,
+        _ => format!("{tooltip}\nkomorebi: {}", komorebi_status.as_str()),
+    }
+}
+
+unsafe extern "system" fn update_tray_status(_hwnd: HWND, _msg: u32, _id: usize, _time: u32) {
+    let komorebi_status = APP_STATE.komorebi_integration.lock().unwrap().get_status();
+    let rule_ids = get_rule_ids();
+
+    TRAY_STATUS.with_borrow_mut(|tray_status| {
+        let Some(tray_status) = tray_status else {
+            return;
+        };
+
+        if tray_status.komorebi_status != komorebi_status {
+            tray_status
+                .tray_icon
+                .set_tooltip(Some(get_tooltip(komorebi_status)))
+                .context("could not update tray icon tooltip")
+                .log_if_err();
+            tray_status.komorebi_status = komorebi_status;
+        }
+
+        // The rules may have changed since the last reload
+        if tray_status.rule_ids != rule_ids {
+            fill_rules_menu(&tray_status.rules_menu, &rule_ids)
+                .context("could not update window rules menu")
+                .log_if_err();
+            tray_status.rule_ids = rule_ids;
+        }
+    });
+}
 
 pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon> {
     let icon = match Icon::from_resource(1, Some((64, 64))) {
@@ -21,12 +116,25 @@ pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon
         }
     };
 
-    let tooltip = format!("{}{}", "tacky-borders v", env!("CARGO_PKG_VERSION"));
+    let komorebi_status = APP_STATE.komorebi_integration.lock().unwrap().get_status();
+    let tooltip = get_tooltip(komorebi_status);
+
+    let is_read_only = APP_STATE.config.read().unwrap().is_read_only();
+
+    let rule_ids = get_rule_ids();
+    let rules_menu = Submenu::new("Window Rules", true);
+    fill_rules_menu(&rules_menu, &rule_ids)?;
 
     let tray_menu = Menu::new();
     tray_menu.append_items(&[
-        &MenuItem::with_id("0", "Show Config", true, None),
+        &MenuItem::with_id("0", "Show Config", !is_read_only, None),
         &MenuItem::with_id("1", "Reload", true, None),
+        &MenuItem::with_id("3", "Show Config Errors", true, None),
+        &MenuItem::with_id("4", "Diagnostics", true, None),
+        &MenuItem::with_id("5", "Capture Diagnostic Snapshot", true, None),
+        &MenuItem::with_id("7", "Create Rule from Window...", !is_read_only, None),
+        &CheckMenuItem::with_id("6", "Presentation Mode", true, is_presentation_mode(), None),
+        &rules_menu,
         &MenuItem::with_id("2", "Close", true, None),
     ])?;
 
@@ -42,36 +150,71 @@ pub fn create_tray_icon(hwineventhook: HWINEVENTHOOK) -> anyhow::Result<TrayIcon
     // Handle tray icon events (i.e. clicking on the menu items)
     MenuEvent::set_event_handler(Some(move |event: MenuEvent| match event.id.0.as_str() {
         // Show Config
-        "0" => match Config::get_dir() {
-            Ok(dir) => {
-                open::that(dir).log_if_err();
+        "0" => {
+            // The menu item is disabled in read-only mode, but the config may have been reloaded
+            // since the tray icon was created
+            if APP_STATE.config.read().unwrap().is_read_only() {
+                warn!("cannot show config in read-only mode");
+                return;
+            }
+
+            match Config::get_dir() {
+                Ok(dir) => {
+                    open::that(dir).log_if_err();
+                }
+                Err(err) => error!("{err}"),
             }
-            Err(err) => error!("{err}"),
-        },
+        }
         // Reload
         "1" => {
             Config::reload();
             reload_borders();
         }
+        // Show Config Errors
+        "3" => {
+            let config = APP_STATE.config.read().unwrap();
+            if config.color_errors.is_empty() {
+                info!("no errors found in config");
+                return;
+            }
+
+            config.report_color_errors();
+        }
+        // Diagnostics
+        "4" => show_diagnostics_window(),
+        // Capture Diagnostic Snapshot
+        "5" => capture_snapshot_after_delay(),
+        // Create Rule from Window
+        "7" => start_rule_picker(),
+        // Presentation Mode (the menu item checks/unchecks itself)
+        "6" => set_presentation_mode(!is_presentation_mode()),
+        // Window Rules (like Presentation Mode, the menu items check/uncheck themselves)
+        menu_id if menu_id.starts_with(RULE_MENU_ID_PREFIX) => {
+            let rule_id = &menu_id[RULE_MENU_ID_PREFIX.len()..];
+            set_window_rule_enabled(rule_id, !is_window_rule_enabled(rule_id));
+        }
         // Close
-        "2" => unsafe {
+        "2" => {
             // Convert hwineventhook_isize back into HWINEVENTHOOK
             let hwineventhook = HWINEVENTHOOK(hwineventhook_isize as _);
 
-            let unhook_bool = UnhookWinEvent(hwineventhook).as_bool();
-            let stop_res = APP_STATE.config_watcher.lock().unwrap().stop();
-            let close_res = APP_STATE.komorebi_integration.lock().unwrap().stop();
-
-            if unhook_bool && stop_res.is_ok() && close_res.is_ok() {
-                PostQuitMessage(0);
-            } else {
-                error!(
-                    "attempt to unhook win event: {unhook_bool:?}; attempt to stop config watcher: {stop_res:?}; attempt to close socket: {close_res:?}"
-                );
+            match stop_engine(hwineventhook) {
+                Ok(()) => unsafe { PostQuitMessage(0) },
+                Err(err) => error!("{err}"),
             }
-        },
+        }
         _ => {}
     }));
 
-    tray_icon.map_err(anyhow::Error::new)
+    let tray_icon = tray_icon.map_err(anyhow::Error::new)?;
+
+    TRAY_STATUS.set(Some(TrayStatus {
+        tray_icon: tray_icon.clone(),
+        komorebi_status,
+        rules_menu,
+        rule_ids,
+    }));
+    unsafe { SetTimer(None, 0, TRAY_STATUS_INTERVAL_MS, Some(update_tray_status)) };
+
+    Ok(tray_icon)
 }
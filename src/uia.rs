@@ -0,0 +1,88 @@
+use windows::Win32::Foundation::{HWND, LPARAM, LRESULT, WPARAM};
+use windows::Win32::System::Variant::VARIANT;
+use windows::Win32::UI::Accessibility::{
+    IRawElementProviderSimple, IRawElementProviderSimple_Impl, ProviderOptions,
+    ProviderOptions_ServerSideProvider, ProviderOptions_UseComThreading, UIA_AriaRolePropertyId,
+    UIA_ControlTypePropertyId, UIA_HasKeyboardFocusPropertyId, UIA_IsContentElementPropertyId,
+    UIA_IsControlElementPropertyId, UIA_IsEnabledPropertyId, UIA_IsKeyboardFocusablePropertyId,
+    UIA_NamePropertyId, UIA_PATTERN_ID, UIA_PROPERTY_ID, UIA_PaneControlTypeId,
+    UiaHostProviderFromHwnd, UiaReturnRawElementProvider, UiaRootObjectId,
+};
+use windows::core::{BSTR, IUnknown, implement};
+
+use crate::APP_STATE;
+
+// UI Automation provider for border windows. Without this, UIA falls back to a default provider
+// that exposes the border as a regular pane, so some automation tools end up enumerating and
+// interacting with it. We mark it as decorative, non-interactive content instead.
+#[implement(IRawElementProviderSimple)]
+struct BorderUiaProvider {
+    hwnd: isize,
+    // Excludes the border from the control and content views. HWNDs always show up in the raw
+    // view, so this is as hidden as we can make them.
+    is_hidden: bool,
+}
+
+impl IRawElementProviderSimple_Impl for BorderUiaProvider_Impl {
+    fn ProviderOptions(&self) -> windows::core::Result<ProviderOptions> {
+        Ok(ProviderOptions_ServerSideProvider | ProviderOptions_UseComThreading)
+    }
+
+    fn GetPatternProvider(&self, _patternid: UIA_PATTERN_ID) -> windows::core::Result<IUnknown> {
+        // We don't support any control patterns (Invoke, Value, etc.), which is what makes the
+        // border non-interactive. An empty error tells UIA to return S_OK with a null pointer.
+        Err(windows::core::Error::empty())
+    }
+
+    // The UIA_* constants aren't upper case, which makes the compiler think they're bindings
+    #[allow(non_upper_case_globals)]
+    fn GetPropertyValue(&self, propertyid: UIA_PROPERTY_ID) -> windows::core::Result<VARIANT> {
+        let value = match propertyid {
+            UIA_NamePropertyId => VARIANT::from(BSTR::from("tacky-borders border")),
+            UIA_ControlTypePropertyId => VARIANT::from(UIA_PaneControlTypeId.0),
+            UIA_AriaRolePropertyId => VARIANT::from(BSTR::from("presentation")),
+            UIA_IsControlElementPropertyId => VARIANT::from(!self.is_hidden),
+            UIA_IsContentElementPropertyId
+            | UIA_IsEnabledPropertyId
+            | UIA_IsKeyboardFocusablePropertyId
+            | UIA_HasKeyboardFocusPropertyId => VARIANT::from(false),
+            // An empty VARIANT tells UIA to use the default value
+            _ => VARIANT::default(),
+        };
+
+        Ok(value)
+    }
+
+    fn HostRawElementProvider(&self) -> windows::core::Result<IRawElementProviderSimple> {
+        unsafe { UiaHostProviderFromHwnd(HWND(self.hwnd as _)) }
+    }
+}
+
+// Handles WM_GETOBJECT for border windows. Returns None if the message isn't meant for UIA.
+pub fn handle_get_object(hwnd: HWND, wparam: WPARAM, lparam: LPARAM) -> Option<LRESULT> {
+    // The object id is passed in the low 32 bits of the LPARAM
+    if lparam.0 as i32 != UiaRootObjectId {
+        return None;
+    }
+
+    let provider: IRawElementProviderSimple = BorderUiaProvider {
+        hwnd: hwnd.0 as isize,
+        is_hidden: APP_STATE.config.read().unwrap().hide_from_uia,
+    }
+    .into();
+
+    Some(unsafe { UiaReturnRawElementProvider(hwnd, wparam, lparam, &provider) })
+}
+
+// Tells UIA to release any providers it's still holding on to. This should be called when the
+// border window is being destroyed.
+pub fn release_providers(hwnd: HWND) {
+    unsafe {
+        UiaReturnRawElementProvider(
+            hwnd,
+            WPARAM(0),
+            LPARAM(0),
+            None::<&IRawElementProviderSimple>,
+        )
+    };
+}
@@ -1,39 +1,60 @@
 use anyhow::{Context, anyhow};
 use regex::Regex;
+use std::collections::HashMap;
+use std::f32::consts::PI;
 use std::ffi::OsString;
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::os::windows::ffi::OsStringExt;
 use std::path::PathBuf;
-use std::{ptr, thread};
+use std::sync::{LazyLock, MutexGuard};
+use std::{mem, ptr, thread};
 use windows::Win32::Foundation::{
     CloseHandle, ERROR_ENVVAR_NOT_FOUND, ERROR_INVALID_WINDOW_HANDLE, ERROR_SUCCESS, FALSE,
     GetLastError, HWND, LPARAM, LRESULT, RECT, SetLastError, WIN32_ERROR, WPARAM,
 };
+use windows::Win32::Graphics::Direct2D::Common::D2D_RECT_F;
 use windows::Win32::Graphics::Dwm::{
-    DWM_WINDOW_CORNER_PREFERENCE, DWMWA_CLOAKED, DWMWA_WINDOW_CORNER_PREFERENCE,
-    DwmGetWindowAttribute,
+    DWM_CLOAKED_SHELL, DWM_WINDOW_CORNER_PREFERENCE, DWMWA_CLOAKED, DWMWA_EXTENDED_FRAME_BOUNDS,
+    DWMWA_WINDOW_CORNER_PREFERENCE, DwmGetWindowAttribute, DwmSetWindowAttribute,
 };
 use windows::Win32::Graphics::Gdi::{
-    GetMonitorInfoW, HMONITOR, MONITOR_DEFAULTTONEAREST, MONITORINFO, MonitorFromWindow,
+    CombineRgn, CreateRectRgn, CreateRoundRectRgn, DeleteObject, EnumDisplayMonitors,
+    GetMonitorInfoW, GetWindowRgn, HDC, HMONITOR, HRGN, MONITOR_DEFAULTTONEAREST, MONITORINFO,
+    MONITORINFOEXW, MonitorFromWindow, RGN_COPY, RGN_ERROR, SetWindowRgn,
 };
 use windows::Win32::System::Diagnostics::Debug::FACILITY_ITF;
+use windows::Win32::System::LibraryLoader::{GetModuleHandleW, GetProcAddress};
+use windows::Win32::System::ProcessStatus::EnumProcesses;
 use windows::Win32::System::Threading::{
     OpenProcess, PROCESS_NAME_WIN32, PROCESS_QUERY_LIMITED_INFORMATION, QueryFullProcessImageNameW,
 };
+use windows::Win32::UI::Accessibility::{HCF_HIGHCONTRASTON, HIGHCONTRASTW};
 use windows::Win32::UI::HiDpi::{
     DPI_AWARENESS_CONTEXT, GetDpiForMonitor, MONITOR_DPI_TYPE, SetProcessDpiAwarenessContext,
 };
 use windows::Win32::UI::Input::Ime::ImmDisableIME;
 use windows::Win32::UI::WindowsAndMessaging::{
-    GWL_EXSTYLE, GWL_STYLE, GetForegroundWindow, GetWindowLongW, GetWindowTextW,
-    GetWindowThreadProcessId, IsIconic, IsWindowVisible, PostMessageW, RealGetWindowClassW,
-    SendMessageW, SendNotifyMessageW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_NCDESTROY,
-    WS_CHILD, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_WINDOWEDGE, WS_MAXIMIZE,
+    FindWindowExW, GA_PARENT, GW_HWNDNEXT, GW_OWNER, GWL_EXSTYLE, GWL_STYLE, GetAncestor,
+    GetForegroundWindow, GetWindow, GetWindowLongW, GetWindowRect, GetWindowTextW,
+    GetWindowThreadProcessId, IsIconic, IsWindow, IsWindowVisible, IsZoomed, PostMessageW,
+    RealGetWindowClassW, SPI_GETHIGHCONTRAST, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SendMessageW,
+    SendNotifyMessageW, SystemParametersInfoW, WINDOW_EX_STYLE, WINDOW_STYLE, WM_APP, WM_CLOSE,
+    WS_CAPTION, WS_CHILD, WS_EX_NOACTIVATE, WS_EX_TOOLWINDOW, WS_EX_WINDOWEDGE, WS_MAXIMIZE,
+    WS_THICKFRAME,
 };
-use windows::core::{BOOL, HRESULT, PWSTR};
+use windows::core::{BOOL, HRESULT, PCWSTR, PWSTR, s, w};
 
-use crate::APP_STATE;
-use crate::config::{EnableMode, MatchKind, MatchStrategy, WindowRule};
+use crate::border_drawer::BorderEdges;
+use crate::border_limit::{forget_window, get_max_borders, make_room_for_border};
+use crate::config::{
+    BorderEdge, ConditionConfig, DISABLED_RULE_IDS, EnableMode, MatchKind, MatchStrategy,
+    TitlePrivacy, WindowRule,
+};
+use crate::error::{BorderError, record_border_error};
+use crate::memory_trim::queue_memory_trim;
+use crate::render_backend::RenderBackendConfig;
 use crate::window_border::WindowBorder;
+use crate::{APP_STATE, are_borders_hidden};
 
 pub const WM_APP_LOCATIONCHANGE: u32 = WM_APP;
 pub const WM_APP_REORDER: u32 = WM_APP + 1;
@@ -42,8 +63,26 @@ pub const WM_APP_SHOWUNCLOAKED: u32 = WM_APP + 3;
 pub const WM_APP_HIDECLOAKED: u32 = WM_APP + 4;
 pub const WM_APP_MINIMIZESTART: u32 = WM_APP + 5;
 pub const WM_APP_MINIMIZEEND: u32 = WM_APP + 6;
+// Just a clock tick; the border's animators decide what actually changes (see Animations)
 pub const WM_APP_ANIMATE: u32 = WM_APP + 7;
 pub const WM_APP_KOMOREBI: u32 = WM_APP + 8;
+pub const WM_APP_RESOURCES: u32 = WM_APP + 9;
+pub const WM_APP_SCHEDULE: u32 = WM_APP + 10;
+pub const WM_APP_SETCOLOR: u32 = WM_APP + 11;
+pub const WM_APP_KOMOREBI_GAPS: u32 = WM_APP + 12;
+pub const WM_APP_DORMANT: u32 = WM_APP + 13;
+pub const WM_APP_WALLPAPER: u32 = WM_APP + 14;
+pub const WM_APP_PROXIMITY: u32 = WM_APP + 15;
+// Sent once a border's move from a position batch has been applied
+pub const WM_APP_POSITIONED: u32 = WM_APP + 16;
+
+// Timer ids used with SetTimer() on border windows
+pub const IDLE_DIM_TIMER_ID: usize = 1;
+pub const ELIGIBILITY_TIMER_ID: usize = 3;
+pub const FADE_OUT_TIMER_ID: usize = 4;
+pub const CROSSFADE_TIMER_ID: usize = 5;
+pub const RECT_SETTLE_TIMER_ID: usize = 6;
+pub const PRESENCE_TIMER_ID: usize = 7;
 
 // Custom HRESULT error code indicating an uninitialized COM object within this application.
 // T_E_UNINIT typically represents an Option::None where an Option::Some(_) was expected. This is
@@ -91,6 +130,44 @@ pub fn is_window_top_level(hwnd: HWND) -> bool {
     !style.contains(WS_CHILD)
 }
 
+// Desktop widgets pin themselves to the desktop either by making the desktop window (Progman, or
+// the WorkerW behind the icons) their owner, or by keeping themselves at the bottom of the z-order.
+// Regular windows can end up at the bottom too, so that only counts for windows that have neither a
+// caption nor a resizable frame, and that are either parented to the desktop window or tool
+// windows.
+pub fn is_desktop_widget(hwnd: HWND) -> bool {
+    let is_desktop_window = |hwnd: HWND| {
+        !hwnd.is_invalid()
+            && get_window_class(hwnd).is_ok_and(|class| class == "Progman" || class == "WorkerW")
+    };
+
+    let owner = unsafe { GetWindow(hwnd, GW_OWNER) }.unwrap_or_default();
+    if is_desktop_window(owner) {
+        return true;
+    }
+
+    let style = get_window_style(hwnd);
+    if style.contains(WS_CAPTION) || style.contains(WS_THICKFRAME) {
+        return false;
+    }
+
+    let parent = unsafe { GetAncestor(hwnd, GA_PARENT) };
+    if !is_desktop_window(parent) && !get_window_ex_style(hwnd).contains(WS_EX_TOOLWINDOW) {
+        return false;
+    }
+
+    // Find the next visible window below this one
+    let mut next = hwnd;
+    while let Ok(window) = unsafe { GetWindow(next, GW_HWNDNEXT) } {
+        if is_window_visible(window) {
+            return is_desktop_window(window);
+        }
+        next = window;
+    }
+
+    false
+}
+
 pub fn has_filtered_style(hwnd: HWND) -> bool {
     let ex_style = get_window_ex_style(hwnd);
 
@@ -119,6 +196,39 @@ pub fn get_window_title(hwnd: HWND) -> anyhow::Result<String> {
     Ok(title_binding.split_once("\0").unwrap().0.to_string())
 }
 
+// Number of characters that TitlePrivacy::Truncate keeps
+const TRUNCATED_TITLE_LEN: usize = 12;
+
+pub fn redact_title(title: &str, title_privacy: TitlePrivacy) -> String {
+    if title.is_empty() {
+        return String::new();
+    }
+
+    match title_privacy {
+        TitlePrivacy::Full => title.to_string(),
+        TitlePrivacy::Truncate => match title.char_indices().nth(TRUNCATED_TITLE_LEN) {
+            Some((idx, _)) => format!("{}...", &title[..idx]),
+            None => title.to_string(),
+        },
+        TitlePrivacy::Hash => {
+            let mut hasher = DefaultHasher::new();
+            title.hash(&mut hasher);
+            format!("<title {:08x}>", hasher.finish() as u32)
+        }
+    }
+}
+
+// Use this whenever a window title ends up anywhere other than rule matching (e.g. logs)
+pub fn get_window_title_for_display(hwnd: HWND, is_private: bool) -> String {
+    let title = get_window_title(hwnd).unwrap_or_default();
+    let title_privacy = match is_private {
+        true => TitlePrivacy::Hash,
+        false => APP_STATE.config.read().unwrap().title_privacy,
+    };
+
+    redact_title(&title, title_privacy)
+}
+
 pub fn get_window_class(hwnd: HWND) -> anyhow::Result<String> {
     let mut class_buf: [u16; 256] = [0; 256];
 
@@ -151,8 +261,12 @@ pub fn get_window_process_name(hwnd: HWND) -> anyhow::Result<String> {
         ));
     }
 
+    get_process_name(process_id).context(format!("could not get process name of {hwnd:?}"))
+}
+
+pub fn get_process_name(process_id: u32) -> anyhow::Result<String> {
     let hprocess = unsafe { OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, false, process_id) }
-        .context(format!("could not open process of {hwnd:?}"))?;
+        .context(format!("could not open process {process_id}"))?;
 
     let mut process_buf = [0u16; 256];
     let mut lpdwsize = process_buf.len() as u32;
@@ -165,7 +279,9 @@ pub fn get_window_process_name(hwnd: HWND) -> anyhow::Result<String> {
             &mut lpdwsize,
         )
     }
-    .context(format!("could not query process image name for {hwnd:?}"));
+    .context(format!(
+        "could not query process image name for {process_id}"
+    ));
 
     unsafe { CloseHandle(hprocess) }.context("could not close {hprocess:?}")?;
 
@@ -187,69 +303,196 @@ pub fn get_window_process_name(hwnd: HWND) -> anyhow::Result<String> {
         .into_owned())
 }
 
+// Process names are compared without the .exe extension, e.g. "komorebi". Processes that we're not
+// allowed to query (e.g. elevated ones) are skipped.
+pub fn is_process_running(process_name: &str) -> bool {
+    let mut process_ids = vec![0u32; 4096];
+    let mut bytes_returned = 0;
+
+    if let Err(err) = unsafe {
+        EnumProcesses(
+            process_ids.as_mut_ptr(),
+            mem::size_of_val(process_ids.as_slice()) as u32,
+            &mut bytes_returned,
+        )
+    } {
+        error!("could not enumerate processes: {err}");
+        return false;
+    }
+
+    process_ids[..bytes_returned as usize / mem::size_of::<u32>()]
+        .iter()
+        .any(|&process_id| {
+            get_process_name(process_id).is_ok_and(|name| name.eq_ignore_ascii_case(process_name))
+        })
+}
+
 // Get the window rule from 'window_rules' in the config
+// We only look at direct children, and only up to a certain amount of them, because some apps have
+// a LOT of child windows and this gets called fairly often.
+const MAX_CHILD_WINDOWS: usize = 32;
+
+pub fn get_child_windows(hwnd: HWND) -> Vec<HWND> {
+    let mut children: Vec<HWND> = Vec::new();
+    let mut child_after: Option<HWND> = None;
+
+    while children.len() < MAX_CHILD_WINDOWS {
+        // FindWindowExW only enumerates direct children, unlike EnumChildWindows
+        match unsafe { FindWindowExW(Some(hwnd), child_after, PCWSTR::null(), PCWSTR::null()) } {
+            Ok(child) if !child.is_invalid() => {
+                children.push(child);
+                child_after = Some(child);
+            }
+            _ => break,
+        }
+    }
+
+    children
+}
+
+fn get_window_name(hwnd: HWND, kind: &MatchKind) -> String {
+    let result = match kind {
+        MatchKind::Title => get_window_title(hwnd),
+        MatchKind::Class => get_window_class(hwnd),
+        MatchKind::Process => get_window_process_name(hwnd),
+    };
+
+    // This happens a lot with windows that are closing or belong to elevated processes, and the
+    // empty name is handled by the caller, so it's not worth more than a debug line
+    result.unwrap_or_else(|err| {
+        debug!("could not retrieve window {kind:?} for {hwnd:?}: {err}");
+        "".to_string()
+    })
+}
+
+fn does_name_match(window_name: &str, match_name: &str, strategy: &Option<MatchStrategy>) -> bool {
+    match strategy {
+        Some(MatchStrategy::Equals) | None => {
+            window_name.to_lowercase().eq(&match_name.to_lowercase())
+        }
+        Some(MatchStrategy::Contains) => window_name
+            .to_lowercase()
+            .contains(&match_name.to_lowercase()),
+        Some(MatchStrategy::Regex) => Regex::new(match_name)
+            .unwrap()
+            .captures(window_name)
+            .is_some(),
+    }
+}
+
+// Checks a build number against a condition like ">=22621", "<22000", or "19045" (exact match)
+pub fn does_build_match(condition: &str, build: u32) -> anyhow::Result<bool> {
+    let condition = condition.trim();
+    let (operator, number) = match condition.find(|c: char| c.is_ascii_digit()) {
+        Some(idx) => condition.split_at(idx),
+        None => {
+            return Err(anyhow!(
+                "missing build number in os condition '{condition}'"
+            ));
+        }
+    };
+    let number: u32 = number
+        .trim()
+        .parse()
+        .with_context(|| format!("invalid build number in os condition '{condition}'"))?;
+
+    match operator.trim() {
+        ">=" => Ok(build >= number),
+        ">" => Ok(build > number),
+        "<=" => Ok(build <= number),
+        "<" => Ok(build < number),
+        "" | "=" | "==" => Ok(build == number),
+        "!=" => Ok(build != number),
+        operator => Err(anyhow!(
+            "unknown operator '{operator}' in os condition '{condition}'"
+        )),
+    }
+}
+
 pub fn get_window_rule(hwnd: HWND) -> WindowRule {
     let mut title_opt: Option<String> = None;
     let mut class_opt: Option<String> = None;
     let mut process_opt: Option<String> = None;
 
-    let title = || -> String {
-        get_window_title(hwnd).unwrap_or_else(|err| {
-            error!("could not retrieve window title for {hwnd:?}: {err}");
-            "".to_string()
-        })
-    };
-    let class = || -> String {
-        get_window_class(hwnd).unwrap_or_else(|err| {
-            error!("could not retrieve window class for {hwnd:?}: {err}");
-            "".to_string()
-        })
-    };
-    let process = || -> String {
-        get_window_process_name(hwnd).unwrap_or_else(|err| {
-            error!("could not retrieve window process name for {hwnd:?}: {err}");
-            "".to_string()
-        })
-    };
+    // These are only queried if a rule has 'match_children' enabled
+    let mut children_opt: Option<Vec<HWND>> = None;
+    let mut child_titles_opt: Option<Vec<String>> = None;
+    let mut child_classes_opt: Option<Vec<String>> = None;
+    let mut child_processes_opt: Option<Vec<String>> = None;
 
     let config = APP_STATE.config.read().unwrap();
+    let disabled_ids = DISABLED_RULE_IDS.lock().unwrap().clone();
 
     for rule in config.window_rules.iter() {
-        let window_name: &String = match rule.kind {
-            Some(MatchKind::Title) => title_opt.get_or_insert_with(title),
-            Some(MatchKind::Class) => class_opt.get_or_insert_with(class),
-            Some(MatchKind::Process) => process_opt.get_or_insert_with(process),
-            None => {
-                error!("expected 'match' for window rule but None found!");
-                continue;
-            }
+        // Rules meant for other machines (e.g. other Windows builds) are skipped entirely, and so
+        // are rules that were turned off from the tray
+        if !rule.when.as_ref().is_none_or(ConditionConfig::is_met)
+            || rule.id.as_ref().is_some_and(|id| disabled_ids.contains(id))
+        {
+            continue;
+        }
+
+        let Some(kind) = &rule.kind else {
+            error!(
+                "expected 'match' for window rule '{}' but None found!",
+                rule.display_name()
+            );
+            continue;
+        };
+
+        let window_name: &String = match kind {
+            MatchKind::Title => &*title_opt.get_or_insert_with(|| get_window_name(hwnd, kind)),
+            MatchKind::Class => &*class_opt.get_or_insert_with(|| get_window_name(hwnd, kind)),
+            MatchKind::Process => &*process_opt.get_or_insert_with(|| get_window_name(hwnd, kind)),
         };
 
         let Some(match_name) = &rule.name else {
-            error!("expected `name` for window rule but None found!");
+            error!(
+                "expected `name` for window rule '{}' but None found!",
+                rule.display_name()
+            );
             continue;
         };
 
-        // Check if the window rule matches the window
-        let has_match = match rule.strategy {
-            Some(MatchStrategy::Equals) | None => {
-                window_name.to_lowercase().eq(&match_name.to_lowercase())
-            }
-            Some(MatchStrategy::Contains) => window_name
-                .to_lowercase()
-                .contains(&match_name.to_lowercase()),
-            Some(MatchStrategy::Regex) => Regex::new(match_name)
-                .unwrap()
-                .captures(window_name)
-                .is_some(),
+        // Empty titles follow their own rules instead of whatever the strategy would make of them
+        let does_match = |name: &str| match kind {
+            MatchKind::Title if name.is_empty() => config.empty_titles.does_match(match_name),
+            _ => does_name_match(name, match_name, &rule.strategy),
         };
 
+        // Check if the window rule matches the window
+        let mut has_match = does_match(window_name);
+
+        // If it doesn't, check the window's children if the rule allows it
+        if !has_match && rule.match_children == Some(true) {
+            let children = children_opt.get_or_insert_with(|| get_child_windows(hwnd));
+            let child_names_opt = match kind {
+                MatchKind::Title => &mut child_titles_opt,
+                MatchKind::Class => &mut child_classes_opt,
+                MatchKind::Process => &mut child_processes_opt,
+            };
+            let child_names = child_names_opt.get_or_insert_with(|| {
+                children
+                    .iter()
+                    .map(|&child| get_window_name(child, kind))
+                    .collect()
+            });
+
+            has_match = child_names.iter().any(|child_name| does_match(child_name));
+        }
+
         // Return the first match
         if has_match {
+            debug!("window rule '{}' matched {hwnd:?}", rule.display_name());
             return rule.clone();
         }
     }
 
+    // Only the default rule is left, but desktop widgets have their own
+    if is_desktop_widget(hwnd) {
+        return config.desktop_widgets.to_window_rule();
+    }
+
     WindowRule::default()
 }
 
@@ -266,6 +509,45 @@ pub fn are_rects_same_size(rect1: &RECT, rect2: &RECT) -> bool {
         && rect1.bottom - rect1.top == rect2.bottom - rect2.top
 }
 
+pub fn is_high_contrast_on() -> bool {
+    let mut high_contrast = HIGHCONTRASTW {
+        cbSize: size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+
+    if let Err(err) = unsafe {
+        SystemParametersInfoW(
+            SPI_GETHIGHCONTRAST,
+            high_contrast.cbSize,
+            Some(ptr::addr_of_mut!(high_contrast) as _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    } {
+        error!("could not check if high contrast is on: {err}");
+        return false;
+    }
+
+    high_contrast.dwFlags.contains(HCF_HIGHCONTRASTON)
+}
+
+// The shell cloaks windows that are on other virtual desktops (among other things)
+pub fn is_window_cloaked_by_shell(hwnd: HWND) -> bool {
+    let mut cloaked_reason: u32 = 0;
+    if unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_CLOAKED,
+            ptr::addr_of_mut!(cloaked_reason) as _,
+            size_of::<u32>() as u32,
+        )
+    }
+    .is_err()
+    {
+        return false;
+    }
+    cloaked_reason & DWM_CLOAKED_SHELL != 0
+}
+
 pub fn is_window_cloaked(hwnd: HWND) -> bool {
     let mut is_cloaked = FALSE;
     if let Err(err) = unsafe {
@@ -290,6 +572,43 @@ pub fn is_window_minimized(hwnd: HWND) -> bool {
     unsafe { IsIconic(hwnd).as_bool() }
 }
 
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum PlacementState {
+    Maximized,
+    // Arranged by Snap (including FancyZones-style snapping that goes through the same API)
+    Snapped,
+    #[default]
+    Floating,
+}
+
+type IsWindowArrangedFn = unsafe extern "system" fn(HWND) -> BOOL;
+
+// IsWindowArranged only exists on build 20348 and later, so linking it directly would keep us from
+// starting at all on older versions of Windows 10
+static IS_WINDOW_ARRANGED: LazyLock<Option<IsWindowArrangedFn>> = LazyLock::new(|| unsafe {
+    let user32 = GetModuleHandleW(w!("user32.dll")).ok()?;
+    let proc = GetProcAddress(user32, s!("IsWindowArranged"))?;
+
+    Some(mem::transmute::<
+        unsafe extern "system" fn() -> isize,
+        IsWindowArrangedFn,
+    >(proc))
+});
+
+pub fn get_placement_state(hwnd: HWND) -> PlacementState {
+    unsafe {
+        if IsZoomed(hwnd).as_bool() {
+            PlacementState::Maximized
+        } else if let Some(is_window_arranged) = *IS_WINDOW_ARRANGED
+            && is_window_arranged(hwnd).as_bool()
+        {
+            PlacementState::Snapped
+        } else {
+            PlacementState::Floating
+        }
+    }
+}
+
 pub fn post_message_w(
     hwnd: Option<HWND>,
     msg: u32,
@@ -334,13 +653,61 @@ pub fn has_native_border(hwnd: HWND) -> bool {
     !style.contains(WS_MAXIMIZE) && ex_style.contains(WS_EX_WINDOWEDGE)
 }
 
+// Games and video players usually go fullscreen by dropping their frame and covering the monitor
+pub fn is_borderless_fullscreen(hwnd: HWND) -> bool {
+    let style = get_window_style(hwnd);
+    if style.contains(WS_CAPTION) || style.contains(WS_THICKFRAME) {
+        return false;
+    }
+
+    let mut rect = RECT::default();
+    if unsafe { GetWindowRect(hwnd, &mut rect) }.is_err() {
+        return false;
+    }
+    let Ok(monitor_info) = get_monitor_info(monitor_from_window(hwnd)) else {
+        return false;
+    };
+    let monitor_rect = monitor_info.rcMonitor;
+
+    rect.left <= monitor_rect.left
+        && rect.top <= monitor_rect.top
+        && rect.right >= monitor_rect.right
+        && rect.bottom >= monitor_rect.bottom
+}
+
+// The windows that has_native_border() filters out only because they're maximized
+pub fn is_maximized_with_native_border(hwnd: HWND) -> bool {
+    let style = get_window_style(hwnd);
+    let ex_style = get_window_ex_style(hwnd);
+
+    style.contains(WS_MAXIMIZE) && ex_style.contains(WS_EX_WINDOWEDGE)
+}
+
 pub fn create_border_for_window(tracking_window: HWND, window_rule: WindowRule) {
-    debug!("creating border for: {:?}", tracking_window);
     let tracking_window_isize = tracking_window.0 as isize;
 
     let _ = thread::spawn(move || {
         let tracking_window = HWND(tracking_window_isize as _);
 
+        // Getting the title means messaging the window, so this happens off the event hook thread
+        let is_title_private = window_rule.private.unwrap_or(false);
+        let display_title = get_window_title_for_display(tracking_window, is_title_private);
+        debug!("creating border for: {tracking_window:?} ({display_title})");
+
+        if are_borders_hidden() {
+            return;
+        }
+
+        // The None render backend means the window shouldn't get a border at all
+        let render_backend = window_rule
+            .render_backend
+            .unwrap_or_else(|| APP_STATE.config.read().unwrap().render_backend);
+        if render_backend == RenderBackendConfig::None {
+            return;
+        }
+
+        let max_borders = get_max_borders();
+
         // Note: 'key' for the hashmap is the tracking window, 'value' is the border window
         let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
 
@@ -349,12 +716,21 @@ pub fn create_border_for_window(tracking_window: HWND, window_rule: WindowRule)
             return;
         }
 
+        if let Some(max_borders) = max_borders
+            && !make_room_for_border(&mut borders_hashmap, tracking_window, max_borders)
+        {
+            debug!("not creating border for {tracking_window:?} since max_borders was reached");
+            return;
+        }
+
         // Otherwise, continue creating the border window
         let mut border = WindowBorder::new(tracking_window);
+        border.set_title_private(is_title_private);
         let border_window = match border.create_window() {
             Ok(hwnd) => hwnd,
             Err(err) => {
-                error!("could not create border window: {err}");
+                record_border_error(&err);
+                error!("{err:#} ({tracking_window:?}: {display_title})");
                 return;
             }
         };
@@ -367,20 +743,88 @@ pub fn create_border_for_window(tracking_window: HWND, window_rule: WindowRule)
         let _ = tracking_window_isize;
 
         // NOTE: init() contains a message loop
-        border.init(window_rule).log_if_err();
+        if let Err(err) = border.init(window_rule) {
+            record_border_error(&err);
+            error!("{err:#} ({tracking_window:?}: {display_title})");
+        }
 
-        // If init() exits, that means the border has been destroyed, so we should remove it from
-        // the hashmap
-        APP_STATE
-            .borders
-            .lock()
-            .unwrap()
-            .remove(&tracking_window_isize);
+        // If init() exits, the border is shutting down. We destroy the window here, on its own
+        // thread and while 'border' is still alive, and only then confirm the teardown.
+        border.destroy_window();
+        confirm_border_teardown(tracking_window_isize, border_window.0 as isize);
     });
 }
 
-pub fn get_adjusted_radius(radius: f32, dpi: u32, border_width: i32) -> f32 {
-    radius * dpi as f32 / 96.0 + (border_width as f32 / 2.0)
+// Asks the border to tear itself down. The border stays in 'closing_borders' until its thread has
+// destroyed the window and called confirm_border_teardown(). If 'is_window_closed' is set, the
+// border plays its close animation first (if it has one).
+//
+// NOTE: the caller must already hold the lock on APP_STATE.borders (and should have removed the
+// border from it). Otherwise, the border thread could confirm its teardown before we insert it
+// into 'closing_borders', leaving behind a stale entry.
+pub fn request_border_teardown(
+    _borders_hashmap: &MutexGuard<HashMap<isize, isize>>,
+    border_isize: isize,
+    is_window_closed: bool,
+) {
+    APP_STATE
+        .closing_borders
+        .lock()
+        .unwrap()
+        .insert(border_isize);
+
+    // If this fails, the window is already gone, but the border thread will still confirm
+    if let Err(err) = post_message_w(
+        Some(HWND(border_isize as _)),
+        WM_CLOSE,
+        WPARAM(is_window_closed as usize),
+        LPARAM(0),
+    ) {
+        debug!("could not post WM_CLOSE to border {border_isize:#x}: {err}");
+    }
+}
+
+fn confirm_border_teardown(tracking_window_isize: isize, border_isize: isize) {
+    let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
+
+    // The border may have exited on its own (e.g. due to an error) without being asked to, in
+    // which case it's still in the hashmap. We check the value because a new border might have
+    // already been created for the same tracking window.
+    if borders_hashmap.get(&tracking_window_isize) == Some(&border_isize) {
+        borders_hashmap.remove(&tracking_window_isize);
+    }
+
+    APP_STATE
+        .closing_borders
+        .lock()
+        .unwrap()
+        .remove(&border_isize);
+    drop(borders_hashmap);
+
+    APP_STATE.borders_closed.notify_all();
+    queue_memory_trim();
+}
+
+// Rounds a stroke's rect so the stroke covers whole pixels. Strokes are centered on the rect's
+// edges, so odd widths need their edges on the middle of a pixel and even widths on a boundary.
+pub fn snap_stroke_rect(rect: &D2D_RECT_F, stroke_width: f32) -> D2D_RECT_F {
+    let center_offset = match stroke_width.round() as i32 % 2 {
+        0 => 0.0,
+        _ => 0.5,
+    };
+    let snap = |edge: f32| (edge - center_offset).round() + center_offset;
+
+    D2D_RECT_F {
+        left: snap(rect.left),
+        top: snap(rect.top),
+        right: snap(rect.right),
+        bottom: snap(rect.bottom),
+    }
+}
+
+// Scales the radius for the DPI, then adds the (already scaled) radius compensation
+pub fn get_adjusted_radius(radius: f32, dpi: u32, compensation: f32) -> f32 {
+    (radius * dpi as f32 / 96.0 + compensation).max(0.0)
 }
 
 pub fn get_window_corner_preference(
@@ -396,11 +840,144 @@ pub fn get_window_corner_preference(
             size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
         )
     }
-    .context("could not retrieve window corner preference")?;
+    .map_err(|err| BorderError::Dwm("could not retrieve window corner preference", err))?;
 
     Ok(corner_preference)
 }
 
+pub fn set_window_corner_preference(
+    tracking_window: HWND,
+    corner_preference: DWM_WINDOW_CORNER_PREFERENCE,
+) -> anyhow::Result<()> {
+    unsafe {
+        DwmSetWindowAttribute(
+            tracking_window,
+            DWMWA_WINDOW_CORNER_PREFERENCE,
+            ptr::addr_of!(corner_preference) as _,
+            size_of::<DWM_WINDOW_CORNER_PREFERENCE>() as u32,
+        )
+    }
+    .map_err(|err| BorderError::Dwm("could not set window corner preference", err).into())
+}
+
+// Rounds the window's corners by clipping it to a region, for when DWM can't do it for us (e.g. on
+// Windows 10, or for radii that DWM doesn't offer). The region is relative to the window's full
+// rect, which includes the invisible resize borders, so we line it up with the visible frame.
+// NOTE: this also clips away the window's shadow.
+pub fn set_window_corner_region(hwnd: HWND, radius: i32) -> anyhow::Result<()> {
+    let mut window_rect = RECT::default();
+    let mut frame_rect = RECT::default();
+
+    unsafe {
+        GetWindowRect(hwnd, &mut window_rect).context("could not get window rect")?;
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            ptr::addr_of_mut!(frame_rect) as _,
+            size_of::<RECT>() as u32,
+        )
+        .map_err(|err| BorderError::Dwm("could not get window frame bounds", err))?;
+
+        // CreateRoundRectRgn excludes the bottom-right edge, hence the + 1
+        let diameter = radius.max(0) * 2;
+        let hrgn = CreateRoundRectRgn(
+            frame_rect.left - window_rect.left,
+            frame_rect.top - window_rect.top,
+            frame_rect.right - window_rect.left + 1,
+            frame_rect.bottom - window_rect.top + 1,
+            diameter,
+            diameter,
+        );
+
+        // The system owns the region after a successful call, so we only clean it up on failure
+        if SetWindowRgn(hwnd, Some(hrgn), true) == 0 {
+            let _ = DeleteObject(hrgn.into());
+            return Err(anyhow!("could not set window region"));
+        }
+    }
+
+    Ok(())
+}
+
+// Returns a copy of the window's region, or None if it doesn't have one (which is almost always).
+// The caller owns the copy.
+pub fn get_window_region(hwnd: HWND) -> Option<HRGN> {
+    unsafe {
+        let hrgn = CreateRectRgn(0, 0, 0, 0);
+        if GetWindowRgn(hwnd, hrgn) == RGN_ERROR {
+            let _ = DeleteObject(hrgn.into());
+            return None;
+        }
+
+        Some(hrgn)
+    }
+}
+
+// Puts back the region from get_window_region(). This sets a copy, so 'hrgn' stays ours.
+// NOTE: SetWindowRgn sends messages to the window and waits for them, so this blocks for as long
+// as the window's thread is busy (or forever if it's hung).
+pub fn restore_window_region(hwnd: HWND, hrgn: Option<HRGN>) {
+    unsafe {
+        let copy = hrgn.map(|hrgn| {
+            let copy = CreateRectRgn(0, 0, 0, 0);
+            CombineRgn(Some(copy), Some(hrgn), None, RGN_COPY);
+            copy
+        });
+
+        if SetWindowRgn(hwnd, copy, true) == 0
+            && let Some(copy) = copy
+        {
+            let _ = DeleteObject(copy.into());
+        }
+    }
+}
+
+// Which sides of the window's visible frame line up with its monitor's work area, e.g. the outer
+// sides of a window that's snapped into a quadrant
+pub fn get_work_area_edges(hwnd: HWND) -> anyhow::Result<BorderEdges> {
+    let mut frame_rect = RECT::default();
+    unsafe {
+        DwmGetWindowAttribute(
+            hwnd,
+            DWMWA_EXTENDED_FRAME_BOUNDS,
+            ptr::addr_of_mut!(frame_rect) as _,
+            size_of::<RECT>() as u32,
+        )
+    }
+    .map_err(|err| BorderError::Dwm("could not get window frame bounds", err))?;
+    let work_area = get_monitor_info(monitor_from_window(hwnd))?.rcWork;
+
+    // Snap lines windows up exactly, but we'll allow for a pixel of rounding
+    let touches = |side: i32, work_area_side: i32| (side - work_area_side).abs() <= 1;
+
+    Ok(BorderEdges {
+        top: touches(frame_rect.top, work_area.top),
+        left: touches(frame_rect.left, work_area.left),
+        right: touches(frame_rect.right, work_area.right),
+        bottom: touches(frame_rect.bottom, work_area.bottom),
+    })
+}
+
+// Which side of the monitor the taskbar is on, going by which side of the work area is inset the
+// most. Auto-hidden taskbars don't take up any of the work area, so we just assume the bottom.
+pub fn get_taskbar_edge(hmonitor: HMONITOR) -> anyhow::Result<BorderEdge> {
+    let monitor_info = get_monitor_info(hmonitor)?;
+    let (monitor, work_area) = (monitor_info.rcMonitor, monitor_info.rcWork);
+
+    let insets = [
+        (BorderEdge::Bottom, monitor.bottom - work_area.bottom),
+        (BorderEdge::Top, work_area.top - monitor.top),
+        (BorderEdge::Left, work_area.left - monitor.left),
+        (BorderEdge::Right, monitor.right - work_area.right),
+    ];
+
+    Ok(insets
+        .into_iter()
+        .filter(|(_, inset)| *inset > 0)
+        .max_by_key(|(_, inset)| *inset)
+        .map_or(BorderEdge::Bottom, |(edge, _)| edge))
+}
+
 pub fn get_dpi_for_monitor(hmonitor: HMONITOR, dpitype: MONITOR_DPI_TYPE) -> anyhow::Result<u32> {
     let (mut dpi_x, mut dpi_y) = (0, 0);
     unsafe { GetDpiForMonitor(hmonitor, dpitype, &mut dpi_x, &mut dpi_y) }?;
@@ -438,6 +1015,64 @@ pub fn get_monitor_info(hmonitor: HMONITOR) -> anyhow::Result<MONITORINFO> {
     Ok(mi)
 }
 
+pub fn get_monitor_device_name(hmonitor: HMONITOR) -> anyhow::Result<String> {
+    let mut mi_ex = MONITORINFOEXW {
+        monitorInfo: MONITORINFO {
+            cbSize: size_of::<MONITORINFOEXW>() as u32,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+
+    if !unsafe { GetMonitorInfoW(hmonitor, ptr::addr_of_mut!(mi_ex) as _) }.as_bool() {
+        return Err(anyhow!(
+            "could not get monitor device name for {:?}: {:?}",
+            hmonitor,
+            get_last_error()
+        ));
+    };
+
+    let device_name = String::from_utf16_lossy(&mi_ex.szDevice);
+    Ok(device_name
+        .split_once("\0")
+        .unwrap_or((&device_name, ""))
+        .0
+        .to_string())
+}
+
+// Returns the monitor's position in the EnumDisplayMonitors order, starting from 0
+pub fn get_monitor_index(hmonitor: HMONITOR) -> Option<usize> {
+    get_monitors().iter().position(|&other| other == hmonitor)
+}
+
+// Returns every connected monitor in the EnumDisplayMonitors order
+pub fn get_monitors() -> Vec<HMONITOR> {
+    let mut hmonitors: Vec<HMONITOR> = Vec::new();
+
+    unsafe extern "system" fn enum_monitors_callback(
+        hmonitor: HMONITOR,
+        _hdc: HDC,
+        _rect: *mut RECT,
+        lparam: LPARAM,
+    ) -> BOOL {
+        let hmonitors = unsafe { &mut *(lparam.0 as *mut Vec<HMONITOR>) };
+        hmonitors.push(hmonitor);
+
+        BOOL(1)
+    }
+
+    let _ = unsafe {
+        EnumDisplayMonitors(
+            None,
+            None,
+            Some(enum_monitors_callback),
+            LPARAM(ptr::addr_of_mut!(hmonitors) as isize),
+        )
+    };
+
+    hmonitors
+}
+
 pub fn get_monitor_resolution(hmonitor: HMONITOR) -> anyhow::Result<(u32, u32)> {
     let m_info = get_monitor_info(hmonitor).context("could not get m_info")?;
     let screen_width = (m_info.rcMonitor.right - m_info.rcMonitor.left) as u32;
@@ -447,17 +1082,34 @@ pub fn get_monitor_resolution(hmonitor: HMONITOR) -> anyhow::Result<(u32, u32)>
 }
 
 pub fn destroy_border_for_window(tracking_window: HWND) {
-    if let Some(&border_isize) = APP_STATE
+    forget_window(tracking_window);
+
+    let mut borders_hashmap = APP_STATE.borders.lock().unwrap();
+
+    // Remove the border from the hashmap right away so that no new messages are routed to it
+    if let Some(border_isize) = borders_hashmap.remove(&(tracking_window.0 as isize)) {
+        request_border_teardown(&borders_hashmap, border_isize, true);
+    }
+}
+
+// Normally, borders are told to close by the event hook, but that never happens if we miss the
+// tracking window's destroy event. This runs on a timer on the event hook's thread to catch those.
+//
+// NOTE: if tacky-borders itself gets killed, Windows destroys the border windows along with the
+// process, so there's nothing left for us to clean up in that case.
+pub extern "system" fn destroy_orphaned_borders(_: HWND, _: u32, _: usize, _: u32) {
+    let orphans: Vec<isize> = APP_STATE
         .borders
         .lock()
         .unwrap()
-        .get(&(tracking_window.0 as isize))
-    {
-        let border_window = HWND(border_isize as _);
-
-        post_message_w(Some(border_window), WM_NCDESTROY, WPARAM(0), LPARAM(0))
-            .context("destroy_border_for_window")
-            .log_if_err();
+        .keys()
+        .copied()
+        .filter(|tracking_isize| !unsafe { IsWindow(Some(HWND(*tracking_isize as _))) }.as_bool())
+        .collect();
+
+    for tracking_isize in orphans {
+        debug!("tracking window {tracking_isize:#x} no longer exists; destroying orphaned border");
+        destroy_border_for_window(HWND(tracking_isize as _));
     }
 }
 
@@ -486,7 +1138,10 @@ pub fn show_border_for_window(hwnd: HWND) {
         let window_rule = get_window_rule(hwnd);
 
         if window_rule.enabled == Some(EnableMode::Bool(false)) {
-            info!("border is disabled for {hwnd:?}");
+            info!(
+                "border is disabled for {hwnd:?} by window rule '{}'",
+                window_rule.display_name()
+            );
         } else if window_rule.enabled == Some(EnableMode::Bool(true)) || !has_filtered_style(hwnd) {
             create_border_for_window(hwnd, window_rule);
         }
@@ -538,7 +1193,7 @@ struct Point {
     y: f32,
 }
 
-fn lerp(t: f32, p1: f32, p2: f32) -> f32 {
+pub fn lerp(t: f32, p1: f32, p2: f32) -> f32 {
     p1 + (p2 - p1) * t
 }
 
@@ -617,10 +1272,182 @@ pub fn cubic_bezier(control_points: &[f32; 4]) -> Result<impl Fn(f32) -> f32 + u
     })
 }
 
+// Parses CSS-style control points like "cubic-bezier(0.25, 0.1, 0.25, 1.0)"
+pub fn parse_cubic_bezier(text: &str) -> Result<[f32; 4], String> {
+    let invalid = || {
+        format!(
+            "invalid easing '{text}'; expected a named easing or \
+            \"cubic-bezier(<x1>, <y1>, <x2>, <y2>)\""
+        )
+    };
+
+    let args = text
+        .trim()
+        .strip_prefix("cubic-bezier(")
+        .and_then(|rest| rest.strip_suffix(')'))
+        .ok_or_else(invalid)?;
+    let points: Vec<f32> = args
+        .split(',')
+        .map(|arg| arg.trim().parse::<f32>())
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+
+    match points[..] {
+        [x1, y1, x2, y2] if points.iter().all(|point| point.is_finite()) => Ok([x1, y1, x2, y2]),
+        _ => Err(invalid()),
+    }
+}
+
+// Bounce and elastic easings go back and forth more than a cubic bezier can, so they're computed
+// directly. These follow the usual formulas (see easings.net).
+const ELASTIC_PERIOD: f32 = 2.0 * PI / 3.0;
+const ELASTIC_IN_OUT_PERIOD: f32 = 2.0 * PI / 4.5;
+
+pub fn ease_out_bounce(x: f32) -> f32 {
+    // Each bounce is a parabola that's a fraction of the height of the previous one
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+
+    if x < 1.0 / D {
+        N * x * x
+    } else if x < 2.0 / D {
+        let x = x - 1.5 / D;
+        N * x * x + 0.75
+    } else if x < 2.5 / D {
+        let x = x - 2.25 / D;
+        N * x * x + 0.9375
+    } else {
+        let x = x - 2.625 / D;
+        N * x * x + 0.984375
+    }
+}
+
+pub fn ease_in_bounce(x: f32) -> f32 {
+    1.0 - ease_out_bounce(1.0 - x)
+}
+
+pub fn ease_in_out_bounce(x: f32) -> f32 {
+    if x < 0.5 {
+        (1.0 - ease_out_bounce(1.0 - 2.0 * x)) / 2.0
+    } else {
+        (1.0 + ease_out_bounce(2.0 * x - 1.0)) / 2.0
+    }
+}
+
+pub fn ease_in_elastic(x: f32) -> f32 {
+    if x <= 0.0 || x >= 1.0 {
+        return x.clamp(0.0, 1.0);
+    }
+
+    -(2.0f32.powf(10.0 * x - 10.0)) * ((10.0 * x - 10.75) * ELASTIC_PERIOD).sin()
+}
+
+pub fn ease_out_elastic(x: f32) -> f32 {
+    if x <= 0.0 || x >= 1.0 {
+        return x.clamp(0.0, 1.0);
+    }
+
+    2.0f32.powf(-10.0 * x) * ((10.0 * x - 0.75) * ELASTIC_PERIOD).sin() + 1.0
+}
+
+pub fn ease_in_out_elastic(x: f32) -> f32 {
+    if x <= 0.0 || x >= 1.0 {
+        return x.clamp(0.0, 1.0);
+    }
+
+    let wave = ((20.0 * x - 11.125) * ELASTIC_IN_OUT_PERIOD).sin();
+    if x < 0.5 {
+        -(2.0f32.powf(20.0 * x - 10.0) * wave) / 2.0
+    } else {
+        2.0f32.powf(-20.0 * x + 10.0) * wave / 2.0 + 1.0
+    }
+}
+
+// Standard CRC-32 (the one zip and png use). It's only used for small files, so we don't bother
+// with a lookup table.
+pub fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = match crc & 1 {
+                1 => (crc >> 1) ^ 0xEDB8_8320,
+                _ => crc >> 1,
+            };
+        }
+    }
+
+    !crc
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_does_build_match() -> anyhow::Result<()> {
+        assert!(does_build_match(">=22621", 22631)?);
+        assert!(does_build_match(">= 22621", 22621)?);
+        assert!(!does_build_match(">=22621", 22000)?);
+        assert!(does_build_match("<22000", 19045)?);
+        assert!(!does_build_match(">19045", 19045)?);
+        assert!(does_build_match("19045", 19045)?);
+        assert!(does_build_match("!=19045", 22000)?);
+
+        assert!(does_build_match("~22621", 22621).is_err());
+        assert!(does_build_match(">=", 22621).is_err());
+        assert!(does_build_match(">=22621a", 22621).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_snap_stroke_rect() {
+        let rect = D2D_RECT_F {
+            left: 0.8,
+            top: 1.2,
+            right: 100.4,
+            bottom: 50.6,
+        };
+
+        let odd = snap_stroke_rect(&rect, 1.0);
+        assert_eq!(
+            (odd.left, odd.top, odd.right, odd.bottom),
+            (0.5, 1.5, 100.5, 50.5)
+        );
+
+        let even = snap_stroke_rect(&rect, 2.0);
+        assert_eq!(
+            (even.left, even.top, even.right, even.bottom),
+            (1.0, 1.0, 100.0, 51.0)
+        );
+    }
+
+    #[test]
+    fn test_crc32() {
+        assert_eq!(crc32(b""), 0);
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn test_redact_title() {
+        let title = "inbox - someone@example.com";
+
+        assert_eq!(redact_title(title, TitlePrivacy::Full), title);
+        assert_eq!(
+            redact_title(title, TitlePrivacy::Truncate),
+            "inbox - some..."
+        );
+        assert_eq!(redact_title("short", TitlePrivacy::Truncate), "short");
+
+        let hashed = redact_title(title, TitlePrivacy::Hash);
+        assert!(!hashed.contains("someone"));
+        assert_eq!(hashed, redact_title(title, TitlePrivacy::Hash));
+        assert_ne!(hashed, redact_title("other", TitlePrivacy::Hash));
+
+        assert_eq!(redact_title("", TitlePrivacy::Hash), "");
+    }
+
     #[test]
     fn test_cubic_bezier() -> anyhow::Result<()> {
         let easing_fn = cubic_bezier(&[0.45, 0.0, 0.55, 1.0])?;
@@ -633,4 +1460,48 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_parse_cubic_bezier() {
+        assert_eq!(
+            parse_cubic_bezier("cubic-bezier(0.25, 0.1, 0.25, 1.0)"),
+            Ok([0.25, 0.1, 0.25, 1.0])
+        );
+        assert_eq!(
+            parse_cubic_bezier(" cubic-bezier(0,-0.5,1,1.5) "),
+            Ok([0.0, -0.5, 1.0, 1.5])
+        );
+
+        assert!(parse_cubic_bezier("cubic-bezier(0.25, 0.1, 0.25)").is_err());
+        assert!(parse_cubic_bezier("cubic-bezier(0.25, 0.1, 0.25, 1.0, 1.0)").is_err());
+        assert!(parse_cubic_bezier("cubic-bezier(a, 0.1, 0.25, 1.0)").is_err());
+        assert!(parse_cubic_bezier("bezier(0.25, 0.1, 0.25, 1.0)").is_err());
+        assert!(parse_cubic_bezier("EaseInOutBouncy").is_err());
+    }
+
+    #[test]
+    fn test_bounce_and_elastic_easings() {
+        let easing_fns: [fn(f32) -> f32; 6] = [
+            ease_in_bounce,
+            ease_out_bounce,
+            ease_in_out_bounce,
+            ease_in_elastic,
+            ease_out_elastic,
+            ease_in_out_elastic,
+        ];
+        for easing_fn in easing_fns {
+            assert!(easing_fn(0.0).abs() < 0.001);
+            assert!((easing_fn(1.0) - 1.0).abs() < 0.001);
+        }
+
+        // The bounces touch back down at the end of each arc, but never go past the end
+        assert!((ease_out_bounce(1.0 / 2.75) - 1.0).abs() < 0.001);
+        assert!((0.0..=1.0).contains(&ease_out_bounce(0.5)));
+        assert!((ease_in_out_bounce(0.5) - 0.5).abs() < 0.001);
+
+        // Elastic easings overshoot before settling
+        assert!(ease_out_elastic(0.1) > 1.0);
+        assert!(ease_in_elastic(0.9) < 0.0);
+        assert!((ease_in_out_elastic(0.5) - 0.5).abs() < 0.001);
+    }
 }
@@ -0,0 +1,207 @@
+// Backs the "wallpaper" color keyword, which resolves to the average color of the desktop
+// wallpaper. Slideshows and Windows Spotlight swap the wallpaper without telling anyone, so a
+// background thread checks the wallpaper file every few seconds and samples it again whenever it
+// changes. The borders that use the keyword then cross-fade to the new color. Once none of them
+// are left, the thread stops.
+use anyhow::Context;
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::os::windows::ffi::OsStringExt;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex, RwLock};
+use std::{env, fs, mem, thread, time};
+use windows::Win32::Foundation::{GENERIC_READ, HWND, LPARAM, MAX_PATH, WPARAM};
+use windows::Win32::Graphics::Direct2D::Common::D2D1_COLOR_F;
+use windows::Win32::Graphics::Imaging::{
+    CLSID_WICImagingFactory, GUID_WICPixelFormat32bppBGRA, IWICImagingFactory, IWICPalette,
+    WICBitmapDitherTypeNone, WICBitmapInterpolationModeFant, WICBitmapPaletteTypeCustom,
+    WICDecodeMetadataCacheOnDemand,
+};
+use windows::Win32::System::Com::{
+    CLSCTX_INPROC_SERVER, COINIT_MULTITHREADED, CoCreateInstance, CoInitializeEx,
+};
+use windows::Win32::UI::WindowsAndMessaging::{
+    SPI_GETDESKWALLPAPER, SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS, SystemParametersInfoW,
+};
+use windows::core::HSTRING;
+
+use crate::utils::{LogIfErr, WM_APP_WALLPAPER, post_message_w};
+
+const POLL_INTERVAL: time::Duration = time::Duration::from_secs(5);
+// We only need the average color, so the wallpaper gets scaled way down before we look at it
+const SAMPLE_SIZE: u32 = 32;
+// How long the borders take to fade over to the new wallpaper's color
+pub const WALLPAPER_CROSSFADE: time::Duration = time::Duration::from_millis(600);
+
+// None until the watcher has sampled the wallpaper (or if there isn't one)
+static WALLPAPER_COLOR: RwLock<Option<D2D1_COLOR_F>> = RwLock::new(None);
+// The border windows whose colors use the "wallpaper" keyword. The watcher runs while this isn't
+// empty, and both only change while holding this lock.
+static WALLPAPER_BORDERS: LazyLock<Mutex<WallpaperBorders>> =
+    LazyLock::new(|| Mutex::new(WallpaperBorders::default()));
+
+#[derive(Default)]
+struct WallpaperBorders {
+    borders: HashSet<isize>,
+    is_watcher_running: bool,
+}
+
+pub fn get_wallpaper_color() -> Option<D2D1_COLOR_F> {
+    *WALLPAPER_COLOR.read().unwrap()
+}
+
+// Called by each border whenever it resolves its colors, and with false when it's destroyed
+pub fn set_uses_wallpaper(border_window: HWND, uses_wallpaper: bool) {
+    let mut wallpaper_borders = WALLPAPER_BORDERS.lock().unwrap();
+
+    match uses_wallpaper {
+        true => wallpaper_borders.borders.insert(border_window.0 as isize),
+        false => wallpaper_borders
+            .borders
+            .remove(&(border_window.0 as isize)),
+    };
+
+    if uses_wallpaper && !wallpaper_borders.is_watcher_running {
+        wallpaper_borders.is_watcher_running = true;
+        start_watcher();
+    }
+}
+
+fn start_watcher() {
+    debug!("starting wallpaper watcher");
+
+    let _ = thread::spawn(|| {
+        // WIC is COM-based
+        if let Err(err) = unsafe { CoInitializeEx(None, COINIT_MULTITHREADED) }.ok() {
+            error!("could not initialize COM for the wallpaper watcher: {err}");
+        }
+
+        // The wallpaper's path and when it was last modified
+        let mut last_signature = None;
+
+        loop {
+            let signature = get_wallpaper_path().map(|path| {
+                let modified = fs::metadata(&path).and_then(|metadata| metadata.modified());
+                (path, modified.ok())
+            });
+
+            if signature != last_signature {
+                let color = signature.as_ref().and_then(|(path, _)| {
+                    sample_wallpaper_color(path)
+                        .inspect_err(|err| error!("could not sample wallpaper color: {err:#}"))
+                        .ok()
+                });
+                last_signature = signature;
+
+                let old_color = mem::replace(&mut *WALLPAPER_COLOR.write().unwrap(), color);
+                if old_color != color {
+                    debug!("wallpaper color has changed to {color:?}");
+                    notify_borders();
+                }
+            }
+
+            thread::sleep(POLL_INTERVAL);
+
+            let mut wallpaper_borders = WALLPAPER_BORDERS.lock().unwrap();
+            if wallpaper_borders.borders.is_empty() {
+                debug!("stopping wallpaper watcher");
+                wallpaper_borders.is_watcher_running = false;
+                break;
+            }
+        }
+    });
+}
+
+fn notify_borders() {
+    for border_isize in WALLPAPER_BORDERS.lock().unwrap().borders.iter() {
+        post_message_w(
+            Some(HWND(*border_isize as _)),
+            WM_APP_WALLPAPER,
+            WPARAM(0),
+            LPARAM(0),
+        )
+        .context("could not post WM_APP_WALLPAPER message")
+        .log_if_err();
+    }
+}
+
+// Returns None if the desktop background is a solid color
+fn get_wallpaper_path() -> Option<PathBuf> {
+    let mut buffer = [0u16; MAX_PATH as usize];
+    unsafe {
+        SystemParametersInfoW(
+            SPI_GETDESKWALLPAPER,
+            buffer.len() as u32,
+            Some(buffer.as_mut_ptr() as _),
+            SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+        )
+    }
+    .ok()?;
+
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    if len == 0 {
+        return None;
+    }
+
+    // Windows keeps a copy of whatever wallpaper is showing, including slideshow and Spotlight
+    // images, so we prefer that over the original file
+    let transcoded_path = env::var_os("APPDATA").map(|appdata| {
+        PathBuf::from(appdata).join(r"Microsoft\Windows\Themes\TranscodedWallpaper")
+    });
+
+    transcoded_path
+        .filter(|path| path.is_file())
+        .or_else(|| Some(PathBuf::from(OsString::from_wide(&buffer[..len]))))
+}
+
+fn sample_wallpaper_color(path: &Path) -> anyhow::Result<D2D1_COLOR_F> {
+    let mut pixels = vec![0u8; (SAMPLE_SIZE * SAMPLE_SIZE * 4) as usize];
+
+    unsafe {
+        let factory: IWICImagingFactory =
+            CoCreateInstance(&CLSID_WICImagingFactory, None, CLSCTX_INPROC_SERVER)?;
+        let decoder = factory
+            .CreateDecoderFromFilename(
+                &HSTRING::from(path.as_os_str()),
+                None,
+                GENERIC_READ,
+                WICDecodeMetadataCacheOnDemand,
+            )
+            .with_context(|| format!("could not decode {}", path.display()))?;
+        let frame = decoder.GetFrame(0)?;
+
+        let scaler = factory.CreateBitmapScaler()?;
+        scaler.Initialize(
+            &frame,
+            SAMPLE_SIZE,
+            SAMPLE_SIZE,
+            WICBitmapInterpolationModeFant,
+        )?;
+
+        let converter = factory.CreateFormatConverter()?;
+        converter.Initialize(
+            &scaler,
+            &GUID_WICPixelFormat32bppBGRA,
+            WICBitmapDitherTypeNone,
+            None::<&IWICPalette>,
+            0.0,
+            WICBitmapPaletteTypeCustom,
+        )?;
+        converter.CopyPixels(std::ptr::null(), SAMPLE_SIZE * 4, &mut pixels)?;
+    }
+
+    let (mut r, mut g, mut b) = (0.0, 0.0, 0.0);
+    for pixel in pixels.chunks_exact(4) {
+        b += pixel[0] as f32;
+        g += pixel[1] as f32;
+        r += pixel[2] as f32;
+    }
+    let count = (SAMPLE_SIZE * SAMPLE_SIZE) as f32 * 255.0;
+
+    Ok(D2D1_COLOR_F {
+        r: r / count,
+        g: g / count,
+        b: b / count,
+        a: 1.0,
+    })
+}
@@ -5,39 +5,64 @@ use std::time;
 use windows::Win32::Foundation::{
     COLORREF, D2DERR_RECREATE_TARGET, FALSE, HWND, LPARAM, LRESULT, RECT, TRUE, WPARAM,
 };
-use windows::Win32::Graphics::Direct2D::Common::D2D_SIZE_U;
-use windows::Win32::Graphics::Direct2D::{D2D1_BRUSH_PROPERTIES, ID2D1RenderTarget};
+use windows::Win32::Graphics::Direct2D::Common::{D2D_SIZE_U, D2D1_COLOR_F};
+use windows::Win32::Graphics::Direct2D::D2D1_ROUNDED_RECT;
 use windows::Win32::Graphics::Dwm::{
-    DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND, DWMWA_EXTENDED_FRAME_BOUNDS,
-    DwmEnableBlurBehindWindow, DwmGetWindowAttribute,
+    DWM_BB_BLURREGION, DWM_BB_ENABLE, DWM_BLURBEHIND, DWM_WINDOW_CORNER_PREFERENCE,
+    DWMWA_EXTENDED_FRAME_BOUNDS, DWMWCP_DEFAULT, DwmEnableBlurBehindWindow, DwmGetWindowAttribute,
 };
-use windows::Win32::Graphics::Gdi::{CreateRectRgn, HMONITOR, ValidateRect};
+use windows::Win32::Graphics::Gdi::{CreateRectRgn, DeleteObject, HMONITOR, HRGN, ValidateRect};
 use windows::Win32::UI::HiDpi::MDT_DEFAULT;
 use windows::Win32::UI::WindowsAndMessaging::{
-    CREATESTRUCTW, CW_USEDEFAULT, CreateWindowExW, DefWindowProcW, DispatchMessageW, GW_HWNDPREV,
-    GWLP_USERDATA, GetMessageW, GetSystemMetrics, GetWindow, GetWindowLongPtrW, HWND_TOP,
-    LWA_ALPHA, MSG, PostQuitMessage, SET_WINDOW_POS_FLAGS, SM_CXVIRTUALSCREEN, SWP_HIDEWINDOW,
-    SWP_NOACTIVATE, SWP_NOREDRAW, SWP_NOSENDCHANGING, SWP_NOZORDER, SWP_SHOWWINDOW,
-    SetLayeredWindowAttributes, SetWindowLongPtrW, SetWindowPos, TranslateMessage, WM_CREATE,
-    WM_DISPLAYCHANGE, WM_DPICHANGED, WM_NCDESTROY, WM_PAINT, WM_WINDOWPOSCHANGED,
-    WM_WINDOWPOSCHANGING, WS_DISABLED, WS_EX_LAYERED, WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT,
-    WS_POPUP,
+    CREATESTRUCTW, CW_USEDEFAULT, CreateWindowExW, DefWindowProcW, DestroyWindow, DispatchMessageW,
+    GW_HWNDNEXT, GW_HWNDPREV, GWLP_USERDATA, GetMessageW, GetSystemMetrics, GetWindow,
+    GetWindowLongPtrW, HWND_TOP, IsWindow, KillTimer, LWA_ALPHA, MSG, PostQuitMessage,
+    SET_WINDOW_POS_FLAGS, SM_CXVIRTUALSCREEN, SPI_SETHIGHCONTRAST, SWP_HIDEWINDOW, SWP_NOACTIVATE,
+    SWP_NOMOVE, SWP_NOREDRAW, SWP_NOSENDCHANGING, SWP_NOSIZE, SWP_NOZORDER, SWP_SHOWWINDOW,
+    SetLayeredWindowAttributes, SetTimer, SetWindowLongPtrW, SetWindowPos, TranslateMessage,
+    WM_CLOSE, WM_CREATE, WM_DISPLAYCHANGE, WM_DPICHANGED, WM_DWMCOLORIZATIONCOLORCHANGED,
+    WM_GETOBJECT, WM_NCDESTROY, WM_PAINT, WM_SETTINGCHANGE, WM_SYSCOLORCHANGE, WM_THEMECHANGED,
+    WM_TIMER, WM_WINDOWPOSCHANGED, WM_WINDOWPOSCHANGING, WS_DISABLED, WS_EX_LAYERED,
+    WS_EX_TOOLWINDOW, WS_EX_TRANSPARENT, WS_POPUP,
 };
 use windows::core::{PCWSTR, w};
 
 use crate::APP_STATE;
-use crate::animations::{AnimType, AnimVec};
-use crate::border_drawer::BorderDrawer;
-use crate::config::WindowRule;
-use crate::komorebi::WindowKind;
+use crate::acrylic::{enable_acrylic, update_acrylic_region};
+use crate::animations::{AnimType, AnimVec, AnimationsConfig, PresenceMotion};
+use crate::autodetect::is_komorebi_detected;
+use crate::bench;
+use crate::border_drawer::{BorderDrawer, BorderEdges, BorderOffset, Outline, PresenceTransform};
+use crate::border_props::{clear_border_props, publish_border_props};
+use crate::colors::ColorBrushConfig;
+use crate::config::{
+    BorderEdge, Config, EnableMode, Global, IdleDimConfig, OutlineSides, ResourceColorsConfig,
+    WindowRule, ZOrder, get_border_width,
+};
+use crate::dormancy;
+use crate::error::{BorderError, record_border_error};
+use crate::komorebi::{KomorebiGapMode, KomorebiStatus, WindowKind};
+use crate::position_batch;
+use crate::proximity;
 use crate::render_backend::{RenderBackend, RenderBackendConfig};
+use crate::resource_sampler::ResourceSampler;
+use crate::uia;
 use crate::utils::{
-    LogIfErr, T_E_UNINIT, WM_APP_ANIMATE, WM_APP_FOREGROUND, WM_APP_HIDECLOAKED, WM_APP_KOMOREBI,
-    WM_APP_LOCATIONCHANGE, WM_APP_MINIMIZEEND, WM_APP_MINIMIZESTART, WM_APP_REORDER,
-    WM_APP_SHOWUNCLOAKED, are_rects_same_size, get_dpi_for_monitor, get_monitor_resolution,
-    get_window_rule, get_window_title, has_native_border, is_rect_visible, is_window_minimized,
-    is_window_visible, loword, monitor_from_window, post_message_w,
+    CROSSFADE_TIMER_ID, ELIGIBILITY_TIMER_ID, FADE_OUT_TIMER_ID, IDLE_DIM_TIMER_ID, LogIfErr,
+    PRESENCE_TIMER_ID, PlacementState, RECT_SETTLE_TIMER_ID, T_E_UNINIT, WM_APP_ANIMATE,
+    WM_APP_DORMANT, WM_APP_FOREGROUND, WM_APP_HIDECLOAKED, WM_APP_KOMOREBI, WM_APP_KOMOREBI_GAPS,
+    WM_APP_LOCATIONCHANGE, WM_APP_MINIMIZEEND, WM_APP_MINIMIZESTART, WM_APP_POSITIONED,
+    WM_APP_PROXIMITY, WM_APP_REORDER, WM_APP_RESOURCES, WM_APP_SCHEDULE, WM_APP_SETCOLOR,
+    WM_APP_SHOWUNCLOAKED, WM_APP_WALLPAPER, are_rects_same_size, get_dpi_for_monitor,
+    get_monitor_resolution, get_placement_state, get_taskbar_edge, get_window_class,
+    get_window_corner_preference, get_window_region, get_window_rule, get_window_title_for_display,
+    get_work_area_edges, has_filtered_style, has_native_border, is_borderless_fullscreen,
+    is_maximized_with_native_border, is_rect_visible, is_window_cloaked,
+    is_window_cloaked_by_shell, is_window_minimized, is_window_visible, loword,
+    monitor_from_window, post_message_w, restore_window_region, set_window_corner_preference,
+    set_window_corner_region,
 };
+use crate::wallpaper::{self, WALLPAPER_CROSSFADE};
 
 #[derive(Debug, Default, Clone)]
 pub struct WindowBorder {
@@ -52,6 +77,97 @@ pub struct WindowBorder {
     initialize_delay: u64,
     unminimize_delay: u64,
     is_paused: bool,
+    // Set while the tracking window is on another virtual desktop. See suspend().
+    is_suspended: bool,
+    // Set while the app is dormant and this border has dropped its renderer. See go_dormant().
+    is_dormant: bool,
+    // The tracking window's corner preference from before we changed it, so we can put it back
+    original_corner_preference: Option<DWM_WINDOW_CORNER_PREFERENCE>,
+    // The radius (before DPI scaling) to clip the tracking window to when window_corner can't use a
+    // corner preference, and the (width, height, scaled radius) that the region was last built for
+    window_corner_region: Option<f32>,
+    window_corner_region_key: Option<(i32, i32, i32)>,
+    // The tracking window's own region from before we clipped it, which we put back afterwards
+    original_window_region: Option<HRGN>,
+    resource_colors: Option<ResourceColorsConfig>,
+    resource_sampler: Option<ResourceSampler>,
+    resource_level: usize, // 0 means no resource threshold is exceeded
+    idle_dim: Option<IdleDimConfig>,
+    last_active_time: Option<time::Instant>,
+    idle_dim_amount: f32,
+    render_backend_config: RenderBackendConfig,
+    is_force_enabled: bool, // The window rule has 'enabled: True', so styles are ignored
+    is_eligibility_pending: bool,
+    fade_out_start: Option<time::Instant>,
+    // The border faded out because the window lost its eligibility. It stays hidden until the
+    // window is eligible again.
+    is_ineligible: bool,
+    // The window wasn't already open when tacky-borders started, so it gets the open animation
+    is_new_window: bool,
+    // When the open/close animation started, if one is running
+    presence_start: Option<time::Instant>,
+    presence_kind: PresenceKind,
+    // Which side of the monitor the taskbar is on, for the restore animations
+    taskbar_edge: BorderEdge,
+    // The Slide restore animation moves the whole border window, since sliding the border within
+    // it would get the border clipped
+    presence_offset: (i32, i32),
+    // Set by the proximity tracker while proximity_reveal is enabled
+    proximity_opacity: Option<f32>,
+    hide_time: Option<time::Instant>,
+    // Set at runtime through set_color_override(). NOTE: this doesn't survive reloads.
+    color_override: Option<ColorOverride>,
+    rect_hysteresis: i32,
+    is_rect_change_pending: bool,
+    // Set while our move is waiting in a position batch. The border window still has its old size
+    // until then, so we hold off on rendering.
+    is_position_deferred: bool,
+    // How much border_offset was reduced to fit within komorebi's gaps (always <= 0)
+    komorebi_offset_adjustment: i32,
+    has_warned_komorebi_gap: bool,
+    is_title_private: bool,
+    // Maximized/snapped/floating, which can each have their own border width
+    placement: PlacementState,
+    z_order: ZOrder,
+    // Window classes of other tools' overlays that may sit between the border and its window
+    friendly_overlays: Vec<String>,
+    show_when_maximized: bool,
+    show_when_fullscreen: bool,
+    is_fullscreen: bool,
+    acrylic_tint: Option<D2D1_COLOR_F>,
+    // The render_rect that the acrylic's window region was last built from
+    acrylic_region_rect: Option<D2D1_ROUNDED_RECT>,
+}
+
+// Payload for WM_APP_SETCOLOR. Colors that are None fall back to the config as usual, so sending
+// this without any colors clears the override.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ColorOverride {
+    pub active_color: Option<ColorBrushConfig>,
+    pub inactive_color: Option<ColorBrushConfig>,
+    // Milliseconds to cross-fade from the old colors. 0 swaps them instantly.
+    pub crossfade: u64,
+}
+
+// How long a window has to stay (in)eligible before we act on it. Some apps briefly toggle styles
+// like WS_EX_TOOLWINDOW or hide themselves while switching modes, which would otherwise make the
+// border flicker.
+const ELIGIBILITY_DEBOUNCE_MS: u32 = 250;
+const FADE_OUT_DURATION: time::Duration = time::Duration::from_millis(150);
+// Windows usually hide themselves right before they're destroyed, so borders that were hidden this
+// recently still play the close animation
+const CLOSE_ANIM_GRACE: time::Duration = time::Duration::from_millis(250);
+// How far the Slide restore animation starts from, as a fraction of the window's size
+const SLIDE_DISTANCE: f32 = 0.15;
+// How long a rect change within the hysteresis has to stick around before we apply it anyways
+const RECT_SETTLE_MS: u32 = 150;
+
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+enum PresenceKind {
+    #[default]
+    Open,
+    Close,
+    Restore,
 }
 
 #[derive(Debug, Default, Clone, Copy, PartialEq)]
@@ -79,10 +195,16 @@ impl WindowBorder {
         }
     }
 
-    pub fn create_window(&mut self) -> windows::core::Result<HWND> {
+    // Must be called before create_window() since the border window's title includes the tracking
+    // window's title
+    pub fn set_title_private(&mut self, is_title_private: bool) {
+        self.is_title_private = is_title_private;
+    }
+
+    pub fn create_window(&mut self) -> anyhow::Result<HWND> {
         let title: Vec<u16> = format!(
             "tacky-border | {} | {:?}\0",
-            get_window_title(self.tracking_window).unwrap_or_default(),
+            get_window_title_for_display(self.tracking_window, self.is_title_private),
             self.tracking_window
         )
         .encode_utf16()
@@ -102,7 +224,8 @@ impl WindowBorder {
                 None,
                 None,
                 Some(ptr::addr_of!(*self) as _),
-            )?;
+            )
+            .map_err(BorderError::WindowCreation)?;
         }
 
         Ok(self.border_window)
@@ -115,10 +238,43 @@ impl WindowBorder {
                 self.cleanup_and_queue_exit();
                 anyhow!("could not get dpi for {:?}: {}", self.current_monitor, err)
             })?;
+
+        // This goes first since border_radius: Auto is based on the window's corner preference
+        if let Some(window_corner) = window_rule.window_corner {
+            // The window may have picked its own preference, which we'll want to restore later
+            let original_preference =
+                get_window_corner_preference(self.tracking_window).unwrap_or(DWMWCP_DEFAULT);
+            let preference_res = window_corner
+                .to_corner_preference()
+                .map(|preference| set_window_corner_preference(self.tracking_window, preference));
+
+            match preference_res {
+                Some(Ok(())) => self.original_corner_preference = Some(original_preference),
+                // Windows 10 doesn't have corner preferences, so we clip the window instead
+                _ => {
+                    if let Some(Err(err)) = preference_res {
+                        debug!("could not set window_corner preference; using a region: {err:#}");
+                    }
+
+                    let config = APP_STATE.config.read().unwrap();
+                    let radius_config = window_rule
+                        .border_radius
+                        .as_ref()
+                        .unwrap_or(&config.global.border_radius);
+                    let radius =
+                        window_corner.get_region_radius(radius_config, self.tracking_window);
+                    self.window_corner_region = (radius > 0.0).then_some(radius);
+                }
+            }
+        }
         self.load_from_config(window_rule, self.current_dpi)?;
+        self.update_komorebi_gap_adjustment();
 
-        // Delay the border while the tracking window is in its creation animation
-        thread::sleep(time::Duration::from_millis(self.initialize_delay));
+        // Delay the border while the tracking window is in its creation animation. The open
+        // animation plays alongside it instead.
+        if !self.should_animate_open() {
+            thread::sleep(time::Duration::from_millis(self.initialize_delay));
+        }
 
         unsafe {
             // Make the window transparent (stole the code from PowerToys; dunno how it works).
@@ -134,11 +290,15 @@ impl WindowBorder {
                 };
             }
             DwmEnableBlurBehindWindow(self.border_window, &bh)
-                .context("could not make window transparent")?;
+                .map_err(|err| BorderError::Dwm("could not make window transparent", err))?;
 
             SetLayeredWindowAttributes(self.border_window, COLORREF(0x00000000), 255, LWA_ALPHA)
                 .context("could not set LWA_ALPHA")?;
 
+            if let Some(ref tint) = self.acrylic_tint {
+                enable_acrylic(self.border_window, tint).log_if_err();
+            }
+
             let (screen_width, screen_height) = get_monitor_resolution(self.current_monitor)
                 .context("could not get monitor resolution")?;
 
@@ -148,34 +308,52 @@ impl WindowBorder {
                 self.border_drawer.border_width,
                 self.window_padding,
             );
+            // The DirectX devices might've been released while we were dormant
+            dormancy::wake();
             self.border_drawer
                 .init_renderer(
                     renderer_size.width,
                     renderer_size.height,
                     self.border_window,
+                    self.tracking_window,
                     &self.window_rect,
-                    APP_STATE.config.read().unwrap().render_backend,
+                    self.render_backend_config,
                 )
                 .context("could not initialize border drawer in init()")?;
 
             self.update_color(Some(self.initialize_delay)).log_if_err();
-            self.update_window_rect().log_if_err();
 
-            if has_native_border(self.tracking_window) {
-                self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
+            if self.is_native_dwm() {
+                // The border window itself stays hidden; we only use it to receive messages
+                dormancy::set_border_visible(self.border_window, true);
                 self.render().log_if_err();
+            } else {
+                self.update_window_rect().log_if_err();
 
-                // TODO: sometimes, the border doesn't show up on the first try. So, we just wait
-                // 5ms and call render() again. This seems to be an issue with the visibility of
-                // the window itself.
-                thread::sleep(time::Duration::from_millis(5));
-                self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
-                self.render().log_if_err();
+                if self.should_show_border() {
+                    if self.should_animate_open() {
+                        self.start_presence_anim(PresenceKind::Open);
+                    }
+
+                    self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
+                    self.render().log_if_err();
+
+                    // TODO: sometimes, the border doesn't show up on the first try. So, we just
+                    // wait 5ms and call render() again. This seems to be an issue with the
+                    // visibility of the window itself.
+                    thread::sleep(time::Duration::from_millis(5));
+                    self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
+                    self.render().log_if_err();
+                }
+
+                self.border_drawer.animations.set_timer_if_enabled(
+                    self.border_window,
+                    &mut self.border_drawer.last_anim_time,
+                );
             }
 
-            self.border_drawer
-                .animations
-                .set_timer_if_enabled(self.border_window, &mut self.border_drawer.last_anim_time);
+            self.update_window_corner_region();
+            self.start_background_work();
 
             // Handle the edge case where the tracking window is already minimized
             if is_window_minimized(self.tracking_window) {
@@ -204,36 +382,127 @@ impl WindowBorder {
         let config = APP_STATE.config.read().unwrap();
         let global = &config.global;
 
-        let width_config = window_rule.border_width.unwrap_or(global.border_width);
-        let offset_config = window_rule.border_offset.unwrap_or(global.border_offset);
+        self.render_backend_config = window_rule.render_backend.unwrap_or(config.render_backend);
+        self.is_force_enabled = window_rule.enabled == Some(EnableMode::Bool(true));
+        self.z_order = window_rule.z_order.unwrap_or(global.z_order);
+        self.friendly_overlays = config.friendly_overlays.clone();
+        self.show_when_maximized = window_rule
+            .show_when_maximized
+            .unwrap_or(global.show_when_maximized);
+        self.show_when_fullscreen = window_rule.show_when_fullscreen.unwrap_or(false);
+        self.is_fullscreen =
+            self.show_when_fullscreen && is_borderless_fullscreen(self.tracking_window);
+
+        self.placement = get_placement_state(self.tracking_window);
+
+        // These need to be set before we call self.get_color_configs() below
+        self.resource_colors = window_rule.resource_colors.clone();
+        self.idle_dim = Some(window_rule.idle_dim.as_ref().unwrap_or(&global.idle_dim))
+            .filter(|idle_dim| idle_dim.enabled && !config.is_presentation_mode_active())
+            .cloned();
+
+        let offset_config = window_rule
+            .border_offset
+            .as_ref()
+            .unwrap_or(&global.border_offset);
         let radius_config = window_rule
             .border_radius
             .as_ref()
             .unwrap_or(&global.border_radius);
-        let active_color_config = window_rule
-            .active_color
-            .as_ref()
-            .unwrap_or(&global.active_color);
-        let inactive_color_config = window_rule
-            .inactive_color
-            .as_ref()
-            .unwrap_or(&global.inactive_color);
-        let animations_config = window_rule
-            .animations
-            .as_ref()
-            .unwrap_or(&global.animations);
+        let radius_compensation = window_rule
+            .radius_compensation
+            .unwrap_or(global.radius_compensation);
+        let (active_color_config, inactive_color_config) =
+            self.get_color_configs(&config, &window_rule);
+        // Presentation mode turns animations off
+        let no_animations = AnimationsConfig::default();
+        let animations_config = match config.is_presentation_mode_active() {
+            true => &no_animations,
+            false => window_rule
+                .animations
+                .as_ref()
+                .unwrap_or(&global.animations),
+        };
         let effects_config = window_rule.effects.as_ref().unwrap_or(&global.effects);
 
         // Adjust the border parameters based on the window/monitor dpi
-        let border_width = (width_config * dpi as f32 / 96.0).round() as i32;
-        let border_offset = (offset_config as f32 * dpi as f32 / 96.0).round() as i32;
-        let border_radius = radius_config.to_radius(border_width, dpi, self.tracking_window);
-        let active_color = active_color_config.to_color_brush(true);
-        let inactive_color = inactive_color_config.to_color_brush(false);
-
-        let animations = animations_config.to_animations();
+        let border_width = get_border_width(&window_rule, &config, dpi, self.placement);
+        let (border_offset, border_radius) = match self.is_flat() {
+            true => (BorderOffset::uniform(-border_width), 0.0),
+            false => {
+                let border_offset = offset_config.to_offset(dpi);
+                let compensation =
+                    radius_compensation.to_compensation(border_width, &border_offset, dpi);
+                (
+                    border_offset,
+                    radius_config.to_radius(compensation, dpi, self.tracking_window),
+                )
+            }
+        };
+        let active_color = active_color_config.to_color_brush(true, self.tracking_window);
+        let inactive_color = inactive_color_config.to_color_brush(false, self.tracking_window);
+
+        let mut animations = animations_config.to_animations();
+        if !config.is_presentation_mode_active() {
+            animations.restore = window_rule
+                .restore_animation
+                .as_ref()
+                .unwrap_or(&global.restore_animation)
+                .to_presence_anim();
+        }
         let effects = effects_config.to_effects();
 
+        let outline_config = window_rule.outline.as_ref().unwrap_or(&global.outline);
+        let outline = outline_config.enabled.then(|| Outline {
+            width: (outline_config.width * dpi as f32 / 96.0).round() as i32,
+            gap: (outline_config.gap * dpi as f32 / 96.0).round() as i32,
+            is_inside_too: outline_config.sides == OutlineSides::Both,
+            active_color: outline_config
+                .active_color
+                .to_color_brush(true, self.tracking_window),
+            inactive_color: outline_config
+                .inactive_color
+                .as_ref()
+                .unwrap_or(&outline_config.active_color)
+                .to_color_brush(false, self.tracking_window),
+        });
+        // The acrylic gets clipped to the border, so it only makes sense when there's a border window
+        let acrylic_config = window_rule.acrylic.as_ref().unwrap_or(&global.acrylic);
+        self.acrylic_tint = match self.render_backend_config {
+            RenderBackendConfig::V2 | RenderBackendConfig::Legacy if acrylic_config.enabled => {
+                Some(
+                    acrylic_config
+                        .tint
+                        .to_color_brush(true, self.tracking_window)
+                        .get_primary_color(),
+                )
+            }
+            _ => None,
+        };
+        self.acrylic_region_rect = None;
+
+        let dim_overlay_config = window_rule
+            .dim_overlay
+            .as_ref()
+            .unwrap_or(&global.dim_overlay);
+        self.border_drawer.dim_overlay_color = dim_overlay_config.enabled.then(|| {
+            let mut color = dim_overlay_config
+                .color
+                .to_color_brush(false, self.tracking_window)
+                .get_primary_color();
+            color.a *= dim_overlay_config.opacity.clamp(0.0, 1.0);
+            color
+        });
+
+        // The outline and the feathering sit outside of the border, so the border window needs room
+        // for whichever reaches further
+        let feather_width =
+            window_rule.feather.unwrap_or(global.feather).max(0.0) * dpi as f32 / 96.0;
+        let outer_padding = outline
+            .as_ref()
+            .map_or(0, |outline| outline.width + outline.gap)
+            .max(feather_width.ceil() as i32);
+
         self.border_drawer.configure_appearance(
             border_width,
             border_offset,
@@ -242,12 +511,61 @@ impl WindowBorder {
             inactive_color,
             animations,
             effects,
+            outline,
+        );
+        self.border_drawer.dpi = dpi;
+        self.border_drawer.border_style = window_rule.border_style.unwrap_or(global.border_style);
+        self.border_drawer.dash_pattern = window_rule
+            .dash_pattern
+            .clone()
+            .or_else(|| global.dash_pattern.clone());
+        self.border_drawer.bracket_length =
+            window_rule.bracket_length.unwrap_or(global.bracket_length) * dpi as f32 / 96.0;
+        self.border_drawer.feather_width = feather_width;
+        self.border_drawer.notch = window_rule
+            .notch
+            .as_ref()
+            .map(|notch_config| notch_config.to_notch(dpi));
+        let titlebar_strip_config = window_rule
+            .titlebar_strip
+            .as_ref()
+            .unwrap_or(&global.titlebar_strip);
+        self.border_drawer.titlebar_strip_height = titlebar_strip_config
+            .enabled
+            .then(|| titlebar_strip_config.height * dpi as f32 / 96.0);
+        let pixel_snapping_config = window_rule
+            .pixel_snapping
+            .as_ref()
+            .unwrap_or(&global.pixel_snapping);
+        self.border_drawer.snap_to_pixels = pixel_snapping_config.enabled;
+        self.border_drawer.alias_thin_borders = pixel_snapping_config.aliased;
+        self.border_drawer.edges = BorderEdges::from_config(
+            window_rule
+                .edges
+                .as_ref()
+                .or(global.edges.as_ref())
+                .map(Vec::as_slice),
         );
 
+        self.border_drawer.screen_edge_offset =
+            self.get_screen_edge_offset(&window_rule, global, dpi);
+        self.border_drawer.screen_edges = None;
+        let max_offset = self
+            .border_drawer
+            .screen_edge_offset
+            .map_or(border_offset.max(), |offset| {
+                offset.max(border_offset.max())
+            });
+
+        // The pulse swells past the border's outer edge
+        let pulse_padding = (self.border_drawer.animations.get_max_pulse_amplitude() * dpi as f32
+            / 96.0)
+            .ceil() as i32;
+
         // This padding is used to adjust the border window such that the border and its effects
         // don't get clipped. However, effects are not supported by the Legacy render backend, so
         // we'll just set the padding to border_offset if that's what's being used.
-        self.window_padding = match config.render_backend {
+        self.window_padding = match self.render_backend_config {
             RenderBackendConfig::V2 => {
                 let max_active_padding = self
                     .border_drawer
@@ -296,27 +614,36 @@ impl WindowBorder {
                     })
                     .unwrap_or(0.0);
 
-                f32::max(max_active_padding, max_inactive_padding).ceil() as i32 + border_offset
+                f32::max(max_active_padding, max_inactive_padding).ceil() as i32
+                    + max_offset
+                    + outer_padding
+                    + pulse_padding
             }
-            RenderBackendConfig::Legacy => border_offset,
+            RenderBackendConfig::Legacy => max_offset + outer_padding + pulse_padding,
+            RenderBackendConfig::NativeDwm | RenderBackendConfig::None => max_offset,
         };
 
         // If the tracking window is part of the initial windows list (meaning it was already open when
         // tacky-borders was launched), then there should be no initialize delay.
-        self.initialize_delay = match APP_STATE
+        self.is_new_window = !APP_STATE
             .initial_windows
             .lock()
             .unwrap()
-            .contains(&(self.tracking_window.0 as isize))
-        {
-            true => 0,
-            false => window_rule
+            .contains(&(self.tracking_window.0 as isize));
+        self.initialize_delay = match self.is_new_window {
+            false => 0,
+            true => window_rule
                 .initialize_delay
                 .unwrap_or(global.initialize_delay),
         };
         self.unminimize_delay = window_rule
             .unminimize_delay
             .unwrap_or(global.unminimize_delay);
+        self.rect_hysteresis = window_rule
+            .rect_hysteresis
+            .unwrap_or(global.rect_hysteresis);
+
+        self.update_border_props();
 
         Ok(())
     }
@@ -349,28 +676,61 @@ impl WindowBorder {
     }
 
     fn update_position(&mut self, other_flags: Option<SET_WINDOW_POS_FLAGS>) -> anyhow::Result<()> {
+        let other_flags = other_flags.unwrap_or_default();
+        if other_flags.contains(SWP_SHOWWINDOW) {
+            if self.is_dormant {
+                self.wake_from_dormancy();
+            }
+            dormancy::set_border_visible(self.border_window, true);
+        } else if other_flags.contains(SWP_HIDEWINDOW) {
+            dormancy::set_border_visible(self.border_window, false);
+        }
+
         unsafe {
-            // Get the hwnd above the tracking hwnd so we can place the border window in between
-            let hwnd_above_tracking = GetWindow(self.tracking_window, GW_HWNDPREV);
+            let insert_after = match self.z_order {
+                // Get the hwnd above the tracking hwnd so we can place the border window in between.
+                // This also keeps the border beneath any friendly overlays above the window.
+                ZOrder::Above => GetWindow(self.tracking_window, GW_HWNDPREV).unwrap_or(HWND_TOP),
+                // Inserting after the tracking window places the border directly beneath it
+                ZOrder::Below => self.tracking_window,
+            };
+
+            let mut swp_flags = SWP_NOSENDCHANGING | SWP_NOACTIVATE | SWP_NOREDRAW | other_flags;
+
+            // If the border is already where it should be, there's no need to change the z-order
+            // (plus it results in an error if we try to insert the border after itself).
+            if self.is_z_order_correct() {
+                swp_flags |= SWP_NOZORDER;
+            }
 
-            let mut swp_flags = SWP_NOSENDCHANGING
-                | SWP_NOACTIVATE
-                | SWP_NOREDRAW
-                | other_flags.unwrap_or_default();
+            let border_rect = RECT {
+                left: self.window_rect.left + self.presence_offset.0,
+                top: self.window_rect.top + self.presence_offset.1,
+                right: self.window_rect.right + self.presence_offset.0,
+                bottom: self.window_rect.bottom + self.presence_offset.1,
+            };
 
-            // If hwnd_above_tracking is the window border itself, we have what we want and there's
-            // no need to change the z-order (plus it results in an error if we try it).
-            if hwnd_above_tracking == Ok(self.border_window) {
-                swp_flags |= SWP_NOZORDER;
+            // If lots of borders are moving at once (e.g. on a desktop switch), this gets applied
+            // along with the others, and we get WM_APP_POSITIONED afterwards
+            if position_batch::queue_position(
+                self.border_window,
+                insert_after,
+                &border_rect,
+                swp_flags,
+            ) {
+                self.is_position_deferred = true;
+                return Ok(());
             }
+            // Hiding takes our move back out of the batch, so we won't hear from it anymore
+            self.is_position_deferred = false;
 
             if let Err(e) = SetWindowPos(
                 self.border_window,
-                Some(hwnd_above_tracking.unwrap_or(HWND_TOP)),
-                self.window_rect.left,
-                self.window_rect.top,
-                self.window_rect.right - self.window_rect.left,
-                self.window_rect.bottom - self.window_rect.top,
+                Some(insert_after),
+                border_rect.left,
+                border_rect.top,
+                border_rect.right - border_rect.left,
+                border_rect.bottom - border_rect.top,
                 swp_flags,
             )
             .context(format!(
@@ -384,12 +744,55 @@ impl WindowBorder {
         Ok(())
     }
 
+    // Whether the border is directly above (or below, depending on z_order) the tracking window.
+    // Friendly overlays are allowed in between. Otherwise we'd move the border back on top of them,
+    // they'd move themselves back on top of the border, and so on.
+    fn is_z_order_correct(&self) -> bool {
+        let get_neighbor = |hwnd: HWND| match self.z_order {
+            ZOrder::Above => unsafe { GetWindow(hwnd, GW_HWNDPREV) },
+            ZOrder::Below => unsafe { GetWindow(hwnd, GW_HWNDNEXT) },
+        };
+
+        let mut neighbor = get_neighbor(self.tracking_window);
+        while let Ok(hwnd) = neighbor
+            && hwnd != self.border_window
+            && self.is_friendly_overlay(hwnd)
+        {
+            neighbor = get_neighbor(hwnd);
+        }
+
+        neighbor == Ok(self.border_window)
+    }
+
+    fn is_friendly_overlay(&self, hwnd: HWND) -> bool {
+        !self.friendly_overlays.is_empty()
+            && get_window_class(hwnd).is_ok_and(|class| {
+                self.friendly_overlays
+                    .iter()
+                    .any(|overlay| overlay.eq_ignore_ascii_case(&class))
+            })
+    }
+
     fn update_color(&mut self, check_delay: Option<u64>) -> anyhow::Result<()> {
         self.window_state.update(
             self.tracking_window.0 as isize,
             *APP_STATE.active_window.lock().unwrap(),
         );
 
+        if self.window_state == WindowState::Active {
+            self.last_active_time = Some(time::Instant::now());
+
+            if self.idle_dim_amount > 0.0 {
+                self.idle_dim_amount = 0.0;
+                self.update_color_brushes()?;
+            }
+        }
+
+        // Native borders don't have brush opacities or animations; render() picks the color
+        if self.is_native_dwm() {
+            return Ok(());
+        }
+
         match self
             .border_drawer
             .animations
@@ -409,6 +812,307 @@ impl WindowBorder {
         Ok(())
     }
 
+    // Same as Config::get_color_configs(), except the exceeded resource threshold (if any) takes
+    // precedence over the rule, monitor, and global colors
+    fn get_color_configs<'a>(
+        &'a self,
+        config: &'a Config,
+        window_rule: &'a WindowRule,
+    ) -> (&'a ColorBrushConfig, &'a ColorBrushConfig) {
+        let (active_color_config, inactive_color_config) =
+            config.get_color_configs(window_rule, self.current_monitor);
+
+        // High contrast and presentation colors take precedence over the resource thresholds too
+        if config.is_high_contrast_active() || config.is_presentation_mode_active() {
+            return (active_color_config, inactive_color_config);
+        }
+
+        if let Some(ref color_override) = self.color_override {
+            return (
+                color_override
+                    .active_color
+                    .as_ref()
+                    .unwrap_or(active_color_config),
+                color_override
+                    .inactive_color
+                    .as_ref()
+                    .unwrap_or(inactive_color_config),
+            );
+        }
+
+        let Some(threshold) = self
+            .resource_level
+            .checked_sub(1)
+            .and_then(|index| self.resource_colors.as_ref()?.thresholds.get(index))
+        else {
+            return (active_color_config, inactive_color_config);
+        };
+
+        (
+            threshold
+                .active_color
+                .as_ref()
+                .unwrap_or(active_color_config),
+            threshold
+                .inactive_color
+                .as_ref()
+                .unwrap_or(inactive_color_config),
+        )
+    }
+
+    // Handles EVENT_OBJECT_LOCATIONCHANGE. Changes smaller than the hysteresis are held back until
+    // the settle timer fires, so apps that jitter by a pixel or two don't cause constant repositioning.
+    fn update_location(&mut self, bypass_hysteresis: bool) {
+        // Fullscreen toggles and such usually move the window too, so this is where we notice that
+        // a faded out border's window wants it back
+        if self.is_ineligible {
+            self.cancel_pending_rect_change();
+            if self.is_eligible() {
+                self.queue_eligibility_check();
+            }
+            return;
+        }
+
+        if self.is_paused {
+            self.cancel_pending_rect_change();
+            return;
+        }
+
+        // Hide tacky-borders' custom border if no native border is present
+        if !self.should_show_border() {
+            self.cancel_pending_rect_change();
+            self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+            return;
+        }
+
+        if !self.is_eligible() {
+            self.queue_eligibility_check();
+        }
+
+        // This needs to happen before update_window_rect(), which makes room for the border width
+        let is_placement_changed = self.update_placement();
+        let is_shape_changed = self.update_screen_edges() || is_placement_changed;
+
+        let prev_rect = self.window_rect;
+        self.update_window_rect().log_if_err();
+
+        // TODO: After restoring a minimized window, render() may use the minimized
+        // (invisible) rect instead of the updated one. This is a temporary "fix".
+        if !is_rect_visible(&self.window_rect) {
+            self.window_rect = prev_rect;
+            self.cancel_pending_rect_change();
+            return;
+        }
+
+        let delta = [
+            self.window_rect.left - prev_rect.left,
+            self.window_rect.top - prev_rect.top,
+            self.window_rect.right - prev_rect.right,
+            self.window_rect.bottom - prev_rect.bottom,
+        ]
+        .into_iter()
+        .map(i32::abs)
+        .max()
+        .unwrap_or_default();
+
+        if !bypass_hysteresis && !is_shape_changed && delta > 0 && delta < self.rect_hysteresis {
+            // We keep comparing against the rect we last applied, so jitter that adds up past the
+            // hysteresis still goes through right away
+            self.window_rect = prev_rect;
+            if !self.is_rect_change_pending {
+                self.is_rect_change_pending = true;
+                unsafe {
+                    SetTimer(
+                        Some(self.border_window),
+                        RECT_SETTLE_TIMER_ID,
+                        RECT_SETTLE_MS,
+                        None,
+                    )
+                };
+            }
+            return;
+        }
+
+        self.cancel_pending_rect_change();
+
+        let update_pos_flags = (!is_window_visible(self.border_window)).then_some(SWP_SHOWWINDOW);
+        self.update_position(update_pos_flags).log_if_err();
+
+        // If the window rect changes size, we need to re-render the border
+        let mut needs_render = !are_rects_same_size(&self.window_rect, &prev_rect);
+
+        if is_shape_changed {
+            needs_render = true;
+            // The renderer's size depends on the border width
+            if let Err(err) = self.update_appearance_and_renderer_if_necessary(self.current_monitor)
+            {
+                error!("could not update renderer for new border width: {err}");
+            }
+        }
+
+        let new_monitor = monitor_from_window(self.tracking_window);
+        if new_monitor != self.current_monitor {
+            self.current_monitor = new_monitor;
+            debug!("monitor has changed! new monitor: {new_monitor:?}");
+
+            needs_render |= match self.update_appearance_and_renderer_if_necessary(new_monitor) {
+                Ok(is_updated) => is_updated,
+                Err(err) => {
+                    error!("could not update appearance and renderer: {err}");
+                    return;
+                }
+            };
+
+            // Monitors may have their own colors
+            if !APP_STATE.config.read().unwrap().monitors.is_empty() {
+                self.update_color_brushes().log_if_err();
+                needs_render = true;
+            }
+        }
+
+        self.update_window_corner_region();
+
+        if needs_render {
+            self.render().log_if_err();
+        }
+    }
+
+    // A rect change held back by rect_hysteresis is stale once the border stops following the
+    // window (or has just caught up with it)
+    fn cancel_pending_rect_change(&mut self) {
+        if self.is_rect_change_pending {
+            self.is_rect_change_pending = false;
+            let _ = unsafe { KillTimer(Some(self.border_window), RECT_SETTLE_TIMER_ID) };
+        }
+    }
+
+    // Keeps the tracking window clipped for window_corner. The region doesn't stretch along with
+    // the window, so it has to be rebuilt whenever the window's size changes.
+    fn update_window_corner_region(&mut self) {
+        let Some(radius) = self.window_corner_region else {
+            return;
+        };
+
+        // Maximized and fullscreen windows don't get rounded corners
+        let radius = match self.placement == PlacementState::Maximized || self.is_fullscreen {
+            true => 0,
+            false => (radius * self.current_dpi as f32 / 96.0).round() as i32,
+        };
+        let key = (
+            self.window_rect.right - self.window_rect.left,
+            self.window_rect.bottom - self.window_rect.top,
+            radius,
+        );
+        if self.window_corner_region_key == Some(key) {
+            return;
+        }
+        if self.window_corner_region_key.replace(key).is_none() {
+            self.original_window_region = get_window_region(self.tracking_window);
+        }
+
+        match radius {
+            0 => restore_window_region(self.tracking_window, self.original_window_region),
+            _ => {
+                if let Err(err) = set_window_corner_region(self.tracking_window, radius) {
+                    record_border_error(&err);
+                    error!("could not apply window_corner region: {err:#}");
+                }
+            }
+        }
+    }
+
+    // Re-resolves the active and inactive colors from the config and swaps in the new brushes
+    fn update_color_brushes(&mut self) -> anyhow::Result<()> {
+        let window_rule = get_window_rule(self.tracking_window);
+        let config = APP_STATE.config.read().unwrap();
+
+        let (active_color_config, inactive_color_config) =
+            self.get_color_configs(&config, &window_rule);
+        let active_color = active_color_config.to_color_brush(true, self.tracking_window);
+        let mut inactive_color = inactive_color_config.to_color_brush(false, self.tracking_window);
+        let uses_wallpaper = active_color_config.uses_keyword("wallpaper")
+            || inactive_color_config.uses_keyword("wallpaper");
+        drop(config);
+
+        wallpaper::set_uses_wallpaper(self.border_window, uses_wallpaper);
+
+        inactive_color.dim(self.idle_dim_amount);
+
+        self.border_drawer
+            .set_active_color(active_color, &self.window_rect)?;
+        self.border_drawer
+            .set_inactive_color(inactive_color, &self.window_rect)?;
+
+        // Komorebi's window kinds take precedence over the active color, so re-apply them
+        if APP_STATE.komorebi_integration.lock().unwrap().is_running() {
+            post_message_w(
+                Some(self.border_window),
+                WM_APP_KOMOREBI,
+                WPARAM(0),
+                LPARAM(0),
+            )
+            .context("could not post WM_APP_KOMOREBI message")?;
+        }
+
+        Ok(())
+    }
+
+    fn set_color_override(&mut self, color_override: ColorOverride) -> anyhow::Result<()> {
+        let crossfade = time::Duration::from_millis(color_override.crossfade);
+        self.color_override = (color_override.active_color.is_some()
+            || color_override.inactive_color.is_some())
+        .then_some(color_override);
+
+        self.crossfade_color_brushes(crossfade)
+    }
+
+    // Re-resolves the colors like update_color_brushes(), but fades over from the old ones
+    fn crossfade_color_brushes(&mut self, crossfade: time::Duration) -> anyhow::Result<()> {
+        // Only the color that's currently visible needs to be cross-faded
+        let old_color = match self.window_state {
+            WindowState::Active => self.border_drawer.active_color.clone(),
+            WindowState::Inactive => self.border_drawer.inactive_color.clone(),
+        };
+
+        self.update_color_brushes()?;
+
+        if !crossfade.is_zero() && !self.is_native_dwm() && !self.is_paused {
+            self.border_drawer.start_crossfade(old_color, crossfade);
+            unsafe { SetTimer(Some(self.border_window), CROSSFADE_TIMER_ID, 16, None) };
+        }
+
+        self.render()
+    }
+
+    // Called periodically by the idle dim timer
+    fn update_idle_dim(&mut self) -> anyhow::Result<()> {
+        let Some(ref idle_dim) = self.idle_dim else {
+            return Ok(());
+        };
+        if self.window_state == WindowState::Active {
+            return Ok(());
+        }
+
+        // If the window has never been active, we'll count from when the border was created
+        let last_active_time = *self.last_active_time.get_or_insert_with(time::Instant::now);
+        let idle_mins = last_active_time.elapsed().as_secs_f32() / 60.0;
+
+        let progress = match idle_dim.duration {
+            0 => (idle_mins >= idle_dim.after as f32) as u8 as f32,
+            duration => ((idle_mins - idle_dim.after as f32) / duration as f32).clamp(0.0, 1.0),
+        };
+        let new_amount = progress * idle_dim.strength;
+
+        // Avoid recreating the brushes for changes that nobody will notice
+        if (new_amount - self.idle_dim_amount).abs() < 0.01 {
+            return Ok(());
+        }
+        self.idle_dim_amount = new_amount;
+
+        self.update_color_brushes()?;
+        self.render()
+    }
+
     fn update_brush_opacities(&mut self) {
         let (top_color, bottom_color) = match self.window_state {
             WindowState::Active => (
@@ -429,21 +1133,244 @@ impl WindowBorder {
         let config = APP_STATE.config.read().unwrap();
         let global = &config.global;
 
-        let width_config = window_rule.border_width.unwrap_or(global.border_width);
-        let offset_config = window_rule.border_offset.unwrap_or(global.border_offset);
+        let offset_config = window_rule
+            .border_offset
+            .as_ref()
+            .unwrap_or(&global.border_offset);
         let radius_config = window_rule
             .border_radius
             .as_ref()
             .unwrap_or(&global.border_radius);
+        let radius_compensation = window_rule
+            .radius_compensation
+            .unwrap_or(global.radius_compensation);
+
+        self.border_drawer.border_width =
+            get_border_width(&window_rule, &config, new_dpi, self.placement);
+        self.border_drawer.dpi = new_dpi;
+        if self.is_flat() {
+            self.border_drawer.border_offset =
+                BorderOffset::uniform(-self.border_drawer.border_width)
+                    .shifted(self.komorebi_offset_adjustment);
+            self.border_drawer.border_radius = 0.0;
+        } else {
+            let border_offset = offset_config.to_offset(new_dpi);
+            let compensation = radius_compensation.to_compensation(
+                self.border_drawer.border_width,
+                &border_offset,
+                new_dpi,
+            );
+            self.border_drawer.border_offset =
+                border_offset.shifted(self.komorebi_offset_adjustment);
+            self.border_drawer.border_radius =
+                radius_config.to_radius(compensation, new_dpi, self.tracking_window);
+        }
+        self.border_drawer.screen_edge_offset =
+            self.get_screen_edge_offset(&window_rule, global, new_dpi);
+        self.border_drawer.bracket_length =
+            window_rule.bracket_length.unwrap_or(global.bracket_length) * new_dpi as f32 / 96.0;
+        self.border_drawer.feather_width =
+            window_rule.feather.unwrap_or(global.feather).max(0.0) * new_dpi as f32 / 96.0;
+        // The acrylic region reaches out to the feathering's edge
+        self.acrylic_region_rect = None;
+        self.border_drawer.notch = window_rule
+            .notch
+            .as_ref()
+            .map(|notch_config| notch_config.to_notch(new_dpi));
+        let titlebar_strip_config = window_rule
+            .titlebar_strip
+            .as_ref()
+            .unwrap_or(&global.titlebar_strip);
+        self.border_drawer.titlebar_strip_height = titlebar_strip_config
+            .enabled
+            .then(|| titlebar_strip_config.height * new_dpi as f32 / 96.0);
+
+        self.update_border_props();
+    }
+
+    // Flat borders sit inside the window, so they don't get a separate offset
+    fn get_screen_edge_offset(
+        &self,
+        window_rule: &WindowRule,
+        global: &Global,
+        dpi: u32,
+    ) -> Option<i32> {
+        window_rule
+            .screen_edge_offset
+            .as_ref()
+            .or(global.screen_edge_offset.as_ref())
+            .filter(|_| !self.is_flat())
+            .map(|offset| offset.to_physical(dpi).round() as i32)
+    }
+
+    // Returns true if different sides of the window now touch its monitor's work area. Only
+    // snapped windows are checked, since floating windows line up with it by accident.
+    fn update_screen_edges(&mut self) -> bool {
+        let screen_edges = match self.border_drawer.screen_edge_offset {
+            Some(_) if self.placement == PlacementState::Snapped => {
+                match get_work_area_edges(self.tracking_window) {
+                    Ok(edges) => Some(edges),
+                    Err(err) => {
+                        error!("could not get screen edges: {err:#}");
+                        None
+                    }
+                }
+            }
+            _ => None,
+        };
+
+        let is_changed = screen_edges != self.border_drawer.screen_edges;
+        self.border_drawer.screen_edges = screen_edges;
+        is_changed
+    }
 
-        self.border_drawer.border_width = (width_config * new_dpi as f32 / 96.0).round() as i32;
-        self.border_drawer.border_offset =
-            (offset_config as f32 * new_dpi as f32 / 96.0).round() as i32;
-        self.border_drawer.border_radius = radius_config.to_radius(
+    // Returns true if the border's shape changed because the window was maximized, snapped, or
+    // restored
+    fn update_placement(&mut self) -> bool {
+        let new_placement = get_placement_state(self.tracking_window);
+        // Only tracked if it matters, since it means checking the monitor on every move
+        let is_fullscreen =
+            self.show_when_fullscreen && is_borderless_fullscreen(self.tracking_window);
+        if new_placement == self.placement && is_fullscreen == self.is_fullscreen {
+            return false;
+        }
+        self.placement = new_placement;
+        self.is_fullscreen = is_fullscreen;
+
+        let prev_shape = (
             self.border_drawer.border_width,
-            new_dpi,
-            self.tracking_window,
+            self.border_drawer.border_offset,
+            self.border_drawer.border_radius,
         );
+        self.update_appearance(self.current_dpi);
+
+        prev_shape
+            != (
+                self.border_drawer.border_width,
+                self.border_drawer.border_offset,
+                self.border_drawer.border_radius,
+            )
+    }
+
+    // Maximized and fullscreen windows don't have a native border, so we normally hide ours for
+    // them too
+    fn should_show_border(&self) -> bool {
+        has_native_border(self.tracking_window)
+            || (self.show_when_maximized && is_maximized_with_native_border(self.tracking_window))
+            || (self.show_when_fullscreen && is_borderless_fullscreen(self.tracking_window))
+    }
+
+    // Maximized and fullscreen windows fill the monitor, so the border goes inside of them instead
+    fn is_flat(&self) -> bool {
+        (self.show_when_maximized && self.placement == PlacementState::Maximized)
+            || self.is_fullscreen
+    }
+
+    // Returns true if border_offset was changed, in which case the caller should update the border's
+    // position and renderer. Both the offset and the window padding are adjusted.
+    fn update_komorebi_gap_adjustment(&mut self) -> bool {
+        let gap_mode = APP_STATE.config.read().unwrap().global.komorebi_gaps.mode;
+        let container_padding = match gap_mode {
+            KomorebiGapMode::Off => None,
+            KomorebiGapMode::Warn | KomorebiGapMode::Adjust => APP_STATE
+                .komorebi_integration
+                .lock()
+                .unwrap()
+                .container_padding
+                .lock()
+                .unwrap()
+                .get(&(self.tracking_window.0 as isize))
+                .copied(),
+        };
+
+        // Each of the two neighboring borders gets half of the gap
+        let reach = self.border_drawer.border_width + self.border_drawer.border_offset.max()
+            - self.komorebi_offset_adjustment;
+        let new_adjustment = match container_padding {
+            Some(padding) if reach > padding / 2 => match gap_mode {
+                KomorebiGapMode::Adjust => padding / 2 - reach,
+                _ => {
+                    if !self.has_warned_komorebi_gap {
+                        self.has_warned_komorebi_gap = true;
+                        warn!(
+                            "border for {:?} reaches {reach}px past the window, which is more than \
+                            half of komorebi's container_padding ({padding}px); it will overlap \
+                            neighboring borders",
+                            self.tracking_window
+                        );
+                    }
+                    0
+                }
+            },
+            _ => 0,
+        };
+
+        if new_adjustment == self.komorebi_offset_adjustment {
+            return false;
+        }
+
+        let delta = new_adjustment - self.komorebi_offset_adjustment;
+        self.border_drawer.border_offset = self.border_drawer.border_offset.shifted(delta);
+        self.window_padding += delta;
+        self.komorebi_offset_adjustment = new_adjustment;
+        self.update_border_props();
+
+        true
+    }
+
+    // The window region only needs to change when the border's shape does, which is rare compared
+    // to how often we render (e.g. during animations)
+    fn update_acrylic_region(&mut self) {
+        if self.acrylic_tint.is_none()
+            || self.acrylic_region_rect == Some(self.border_drawer.render_rect)
+        {
+            return;
+        }
+
+        // Like the border window's padding, this has to cover the outline and the feathering
+        let outer_padding = self
+            .border_drawer
+            .outline
+            .as_ref()
+            .map_or(0.0, |outline| (outline.width + outline.gap) as f32)
+            .max(self.border_drawer.feather_width);
+
+        update_acrylic_region(
+            self.border_window,
+            &self.border_drawer.render_rect,
+            self.border_drawer.border_width as f32,
+            outer_padding,
+        )
+        .log_if_err();
+        self.acrylic_region_rect = Some(self.border_drawer.render_rect);
+    }
+
+    // NativeDwm draws inside the window's own frame, so it doesn't add any margin worth reporting
+    fn update_border_props(&self) {
+        // The outline is drawn right outside of the border, so it counts towards the width
+        let outline_width = self
+            .border_drawer
+            .outline
+            .as_ref()
+            .map_or(0, |outline| outline.width + outline.gap);
+        let border_offset = self.border_drawer.border_offset.max();
+        let max_offset = self
+            .border_drawer
+            .screen_edge_offset
+            .map_or(border_offset, |offset| offset.max(border_offset));
+
+        match self.render_backend_config {
+            RenderBackendConfig::V2 | RenderBackendConfig::Legacy => publish_border_props(
+                self.tracking_window,
+                self.border_drawer.border_width + outline_width,
+                max_offset,
+            )
+            .context("could not publish border props")
+            .log_if_err(),
+            RenderBackendConfig::NativeDwm | RenderBackendConfig::None => {
+                clear_border_props(self.tracking_window)
+            }
+        }
     }
 
     fn compute_proper_renderer_size(
@@ -459,6 +1386,11 @@ impl WindowBorder {
     }
 
     fn update_renderer_size(&mut self, screen_width: u32, screen_height: u32) {
+        // The renderer gets created at the right size once we wake up
+        if self.is_dormant {
+            return;
+        }
+
         let renderer_size = Self::compute_proper_renderer_size(
             screen_width,
             screen_height,
@@ -503,6 +1435,10 @@ impl WindowBorder {
             self.update_appearance(new_dpi);
         }
 
+        if self.is_dormant {
+            return Ok(is_updated);
+        }
+
         let (screen_width, screen_height) =
             get_monitor_resolution(new_monitor).context("could not get monitor resolution")?;
 
@@ -518,20 +1454,35 @@ impl WindowBorder {
     }
 
     fn render(&mut self) -> anyhow::Result<()> {
-        if let Err(err) =
+        // Dormant borders are hidden and don't have a renderer. Deferred borders render once they
+        // get WM_APP_POSITIONED.
+        if self.is_dormant || self.is_position_deferred {
+            return Ok(());
+        }
+
+        let render_start = time::Instant::now();
+        let render_res =
             self.border_drawer
-                .render(&self.window_rect, self.window_padding, self.window_state)
-        {
+                .render(&self.window_rect, self.window_padding, self.window_state);
+        bench::record_frame_time(render_start.elapsed());
+
+        if render_res.is_ok() {
+            self.update_acrylic_region();
+        }
+
+        if let Err(err) = render_res {
             if err.code() == D2DERR_RECREATE_TARGET {
                 // D2DERR_RECREATE_TARGET is recoverable if we just recreate the render target.
                 // This error can be caused by things like waking up from sleep, updating GPU
                 // drivers, changing screen resolution, etc.
+                record_border_error(&self.to_render_error(err));
                 warn!("render target has been lost; attempting to recreate");
 
                 let pixel_size = self.border_drawer.render_backend.get_pixel_size()?;
                 let render_backend_config = match self.border_drawer.render_backend {
                     RenderBackend::V2(_) => RenderBackendConfig::V2,
                     RenderBackend::Legacy(_) => RenderBackendConfig::Legacy,
+                    RenderBackend::NativeDwm(_) => RenderBackendConfig::NativeDwm,
                     RenderBackend::None => {
                         // This branch should be unreachable (theoretically)
                         self.cleanup_and_queue_exit();
@@ -543,9 +1494,11 @@ impl WindowBorder {
                     pixel_size.width,
                     pixel_size.height,
                     self.border_window,
+                    self.tracking_window,
                     &self.window_rect,
                     render_backend_config,
                 ) {
+                    record_border_error(&err_2);
                     self.cleanup_and_queue_exit();
                     return Err(anyhow!(
                         "could not recreate render target; exiting thread: {err_2}"
@@ -559,17 +1512,460 @@ impl WindowBorder {
                 // temporary, so I'll just use debug! instead of logging it as a full error.
                 debug!("an object is currently unitialized: {err}");
             } else {
+                let err = self.to_render_error(err);
+                record_border_error(&err);
                 self.cleanup_and_queue_exit();
-                return Err(anyhow!("self.render() failed; exiting thread: {err}"));
+                return Err(anyhow!("self.render() failed; exiting thread: {err:#}"));
             }
         };
 
         Ok(())
     }
 
+    // NativeDwm "renders" by setting the window's DWM border color, everything else uses Direct2D
+    fn to_render_error(&self, err: windows::core::Error) -> anyhow::Error {
+        match self.border_drawer.render_backend {
+            RenderBackend::NativeDwm(_) => {
+                BorderError::Dwm("could not set native border color", err).into()
+            }
+            _ => BorderError::Direct2D("could not render border", err.into()).into(),
+        }
+    }
+
+    // This must be called from the border's own thread after its message loop has exited
+    pub fn destroy_window(&mut self) {
+        dormancy::set_border_visible(self.border_window, false);
+        wallpaper::set_uses_wallpaper(self.border_window, false);
+
+        unsafe {
+            // Detach the window procedure from this struct first; we're about to go out of scope
+            SetWindowLongPtrW(self.border_window, GWLP_USERDATA, 0);
+
+            if IsWindow(Some(self.border_window)).as_bool() {
+                // The wnd_proc can't reach WM_NCDESTROY anymore, so this has to happen here
+                uia::release_providers(self.border_window);
+
+                DestroyWindow(self.border_window)
+                    .context(format!("could not destroy border {:?}", self.border_window))
+                    .log_if_err();
+            }
+        }
+    }
+
+    fn is_eligible(&self) -> bool {
+        self.is_force_enabled || !has_filtered_style(self.tracking_window)
+    }
+
+    fn is_tracking_window_hidden(&self) -> bool {
+        !is_window_visible(self.tracking_window) || is_window_cloaked(self.tracking_window)
+    }
+
+    fn queue_eligibility_check(&mut self) {
+        if self.is_eligibility_pending || self.fade_out_start.is_some() {
+            return;
+        }
+
+        self.is_eligibility_pending = true;
+        unsafe {
+            SetTimer(
+                Some(self.border_window),
+                ELIGIBILITY_TIMER_ID,
+                ELIGIBILITY_DEBOUNCE_MS,
+                None,
+            )
+        };
+    }
+
+    fn check_eligibility(&mut self) {
+        let _ = unsafe { KillTimer(Some(self.border_window), ELIGIBILITY_TIMER_ID) };
+        self.is_eligibility_pending = false;
+
+        if self.is_tracking_window_hidden() {
+            self.hide();
+        } else if !self.is_eligible() {
+            // The window still doesn't want a border, so fade it out and keep it hidden. Location
+            // changes check again whether the window has become eligible in the meantime.
+            debug!(
+                "{:?} is no longer eligible for a border; fading out",
+                self.tracking_window
+            );
+            self.start_fade_out();
+        } else if self.is_ineligible {
+            debug!(
+                "{:?} is eligible for a border again; showing it",
+                self.tracking_window
+            );
+            self.is_ineligible = false;
+            post_message_w(
+                Some(self.border_window),
+                WM_APP_SHOWUNCLOAKED,
+                WPARAM(0),
+                LPARAM(0),
+            )
+            .context("could not post WM_APP_SHOWUNCLOAKED message")
+            .log_if_err();
+        }
+    }
+
+    // Timers and threads that run for as long as the border isn't suspended
+    fn start_background_work(&mut self) {
+        if self.idle_dim.is_some() {
+            // We don't need much precision here since the idle times are in minutes
+            unsafe { SetTimer(Some(self.border_window), IDLE_DIM_TIMER_ID, 30_000, None) };
+        }
+
+        if let Some(ref resource_colors) = self.resource_colors
+            && !resource_colors.thresholds.is_empty()
+            && self.resource_sampler.is_none()
+        {
+            self.resource_sampler = Some(ResourceSampler::start(
+                self.border_window,
+                self.tracking_window,
+                resource_colors.thresholds.clone(),
+                resource_colors.interval,
+            ));
+        }
+    }
+
+    fn hide(&mut self) {
+        self.hide_time = Some(time::Instant::now());
+        self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+        self.border_drawer.animations.destroy_timer();
+        self.is_paused = true;
+
+        // Windows on other virtual desktops tend to stay hidden for a while
+        if is_window_cloaked_by_shell(self.tracking_window) {
+            self.suspend();
+        }
+    }
+
+    // Stops all of the border's timers and threads so that it sits completely idle until the
+    // tracking window is uncloaked. Destroyed tracking windows are still caught by the event hook.
+    fn suspend(&mut self) {
+        if self.is_suspended || self.fade_out_start.is_some() {
+            return;
+        }
+
+        debug!(
+            "{:?} is on another virtual desktop; suspending its border",
+            self.tracking_window
+        );
+
+        for timer_id in [
+            IDLE_DIM_TIMER_ID,
+            ELIGIBILITY_TIMER_ID,
+            CROSSFADE_TIMER_ID,
+            RECT_SETTLE_TIMER_ID,
+        ] {
+            let _ = unsafe { KillTimer(Some(self.border_window), timer_id) };
+        }
+        self.is_eligibility_pending = false;
+        self.is_rect_change_pending = false;
+        self.border_drawer.crossfade = None;
+
+        if let Some(mut resource_sampler) = self.resource_sampler.take() {
+            resource_sampler.stop();
+        }
+
+        self.is_suspended = true;
+    }
+
+    fn resume(&mut self) {
+        if !self.is_suspended {
+            return;
+        }
+
+        debug!("resuming border for {:?}", self.tracking_window);
+        self.is_suspended = false;
+        self.start_background_work();
+    }
+
+    // Like suspend(), but we also drop the renderer so that the app can release the DirectX devices.
+    // The border keeps handling messages as usual and wakes up once it needs to show again.
+    fn go_dormant(&mut self) {
+        if self.is_dormant
+            || self.is_native_dwm()
+            || self.fade_out_start.is_some()
+            || is_window_visible(self.border_window)
+        {
+            return;
+        }
+
+        self.border_drawer.animations.destroy_timer();
+        for timer_id in [
+            IDLE_DIM_TIMER_ID,
+            ELIGIBILITY_TIMER_ID,
+            CROSSFADE_TIMER_ID,
+            RECT_SETTLE_TIMER_ID,
+        ] {
+            let _ = unsafe { KillTimer(Some(self.border_window), timer_id) };
+        }
+        self.is_eligibility_pending = false;
+        self.is_rect_change_pending = false;
+
+        if let Some(mut resource_sampler) = self.resource_sampler.take() {
+            resource_sampler.stop();
+        }
+
+        self.border_drawer.release_renderer();
+        self.is_dormant = true;
+    }
+
+    fn wake_from_dormancy(&mut self) {
+        self.is_dormant = false;
+        dormancy::wake();
+
+        let init_res = get_monitor_resolution(self.current_monitor)
+            .context("could not get monitor resolution")
+            .and_then(|(screen_width, screen_height)| {
+                let renderer_size = Self::compute_proper_renderer_size(
+                    screen_width,
+                    screen_height,
+                    self.border_drawer.border_width,
+                    self.window_padding,
+                );
+                self.border_drawer.init_renderer(
+                    renderer_size.width,
+                    renderer_size.height,
+                    self.border_window,
+                    self.tracking_window,
+                    &self.window_rect,
+                    self.render_backend_config,
+                )
+            });
+        if let Err(err) = init_res {
+            error!("could not recreate renderer after dormancy; exiting thread: {err:#}");
+            self.cleanup_and_queue_exit();
+            return;
+        }
+
+        self.update_brush_opacities();
+        if !self.is_suspended {
+            self.start_background_work();
+        }
+        if !self.is_paused {
+            self.border_drawer
+                .animations
+                .set_timer_if_enabled(self.border_window, &mut self.border_drawer.last_anim_time);
+        }
+    }
+
+    fn start_fade_out(&mut self) {
+        // Pause everything else so that nothing fights over the brush opacities
+        self.border_drawer.animations.destroy_timer();
+        self.is_paused = true;
+
+        self.fade_out_start = Some(time::Instant::now());
+        unsafe { SetTimer(Some(self.border_window), FADE_OUT_TIMER_ID, 16, None) };
+    }
+
+    fn update_fade_out(&mut self) {
+        let Some(fade_out_start) = self.fade_out_start else {
+            return;
+        };
+
+        let progress =
+            (fade_out_start.elapsed().as_secs_f32() / FADE_OUT_DURATION.as_secs_f32()).min(1.0);
+        if progress >= 1.0 {
+            let _ = unsafe { KillTimer(Some(self.border_window), FADE_OUT_TIMER_ID) };
+            self.fade_out_start = None;
+            self.is_ineligible = true;
+            self.hide();
+            return;
+        }
+
+        let (top_color, bottom_color) = match self.window_state {
+            WindowState::Active => (
+                &self.border_drawer.active_color,
+                &self.border_drawer.inactive_color,
+            ),
+            WindowState::Inactive => (
+                &self.border_drawer.inactive_color,
+                &self.border_drawer.active_color,
+            ),
+        };
+        top_color.set_opacity(1.0 - progress).log_if_err();
+        bottom_color.set_opacity(0.0).log_if_err();
+
+        self.render().log_if_err();
+    }
+
+    fn should_animate_open(&self) -> bool {
+        self.is_new_window && self.border_drawer.animations.open.is_some() && !self.is_native_dwm()
+    }
+
+    fn should_animate_close(&self) -> bool {
+        self.border_drawer.animations.close.is_some()
+            && !self.is_native_dwm()
+            && !self.is_dormant
+            && !self.is_suspended
+            && self.fade_out_start.is_none()
+            && (is_window_visible(self.border_window)
+                || self
+                    .hide_time
+                    .is_some_and(|hide_time| hide_time.elapsed() < CLOSE_ANIM_GRACE))
+    }
+
+    fn start_presence_anim(&mut self, presence_kind: PresenceKind) {
+        if presence_kind == PresenceKind::Close {
+            // Like the fade out, nothing else should be animating while the border goes away
+            self.border_drawer.animations.destroy_timer();
+            self.is_paused = true;
+        }
+
+        self.presence_kind = presence_kind;
+        self.presence_start = Some(time::Instant::now());
+        self.taskbar_edge = get_taskbar_edge(self.current_monitor).unwrap_or(BorderEdge::Bottom);
+        self.update_presence_anim();
+        unsafe { SetTimer(Some(self.border_window), PRESENCE_TIMER_ID, 16, None) };
+    }
+
+    fn update_presence_anim(&mut self) {
+        let Some(presence_start) = self.presence_start else {
+            return;
+        };
+        let is_closing = self.presence_kind == PresenceKind::Close;
+        let presence_anim = match self.presence_kind {
+            PresenceKind::Open => self.border_drawer.animations.open.as_ref(),
+            PresenceKind::Close => self.border_drawer.animations.close.as_ref(),
+            PresenceKind::Restore => self.border_drawer.animations.restore.as_ref(),
+        };
+        let (presence, is_done, presence_offset) = match presence_anim {
+            Some(presence_anim) => {
+                let (presence, is_done) =
+                    presence_anim.get_presence(presence_start.elapsed(), is_closing);
+                self.border_drawer.presence_transform =
+                    Some(self.get_presence_transform(presence_anim.motion, presence));
+                let presence_offset = match presence_anim.motion {
+                    PresenceMotion::SlideFromTaskbar => self.get_slide_offset(presence),
+                    _ => (0, 0),
+                };
+                (presence, is_done, presence_offset)
+            }
+            None => (1.0, true, (0, 0)),
+        };
+
+        if is_done {
+            let _ = unsafe { KillTimer(Some(self.border_window), PRESENCE_TIMER_ID) };
+            self.presence_start = None;
+            self.border_drawer.presence_transform = None;
+        }
+
+        let presence_offset = match is_done {
+            true => (0, 0),
+            false => presence_offset,
+        };
+        if presence_offset != self.presence_offset {
+            self.presence_offset = presence_offset;
+            if !is_closing {
+                self.update_position(None).log_if_err();
+            }
+        }
+
+        if is_done && is_closing {
+            self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
+            self.cleanup_and_queue_exit();
+            return;
+        }
+
+        self.update_window_alpha(match is_done {
+            true => 1.0,
+            false => presence,
+        });
+        self.render().log_if_err();
+    }
+
+    // The window's alpha covers everything at once, including the effects and the outline. Both the
+    // presence animations and proximity_reveal go through it.
+    fn update_window_alpha(&self, presence: f32) {
+        let opacity = presence.clamp(0.0, 1.0) * self.proximity_opacity.unwrap_or(1.0);
+        let alpha = (opacity * 255.0).round() as u8;
+
+        unsafe {
+            SetLayeredWindowAttributes(self.border_window, COLORREF(0x00000000), alpha, LWA_ALPHA)
+        }
+        .context("could not set LWA_ALPHA")
+        .log_if_err();
+    }
+
+    fn get_presence_transform(&self, motion: PresenceMotion, presence: f32) -> PresenceTransform {
+        let mut transform = PresenceTransform {
+            scale: 1.0,
+            anchor: (0.5, 0.5),
+        };
+
+        match motion {
+            // SlideFromTaskbar moves the border window instead (see get_slide_offset())
+            PresenceMotion::None | PresenceMotion::SlideFromTaskbar => {}
+            PresenceMotion::Scale(scale) => transform.scale = scale + (1.0 - scale) * presence,
+            PresenceMotion::GrowFromTaskbar => {
+                transform.scale = presence;
+                transform.anchor = self.get_taskbar_anchor();
+            }
+        }
+
+        transform
+    }
+
+    // Where the taskbar is, as a point on the border ((0.5, 0.5) is the center)
+    fn get_taskbar_anchor(&self) -> (f32, f32) {
+        match self.taskbar_edge {
+            BorderEdge::Top => (0.5, 0.0),
+            BorderEdge::Left => (0.0, 0.5),
+            BorderEdge::Right => (1.0, 0.5),
+            BorderEdge::Bottom => (0.5, 1.0),
+        }
+    }
+
+    // In physical pixels
+    fn get_slide_offset(&self, presence: f32) -> (i32, i32) {
+        let taskbar_anchor = self.get_taskbar_anchor();
+        let width = (self.window_rect.right - self.window_rect.left) as f32;
+        let height = (self.window_rect.bottom - self.window_rect.top) as f32;
+        let distance = (1.0 - presence) * SLIDE_DISTANCE;
+
+        (
+            ((taskbar_anchor.0 - 0.5) * 2.0 * width * distance).round() as i32,
+            ((taskbar_anchor.1 - 0.5) * 2.0 * height * distance).round() as i32,
+        )
+    }
+
+    fn is_native_dwm(&self) -> bool {
+        self.render_backend_config == RenderBackendConfig::NativeDwm
+    }
+
     fn cleanup_and_queue_exit(&mut self) {
+        if let RenderBackend::NativeDwm(ref backend) = self.border_drawer.render_backend {
+            // This fails if the tracking window has already been destroyed, which is fine
+            let _ = backend.reset();
+        }
+
+        clear_border_props(self.tracking_window);
+
+        // This also fails if the tracking window has already been destroyed
+        if let Some(original_preference) = self.original_corner_preference.take() {
+            let _ = set_window_corner_preference(self.tracking_window, original_preference);
+        }
+        if self.window_corner_region_key.take().is_some() {
+            restore_window_region(self.tracking_window, self.original_window_region);
+        }
+        if let Some(hrgn) = self.original_window_region.take() {
+            let _ = unsafe { DeleteObject(hrgn.into()) };
+        }
+
         self.is_paused = true;
         self.border_drawer.animations.destroy_timer();
+        if self.idle_dim.is_some() {
+            let _ = unsafe { KillTimer(Some(self.border_window), IDLE_DIM_TIMER_ID) };
+        }
+        let _ = unsafe { KillTimer(Some(self.border_window), ELIGIBILITY_TIMER_ID) };
+        let _ = unsafe { KillTimer(Some(self.border_window), FADE_OUT_TIMER_ID) };
+        let _ = unsafe { KillTimer(Some(self.border_window), CROSSFADE_TIMER_ID) };
+        let _ = unsafe { KillTimer(Some(self.border_window), RECT_SETTLE_TIMER_ID) };
+        let _ = unsafe { KillTimer(Some(self.border_window), PRESENCE_TIMER_ID) };
+        if let Some(resource_sampler) = self.resource_sampler.as_mut() {
+            resource_sampler.stop();
+        }
         unsafe { PostQuitMessage(0) };
     }
 
@@ -608,62 +2004,57 @@ impl WindowBorder {
         wparam: WPARAM,
         lparam: LPARAM,
     ) -> LRESULT {
-        match message {
-            // EVENT_OBJECT_LOCATIONCHANGE
-            WM_APP_LOCATIONCHANGE => {
-                if self.is_paused {
-                    return LRESULT(0);
-                }
-
-                // Hide tacky-borders' custom border if no native border is present
-                if !has_native_border(self.tracking_window) {
-                    self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
-                    return LRESULT(0);
-                }
-
-                let prev_rect = self.window_rect;
-                self.update_window_rect().log_if_err();
-
-                // TODO: After restoring a minimized window, render() may use the minimized
-                // (invisible) rect instead of the updated one. This is a temporary "fix".
-                if !is_rect_visible(&self.window_rect) {
-                    self.window_rect = prev_rect;
+        // With the NativeDwm render backend, Windows draws the border for us, so we only need to
+        // handle color changes. Everything else falls through to the usual handlers below.
+        if self.is_native_dwm() {
+            match message {
+                WM_APP_FOREGROUND | WM_APP_SHOWUNCLOAKED | WM_APP_MINIMIZEEND => {
+                    self.update_color(None).log_if_err();
+                    self.render().log_if_err();
                     return LRESULT(0);
                 }
+                WM_APP_LOCATIONCHANGE
+                | WM_APP_REORDER
+                | WM_APP_HIDECLOAKED
+                | WM_APP_MINIMIZESTART
+                | WM_APP_ANIMATE
+                | WM_DISPLAYCHANGE
+                | WM_DPICHANGED
+                | WM_WINDOWPOSCHANGING
+                | WM_WINDOWPOSCHANGED => return LRESULT(0),
+                _ => {}
+            }
+        }
 
-                let update_pos_flags =
-                    (!is_window_visible(self.border_window)).then_some(SWP_SHOWWINDOW);
-                self.update_position(update_pos_flags).log_if_err();
-
-                // If the window rect changes size, we need to re-render the border
-                let mut needs_render = !are_rects_same_size(&self.window_rect, &prev_rect);
-
-                let new_monitor = monitor_from_window(self.tracking_window);
-                if new_monitor != self.current_monitor {
-                    self.current_monitor = new_monitor;
-                    debug!("monitor has changed! new monitor: {new_monitor:?}");
-
-                    needs_render |=
-                        match self.update_appearance_and_renderer_if_necessary(new_monitor) {
-                            Ok(is_updated) => is_updated,
-                            Err(err) => {
-                                error!("could not update appearance and renderer: {err}");
-                                return LRESULT(0);
-                            }
-                        };
-                }
+        // Suspended borders only wait for their tracking window to be uncloaked. Messages that
+        // change the border's colors are still handled so that they're up to date once it resumes.
+        if self.is_suspended {
+            match message {
+                WM_APP_SHOWUNCLOAKED => self.resume(),
+                WM_APP_LOCATIONCHANGE
+                | WM_APP_REORDER
+                | WM_APP_FOREGROUND
+                | WM_APP_HIDECLOAKED
+                | WM_APP_MINIMIZESTART
+                | WM_APP_MINIMIZEEND
+                | WM_APP_ANIMATE
+                | WM_APP_RESOURCES
+                | WM_TIMER => return LRESULT(0),
+                _ => {}
+            }
+        }
 
-                if needs_render {
-                    self.render().log_if_err();
-                }
+        match message {
+            // EVENT_OBJECT_LOCATIONCHANGE
+            WM_APP_LOCATIONCHANGE => {
+                self.update_location(false);
             }
             // EVENT_OBJECT_REORDER
             WM_APP_REORDER => {
                 // When the tracking window reorders its contents, it may change the z-order. So,
-                // we first check whether the border is still above the tracking window, and if
-                // not, we must update its position and place it back on top
-                if unsafe { GetWindow(self.tracking_window, GW_HWNDPREV) } != Ok(self.border_window)
-                {
+                // we first check whether the border is still right next to the tracking window, and
+                // if not, we must update its position and put it back
+                if !self.is_z_order_correct() {
                     self.update_position(None).log_if_err();
                 }
             }
@@ -672,9 +2063,29 @@ impl WindowBorder {
                 self.update_color(None).log_if_err();
                 self.update_position(None).log_if_err();
                 self.render().log_if_err();
+
+                // Inactive borders may have slowed down or stopped their animation timer
+                if !self.is_paused {
+                    self.border_drawer
+                        .update_anim_timer_rate(self.border_window, self.window_state);
+                }
             }
             // EVENT_OBJECT_SHOW / EVENT_OBJECT_UNCLOAKED
             WM_APP_SHOWUNCLOAKED => {
+                if self.fade_out_start.is_some() {
+                    return LRESULT(0);
+                }
+
+                // The window may have lost its eligibility while it was hidden, but we'll give it
+                // a moment in case it's just switching modes
+                if !self.is_eligible() {
+                    self.queue_eligibility_check();
+                    if self.is_ineligible {
+                        return LRESULT(0);
+                    }
+                }
+                self.is_ineligible = false;
+
                 // With GlazeWM, if I switch to another workspace while a window is minimized and
                 // switch back, then we will receive this message even though the window is not yet
                 // visible. And, the window rect will be all weird. So, we apply the following fix.
@@ -688,7 +2099,7 @@ impl WindowBorder {
 
                 self.update_color(None).log_if_err();
 
-                if has_native_border(self.tracking_window) {
+                if self.should_show_border() {
                     self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
                     self.render().log_if_err();
                 }
@@ -701,9 +2112,12 @@ impl WindowBorder {
             }
             // EVENT_OBJECT_HIDE / EVENT_OBJECT_CLOAKED
             WM_APP_HIDECLOAKED => {
-                self.update_position(Some(SWP_HIDEWINDOW)).log_if_err();
-                self.border_drawer.animations.destroy_timer();
-                self.is_paused = true;
+                // If the window still looks visible, it might just be toggling its styles (e.g.
+                // during a mode change), so we wait a bit before hiding the border
+                match self.is_tracking_window_hidden() {
+                    true => self.hide(),
+                    false => self.queue_eligibility_check(),
+                }
             }
             // EVENT_OBJECT_MINIMIZESTART
             WM_APP_MINIMIZESTART => {
@@ -723,12 +2137,20 @@ impl WindowBorder {
             }
             // EVENT_SYSTEM_MINIMIZEEND
             WM_APP_MINIMIZEEND => {
-                // Keep the border hidden while the tracking window is in its unminimize animation
-                thread::sleep(time::Duration::from_millis(self.unminimize_delay));
+                // Keep the border hidden while the tracking window is in its unminimize animation.
+                // The restore animation plays alongside it instead.
+                let should_animate_restore =
+                    self.border_drawer.animations.restore.is_some() && !self.is_native_dwm();
+                if !should_animate_restore {
+                    thread::sleep(time::Duration::from_millis(self.unminimize_delay));
+                }
 
-                if has_native_border(self.tracking_window) {
+                if self.should_show_border() {
                     self.update_color(Some(self.unminimize_delay)).log_if_err();
                     self.update_window_rect().log_if_err();
+                    if should_animate_restore {
+                        self.start_presence_anim(PresenceKind::Restore);
+                    }
                     self.update_position(Some(SWP_SHOWWINDOW)).log_if_err();
                     self.render().log_if_err();
                 }
@@ -747,18 +2169,39 @@ impl WindowBorder {
                 self.border_drawer
                     .animate(&self.window_rect, self.window_padding, self.window_state)
                     .log_if_err();
+                self.border_drawer
+                    .update_anim_timer_rate(self.border_window, self.window_state);
+            }
+            // Sent to every border when no borders have been visible for a while
+            WM_APP_DORMANT if dormancy::is_dormant() => self.go_dormant(),
+            WM_APP_POSITIONED => {
+                self.is_position_deferred = false;
+                self.render().log_if_err();
+            }
+            WM_APP_KOMOREBI_GAPS => {
+                if self.update_komorebi_gap_adjustment() {
+                    if let Err(err) =
+                        self.update_appearance_and_renderer_if_necessary(self.current_monitor)
+                    {
+                        error!("could not update renderer for komorebi gaps: {err}");
+                    }
+                    self.update_location(true);
+                }
             }
             WM_APP_KOMOREBI => {
                 let window_rule = get_window_rule(self.tracking_window);
-                let global = &APP_STATE.config.read().unwrap().global;
+                let config = APP_STATE.config.read().unwrap();
+                let global = &config.global;
 
-                // Exit if komorebi colors are disabled for this tracking window
+                // Exit if komorebi colors are disabled for this tracking window, or if high contrast
+                // mode is on (we want to stick to the high contrast colors in that case)
                 // TODO: it might be better to store komorebi_colors in this WindowBorder struct
-                if !window_rule
-                    .komorebi_colors
-                    .as_ref()
-                    .map(|komocolors| komocolors.enabled)
-                    .unwrap_or(global.komorebi_colors.enabled)
+                if config.is_high_contrast_active()
+                    || !window_rule
+                        .komorebi_colors
+                        .as_ref()
+                        .map(|komocolors| komocolors.enabled)
+                        .unwrap_or(global.komorebi_colors.enabled || is_komorebi_detected(&config))
                 {
                     return LRESULT(0);
                 }
@@ -766,92 +2209,197 @@ impl WindowBorder {
                 let komorebi_integration = APP_STATE.komorebi_integration.lock().unwrap();
                 let focus_state = komorebi_integration.focus_state.lock().unwrap();
 
-                let window_kind = *focus_state
-                    .get(&(self.tracking_window.0 as isize))
-                    .unwrap_or_else(|| {
+                let window_kind = match focus_state.get(&(self.tracking_window.0 as isize)) {
+                    Some(window_kind) => *window_kind,
+                    // The focus state gets cleared while komorebi isn't talking to us, in which
+                    // case we just fall back to the regular colors
+                    None if komorebi_integration.get_status() != KomorebiStatus::Connected => {
+                        WindowKind::Single
+                    }
+                    None => {
                         error!("could not get window_kind for komorebi integration");
-                        &WindowKind::Single
-                    });
+                        WindowKind::Single
+                    }
+                };
+                let is_on_focused_monitor = komorebi_integration
+                    .monitor_focus
+                    .lock()
+                    .unwrap()
+                    .get(&(self.tracking_window.0 as isize))
+                    .copied()
+                    .unwrap_or(false);
 
                 drop(focus_state);
                 drop(komorebi_integration);
 
-                // Ignore Unfocused window kind
-                if window_kind == WindowKind::Unfocused {
-                    return LRESULT(0);
-                }
-
-                let active_color_config = window_rule
-                    .active_color
-                    .as_ref()
-                    .unwrap_or(&global.active_color);
+                let (active_color_config, inactive_color_config) =
+                    self.get_color_configs(&config, &window_rule);
                 let komorebi_colors_config = window_rule
                     .komorebi_colors
                     .as_ref()
                     .unwrap_or(&global.komorebi_colors);
 
-                let old_opacity = self
-                    .border_drawer
-                    .active_color
-                    .get_opacity()
-                    .unwrap_or_default();
-                let old_transform = self
-                    .border_drawer
-                    .active_color
-                    .get_transform()
-                    .unwrap_or_default();
+                let inactive_color = komorebi_colors_config.focused_monitor_color.as_ref().map(
+                    |focused_monitor_color| {
+                        match is_on_focused_monitor {
+                            true => focused_monitor_color,
+                            false => inactive_color_config,
+                        }
+                        .to_color_brush(false, self.tracking_window)
+                    },
+                );
 
-                self.border_drawer.active_color = match window_kind {
-                    WindowKind::Single => active_color_config.to_color_brush(true),
-                    WindowKind::Stack => komorebi_colors_config
-                        .stack_color
-                        .as_ref()
-                        .unwrap_or(active_color_config)
-                        .to_color_brush(true),
-                    WindowKind::Monocle => komorebi_colors_config
-                        .monocle_color
-                        .as_ref()
-                        .unwrap_or(active_color_config)
-                        .to_color_brush(true),
-                    WindowKind::Floating => komorebi_colors_config
-                        .floating_color
-                        .as_ref()
-                        .unwrap_or(active_color_config)
-                        .to_color_brush(true),
-                    WindowKind::Unfocused => {
-                        debug!("what."); // It shouldn't be possible to reach this match branch
-                        return LRESULT(0);
+                // The Unfocused window kind just uses the regular active color
+                let active_color = match window_kind {
+                    WindowKind::Single => {
+                        Some(active_color_config.to_color_brush(true, self.tracking_window))
                     }
+                    WindowKind::Stack => Some(
+                        komorebi_colors_config
+                            .stack_color
+                            .as_ref()
+                            .unwrap_or(active_color_config)
+                            .to_color_brush(true, self.tracking_window),
+                    ),
+                    WindowKind::Monocle => Some(
+                        komorebi_colors_config
+                            .monocle_color
+                            .as_ref()
+                            .unwrap_or(active_color_config)
+                            .to_color_brush(true, self.tracking_window),
+                    ),
+                    WindowKind::Floating => Some(
+                        komorebi_colors_config
+                            .floating_color
+                            .as_ref()
+                            .unwrap_or(active_color_config)
+                            .to_color_brush(true, self.tracking_window),
+                    ),
+                    WindowKind::Unfocused => None,
                 };
 
-                let renderer: &ID2D1RenderTarget = match self.border_drawer.render_backend {
-                    RenderBackend::V2(ref backend) => &backend.d2d_context,
-                    RenderBackend::Legacy(ref backend) => &backend.render_target,
-                    RenderBackend::None => {
-                        error!("render backend is None");
-                        return LRESULT(0);
-                    }
-                };
+                drop(config);
 
-                let brush_properties = D2D1_BRUSH_PROPERTIES {
-                    opacity: old_opacity,
-                    transform: old_transform,
-                };
+                if let Some(active_color) = active_color {
+                    self.border_drawer
+                        .set_active_color(active_color, &self.window_rect)
+                        .log_if_err();
+                }
+                if let Some(inactive_color) = inactive_color {
+                    self.border_drawer
+                        .set_inactive_color(inactive_color, &self.window_rect)
+                        .log_if_err();
+                    self.render().log_if_err();
+                }
+            }
+            WM_APP_RESOURCES => {
+                let level = wparam.0;
+                if level == self.resource_level {
+                    return LRESULT(0);
+                }
+                self.resource_level = level;
 
-                self.border_drawer
-                    .active_color
-                    .init_brush(renderer, &self.window_rect, &brush_properties)
+                self.update_color_brushes().log_if_err();
+                self.render().log_if_err();
+            }
+            // Sent by the schedule timer when a different color schedule becomes active
+            WM_APP_SCHEDULE => {
+                self.update_color_brushes().log_if_err();
+                self.render().log_if_err();
+            }
+            // Sent by set_color_override(). The LPARAM is a boxed ColorOverride that we now own.
+            WM_APP_SETCOLOR => {
+                let color_override = unsafe { Box::from_raw(lparam.0 as *mut ColorOverride) };
+                self.set_color_override(*color_override).log_if_err();
+            }
+            // Sent by the wallpaper watcher when the wallpaper's color changes
+            WM_APP_WALLPAPER => {
+                self.crossfade_color_brushes(WALLPAPER_CROSSFADE)
                     .log_if_err();
             }
+            // Sent by the proximity tracker as the cursor moves towards or away from the window
+            WM_APP_PROXIMITY => {
+                self.proximity_opacity = Some(wparam.0 as f32 / proximity::OPACITY_SCALE);
+
+                // Otherwise, the presence animation picks it up on its next frame
+                if self.presence_start.is_none() {
+                    self.update_window_alpha(1.0);
+                }
+            }
+            WM_GETOBJECT => {
+                return uia::handle_get_object(window, wparam, lparam)
+                    .unwrap_or_else(|| unsafe { DefWindowProcW(window, message, wparam, lparam) });
+            }
+            WM_TIMER if wparam.0 == IDLE_DIM_TIMER_ID => {
+                self.update_idle_dim().log_if_err();
+            }
+            WM_TIMER if wparam.0 == ELIGIBILITY_TIMER_ID => {
+                self.check_eligibility();
+            }
+            WM_TIMER if wparam.0 == PRESENCE_TIMER_ID => {
+                self.update_presence_anim();
+            }
+            WM_TIMER if wparam.0 == FADE_OUT_TIMER_ID => {
+                self.update_fade_out();
+            }
+            WM_TIMER if wparam.0 == RECT_SETTLE_TIMER_ID => {
+                self.update_location(true);
+            }
+            WM_TIMER if wparam.0 == CROSSFADE_TIMER_ID => {
+                if !self.border_drawer.update_crossfade() {
+                    let _ = unsafe { KillTimer(Some(self.border_window), CROSSFADE_TIMER_ID) };
+                }
+                self.render().log_if_err();
+            }
             WM_PAINT => {
                 let _ = unsafe { ValidateRect(Some(window), None) };
             }
+            // Sent by request_border_teardown(). The window itself gets destroyed after the
+            // message loop exits (see destroy_window()). If the tracking window was closed, we play
+            // the close animation first, but borders torn down for other reasons (e.g. a reload)
+            // exit right away.
+            WM_CLOSE => {
+                if wparam.0 != 0 && self.should_animate_close() {
+                    // The tracking window is gone, so keep the border where it was instead of
+                    // going through update_position()
+                    if !is_window_visible(self.border_window) {
+                        let _ = unsafe {
+                            SetWindowPos(
+                                self.border_window,
+                                None,
+                                0,
+                                0,
+                                0,
+                                0,
+                                SWP_NOMOVE
+                                    | SWP_NOSIZE
+                                    | SWP_NOZORDER
+                                    | SWP_NOACTIVATE
+                                    | SWP_SHOWWINDOW,
+                            )
+                        };
+                    }
+                    self.start_presence_anim(PresenceKind::Close);
+                } else {
+                    self.cleanup_and_queue_exit();
+                }
+            }
             WM_NCDESTROY => {
                 // We'll set GWLP_USERDATA to 0 so that the window procedure can't find the
                 // border's pointer anymore, making it stop processing our custom messages.
                 unsafe { SetWindowLongPtrW(window, GWLP_USERDATA, 0) };
                 self.cleanup_and_queue_exit();
             }
+            // Sent when the high contrast mode or system colors change. WM_DWMCOLORIZATIONCOLORCHANGED
+            // is sent when the accent color changes, so we re-resolve any "accent" colors too.
+            WM_THEMECHANGED | WM_SYSCOLORCHANGE | WM_DWMCOLORIZATIONCOLORCHANGED => {
+                self.update_color_brushes().log_if_err();
+                self.render().log_if_err();
+            }
+            WM_SETTINGCHANGE if wparam.0 as u32 == SPI_SETHIGHCONTRAST.0 => {
+                self.update_color_brushes().log_if_err();
+                self.render().log_if_err();
+            }
             // This message is sent when a display setting has changed (e.g. resolution change). It
             // is not sent when the window moves to a different monitor.
             WM_DISPLAYCHANGE => {
@@ -1,11 +1,12 @@
 use tacky_borders::animations::Animations;
-use tacky_borders::border_drawer::BorderDrawer;
-use tacky_borders::colors::ColorBrush;
+use tacky_borders::border_drawer::{BorderDrawer, BorderOffset, Notch, Outline};
+use tacky_borders::colors::{ColorBrush, ColorBrushConfig};
+use tacky_borders::config::{BorderEdge, NotchAlign};
 use tacky_borders::effects::Effects;
 use tacky_borders::register_border_window_class;
 use tacky_borders::render_backend::{RenderBackend, RenderBackendConfig};
 use tacky_borders::window_border::{WindowBorder, WindowState};
-use windows::Win32::Foundation::RECT;
+use windows::Win32::Foundation::{HWND, RECT};
 use windows::Win32::Graphics::Direct2D::Common::D2D_SIZE_U;
 
 #[test]
@@ -14,7 +15,8 @@ fn test_render_backend_v2_with_extra_bitmaps() -> anyhow::Result<()> {
     register_border_window_class()?;
     let hwnd = border_window.create_window()?;
 
-    let render_backend = RenderBackendConfig::V2.to_render_backend(1920, 1080, hwnd, true)?;
+    let render_backend =
+        RenderBackendConfig::V2.to_render_backend(1920, 1080, hwnd, HWND::default(), true)?;
     if let RenderBackend::V2(ref backend) = render_backend {
         assert!(backend.mask_bitmap.is_some());
         assert!(backend.border_bitmap.is_some());
@@ -38,7 +40,8 @@ fn test_render_backend_v2_without_extra_bitmaps() -> anyhow::Result<()> {
     register_border_window_class()?;
     let hwnd = border_window.create_window()?;
 
-    let render_backend = RenderBackendConfig::V2.to_render_backend(1920, 1080, hwnd, false)?;
+    let render_backend =
+        RenderBackendConfig::V2.to_render_backend(1920, 1080, hwnd, HWND::default(), false)?;
     if let RenderBackend::V2(ref backend) = render_backend {
         assert!(backend.mask_bitmap.is_none());
         assert!(backend.border_bitmap.is_none());
@@ -66,14 +69,22 @@ fn test_border_drawer_update() -> anyhow::Result<()> {
 
     border_drawer.configure_appearance(
         4,
-        -1,
+        BorderOffset::uniform(-1),
         8.0,
         ColorBrush::default(),
         ColorBrush::default(),
         Animations::default(),
         Effects::default(),
+        None,
     );
-    border_drawer.init_renderer(1920, 1080, hwnd, &RECT::default(), RenderBackendConfig::V2)?;
+    border_drawer.init_renderer(
+        1920,
+        1080,
+        hwnd,
+        HWND::default(),
+        &RECT::default(),
+        RenderBackendConfig::V2,
+    )?;
 
     assert!(
         border_drawer
@@ -96,3 +107,78 @@ fn test_border_drawer_update() -> anyhow::Result<()> {
 
     Ok(())
 }
+
+// Renders a full-screen border with the given outline and notch, which should just succeed
+fn render_border_with(outline: Option<Outline>, notch: Option<Notch>) -> anyhow::Result<()> {
+    let mut border_window = WindowBorder::default();
+    let mut border_drawer = BorderDrawer::default();
+
+    register_border_window_class()?;
+    let hwnd = border_window.create_window()?;
+
+    border_drawer.configure_appearance(
+        4,
+        BorderOffset::uniform(-1),
+        8.0,
+        ColorBrush::default(),
+        ColorBrush::default(),
+        Animations::default(),
+        Effects::default(),
+        outline,
+    );
+    border_drawer.notch = notch;
+    border_drawer.init_renderer(
+        1920,
+        1080,
+        hwnd,
+        HWND::default(),
+        &RECT::default(),
+        RenderBackendConfig::V2,
+    )?;
+
+    let window_rect = RECT {
+        left: 0,
+        top: 0,
+        right: 1920,
+        bottom: 1080,
+    };
+    assert!(
+        border_drawer
+            .render(&window_rect, 3, WindowState::default())
+            .is_ok()
+    );
+
+    Ok(())
+}
+
+fn black_outline(is_inside_too: bool) -> Outline {
+    Outline {
+        width: 1,
+        gap: 2,
+        is_inside_too,
+        active_color: ColorBrushConfig::solid("#000000").to_color_brush(true, HWND::default()),
+        inactive_color: ColorBrushConfig::solid("#000000").to_color_brush(false, HWND::default()),
+    }
+}
+
+#[test]
+fn test_border_drawer_with_outline() -> anyhow::Result<()> {
+    render_border_with(Some(black_outline(false)), None)
+}
+
+#[test]
+fn test_border_drawer_with_inside_outline() -> anyhow::Result<()> {
+    render_border_with(Some(black_outline(true)), None)
+}
+
+#[test]
+fn test_border_drawer_with_notch() -> anyhow::Result<()> {
+    let notch = Notch {
+        edge: BorderEdge::Top,
+        align: NotchAlign::Center,
+        width: 200.0,
+        offset: 0.0,
+    };
+
+    render_border_with(None, Some(notch))
+}